@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+
+/// Exposes the resolved pgmold version as `PGMOLD_VERSION`, read from the
+/// lockfile of the package or workspace being built.
+fn main() {
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let lockfile = manifest_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.exists());
+    if let Some(lockfile) = &lockfile {
+        println!("cargo:rerun-if-changed={}", lockfile.display());
+    }
+    let version = lockfile
+        .as_deref()
+        .and_then(|path| locked_version(path, "pgmold"))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=PGMOLD_VERSION={version}");
+}
+
+fn locked_version(lockfile: &Path, package: &str) -> Option<String> {
+    let content = std::fs::read_to_string(lockfile).ok()?;
+    let name = format!("name = \"{package}\"");
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name {
+            let version = lines.next()?.trim();
+            return Some(
+                version
+                    .strip_prefix("version = \"")?
+                    .trim_end_matches('"')
+                    .to_string(),
+            );
+        }
+    }
+    None
+}
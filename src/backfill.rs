@@ -0,0 +1,130 @@
+use pgmold::lint::LintSeverity;
+
+use crate::pipeline::MigrationPlan;
+
+/// Rows updated per backfill batch in the generated template.
+const BACKFILL_BATCH_ROWS: usize = 10_000;
+
+/// An `ADD COLUMN ... NOT NULL` without a default, which fails on a table that
+/// already has rows.
+#[derive(Debug, PartialEq)]
+struct NotNullAddition<'a> {
+    table: &'a str,
+    column: &'a str,
+    /// Column definition without `NOT NULL`.
+    definition: String,
+}
+
+impl<'a> NotNullAddition<'a> {
+    fn parse(statement: &'a str) -> Option<Self> {
+        let re = regex::Regex::new(
+            r#"(?is)^\s*ALTER\s+TABLE\s+(?:ONLY\s+)?((?:"[^"]*"|[\w$]+)(?:\.(?:"[^"]*"|[\w$]+))?)\s+ADD\s+COLUMN\s+(?:IF\s+NOT\s+EXISTS\s+)?((?:"[^"]*"|[\w$]+))\s+(.*?)\s*;?\s*$"#,
+        )
+        .unwrap();
+        let captures = re.captures(statement)?;
+        let definition = captures.get(3)?.as_str();
+
+        let not_null = regex::Regex::new(r"(?i)\s*\bNOT\s+NULL\b").unwrap();
+        let default = regex::Regex::new(r"(?i)\b(DEFAULT|GENERATED)\b").unwrap();
+        if !not_null.is_match(definition) || default.is_match(definition) {
+            return None;
+        }
+
+        Some(NotNullAddition {
+            table: captures.get(1)?.as_str(),
+            column: captures.get(2)?.as_str(),
+            definition: not_null.replace_all(definition, "").trim().to_string(),
+        })
+    }
+
+    fn template(&self) -> String {
+        let NotNullAddition {
+            table,
+            column,
+            definition,
+        } = self;
+        [
+            format!("-- Backfill template: {table}.{column} is NOT NULL without a default, which fails on a table with rows."),
+            "-- 1. Add the column as nullable".to_string(),
+            format!("ALTER TABLE {table} ADD COLUMN {column} {definition};"),
+            "-- 2. Backfill in batches, filling in <value> and repeating until no rows are updated:".to_string(),
+            format!("-- UPDATE {table} SET {column} = <value> WHERE ctid IN (SELECT ctid FROM {table} WHERE {column} IS NULL LIMIT {BACKFILL_BATCH_ROWS});"),
+            "-- 3. Once every row has a value, enforce the constraint:".to_string(),
+            format!("-- ALTER TABLE {table} ALTER COLUMN {column} SET NOT NULL;"),
+        ]
+        .join("\n")
+    }
+}
+
+/// Replaces each `ADD COLUMN ... NOT NULL` without a default with a nullable add
+/// followed by commented backfill and `SET NOT NULL` steps to complete by hand.
+/// Lint errors about those columns become warnings, since the template no longer
+/// takes the locking path they warn about.
+pub fn apply_templates(plan: &mut MigrationPlan) {
+    let mut columns = Vec::new();
+    for statement in &mut plan.statements {
+        if let Some(addition) = NotNullAddition::parse(statement) {
+            columns.push(addition.column.trim_matches('"').to_string());
+            *statement = addition.template();
+        }
+    }
+
+    for lint in &mut plan.lint_results {
+        if lint.severity == LintSeverity::Error && is_about_not_null(&lint.message, &columns) {
+            lint.severity = LintSeverity::Warning;
+        }
+    }
+}
+
+fn is_about_not_null(message: &str, columns: &[String]) -> bool {
+    let lowercase = message.to_lowercase();
+    lowercase.contains("not null")
+        && columns
+            .iter()
+            .any(|column| message.contains(column.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_finds_not_null_additions_without_default() {
+        let addition = NotNullAddition::parse(
+            "ALTER TABLE \"public\".\"users\" ADD COLUMN \"email\" TEXT NOT NULL;",
+        )
+        .unwrap();
+        assert_eq!(addition.table, "\"public\".\"users\"");
+        assert_eq!(addition.column, "\"email\"");
+        assert_eq!(addition.definition, "TEXT");
+
+        assert!(NotNullAddition::parse(
+            "ALTER TABLE users ADD COLUMN active BOOLEAN NOT NULL DEFAULT true;"
+        )
+        .is_none());
+        assert!(NotNullAddition::parse("ALTER TABLE users ADD COLUMN nickname TEXT;").is_none());
+        assert!(NotNullAddition::parse("CREATE TABLE users (id INT NOT NULL);").is_none());
+    }
+
+    #[test]
+    fn template_adds_nullable_column_and_comments_the_rest() {
+        let addition =
+            NotNullAddition::parse("ALTER TABLE users ADD COLUMN email TEXT NOT NULL").unwrap();
+        let lines: Vec<String> = addition.template().lines().map(str::to_string).collect();
+        let active: Vec<&String> = lines.iter().filter(|l| !l.starts_with("--")).collect();
+        assert_eq!(active, ["ALTER TABLE users ADD COLUMN email TEXT;"]);
+        assert!(
+            lines.contains(&"-- ALTER TABLE users ALTER COLUMN email SET NOT NULL;".to_string())
+        );
+    }
+
+    #[test]
+    fn is_about_not_null_matches_templated_columns() {
+        let columns = vec!["email".to_string()];
+        assert!(is_about_not_null(
+            "Adding NOT NULL column users.email without a default",
+            &columns
+        ));
+        assert!(!is_about_not_null("Dropping column users.email", &columns));
+    }
+}
@@ -0,0 +1,557 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tf_provider::{AttributePath, Diagnostics};
+
+/// `pg_dump --format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DumpFormat {
+    /// Compressed archive for `pg_restore`.
+    #[default]
+    Custom,
+    /// SQL script for `psql`.
+    Plain,
+}
+
+impl DumpFormat {
+    pub const NAMES: [&'static str; 2] = ["custom", "plain"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "custom" => Some(DumpFormat::Custom),
+            "plain" => Some(DumpFormat::Plain),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DumpFormat::Custom => "custom",
+            DumpFormat::Plain => "plain",
+        }
+    }
+}
+
+/// What a dump contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DumpContent {
+    #[default]
+    All,
+    Schema,
+    Data,
+}
+
+impl DumpContent {
+    pub const NAMES: [&'static str; 3] = ["all", "schema", "data"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "all" => Some(DumpContent::All),
+            "schema" => Some(DumpContent::Schema),
+            "data" => Some(DumpContent::Data),
+            _ => None,
+        }
+    }
+}
+
+/// Reports an unknown `format` value on the attribute.
+pub fn check_format(diags: &mut Diagnostics, format: Option<&str>) -> Option<()> {
+    match format {
+        Some(name) if DumpFormat::parse(name).is_none() => {
+            diags.error_short(
+                format!(
+                    "Unknown format \"{name}\", expected one of: {}",
+                    DumpFormat::NAMES.join(", ")
+                ),
+                AttributePath::new("format"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+/// Reports an unknown `content` value on the attribute.
+pub fn check_content(diags: &mut Diagnostics, content: Option<&str>) -> Option<()> {
+    match content {
+        Some(name) if DumpContent::parse(name).is_none() => {
+            diags.error_short(
+                format!(
+                    "Unknown content \"{name}\", expected one of: {}",
+                    DumpContent::NAMES.join(", ")
+                ),
+                AttributePath::new("content"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+/// Where a dump artifact is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destination {
+    Local(PathBuf),
+    S3 { bucket: String, key: String },
+}
+
+impl Destination {
+    /// `s3://bucket/key` or a local path.
+    pub fn parse(destination: &str) -> anyhow::Result<Self> {
+        let Some(rest) = destination.strip_prefix("s3://") else {
+            return Ok(Destination::Local(PathBuf::from(destination)));
+        };
+        match rest.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok(Destination::S3 {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+            }),
+            _ => anyhow::bail!("expected s3://<bucket>/<key>, got {destination}"),
+        }
+    }
+}
+
+/// Reports a `destination` that isn't a local path or `s3://bucket/key`.
+pub fn check_destination(diags: &mut Diagnostics, destination: Option<&str>) -> Option<()> {
+    if let Err(e) = destination.map(Destination::parse).transpose() {
+        diags.error_short(
+            format!("Invalid destination: {e}"),
+            AttributePath::new("destination"),
+        );
+        return None;
+    }
+    Some(())
+}
+
+/// Replaces `{timestamp}` in a destination, so each backup can get its own artifact.
+pub fn expand_destination(destination: &str, now: chrono::DateTime<chrono::Utc>) -> String {
+    destination.replace("{timestamp}", &now.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DumpOptions {
+    pub format: DumpFormat,
+    pub content: DumpContent,
+    /// Schemas to dump; empty dumps the whole database.
+    pub schemas: Vec<String>,
+}
+
+/// `pg_dump` arguments writing a dump to `file`.
+fn pg_dump_args(options: &DumpOptions, file: &Path) -> Vec<String> {
+    let mut args = vec![
+        format!("--format={}", options.format.as_str()),
+        "--no-password".to_string(),
+        format!("--file={}", file.display()),
+    ];
+    match options.content {
+        DumpContent::All => {}
+        DumpContent::Schema => args.push("--schema-only".to_string()),
+        DumpContent::Data => args.push("--data-only".to_string()),
+    }
+    for schema in &options.schemas {
+        args.push(format!("--schema={schema}"));
+    }
+    args
+}
+
+/// A stored dump.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    pub location: String,
+    /// SHA256 of the dump file.
+    pub checksum: String,
+    pub size_bytes: i64,
+}
+
+/// Runs `pg_dump` against `database_url` and stores the result at `destination`.
+/// S3 uploads use the default AWS credential chain.
+pub async fn run(
+    pg_dump: &str,
+    database_url: &str,
+    destination: &str,
+    options: &DumpOptions,
+) -> anyhow::Result<Artifact> {
+    let destination = Destination::parse(destination)?;
+    let file = match &destination {
+        Destination::Local(path) => path.clone(),
+        Destination::S3 { .. } => temp_file("backup"),
+    };
+    if let Some(parent) = file.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    run_client(pg_dump, database_url, pg_dump_args(options, &file)).await?;
+
+    let checksum = file_sha256(&file)?;
+    let size_bytes = std::fs::metadata(&file)?.len() as i64;
+    let location = match destination {
+        Destination::Local(path) => path.display().to_string(),
+        Destination::S3 { bucket, key } => {
+            let uploaded = upload(&file, &bucket, &key).await;
+            let _ = std::fs::remove_file(&file);
+            uploaded?;
+            format!("s3://{bucket}/{key}")
+        }
+    };
+    Ok(Artifact {
+        location,
+        checksum,
+        size_bytes,
+    })
+}
+
+/// Runs a PostgreSQL client program against `database_url`, failing with its
+/// stderr when it exits unsuccessfully.
+async fn run_client(program: &str, database_url: &str, args: Vec<String>) -> anyhow::Result<()> {
+    // The password goes through the environment so it doesn't show up in process listings
+    let (database_url, password) = split_password(database_url);
+    let mut command = tokio::process::Command::new(program);
+    command.args(args).arg(format!("--dbname={database_url}"));
+    if let Some(password) = password {
+        command.env("PGPASSWORD", password);
+    }
+    let output = command
+        .output()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to run {program}: {e}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{program} failed ({}): {}",
+            output.status,
+            crate::util::redact_url(String::from_utf8_lossy(&output.stderr).trim())
+        );
+    }
+    Ok(())
+}
+
+fn temp_file(purpose: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "pgmold-{purpose}-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ))
+}
+
+#[derive(Debug, Clone)]
+pub struct RestoreOptions {
+    /// Drop objects before recreating them (`pg_restore --clean --if-exists`).
+    pub clean: bool,
+    /// Skip ownership changes, for targets without the source's roles.
+    pub no_owner: bool,
+    pub pg_restore: String,
+    pub psql: String,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        RestoreOptions {
+            clean: false,
+            no_owner: false,
+            pg_restore: "pg_restore".to_string(),
+            psql: "psql".to_string(),
+        }
+    }
+}
+
+/// Custom-format archives start with this magic; anything else is a SQL script.
+fn is_custom_archive(header: &[u8]) -> bool {
+    header.starts_with(b"PGDMP")
+}
+
+/// `pg_restore` arguments for a custom-format archive, or `psql` arguments
+/// for a plain SQL dump.
+fn restore_args(custom: bool, file: &Path, options: &RestoreOptions) -> Vec<String> {
+    if !custom {
+        return vec![
+            "--no-password".to_string(),
+            "--quiet".to_string(),
+            "--set=ON_ERROR_STOP=1".to_string(),
+            format!("--file={}", file.display()),
+        ];
+    }
+    let mut args = vec!["--no-password".to_string(), "--exit-on-error".to_string()];
+    if options.clean {
+        args.push("--clean".to_string());
+        args.push("--if-exists".to_string());
+    }
+    if options.no_owner {
+        args.push("--no-owner".to_string());
+    }
+    args.push(file.display().to_string());
+    args
+}
+
+/// Restores the dump at `source` (a local path or `s3://bucket/key`) into
+/// `database_url`, returning its checksum. Fails before restoring anything when
+/// `expected_checksum` is set and doesn't match.
+pub async fn restore(
+    database_url: &str,
+    source: &str,
+    expected_checksum: Option<&str>,
+    options: &RestoreOptions,
+) -> anyhow::Result<String> {
+    let (file, downloaded) = match Destination::parse(source)? {
+        Destination::Local(path) => (path, false),
+        Destination::S3 { bucket, key } => {
+            let file = temp_file("restore");
+            download(&bucket, &key, &file).await?;
+            (file, true)
+        }
+    };
+    let restored = restore_file(database_url, &file, expected_checksum, options).await;
+    if downloaded {
+        let _ = std::fs::remove_file(&file);
+    }
+    restored
+}
+
+async fn restore_file(
+    database_url: &str,
+    file: &Path,
+    expected_checksum: Option<&str>,
+    options: &RestoreOptions,
+) -> anyhow::Result<String> {
+    let checksum = file_sha256(file)?;
+    if let Some(expected) = expected_checksum.filter(|e| *e != checksum) {
+        anyhow::bail!("checksum mismatch: expected {expected}, the dump has {checksum}");
+    }
+
+    let mut header = [0u8; 5];
+    let read = std::fs::File::open(file)?.read(&mut header)?;
+    let custom = is_custom_archive(&header[..read]);
+    let program = if custom {
+        &options.pg_restore
+    } else {
+        &options.psql
+    };
+    run_client(program, database_url, restore_args(custom, file, options)).await?;
+    Ok(checksum)
+}
+
+async fn download(bucket: &str, key: &str, file: &Path) -> anyhow::Result<()> {
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    let client = aws_sdk_s3::Client::new(&config);
+    let object = client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("download of s3://{bucket}/{key} failed: {e}"))?;
+    let mut body = object.body.into_async_read();
+    let mut output = tokio::fs::File::create(file).await?;
+    tokio::io::copy(&mut body, &mut output).await?;
+    Ok(())
+}
+
+/// Splits a connection URL into one for the `postgres` maintenance database on
+/// the same server and the name of the database it points at.
+pub fn maintenance_url(database_url: &str) -> Option<(String, String)> {
+    let re = regex::Regex::new(r"^([A-Za-z][A-Za-z0-9+.-]*://[^/?#]*)/([^/?#]+)(.*)$").unwrap();
+    let captures = re.captures(database_url)?;
+    let name = percent_decode(&captures[2]);
+    Some((format!("{}/postgres{}", &captures[1], &captures[3]), name))
+}
+
+async fn upload(file: &Path, bucket: &str, key: &str) -> anyhow::Result<()> {
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    let client = aws_sdk_s3::Client::new(&config);
+    let body = aws_sdk_s3::primitives::ByteStream::from_path(file).await?;
+    client
+        .put_object()
+        .bucket(bucket)
+        .key(key)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("upload to s3://{bucket}/{key} failed: {e}"))?;
+    Ok(())
+}
+
+/// Removes the password from a connection URL's userinfo, returning it decoded.
+fn split_password(url: &str) -> (String, Option<String>) {
+    let re = regex::Regex::new(r"^([A-Za-z][A-Za-z0-9+.-]*://[^:/?#@]*):([^/?#@]*)@").unwrap();
+    match re.captures(url) {
+        Some(captures) => (
+            re.replace(url, "$1@").to_string(),
+            Some(percent_decode(&captures[2])),
+        ),
+        None => (url.to_string(), None),
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn file_sha256(path: &Path) -> anyhow::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destination_parses_s3_urls_and_paths() {
+        assert_eq!(
+            Destination::parse("s3://backups/app/pre-migrate.dump").unwrap(),
+            Destination::S3 {
+                bucket: "backups".to_string(),
+                key: "app/pre-migrate.dump".to_string(),
+            }
+        );
+        assert_eq!(
+            Destination::parse("backups/app.dump").unwrap(),
+            Destination::Local(PathBuf::from("backups/app.dump"))
+        );
+        assert!(Destination::parse("s3://backups").is_err());
+    }
+
+    #[test]
+    fn expand_destination_fills_in_timestamp() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T12:30:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            expand_destination("backups/app-{timestamp}.dump", now),
+            "backups/app-20240501T123000Z.dump"
+        );
+    }
+
+    #[test]
+    fn pg_dump_args_follow_options() {
+        let options = DumpOptions {
+            format: DumpFormat::Plain,
+            content: DumpContent::Schema,
+            schemas: vec!["app".to_string()],
+        };
+        assert_eq!(
+            pg_dump_args(&options, Path::new("out.sql")),
+            vec![
+                "--format=plain",
+                "--no-password",
+                "--file=out.sql",
+                "--schema-only",
+                "--schema=app",
+            ]
+        );
+    }
+
+    #[test]
+    fn split_password_moves_password_out_of_url() {
+        assert_eq!(
+            split_password("postgres://app:p%40ss@db:5432/app"),
+            (
+                "postgres://app@db:5432/app".to_string(),
+                Some("p@ss".to_string())
+            )
+        );
+        assert_eq!(
+            split_password("postgres://app@db/app"),
+            ("postgres://app@db/app".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn restore_args_pick_client_by_format() {
+        let options = RestoreOptions {
+            clean: true,
+            no_owner: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            restore_args(true, Path::new("app.dump"), &options),
+            vec![
+                "--no-password",
+                "--exit-on-error",
+                "--clean",
+                "--if-exists",
+                "--no-owner",
+                "app.dump",
+            ]
+        );
+        assert_eq!(
+            restore_args(false, Path::new("app.sql"), &options),
+            vec![
+                "--no-password",
+                "--quiet",
+                "--set=ON_ERROR_STOP=1",
+                "--file=app.sql",
+            ]
+        );
+        assert!(is_custom_archive(b"PGDMP\x01"));
+        assert!(!is_custom_archive(b"--\n-- PostgreSQL database dump"));
+    }
+
+    #[test]
+    fn maintenance_url_points_at_postgres_database() {
+        assert_eq!(
+            maintenance_url("postgres://app:secret@db:5432/preview_42?sslmode=require"),
+            Some((
+                "postgres://app:secret@db:5432/postgres?sslmode=require".to_string(),
+                "preview_42".to_string()
+            ))
+        );
+        assert_eq!(maintenance_url("postgres://db:5432"), None);
+    }
+
+    #[tokio::test]
+    async fn restore_file_rejects_checksum_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dump");
+        std::fs::write(&path, "abc").unwrap();
+        let restored = restore_file(
+            "postgres://db/app",
+            &path,
+            Some("0000"),
+            &RestoreOptions::default(),
+        )
+        .await;
+        assert!(restored
+            .unwrap_err()
+            .to_string()
+            .contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn file_sha256_hashes_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dump");
+        std::fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            file_sha256(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
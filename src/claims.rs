@@ -0,0 +1,99 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::object_filter::ObjectFilter;
+
+/// What one resource manages in a database: everything in `schemas` its
+/// filter matches, and the objects its schema file declares there.
+#[derive(Debug, Clone)]
+pub struct Claim {
+    pub schemas: Vec<String>,
+    pub filter: ObjectFilter,
+    pub objects: BTreeSet<(String, String)>,
+}
+
+impl Claim {
+    /// Whether the resource would create, change or drop `object`.
+    fn covers(&self, (schema, name): &(String, String)) -> bool {
+        self.schemas.contains(schema) && self.filter.matches(schema, name)
+    }
+}
+
+/// The claims of every resource planned by a provider instance, per target
+/// database, so two resources managing the same object are caught at plan.
+#[derive(Debug, Default, Clone)]
+pub struct ClaimRegistry {
+    claims: Arc<Mutex<HashMap<String, BTreeMap<String, Claim>>>>,
+}
+
+impl ClaimRegistry {
+    /// Records `claim` as what `resource` manages in `database`, replacing its
+    /// previous claim. Returns, as `schema.name`, the objects one of the
+    /// resources declares and another one manages, with that other resource.
+    pub async fn claim(
+        &self,
+        database: &str,
+        resource: &str,
+        claim: Claim,
+    ) -> BTreeSet<(String, String)> {
+        let mut claims = self.claims.lock().await;
+        let claims = claims.entry(database.to_string()).or_default();
+        let mut conflicts = BTreeSet::new();
+        for (other, theirs) in claims.iter().filter(|(id, _)| *id != resource) {
+            let contested = claim
+                .objects
+                .iter()
+                .filter(|o| theirs.covers(o))
+                .chain(theirs.objects.iter().filter(|o| claim.covers(o)));
+            for (schema, name) in contested {
+                conflicts.insert((format!("{schema}.{name}"), other.clone()));
+            }
+        }
+        claims.insert(resource.to_string(), claim);
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(patterns: &[&str], objects: &[&str]) -> Claim {
+        Claim {
+            schemas: vec!["public".to_string()],
+            filter: ObjectFilter::parse(patterns).unwrap(),
+            objects: objects
+                .iter()
+                .map(|o| ("public".to_string(), o.to_string()))
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn claim_reports_objects_managed_twice() {
+        let registry = ClaimRegistry::default();
+        let billing = claim(&["public.invoice*"], &["invoices"]);
+        let orders = claim(&["public.order*"], &["orders", "order_items"]);
+        assert!(registry.claim("db", "billing", billing).await.is_empty());
+        assert!(registry
+            .claim("db", "orders", orders.clone())
+            .await
+            .is_empty());
+        // Planning again replaces the resource's own claim
+        assert!(registry.claim("db", "orders", orders).await.is_empty());
+
+        // Without patterns, a resource would drop what the others declare
+        let everything = claim(&[], &["users", "orders"]);
+        assert_eq!(
+            registry.claim("db", "app", everything.clone()).await,
+            BTreeSet::from([
+                ("public.invoices".to_string(), "billing".to_string()),
+                ("public.order_items".to_string(), "orders".to_string()),
+                ("public.orders".to_string(), "orders".to_string()),
+            ])
+        );
+        assert!(registry.claim("other", "app", everything).await.is_empty());
+    }
+}
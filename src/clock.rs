@@ -0,0 +1,19 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+
+static FROZEN: RwLock<Option<DateTime<Utc>>> = RwLock::new(None);
+
+/// Current time for timestamps recorded in state, files and logs. Tests can
+/// pin it with [`freeze`] so those values are reproducible.
+pub fn now() -> DateTime<Utc> {
+    FROZEN
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .unwrap_or_else(Utc::now)
+}
+
+/// Makes [`now`] return `at` until called again with `None`.
+pub fn freeze(at: Option<DateTime<Utc>>) {
+    *FROZEN.write().unwrap_or_else(|e| e.into_inner()) = at;
+}
@@ -0,0 +1,59 @@
+//! Explicit opt-in for destructive applies. `allow_destructive` is often set by
+//! a module's defaults, so with `require_confirmation` on, destructive
+//! operations also need whoever runs the apply to confirm them: a person with
+//! [`CONFIRM_VAR`], or a pipeline with the provider's `confirmation_token` set
+//! to the `destructive_operations_hash` of the plan it reviewed.
+
+/// Environment variable that confirms destructive applies when set to `yes`.
+pub const CONFIRM_VAR: &str = "PGMOLD_CONFIRM";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Confirmation {
+    pub required: bool,
+    /// Non-empty `confirmation_token` of the provider.
+    pub token: Option<String>,
+}
+
+impl Confirmation {
+    /// Whether the destructive operations hashing to `destructive_hash` may run.
+    pub fn confirmed(&self, destructive_hash: &str) -> bool {
+        self.confirms(std::env::var(CONFIRM_VAR).ok().as_deref(), destructive_hash)
+    }
+
+    /// A token only confirms the plan it was taken from, so a stale one left in
+    /// a pipeline doesn't confirm whatever drops the next plan brings.
+    fn confirms(&self, env: Option<&str>, destructive_hash: &str) -> bool {
+        !self.required
+            || self.token.as_deref() == Some(destructive_hash)
+            || env.is_some_and(|v| v.eq_ignore_ascii_case("yes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirms_with_env_or_token_when_required() {
+        let mut confirmation = Confirmation::default();
+        assert!(confirmation.confirms(None, "abc"));
+
+        confirmation.required = true;
+        assert!(!confirmation.confirms(None, "abc"));
+        assert!(!confirmation.confirms(Some("1"), "abc"));
+        assert!(confirmation.confirms(Some("yes"), "abc"));
+
+        confirmation.token = Some("abc".to_string());
+        assert!(confirmation.confirms(None, "abc"));
+    }
+
+    #[test]
+    fn rejects_token_of_another_plan() {
+        let confirmation = Confirmation {
+            required: true,
+            token: Some("abc".to_string()),
+        };
+
+        assert!(!confirmation.confirms(None, "def"));
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use pgmold::model::Schema;
+use pgmold::pg::connection::PgConnection;
+use tf_provider::Diagnostics;
+use tokio::sync::{Mutex, RwLock};
+
+/// `statement_timeout` of introspection sessions unless the provider sets one.
+pub const DEFAULT_INTROSPECTION_TIMEOUT: &str = "30s";
+
+/// The provider's `database_url` and `target_schemas`, which resources that
+/// don't set their own inherit.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceDefaults {
+    pub database_url: Option<String>,
+    pub target_schemas: Option<Vec<String>>,
+}
+
+/// Pooled connections shared by every resource of a provider instance, so a
+/// plan-time diff and the following apply reuse the same pool instead of
+/// reconnecting.
+#[derive(Default, Clone)]
+pub struct ConnectionCache {
+    connections: Arc<Mutex<HashMap<String, Arc<PgConnection>>>>,
+    keepalives_idle: Arc<RwLock<Option<u32>>>,
+    redact_hosts: Arc<RwLock<bool>>,
+    snapshot: Arc<RwLock<Option<Arc<Schema>>>>,
+    target_server_version: Arc<RwLock<Option<i64>>>,
+    plan_signing_key: Arc<RwLock<Option<String>>>,
+    introspection_timeout: Arc<RwLock<Option<String>>>,
+    run_metadata: Arc<RwLock<crate::run_metadata::RunMetadata>>,
+    failpoints: Arc<RwLock<crate::failpoints::Failpoints>>,
+    lint_rules: Arc<RwLock<Vec<crate::lint_rules::LintRule>>>,
+    escalate_warnings: Arc<RwLock<bool>>,
+    operation_policy: Arc<RwLock<crate::operation_policy::OperationPolicy>>,
+    confirmation: Arc<RwLock<crate::confirmation::Confirmation>>,
+    resource_defaults: Arc<RwLock<ResourceDefaults>>,
+}
+
+impl std::fmt::Debug for ConnectionCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionCache").finish_non_exhaustive()
+    }
+}
+
+impl ConnectionCache {
+    /// Seconds of idleness after which the server sends TCP keepalives on new connections.
+    pub async fn set_keepalives_idle(&self, seconds: Option<u32>) {
+        *self.keepalives_idle.write().await = seconds;
+    }
+
+    /// Whether connection errors mask the database host and port.
+    pub async fn set_redact_hosts(&self, redact: bool) {
+        *self.redact_hosts.write().await = redact;
+    }
+
+    /// Switches to mock mode, where plans diff against `snapshot` and no
+    /// connections are opened.
+    pub async fn set_mock(&self, snapshot: Option<Schema>) {
+        *self.snapshot.write().await = snapshot.map(Arc::new);
+    }
+
+    /// The schema standing in for every database in mock mode.
+    pub async fn snapshot(&self) -> Option<Arc<Schema>> {
+        self.snapshot.read().await.clone()
+    }
+
+    /// Pins the `server_version_num` SQL is generated for, instead of
+    /// detecting it from each connection.
+    pub async fn set_target_server_version(&self, version: Option<i64>) {
+        *self.target_server_version.write().await = version;
+    }
+
+    pub async fn target_server_version(&self) -> Option<i64> {
+        *self.target_server_version.read().await
+    }
+
+    /// Key plan artifacts and generated migrations are signed with.
+    pub async fn set_plan_signing_key(&self, key: Option<String>) {
+        *self.plan_signing_key.write().await = key;
+    }
+
+    pub async fn plan_signing_key(&self) -> Option<String> {
+        self.plan_signing_key.read().await.clone()
+    }
+
+    /// Workspace, run and commit stamped into migrations and execution logs.
+    pub async fn set_run_metadata(&self, run: crate::run_metadata::RunMetadata) {
+        *self.run_metadata.write().await = run;
+    }
+
+    pub async fn run_metadata(&self) -> crate::run_metadata::RunMetadata {
+        self.run_metadata.read().await.clone()
+    }
+
+    /// Failures injected into applies, see [`crate::failpoints`].
+    pub async fn set_failpoints(&self, failpoints: crate::failpoints::Failpoints) {
+        *self.failpoints.write().await = failpoints;
+    }
+
+    pub async fn failpoints(&self) -> crate::failpoints::Failpoints {
+        self.failpoints.read().await.clone()
+    }
+
+    /// House lint rules every plan checks, see [`crate::lint_rules`].
+    pub async fn set_lint_rules(&self, rules: Vec<crate::lint_rules::LintRule>) {
+        *self.lint_rules.write().await = rules;
+    }
+
+    pub async fn lint_rules(&self) -> Vec<crate::lint_rules::LintRule> {
+        self.lint_rules.read().await.clone()
+    }
+
+    /// Whether the `lint_policy` entry of the provider's `environment` turns
+    /// lint warnings into errors.
+    pub async fn set_escalate_warnings(&self, escalate: bool) {
+        *self.escalate_warnings.write().await = escalate;
+    }
+
+    pub async fn escalate_warnings(&self) -> bool {
+        *self.escalate_warnings.read().await
+    }
+
+    /// Operations every plan is checked against, see
+    /// [`crate::operation_policy`].
+    pub async fn set_operation_policy(&self, policy: crate::operation_policy::OperationPolicy) {
+        *self.operation_policy.write().await = policy;
+    }
+
+    pub async fn operation_policy(&self) -> crate::operation_policy::OperationPolicy {
+        self.operation_policy.read().await.clone()
+    }
+
+    /// What destructive applies need to run, see [`crate::confirmation`].
+    pub async fn set_confirmation(&self, confirmation: crate::confirmation::Confirmation) {
+        *self.confirmation.write().await = confirmation;
+    }
+
+    pub async fn confirmation(&self) -> crate::confirmation::Confirmation {
+        self.confirmation.read().await.clone()
+    }
+
+    pub async fn set_resource_defaults(&self, defaults: ResourceDefaults) {
+        *self.resource_defaults.write().await = defaults;
+    }
+
+    pub async fn resource_defaults(&self) -> ResourceDefaults {
+        self.resource_defaults.read().await.clone()
+    }
+
+    /// `statement_timeout` of the sessions [`Self::connect_read_only`] opens.
+    pub async fn set_introspection_timeout(&self, timeout: Option<String>) {
+        *self.introspection_timeout.write().await = timeout;
+    }
+
+    pub async fn connect(
+        &self,
+        diags: &mut Diagnostics,
+        db_url: &str,
+    ) -> Option<Arc<PgConnection>> {
+        self.connect_with(diags, db_url, false).await
+    }
+
+    /// A pool for plans and refreshes, kept apart from the one applies use:
+    /// every transaction on it is `READ ONLY` and statements time out after
+    /// the introspection timeout, so a misbehaving introspection query can
+    /// neither change the database nor hang the plan.
+    pub async fn connect_read_only(
+        &self,
+        diags: &mut Diagnostics,
+        db_url: &str,
+    ) -> Option<Arc<PgConnection>> {
+        self.connect_with(diags, db_url, true).await
+    }
+
+    async fn connect_with(
+        &self,
+        diags: &mut Diagnostics,
+        db_url: &str,
+        read_only: bool,
+    ) -> Option<Arc<PgConnection>> {
+        if self.snapshot.read().await.is_some() {
+            diags.root_error(
+                "No database connection in mock mode",
+                "The provider is configured with mock = true, so only plans against the snapshot are available. Disable mock mode to apply or read from the database.",
+            );
+            return None;
+        }
+        let mut connections = self.connections.lock().await;
+        let database = crate::util::database_identity(db_url);
+        let key = if read_only {
+            format!("read-only {db_url}")
+        } else {
+            db_url.to_string()
+        };
+        if let Some(connection) = connections.get(&key) {
+            tracing::trace!(database, read_only, "reusing cached connection");
+            return Some(connection.clone());
+        }
+
+        tracing::debug!(database, read_only, "connecting to database");
+        let mut url = match *self.keepalives_idle.read().await {
+            Some(seconds) => with_keepalives(db_url, seconds),
+            None => db_url.to_string(),
+        };
+        if read_only {
+            let timeout = self.introspection_timeout.read().await.clone();
+            url = read_only_url(
+                &url,
+                timeout.as_deref().unwrap_or(DEFAULT_INTROSPECTION_TIMEOUT),
+            );
+        }
+        let redact_hosts = *self.redact_hosts.read().await;
+        let connection = Arc::new(crate::util::connect_redacting(diags, &url, redact_hosts).await?);
+        connections.insert(key, connection.clone());
+        Some(connection)
+    }
+}
+
+/// Adds server-side TCP keepalive settings to a connection URL, so long diffs
+/// aren't dropped by NAT gateways or firewalls that expire idle flows. URLs that
+/// already set `options` are left alone.
+pub fn with_keepalives(db_url: &str, idle_seconds: u32) -> String {
+    if db_url.contains("options=") {
+        return db_url.to_string();
+    }
+    let separator = if db_url.contains('?') { '&' } else { '?' };
+    format!(
+        "{db_url}{separator}options=-c%20tcp_keepalives_idle%3D{idle_seconds}%20-c%20tcp_keepalives_interval%3D{}",
+        (idle_seconds / 4).max(1)
+    )
+}
+
+/// Makes every session opened from `db_url` default to `READ ONLY`
+/// transactions with `statement_timeout` set to `timeout`. The settings are
+/// appended to any `options` the URL already has, so they override them.
+pub fn read_only_url(db_url: &str, timeout: &str) -> String {
+    let settings =
+        format!("-c%20default_transaction_read_only%3Don%20-c%20statement_timeout%3D{timeout}");
+    let options = regex::Regex::new(r"([?&]options=)([^&#]*)").unwrap();
+    if options.is_match(db_url) {
+        return options
+            .replace(db_url, |caps: &regex::Captures| {
+                format!("{}{}%20{settings}", &caps[1], &caps[2])
+            })
+            .into_owned();
+    }
+    let separator = if db_url.contains('?') { '&' } else { '?' };
+    format!("{db_url}{separator}options={settings}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_keepalives_appends_options() {
+        assert_eq!(
+            with_keepalives("postgres://localhost/db", 60),
+            "postgres://localhost/db?options=-c%20tcp_keepalives_idle%3D60%20-c%20tcp_keepalives_interval%3D15"
+        );
+    }
+
+    #[test]
+    fn with_keepalives_extends_existing_query() {
+        assert!(
+            with_keepalives("postgres://localhost/db?sslmode=require", 60)
+                .starts_with("postgres://localhost/db?sslmode=require&options=")
+        );
+    }
+
+    #[test]
+    fn read_only_url_sets_session_defaults() {
+        assert_eq!(
+            read_only_url("postgres://localhost/db", "30s"),
+            "postgres://localhost/db?options=-c%20default_transaction_read_only%3Don%20-c%20statement_timeout%3D30s"
+        );
+    }
+
+    #[test]
+    fn read_only_url_overrides_user_options() {
+        let url = read_only_url(
+            "postgres://localhost/db?options=-c%20default_transaction_read_only%3Doff&sslmode=require",
+            "5s",
+        );
+        assert_eq!(
+            url,
+            "postgres://localhost/db?options=-c%20default_transaction_read_only%3Doff%20-c%20default_transaction_read_only%3Don%20-c%20statement_timeout%3D5s&sslmode=require"
+        );
+    }
+
+    #[test]
+    fn with_keepalives_keeps_user_options() {
+        let url = "postgres://localhost/db?options=-c%20search_path%3Dapp";
+        assert_eq!(with_keepalives(url, 60), url);
+    }
+}
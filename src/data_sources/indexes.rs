@@ -0,0 +1,232 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::ValueEmpty,
+    DataSource, Diagnostics,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct IndexInfo {
+    pub schema: String,
+    pub table: String,
+    pub name: String,
+    pub scans: i64,
+    pub tuples_read: i64,
+    pub tuples_fetched: i64,
+    pub size_bytes: i64,
+    pub is_valid: bool,
+    pub is_unique: bool,
+    pub is_primary: bool,
+}
+
+impl IndexInfo {
+    /// Never scanned and not backing a primary key or unique constraint.
+    pub fn is_unused(&self) -> bool {
+        self.scans == 0 && !self.is_unique && !self.is_primary
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct IndexesDataSourceState {
+    pub id: Option<String>,
+    pub database_url: Option<String>,
+    pub schemas: Option<Vec<String>>,
+    pub unused_only: Option<bool>,
+    pub indexes: Option<Vec<IndexInfo>>,
+}
+
+pub struct IndexesDataSource;
+
+#[async_trait]
+impl DataSource for IndexesDataSource {
+    type State<'a> = IndexesDataSourceState;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain("Lists indexes with usage statistics"),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Data source identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "schemas" => Attribute {
+                        description: Description::plain("PostgreSQL schemas to list (default: public)"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "unused_only" => Attribute {
+                        description: Description::plain("Only return never-scanned, non-unique indexes"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "indexes" => Attribute {
+                        description: Description::plain("Indexes with pg_stat_user_indexes counters and validity flags"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::Object(map! {
+                            "schema" => AttributeType::String,
+                            "table" => AttributeType::String,
+                            "name" => AttributeType::String,
+                            "scans" => AttributeType::Number,
+                            "tuples_read" => AttributeType::Number,
+                            "tuples_fetched" => AttributeType::Number,
+                            "size_bytes" => AttributeType::Number,
+                            "is_valid" => AttributeType::Bool,
+                            "is_unique" => AttributeType::Bool,
+                            "is_primary" => AttributeType::Bool
+                        }))),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        config: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::State<'a>> {
+        let Some(db_url) = config.database_url.as_deref() else {
+            diags.root_error_short("database_url is required");
+            return None;
+        };
+
+        let schemas = config
+            .schemas
+            .clone()
+            .unwrap_or_else(|| vec!["public".to_string()]);
+
+        let url = crate::connections::read_only_url(
+            db_url,
+            crate::connections::DEFAULT_INTROSPECTION_TIMEOUT,
+        );
+        let connection = crate::util::connect(diags, &url).await?;
+
+        #[allow(clippy::type_complexity)]
+        let rows: Vec<(String, String, String, i64, i64, i64, i64, bool, bool, bool)> =
+            match sqlx::query_as(
+                "SELECT s.schemaname::text, s.relname::text, s.indexrelname::text, \
+                        s.idx_scan, s.idx_tup_read, s.idx_tup_fetch, \
+                        pg_relation_size(s.indexrelid), \
+                        i.indisvalid, i.indisunique, i.indisprimary \
+                 FROM pg_stat_user_indexes s \
+                 JOIN pg_index i ON i.indexrelid = s.indexrelid \
+                 WHERE s.schemaname = ANY($1) \
+                 ORDER BY s.schemaname, s.relname, s.indexrelname",
+            )
+            .bind(&schemas)
+            .fetch_all(connection.pool())
+            .await
+            {
+                Ok(rows) => rows,
+                Err(e) => {
+                    diags.root_error_short(format!("Failed to list indexes: {e}"));
+                    return None;
+                }
+            };
+
+        let unused_only = config.unused_only.unwrap_or(false);
+        let indexes = rows
+            .into_iter()
+            .map(
+                |(
+                    schema,
+                    table,
+                    name,
+                    scans,
+                    tuples_read,
+                    tuples_fetched,
+                    size_bytes,
+                    is_valid,
+                    is_unique,
+                    is_primary,
+                )| IndexInfo {
+                    schema,
+                    table,
+                    name,
+                    scans,
+                    tuples_read,
+                    tuples_fetched,
+                    size_bytes,
+                    is_valid,
+                    is_unique,
+                    is_primary,
+                },
+            )
+            .filter(|index| !unused_only || index.is_unused())
+            .collect();
+
+        let mut state = config;
+        state.id = Some(format!("pgmold-indexes-{}", schemas.join(",")));
+        state.indexes = Some(indexes);
+
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexes_has_usage_attributes() {
+        let data_source = IndexesDataSource;
+        let mut diags = Diagnostics::default();
+        let schema = data_source.schema(&mut diags).expect("schema should exist");
+
+        for name in ["schemas", "unused_only", "indexes"] {
+            assert!(
+                schema.block.attributes.contains_key(name),
+                "missing: {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn unscanned_plain_index_is_unused() {
+        let index = IndexInfo {
+            name: "users_email_idx".to_string(),
+            ..Default::default()
+        };
+        assert!(index.is_unused());
+    }
+
+    #[test]
+    fn unscanned_unique_index_is_not_unused() {
+        let index = IndexInfo {
+            name: "users_email_key".to_string(),
+            is_unique: true,
+            ..Default::default()
+        };
+        assert!(!index.is_unused());
+    }
+
+    #[test]
+    fn scanned_index_is_not_unused() {
+        let index = IndexInfo {
+            name: "users_email_idx".to_string(),
+            scans: 3,
+            ..Default::default()
+        };
+        assert!(!index.is_unused());
+    }
+}
@@ -0,0 +1,15 @@
+pub mod indexes;
+pub mod pending_operations;
+pub mod provider_info;
+pub mod query;
+pub mod schema_dump;
+pub mod server_info;
+pub mod tables;
+
+pub use indexes::IndexesDataSource;
+pub use pending_operations::PendingOperationsDataSource;
+pub use provider_info::ProviderInfoDataSource;
+pub use query::QueryDataSource;
+pub use schema_dump::SchemaDumpDataSource;
+pub use server_info::ServerInfoDataSource;
+pub use tables::TablesDataSource;
@@ -0,0 +1,213 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::ValueEmpty,
+    DataSource, Diagnostics,
+};
+
+use crate::operations::{classify, OperationClass};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PendingOperationsDataSourceState {
+    pub id: Option<String>,
+    pub schema_file: Option<String>,
+    pub database_url: Option<String>,
+    pub target_schemas: Option<Vec<String>>,
+    pub operation_count: Option<i64>,
+    pub additive_count: Option<i64>,
+    pub destructive_count: Option<i64>,
+    pub rewrite_count: Option<i64>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PendingOperationsDataSource {
+    pub connections: crate::connections::ConnectionCache,
+}
+
+#[async_trait]
+impl DataSource for PendingOperationsDataSource {
+    type State<'a> = PendingOperationsDataSourceState;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Counts pending operations between a schema file and the database",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Data source identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "schema_file" => Attribute {
+                        description: Description::plain("Path to SQL schema file"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "target_schemas" => Attribute {
+                        description: Description::plain("PostgreSQL schemas to introspect (default: public)"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "operation_count" => Attribute {
+                        description: Description::plain("Total number of pending operations"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "additive_count" => Attribute {
+                        description: Description::plain("Number of additive operations"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "destructive_count" => Attribute {
+                        description: Description::plain("Number of destructive operations (drops)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "rewrite_count" => Attribute {
+                        description: Description::plain("Number of operations that rewrite a table"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        config: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::State<'a>> {
+        let Some(db_url) = config.database_url.as_deref() else {
+            diags.root_error_short("database_url is required");
+            return None;
+        };
+        let Some(schema_file) = config.schema_file.as_deref() else {
+            diags.root_error_short("schema_file is required");
+            return None;
+        };
+
+        let schema_path = std::path::Path::new(schema_file);
+        if !schema_path.exists() {
+            diags.root_error_short(format!("schema_file not found: {schema_file}"));
+            return None;
+        }
+
+        let schema_hash = match crate::util::compute_schema_hash(schema_path) {
+            Ok(h) => h,
+            Err(e) => {
+                diags.root_error_short(format!("Failed to read schema file: {e}"));
+                return None;
+            }
+        };
+
+        let target = match crate::pipeline::parse_schema_file(schema_file) {
+            Ok(s) => s,
+            Err(e) => {
+                diags.root_error_short(format!("Failed to parse schema file: {e}"));
+                return None;
+            }
+        };
+
+        let target_schemas = config
+            .target_schemas
+            .clone()
+            .unwrap_or_else(|| vec!["public".to_string()]);
+
+        let current = match self.connections.snapshot().await {
+            Some(snapshot) => crate::pipeline::snapshot_subset(&snapshot, &target_schemas),
+            None => {
+                let connection = self.connections.connect_read_only(diags, db_url).await?;
+                match pgmold::pg::introspect::introspect_schema(&connection, &target_schemas, false)
+                    .await
+                {
+                    Ok(s) => s,
+                    Err(e) => {
+                        diags.root_error_short(format!("Failed to introspect database: {e}"));
+                        return None;
+                    }
+                }
+            }
+        };
+
+        let operations = pgmold::diff::compute_diff(&current, &target);
+        let count = |class: OperationClass| {
+            operations.iter().filter(|op| classify(op) == class).count() as i64
+        };
+
+        let mut state = config;
+        state.id = Some(format!("pgmold-pending-{}", &schema_hash[..8]));
+        state.operation_count = Some(operations.len() as i64);
+        state.additive_count = Some(count(OperationClass::Additive));
+        state.destructive_count = Some(count(OperationClass::Destructive));
+        state.rewrite_count = Some(count(OperationClass::Rewrite));
+
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_operations_has_count_attributes() {
+        let data_source = PendingOperationsDataSource::default();
+        let mut diags = Diagnostics::default();
+        let schema = data_source.schema(&mut diags).expect("schema should exist");
+
+        for name in [
+            "operation_count",
+            "additive_count",
+            "destructive_count",
+            "rewrite_count",
+        ] {
+            assert!(
+                schema.block.attributes.contains_key(name),
+                "missing: {name}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn read_fails_with_nonexistent_schema_file() {
+        let data_source = PendingOperationsDataSource::default();
+        let mut diags = Diagnostics::default();
+
+        let config = PendingOperationsDataSourceState {
+            schema_file: Some("/nonexistent/schema.sql".to_string()),
+            database_url: Some("postgres://test".to_string()),
+            ..Default::default()
+        };
+
+        let result = data_source
+            .read(&mut diags, config, ValueEmpty::default())
+            .await;
+
+        assert!(result.is_none());
+        assert!(!diags.errors.is_empty());
+    }
+}
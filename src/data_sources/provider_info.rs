@@ -0,0 +1,184 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::ValueEmpty,
+    AttributePath, DataSource, Diagnostics,
+};
+
+/// Version of this provider.
+pub const PROVIDER_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Version of the pgmold library the provider was built with, or `unknown`.
+pub const PGMOLD_VERSION: &str = env!("PGMOLD_VERSION");
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProviderInfoDataSourceState {
+    pub id: Option<String>,
+    pub min_pgmold_version: Option<String>,
+    pub provider_version: Option<String>,
+    pub pgmold_version: Option<String>,
+    pub pgmold_version_num: Option<i64>,
+}
+
+pub struct ProviderInfoDataSource;
+
+#[async_trait]
+impl DataSource for ProviderInfoDataSource {
+    type State<'a> = ProviderInfoDataSourceState;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Reports the provider version and the pgmold engine it embeds",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Provider and pgmold versions"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "min_pgmold_version" => Attribute {
+                        description: Description::plain("Fail unless the embedded pgmold is at least this version (e.g. 0.14.2)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "provider_version" => Attribute {
+                        description: Description::plain("Version of this provider"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "pgmold_version" => Attribute {
+                        description: Description::plain("Version of the embedded pgmold library"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "pgmold_version_num" => Attribute {
+                        description: Description::plain("Numeric pgmold version, major * 10000 + minor * 100 + patch (e.g. 1402 for 0.14.2)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        config: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::State<'a>> {
+        let pgmold_version_num = version_num(PGMOLD_VERSION);
+        if let Some(min) = config.min_pgmold_version.as_deref() {
+            let Some(min_num) = version_num(min) else {
+                diags.error_short(
+                    format!("Invalid min_pgmold_version \"{min}\", expected major.minor.patch"),
+                    AttributePath::new("min_pgmold_version"),
+                );
+                return None;
+            };
+            if pgmold_version_num.is_none() {
+                diags.warning_short(
+                    format!("Cannot check min_pgmold_version: pgmold version is {PGMOLD_VERSION}"),
+                    AttributePath::new("min_pgmold_version"),
+                );
+            }
+            if pgmold_version_num.is_some_and(|num| num < min_num) {
+                diags.error(
+                    "pgmold version too old",
+                    format!(
+                        "The provider embeds pgmold {PGMOLD_VERSION}, but {min} or newer is required. Upgrade the provider."
+                    ),
+                    AttributePath::new("min_pgmold_version"),
+                );
+                return None;
+            }
+        }
+
+        let mut state = config;
+        state.id = Some(format!("{PROVIDER_VERSION}/{PGMOLD_VERSION}"));
+        state.provider_version = Some(PROVIDER_VERSION.to_string());
+        state.pgmold_version = Some(PGMOLD_VERSION.to_string());
+        state.pgmold_version_num = pgmold_version_num;
+        Some(state)
+    }
+}
+
+/// `major * 10000 + minor * 100 + patch`, ignoring pre-release and build
+/// suffixes. `None` unless the version has three numeric parts.
+fn version_num(version: &str) -> Option<i64> {
+    let core = version.split(['-', '+']).next()?;
+    let parts: Vec<i64> = core
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [major, minor, patch] = parts[..] else {
+        return None;
+    };
+    Some(major * 10000 + minor * 100 + patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_num_orders_versions() {
+        assert_eq!(version_num("0.14.2"), Some(1402));
+        assert_eq!(version_num("1.0.0-beta.1"), Some(10000));
+        assert!(version_num("0.9.12") < version_num("0.14.0"));
+        assert_eq!(version_num("0.14"), None);
+        assert_eq!(version_num("unknown"), None);
+    }
+
+    #[tokio::test]
+    async fn read_reports_versions() {
+        let mut diags = Diagnostics::default();
+        let state = ProviderInfoDataSource
+            .read(
+                &mut diags,
+                ProviderInfoDataSourceState::default(),
+                ValueEmpty::default(),
+            )
+            .await
+            .expect("read should succeed");
+
+        assert_eq!(
+            state.provider_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert!(state.pgmold_version.is_some());
+    }
+
+    #[tokio::test]
+    async fn read_rejects_invalid_min_version() {
+        let mut diags = Diagnostics::default();
+        let result = ProviderInfoDataSource
+            .read(
+                &mut diags,
+                ProviderInfoDataSourceState {
+                    min_pgmold_version: Some("latest".to_string()),
+                    ..Default::default()
+                },
+                ValueEmpty::default(),
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("min_pgmold_version")
+        );
+    }
+}
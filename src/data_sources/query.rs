@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::ValueEmpty,
+    DataSource, Diagnostics,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct QueryDataSourceState {
+    pub id: Option<String>,
+    pub database_url: Option<String>,
+    pub query: Option<String>,
+    pub rows: Option<Vec<BTreeMap<String, Option<String>>>>,
+}
+
+pub struct QueryDataSource;
+
+#[async_trait]
+impl DataSource for QueryDataSource {
+    type State<'a> = QueryDataSourceState;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain("Runs a read-only SQL query and returns its rows"),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Data source identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "query" => Attribute {
+                        description: Description::plain("SQL query, executed in a read-only transaction"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "rows" => Attribute {
+                        description: Description::plain("Result rows as maps of column name to text value"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::Map(Box::new(AttributeType::String)))),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        config: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::State<'a>> {
+        let Some(db_url) = config.database_url.as_deref() else {
+            diags.root_error_short("database_url is required");
+            return None;
+        };
+        let Some(query) = config.query.as_deref() else {
+            diags.root_error_short("query is required");
+            return None;
+        };
+
+        let connection = crate::util::connect(diags, db_url).await?;
+
+        let mut tx = match connection.pool().begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                diags.root_error_short(format!("Failed to start transaction: {e}"));
+                return None;
+            }
+        };
+
+        if let Err(e) = sqlx::query("SET TRANSACTION READ ONLY")
+            .execute(&mut *tx)
+            .await
+        {
+            diags.root_error_short(format!("Failed to set transaction read-only: {e}"));
+            return None;
+        }
+
+        let json_rows: Vec<(String,)> =
+            match sqlx::query_as(&wrap_query(query)).fetch_all(&mut *tx).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    diags.root_error_short(format!("Query failed: {e}"));
+                    return None;
+                }
+            };
+
+        // Nothing is written, but roll back explicitly rather than relying on drop
+        let _ = tx.rollback().await;
+
+        let mut rows = Vec::with_capacity(json_rows.len());
+        for (json,) in &json_rows {
+            match row_from_json(json) {
+                Ok(row) => rows.push(row),
+                Err(e) => {
+                    diags.root_error_short(format!("Failed to decode query row: {e}"));
+                    return None;
+                }
+            }
+        }
+
+        let mut state = config;
+        state.id = Some(format!(
+            "pgmold-query-{}",
+            &crate::util::compute_content_hash(query)[..8]
+        ));
+        state.rows = Some(rows);
+
+        Some(state)
+    }
+}
+
+/// Wraps the user query so each row comes back as a single JSON object.
+fn wrap_query(query: &str) -> String {
+    let query = query.trim().trim_end_matches(';').trim_end();
+    format!("SELECT row_to_json(q)::text FROM ({query}) AS q")
+}
+
+fn row_from_json(json: &str) -> anyhow::Result<BTreeMap<String, Option<String>>> {
+    let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)?;
+    Ok(object
+        .into_iter()
+        .map(|(column, value)| {
+            let text = match value {
+                serde_json::Value::Null => None,
+                serde_json::Value::String(s) => Some(s),
+                other => Some(other.to_string()),
+            };
+            (column, text)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_has_rows_attribute() {
+        let data_source = QueryDataSource;
+        let mut diags = Diagnostics::default();
+        let schema = data_source.schema(&mut diags).expect("schema should exist");
+
+        for name in ["query", "rows"] {
+            assert!(
+                schema.block.attributes.contains_key(name),
+                "missing: {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn wrap_query_strips_trailing_semicolon() {
+        assert_eq!(
+            wrap_query("SELECT id FROM tenants;\n"),
+            "SELECT row_to_json(q)::text FROM (SELECT id FROM tenants) AS q"
+        );
+    }
+
+    #[test]
+    fn row_from_json_converts_values_to_text() {
+        let row =
+            row_from_json(r#"{"id": 1, "name": "acme", "active": true, "note": null}"#).unwrap();
+
+        assert_eq!(row["id"].as_deref(), Some("1"));
+        assert_eq!(row["name"].as_deref(), Some("acme"));
+        assert_eq!(row["active"].as_deref(), Some("true"));
+        assert_eq!(row["note"], None);
+    }
+}
@@ -0,0 +1,153 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::ValueEmpty,
+    DataSource, Diagnostics,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SchemaDumpDataSourceState {
+    pub id: Option<String>,
+    pub database_url: Option<String>,
+    pub target_schemas: Option<Vec<String>>,
+    pub sql: Option<String>,
+    pub schema_hash: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SchemaDumpDataSource {
+    pub connections: crate::connections::ConnectionCache,
+}
+
+#[async_trait]
+impl DataSource for SchemaDumpDataSource {
+    type State<'a> = SchemaDumpDataSourceState;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain("Dumps the live database schema as canonical SQL"),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Data source identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "target_schemas" => Attribute {
+                        description: Description::plain("PostgreSQL schemas to dump (default: public)"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "sql" => Attribute {
+                        description: Description::plain("Canonical SQL generated by pgmold"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "schema_hash" => Attribute {
+                        description: Description::plain("SHA256 hash of the generated SQL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        config: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::State<'a>> {
+        let Some(db_url) = config.database_url.as_deref() else {
+            diags.root_error_short("database_url is required");
+            return None;
+        };
+
+        let target_schemas = config
+            .target_schemas
+            .clone()
+            .unwrap_or_else(|| vec!["public".to_string()]);
+
+        let current = match self.connections.snapshot().await {
+            Some(snapshot) => crate::pipeline::snapshot_subset(&snapshot, &target_schemas),
+            None => {
+                let connection = self.connections.connect_read_only(diags, db_url).await?;
+                match pgmold::pg::introspect::introspect_schema(&connection, &target_schemas, false)
+                    .await
+                {
+                    Ok(s) => s,
+                    Err(e) => {
+                        diags.root_error_short(format!("Failed to introspect database: {e}"));
+                        return None;
+                    }
+                }
+            }
+        };
+
+        let sql = crate::pipeline::canonical_sql(&current);
+        let schema_hash = crate::util::compute_content_hash(&sql);
+
+        let mut state = config;
+        state.id = Some(format!("pgmold-dump-{}", &schema_hash[..8]));
+        state.sql = Some(sql);
+        state.schema_hash = Some(schema_hash);
+
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_dump_has_computed_sql() {
+        let data_source = SchemaDumpDataSource::default();
+        let mut diags = Diagnostics::default();
+        let schema = data_source.schema(&mut diags).expect("schema should exist");
+
+        for name in ["sql", "schema_hash"] {
+            let attr = schema
+                .block
+                .attributes
+                .get(name)
+                .unwrap_or_else(|| panic!("missing: {name}"));
+            assert_eq!(attr.constraint, AttributeConstraint::Computed);
+        }
+    }
+
+    #[tokio::test]
+    async fn read_fails_without_database_url() {
+        let data_source = SchemaDumpDataSource::default();
+        let mut diags = Diagnostics::default();
+
+        let result = data_source
+            .read(
+                &mut diags,
+                SchemaDumpDataSourceState::default(),
+                ValueEmpty::default(),
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert!(!diags.errors.is_empty());
+    }
+}
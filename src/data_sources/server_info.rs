@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::ValueEmpty,
+    DataSource, Diagnostics,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ServerInfoDataSourceState {
+    pub id: Option<String>,
+    pub database_url: Option<String>,
+    pub server_version: Option<String>,
+    pub server_version_num: Option<i64>,
+    pub extensions: Option<BTreeMap<String, String>>,
+    pub max_connections: Option<i64>,
+    pub is_replica: Option<bool>,
+}
+
+pub struct ServerInfoDataSource;
+
+#[async_trait]
+impl DataSource for ServerInfoDataSource {
+    type State<'a> = ServerInfoDataSourceState;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Reads version and capabilities of a PostgreSQL server",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Name of the connected database"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "server_version" => Attribute {
+                        description: Description::plain("Server version string (e.g. 16.2)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "server_version_num" => Attribute {
+                        description: Description::plain("Numeric server version (e.g. 160002)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "extensions" => Attribute {
+                        description: Description::plain("Installed extensions mapped to their versions"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "max_connections" => Attribute {
+                        description: Description::plain("Value of the max_connections setting"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "is_replica" => Attribute {
+                        description: Description::plain("Whether the server is in recovery (read replica)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        config: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::State<'a>> {
+        let Some(db_url) = config.database_url.as_deref() else {
+            diags.root_error_short("database_url is required");
+            return None;
+        };
+
+        let url = crate::connections::read_only_url(
+            db_url,
+            crate::connections::DEFAULT_INTROSPECTION_TIMEOUT,
+        );
+        let connection = crate::util::connect(diags, &url).await?;
+
+        let (database, server_version, server_version_num, max_connections, is_replica): (
+            String,
+            String,
+            i64,
+            i64,
+            bool,
+        ) = match sqlx::query_as(
+            "SELECT current_database(), \
+                    current_setting('server_version'), \
+                    current_setting('server_version_num')::bigint, \
+                    current_setting('max_connections')::bigint, \
+                    pg_is_in_recovery()",
+        )
+        .fetch_one(connection.pool())
+        .await
+        {
+            Ok(row) => row,
+            Err(e) => {
+                diags.root_error_short(format!("Failed to read server settings: {e}"));
+                return None;
+            }
+        };
+
+        let extensions: Vec<(String, String)> =
+            match sqlx::query_as("SELECT extname, extversion FROM pg_extension ORDER BY extname")
+                .fetch_all(connection.pool())
+                .await
+            {
+                Ok(rows) => rows,
+                Err(e) => {
+                    diags.root_error_short(format!("Failed to list extensions: {e}"));
+                    return None;
+                }
+            };
+
+        let mut state = config;
+        state.id = Some(database);
+        state.server_version = Some(server_version);
+        state.server_version_num = Some(server_version_num);
+        state.extensions = Some(extensions.into_iter().collect());
+        state.max_connections = Some(max_connections);
+        state.is_replica = Some(is_replica);
+
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_info_has_computed_attributes() {
+        let data_source = ServerInfoDataSource;
+        let mut diags = Diagnostics::default();
+        let schema = data_source.schema(&mut diags).expect("schema should exist");
+
+        for name in [
+            "server_version",
+            "server_version_num",
+            "extensions",
+            "max_connections",
+            "is_replica",
+        ] {
+            let attr = schema
+                .block
+                .attributes
+                .get(name)
+                .unwrap_or_else(|| panic!("missing: {name}"));
+            assert_eq!(attr.constraint, AttributeConstraint::Computed);
+        }
+    }
+
+    #[test]
+    fn server_info_database_url_is_sensitive() {
+        let data_source = ServerInfoDataSource;
+        let mut diags = Diagnostics::default();
+        let schema = data_source.schema(&mut diags).expect("schema should exist");
+
+        let attr = schema
+            .block
+            .attributes
+            .get("database_url")
+            .expect("database_url attribute should exist");
+
+        assert!(attr.sensitive, "database_url should be sensitive");
+    }
+
+    #[tokio::test]
+    async fn read_fails_without_database_url() {
+        let data_source = ServerInfoDataSource;
+        let mut diags = Diagnostics::default();
+
+        let result = data_source
+            .read(
+                &mut diags,
+                ServerInfoDataSourceState::default(),
+                ValueEmpty::default(),
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert!(!diags.errors.is_empty());
+    }
+}
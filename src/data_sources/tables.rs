@@ -0,0 +1,246 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::ValueEmpty,
+    DataSource, Diagnostics,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct TableInfo {
+    pub schema: String,
+    pub name: String,
+    pub row_estimate: i64,
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TablesDataSourceState {
+    pub id: Option<String>,
+    pub database_url: Option<String>,
+    pub schemas: Option<Vec<String>>,
+    pub name_regex: Option<String>,
+    pub exclude_regex: Option<String>,
+    pub tables: Option<Vec<TableInfo>>,
+}
+
+pub struct TablesDataSource;
+
+#[async_trait]
+impl DataSource for TablesDataSource {
+    type State<'a> = TablesDataSourceState;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain("Lists tables in the given schemas"),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Data source identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "schemas" => Attribute {
+                        description: Description::plain("PostgreSQL schemas to list (default: public)"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "name_regex" => Attribute {
+                        description: Description::plain("Only include tables whose name matches this regex"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "exclude_regex" => Attribute {
+                        description: Description::plain("Exclude tables whose name matches this regex"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "tables" => Attribute {
+                        description: Description::plain("Matching tables with row estimates and sizes"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::Object(map! {
+                            "schema" => AttributeType::String,
+                            "name" => AttributeType::String,
+                            "row_estimate" => AttributeType::Number,
+                            "size_bytes" => AttributeType::Number
+                        }))),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        config: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::State<'a>> {
+        let Some(db_url) = config.database_url.as_deref() else {
+            diags.root_error_short("database_url is required");
+            return None;
+        };
+
+        let name_regex = compile_regex(diags, "name_regex", config.name_regex.as_deref())?;
+        let exclude_regex = compile_regex(diags, "exclude_regex", config.exclude_regex.as_deref())?;
+
+        let schemas = config
+            .schemas
+            .clone()
+            .unwrap_or_else(|| vec!["public".to_string()]);
+
+        let url = crate::connections::read_only_url(
+            db_url,
+            crate::connections::DEFAULT_INTROSPECTION_TIMEOUT,
+        );
+        let connection = crate::util::connect(diags, &url).await?;
+
+        let rows: Vec<(String, String, i64, i64)> = match sqlx::query_as(
+            "SELECT n.nspname, c.relname, \
+                    GREATEST(c.reltuples, 0)::bigint, \
+                    pg_total_relation_size(c.oid) \
+             FROM pg_class c \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE c.relkind IN ('r', 'p') AND n.nspname = ANY($1) \
+             ORDER BY n.nspname, c.relname",
+        )
+        .bind(&schemas)
+        .fetch_all(connection.pool())
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                diags.root_error_short(format!("Failed to list tables: {e}"));
+                return None;
+            }
+        };
+
+        let tables = filter_tables(
+            rows.into_iter()
+                .map(|(schema, name, row_estimate, size_bytes)| TableInfo {
+                    schema,
+                    name,
+                    row_estimate,
+                    size_bytes,
+                })
+                .collect(),
+            name_regex.as_ref(),
+            exclude_regex.as_ref(),
+        );
+
+        let mut state = config;
+        state.id = Some(format!("pgmold-tables-{}", schemas.join(",")));
+        state.tables = Some(tables);
+
+        Some(state)
+    }
+}
+
+fn compile_regex(
+    diags: &mut Diagnostics,
+    attribute: &str,
+    pattern: Option<&str>,
+) -> Option<Option<regex::Regex>> {
+    match pattern.map(regex::Regex::new).transpose() {
+        Ok(re) => Some(re),
+        Err(e) => {
+            diags.root_error_short(format!("Invalid {attribute}: {e}"));
+            None
+        }
+    }
+}
+
+fn filter_tables(
+    tables: Vec<TableInfo>,
+    name_regex: Option<&regex::Regex>,
+    exclude_regex: Option<&regex::Regex>,
+) -> Vec<TableInfo> {
+    tables
+        .into_iter()
+        .filter(|t| name_regex.is_none_or(|re| re.is_match(&t.name)))
+        .filter(|t| !exclude_regex.is_some_and(|re| re.is_match(&t.name)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str) -> TableInfo {
+        TableInfo {
+            schema: "public".to_string(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tables_has_filter_attributes() {
+        let data_source = TablesDataSource;
+        let mut diags = Diagnostics::default();
+        let schema = data_source.schema(&mut diags).expect("schema should exist");
+
+        for name in ["schemas", "name_regex", "exclude_regex", "tables"] {
+            assert!(
+                schema.block.attributes.contains_key(name),
+                "missing: {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn filter_tables_applies_include_and_exclude() {
+        let include = regex::Regex::new("^user").unwrap();
+        let exclude = regex::Regex::new("_archive$").unwrap();
+
+        let result = filter_tables(
+            vec![table("users"), table("user_archive"), table("posts")],
+            Some(&include),
+            Some(&exclude),
+        );
+
+        assert_eq!(result, vec![table("users")]);
+    }
+
+    #[test]
+    fn filter_tables_without_filters_keeps_all() {
+        let result = filter_tables(vec![table("users"), table("posts")], None, None);
+        assert_eq!(result.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn read_fails_with_invalid_regex() {
+        let data_source = TablesDataSource;
+        let mut diags = Diagnostics::default();
+
+        let config = TablesDataSourceState {
+            database_url: Some("postgres://test".to_string()),
+            name_regex: Some("(".to_string()),
+            ..Default::default()
+        };
+
+        let result = data_source
+            .read(&mut diags, config, ValueEmpty::default())
+            .await;
+
+        assert!(result.is_none());
+        assert!(!diags.errors.is_empty());
+    }
+}
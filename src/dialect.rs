@@ -0,0 +1,95 @@
+use tf_provider::{AttributePath, Diagnostics};
+
+/// PostgreSQL-compatible engines the provider can target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    #[default]
+    Postgres,
+    CockroachDb,
+    Aurora,
+}
+
+impl Dialect {
+    pub const NAMES: [&'static str; 3] = ["postgres", "cockroachdb", "aurora"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "postgres" => Some(Dialect::Postgres),
+            "cockroachdb" => Some(Dialect::CockroachDb),
+            "aurora" => Some(Dialect::Aurora),
+            _ => None,
+        }
+    }
+
+    /// Whether DDL can be grouped into multi-statement transactions. CockroachDB
+    /// runs schema changes asynchronously and rejects many of them mid-transaction.
+    pub fn transactional_ddl(self) -> bool {
+        !matches!(self, Dialect::CockroachDb)
+    }
+
+    /// Whether the catalog functions used for table fingerprints are available.
+    pub fn supports_fingerprints(self) -> bool {
+        !matches!(self, Dialect::CockroachDb)
+    }
+
+    /// Rewrites a generated statement for the engine. CockroachDB always builds
+    /// indexes online, so `CONCURRENTLY` is dropped.
+    pub fn adapt(self, statement: &str) -> String {
+        match self {
+            Dialect::CockroachDb => {
+                let re = regex::Regex::new(r"(?i)\s+CONCURRENTLY\b").unwrap();
+                re.replace_all(statement, "").into_owned()
+            }
+            Dialect::Postgres | Dialect::Aurora => statement.to_string(),
+        }
+    }
+}
+
+/// Reports an unknown `dialect` value on the attribute.
+pub fn check_dialect(diags: &mut Diagnostics, dialect: Option<&str>) -> Option<()> {
+    match dialect {
+        Some(name) if Dialect::parse(name).is_none() => {
+            diags.error_short(
+                format!(
+                    "Unknown dialect \"{name}\", expected one of: {}",
+                    Dialect::NAMES.join(", ")
+                ),
+                AttributePath::new("dialect"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_known_dialects() {
+        for name in Dialect::NAMES {
+            assert!(Dialect::parse(name).is_some(), "{name}");
+        }
+        assert_eq!(Dialect::parse("mysql"), None);
+    }
+
+    #[test]
+    fn cockroachdb_drops_concurrently() {
+        assert_eq!(
+            Dialect::CockroachDb.adapt("CREATE INDEX CONCURRENTLY idx ON users (email)"),
+            "CREATE INDEX idx ON users (email)"
+        );
+        assert_eq!(
+            Dialect::Postgres.adapt("CREATE INDEX CONCURRENTLY idx ON users (email)"),
+            "CREATE INDEX CONCURRENTLY idx ON users (email)"
+        );
+    }
+
+    #[test]
+    fn only_cockroachdb_runs_ddl_outside_transactions() {
+        assert!(Dialect::Postgres.transactional_ddl());
+        assert!(Dialect::Aurora.transactional_ddl());
+        assert!(!Dialect::CockroachDb.transactional_ddl());
+    }
+}
@@ -0,0 +1,162 @@
+//! Schema files encrypted with age or SOPS, for organizations that treat the
+//! structure of their schema as sensitive. They are decrypted in memory
+//! whenever they are read; the plaintext is never written to disk.
+
+use std::io::Read;
+use std::path::Path;
+use std::sync::RwLock;
+
+use regex::bytes::Regex;
+
+/// Environment variable holding the age identities (`AGE-SECRET-KEY-1...`
+/// lines) that decrypt schema files, when the provider doesn't set
+/// `schema_decryption_key`.
+pub const KEY_VAR: &str = "PGMOLD_AGE_KEY";
+
+/// The provider's `schema_decryption_key`. Schema files are read from code
+/// without access to provider state, so it is kept for the whole process.
+static PROVIDER_KEY: RwLock<Option<String>> = RwLock::new(None);
+
+pub fn set_key(key: Option<String>) {
+    *PROVIDER_KEY.write().unwrap() = key;
+}
+
+fn key() -> Option<String> {
+    PROVIDER_KEY
+        .read()
+        .unwrap()
+        .clone()
+        .or_else(|| std::env::var(KEY_VAR).ok())
+        .filter(|key| !key.trim().is_empty())
+}
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    /// Binary or ASCII-armored age.
+    Age,
+    /// A file SOPS encrypted as binary: JSON with the ciphertext in `data`.
+    Sops,
+}
+
+fn format(start: &[u8]) -> Option<Format> {
+    let sops = Regex::new(r#"^\s*\{\s*"data"\s*:\s*"ENC\[AES256_GCM,"#).unwrap();
+    if start.starts_with(b"age-encryption.org/v1")
+        || start
+            .trim_ascii_start()
+            .starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+    {
+        Some(Format::Age)
+    } else if sops.is_match(start) {
+        Some(Format::Sops)
+    } else {
+        None
+    }
+}
+
+/// Whether a file starting with `start` needs [`decrypt`].
+pub fn is_encrypted(start: &[u8]) -> bool {
+    format(start).is_some()
+}
+
+/// The plaintext of the schema file at `path`, whose content is `bytes`.
+/// Files that aren't encrypted are returned as they are.
+pub fn decrypt(path: &Path, bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    match format(&bytes) {
+        None => Ok(bytes),
+        Some(Format::Age) => decrypt_age(path, &bytes),
+        Some(Format::Sops) => decrypt_sops(path),
+    }
+}
+
+fn decrypt_age(path: &Path, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let error = |e: &dyn std::fmt::Display| {
+        std::io::Error::other(format!("Failed to decrypt {}: {e}", path.display()))
+    };
+    let key = key().ok_or_else(|| {
+        error(&format!(
+            "the file is encrypted with age; set the provider's schema_decryption_key or {KEY_VAR}"
+        ))
+    })?;
+    let identities = age::IdentityFile::from_buffer(key.as_bytes())
+        .and_then(|file| file.into_identities().map_err(std::io::Error::other))
+        .map_err(|e| error(&format!("invalid age identity: {e}")))?;
+    let decryptor =
+        age::Decryptor::new(age::armor::ArmoredReader::new(bytes)).map_err(|e| error(&e))?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i.as_ref() as &dyn age::Identity))
+        .map_err(|e| error(&e))?;
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Decrypts through the `sops` binary, which supports every key service SOPS
+/// does (age, PGP, cloud KMS). An age key configured for pgmold is passed on.
+fn decrypt_sops(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut command = std::process::Command::new("sops");
+    command
+        .args([
+            "--decrypt",
+            "--input-type",
+            "binary",
+            "--output-type",
+            "binary",
+        ])
+        .arg(path);
+    if let Some(key) = key() {
+        command.env("SOPS_AGE_KEY", key);
+    }
+    let output = command.output().map_err(|e| {
+        std::io::Error::other(format!(
+            "Failed to run sops to decrypt {}: {e}",
+            path.display()
+        ))
+    })?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "sops failed to decrypt {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn decrypt_reads_age_files_with_the_provider_key() {
+        let identity = age::x25519::Identity::generate();
+        let sql = b"CREATE TABLE users (id INT PRIMARY KEY);\n";
+        let encryptor = age::Encryptor::with_recipients(std::iter::once(
+            &identity.to_public() as &dyn age::Recipient
+        ))
+        .unwrap();
+        let mut armored =
+            age::armor::ArmoredWriter::wrap_output(Vec::new(), age::armor::Format::AsciiArmor)
+                .unwrap();
+        let mut writer = encryptor.wrap_output(&mut armored).unwrap();
+        writer.write_all(sql).unwrap();
+        writer.finish().unwrap();
+        let ciphertext = armored.finish().unwrap();
+
+        let path = Path::new("schema.sql.age");
+        assert!(is_encrypted(&ciphertext));
+        set_key(None);
+        assert!(decrypt(path, ciphertext.clone()).is_err());
+
+        use age::secrecy::ExposeSecret;
+        set_key(Some(identity.to_string().expose_secret().to_string()));
+        assert_eq!(decrypt(path, ciphertext).unwrap(), sql);
+        set_key(None);
+
+        assert!(!is_encrypted(sql));
+        assert_eq!(
+            format(b"{\n\t\"data\": \"ENC[AES256_GCM,data:abc=,iv:...]\""),
+            Some(Format::Sops)
+        );
+    }
+}
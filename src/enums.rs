@@ -0,0 +1,219 @@
+//! Enum changes the diff would get wrong. New values are added in place with
+//! `ADD VALUE IF NOT EXISTS`, at their declared position. Removing or
+//! reordering values means replacing the type, which rewrites every column
+//! using it, so it only happens when `rebuild_enums` asks for it.
+
+use pgmold::model::Schema;
+
+use crate::functions::quote::{quote_ident, quote_literal};
+
+/// Statements for the enums whose values changed between `current` and
+/// `target`. The planned enums are made to match `target` in `current`, so
+/// the diff leaves them alone.
+#[derive(Debug, Default, PartialEq)]
+pub struct EnumChanges {
+    /// `ALTER TYPE ... ADD VALUE` statements. They must commit before
+    /// anything uses the new values, so they run outside transactions.
+    pub additions: Vec<String>,
+    /// One `DO` block per enum that has to be replaced.
+    pub rebuilds: Vec<String>,
+}
+
+/// Plans the value changes of enums in both schemas. Fails naming the enums
+/// that lose or reorder values unless `rebuild` is set.
+pub fn plan(current: &mut Schema, target: &Schema, rebuild: bool) -> Result<EnumChanges, String> {
+    let mut changes = EnumChanges::default();
+    let mut blocked = Vec::new();
+    for (key, wanted) in &target.enums {
+        let Some(existing) = current.enums.get_mut(key) else {
+            continue;
+        };
+        if existing.values == wanted.values {
+            continue;
+        }
+        let name = format!(
+            "{}.{}",
+            quote_ident(&wanted.schema),
+            quote_ident(&wanted.name)
+        );
+        if is_subsequence(&existing.values, &wanted.values) {
+            changes
+                .additions
+                .extend(additions(&name, &existing.values, &wanted.values));
+        } else if rebuild {
+            changes.rebuilds.push(rebuild_statement(
+                &wanted.schema,
+                &wanted.name,
+                &wanted.values,
+            ));
+        } else {
+            let removed: Vec<&str> = existing
+                .values
+                .iter()
+                .filter(|v| !wanted.values.contains(v))
+                .map(String::as_str)
+                .collect();
+            blocked.push(if removed.is_empty() {
+                format!("{}.{} reorders its values", wanted.schema, wanted.name)
+            } else {
+                format!(
+                    "{}.{} drops {}",
+                    wanted.schema,
+                    wanted.name,
+                    removed.join(", ")
+                )
+            });
+            continue;
+        }
+        existing.values = wanted.values.clone();
+    }
+    if !blocked.is_empty() {
+        return Err(blocked.join("; "));
+    }
+    Ok(changes)
+}
+
+/// Whether `values` appear in `within` in the same order, so `within` only adds values.
+fn is_subsequence(values: &[String], within: &[String]) -> bool {
+    let mut within = within.iter();
+    values.iter().all(|v| within.any(|w| w == v))
+}
+
+/// `ADD VALUE` statements turning `existing` into `wanted`, each placed after
+/// its predecessor in `wanted` (or before the first value, if it leads).
+fn additions(name: &str, existing: &[String], wanted: &[String]) -> Vec<String> {
+    let mut statements = Vec::new();
+    for (i, value) in wanted.iter().enumerate() {
+        if existing.contains(value) {
+            continue;
+        }
+        let position = match (i.checked_sub(1), existing.first()) {
+            (Some(previous), _) => format!(" AFTER {}", quote_literal(&wanted[previous])),
+            (None, Some(first)) => format!(" BEFORE {}", quote_literal(first)),
+            (None, None) => String::new(),
+        };
+        statements.push(format!(
+            "ALTER TYPE {name} ADD VALUE IF NOT EXISTS {}{position}",
+            quote_literal(value)
+        ));
+    }
+    statements
+}
+
+/// A `DO` block replacing the enum with one holding `values`: it creates the
+/// new type, converts each table column through its text value, restores
+/// column defaults, drops the old type and takes over its name. It runs as a
+/// single statement, so a row holding a removed value, or a view, function or
+/// array column still using the old type, fails it without changing anything.
+fn rebuild_statement(schema: &str, name: &str, values: &[String]) -> String {
+    let old = format!("{}.{}", quote_ident(schema), quote_ident(name));
+    let new = format!(
+        "{}.{}",
+        quote_ident(schema),
+        quote_ident(&format!("{name}__pgmold_new"))
+    );
+    let values: Vec<String> = values.iter().map(|v| quote_literal(v)).collect();
+    format!(
+        r#"DO $pgmold$
+DECLARE
+    new_type text := {new_literal};
+    col record;
+    value text;
+BEGIN
+    CREATE TYPE {new} AS ENUM ({values});
+    FOR col IN
+        SELECT a.attrelid::regclass AS tbl, a.attname, pg_get_expr(d.adbin, d.adrelid) AS def
+        FROM pg_attribute a
+        JOIN pg_class c ON c.oid = a.attrelid
+        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE a.atttypid = {old_literal}::regtype
+          AND NOT a.attisdropped
+          AND a.attinhcount = 0
+          AND c.relkind IN ('r', 'p')
+    LOOP
+        IF col.def IS NOT NULL THEN
+            EXECUTE format('SELECT (%s)::text', col.def) INTO value;
+            EXECUTE format('ALTER TABLE %s ALTER COLUMN %I DROP DEFAULT', col.tbl, col.attname);
+        END IF;
+        EXECUTE format('ALTER TABLE %1$s ALTER COLUMN %2$I TYPE %3$s USING %2$I::text::%3$s',
+                       col.tbl, col.attname, new_type);
+        IF col.def IS NOT NULL THEN
+            EXECUTE format('ALTER TABLE %s ALTER COLUMN %I SET DEFAULT %L::%s',
+                           col.tbl, col.attname, value, new_type);
+        END IF;
+    END LOOP;
+    DROP TYPE {old};
+    ALTER TYPE {new} RENAME TO {name};
+END
+$pgmold$"#,
+        new_literal = quote_literal(&new),
+        old_literal = quote_literal(&old),
+        values = values.join(", "),
+        name = quote_ident(name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(values: &str) -> Schema {
+        pgmold::parser::parse_sql_string(&format!(
+            "CREATE TYPE status AS ENUM ({values});\n\
+             CREATE TABLE users (id INT PRIMARY KEY, status status NOT NULL);"
+        ))
+        .unwrap()
+    }
+
+    fn values(schema: &Schema) -> &[String] {
+        &schema.enums.values().next().unwrap().values
+    }
+
+    #[test]
+    fn plan_adds_values_in_place() {
+        let mut current = schema("'active', 'disabled'");
+        let target = schema("'pending', 'active', 'suspended', 'disabled', 'deleted'");
+
+        let changes = plan(&mut current, &target, false).unwrap();
+
+        assert_eq!(
+            changes.additions,
+            vec![
+                "ALTER TYPE \"public\".\"status\" ADD VALUE IF NOT EXISTS 'pending' BEFORE 'active'",
+                "ALTER TYPE \"public\".\"status\" ADD VALUE IF NOT EXISTS 'suspended' AFTER 'active'",
+                "ALTER TYPE \"public\".\"status\" ADD VALUE IF NOT EXISTS 'deleted' AFTER 'disabled'",
+            ]
+        );
+        assert!(changes.rebuilds.is_empty());
+        // The diff sees nothing left to change
+        assert_eq!(values(&current), values(&target));
+    }
+
+    #[test]
+    fn plan_blocks_removals_without_rebuild() {
+        let mut current = schema("'active', 'disabled'");
+        let err = plan(&mut current, &schema("'active'"), false).unwrap_err();
+        assert_eq!(err, "public.status drops disabled");
+
+        let err = plan(&mut current, &schema("'disabled', 'active'"), false).unwrap_err();
+        assert_eq!(err, "public.status reorders its values");
+    }
+
+    #[test]
+    fn plan_rebuilds_when_allowed() {
+        let mut current = schema("'active', 'disabled'");
+        let target = schema("'active', 'archived'");
+
+        let changes = plan(&mut current, &target, true).unwrap();
+
+        assert!(changes.additions.is_empty());
+        assert_eq!(changes.rebuilds.len(), 1);
+        let rebuild = &changes.rebuilds[0];
+        assert!(rebuild.contains(
+            "CREATE TYPE \"public\".\"status__pgmold_new\" AS ENUM ('active', 'archived');"
+        ));
+        assert!(rebuild.contains("DROP TYPE \"public\".\"status\";"));
+        assert!(rebuild.contains("RENAME TO \"status\";"));
+        assert_eq!(values(&current), values(&target));
+    }
+}
@@ -0,0 +1,166 @@
+//! Rough durations for operations that scan or rewrite an existing table,
+//! from the table's size on disk. They assume an otherwise idle server and
+//! only indicate the order of magnitude, for scheduling maintenance windows.
+
+use std::collections::BTreeMap;
+
+use pgmold::diff::MigrationOp;
+use sqlx::PgPool;
+
+use crate::operations::{classify, OperationClass};
+
+/// Planner statistics for one table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TableSize {
+    /// `pg_class.reltuples`, 0 for never-analyzed tables.
+    pub rows: i64,
+    /// Heap and TOAST bytes, without indexes.
+    pub bytes: i64,
+}
+
+/// Sizes keyed by `schema.table`.
+pub type TableSizes = BTreeMap<String, TableSize>;
+
+const MIB: u64 = 1024 * 1024;
+/// Copying the heap and rebuilding every index on it.
+const REWRITE_BYTES_PER_SECOND: u64 = 50 * MIB;
+/// Scanning and sorting the heap to build one index.
+const INDEX_BYTES_PER_SECOND: u64 = 100 * MIB;
+/// Scanning the heap to validate a constraint.
+const SCAN_BYTES_PER_SECOND: u64 = 300 * MIB;
+
+pub async fn table_sizes(pool: &PgPool, schemas: &[String]) -> sqlx::Result<TableSizes> {
+    let rows: Vec<(String, i64, i64)> = sqlx::query_as(
+        "SELECT n.nspname || '.' || c.relname, \
+                GREATEST(c.reltuples, 0)::bigint, \
+                pg_table_size(c.oid) \
+         FROM pg_class c \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE c.relkind IN ('r', 'p') AND n.nspname = ANY($1)",
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(table, rows, bytes)| (table, TableSize { rows, bytes }))
+        .collect())
+}
+
+/// Whether `op` reads or rewrites an existing table, so its size matters.
+pub fn scans_table(op: &MigrationOp) -> bool {
+    throughput(op).is_some()
+}
+
+/// How long an operation is expected to hold its lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    /// Rounded up, at least 1.
+    pub seconds: u64,
+    /// Rows in the table the operation scans.
+    pub rows: i64,
+}
+
+/// `None` for operations that don't scan a table, or when the table's size
+/// is unknown (e.g. in mock mode).
+pub fn estimate(op: &MigrationOp, sizes: &TableSizes) -> Option<Estimate> {
+    let bytes_per_second = throughput(op)?;
+    let size = sizes.get(&table_of(op)?)?;
+    let seconds = (size.bytes.max(0) as u64).div_ceil(bytes_per_second);
+    Some(Estimate {
+        seconds: seconds.max(1),
+        rows: size.rows,
+    })
+}
+
+fn throughput(op: &MigrationOp) -> Option<u64> {
+    if classify(op) == OperationClass::Rewrite {
+        return Some(REWRITE_BYTES_PER_SECOND);
+    }
+    match op {
+        MigrationOp::AddIndex { .. } | MigrationOp::AddPrimaryKey { .. } => {
+            Some(INDEX_BYTES_PER_SECOND)
+        }
+        MigrationOp::AddForeignKey { .. } | MigrationOp::AddCheckConstraint { .. } => {
+            Some(SCAN_BYTES_PER_SECOND)
+        }
+        _ => None,
+    }
+}
+
+/// `schema.table` of the table a scanning operation reads.
+fn table_of(op: &MigrationOp) -> Option<String> {
+    match op {
+        MigrationOp::AlterColumn { table, .. }
+        | MigrationOp::AddIndex { table, .. }
+        | MigrationOp::AddPrimaryKey { table, .. }
+        | MigrationOp::AddForeignKey { table, .. }
+        | MigrationOp::AddCheckConstraint { table, .. } => {
+            Some(crate::seed::qualified_table(table))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(table: &str, bytes: u64) -> TableSizes {
+        TableSizes::from([(
+            table.to_string(),
+            TableSize {
+                rows: 1000,
+                bytes: bytes as i64,
+            },
+        )])
+    }
+
+    /// Operations pgmold plans from `current` to `target`.
+    fn diff(current: &str, target: &str) -> Vec<MigrationOp> {
+        pgmold::diff::compute_diff(
+            &pgmold::parser::parse_sql_string(current).unwrap(),
+            &pgmold::parser::parse_sql_string(target).unwrap(),
+        )
+    }
+
+    #[test]
+    fn estimates_rewrites_from_table_size() {
+        let ops = diff(
+            "CREATE TABLE events (id INT PRIMARY KEY);",
+            "CREATE TABLE events (id BIGINT PRIMARY KEY);",
+        );
+        let rewrite = ops.iter().find(|op| scans_table(op)).unwrap();
+        assert_eq!(
+            estimate(rewrite, &sizes("public.events", 500 * MIB)),
+            Some(Estimate {
+                seconds: 10,
+                rows: 1000
+            })
+        );
+        assert_eq!(estimate(rewrite, &sizes("public.users", MIB)), None);
+    }
+
+    #[test]
+    fn only_table_scans_are_estimated() {
+        let sizes = sizes("crm.contacts", 200 * MIB);
+        let current =
+            "CREATE SCHEMA crm;\nCREATE TABLE crm.contacts (id INT PRIMARY KEY, email TEXT);";
+        let indexed = diff(
+            current,
+            &format!("{current}\nCREATE INDEX contacts_email_idx ON crm.contacts (email);"),
+        );
+        assert_eq!(
+            indexed
+                .iter()
+                .find_map(|op| estimate(op, &sizes))
+                .map(|e| e.seconds),
+            Some(2)
+        );
+        let added = diff(
+            current,
+            "CREATE SCHEMA crm;\nCREATE TABLE crm.contacts (id INT PRIMARY KEY, email TEXT, note TEXT);",
+        );
+        assert!(added.iter().all(|op| estimate(op, &sizes).is_none()));
+    }
+}
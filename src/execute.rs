@@ -0,0 +1,708 @@
+//! Statement executor for schema applies. pgmold's `apply_migration` plans
+//! again from the schema files and runs the whole migration in one
+//! transaction, so it can neither apply the plan that was reviewed nor run
+//! statements PostgreSQL refuses inside a transaction block, such as
+//! `CREATE INDEX CONCURRENTLY`. Here the planned statements are split into
+//! transactional segments and statements that run on their own.
+
+use std::sync::LazyLock;
+
+use sqlx::{PgConnection, PgPool};
+use tf_provider::{AttributePath, Diagnostics};
+
+use crate::progress::{backend_pid, is_long_running, track};
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions {
+    /// Send consecutive transactional statements in a single round trip.
+    pub batch: bool,
+    /// Run every statement on its own instead of grouping them into transactions.
+    pub standalone: bool,
+    /// Apply column type changes through a shadow table rather than in place.
+    pub rebuild: Option<crate::rebuild::RebuildOptions>,
+    /// Commit transactional statements every this many statements, so a failure
+    /// keeps the work done so far, instead of once per group.
+    pub chunk_size: Option<usize>,
+    /// Times a failed `CREATE INDEX CONCURRENTLY` is retried after dropping
+    /// the invalid index it left behind.
+    pub concurrent_index_retries: u32,
+    /// Failures to inject in place of statements, for testing recovery.
+    pub failpoints: crate::failpoints::Failpoints,
+    /// SQLSTATE codes a transactional statement may fail with and be skipped.
+    /// When set, each statement runs in a savepoint it is rolled back to.
+    pub continue_on: Vec<String>,
+}
+
+/// A transactional statement that failed with a `continue_on` error and was
+/// rolled back, while the rest of its transaction committed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedStatement {
+    pub statement: String,
+    pub error: String,
+}
+
+/// Condition names accepted in `continue_on`, with their SQLSTATE. The ones
+/// an optional statement typically fails with; any other error can be given
+/// by its code.
+const CONDITIONS: &[(&str, &str)] = &[
+    ("check_violation", "23514"),
+    ("dependent_objects_still_exist", "2BP01"),
+    ("duplicate_column", "42701"),
+    ("duplicate_function", "42723"),
+    ("duplicate_object", "42710"),
+    ("duplicate_table", "42P07"),
+    ("feature_not_supported", "0A000"),
+    ("foreign_key_violation", "23503"),
+    ("insufficient_privilege", "42501"),
+    ("invalid_schema_name", "3F000"),
+    ("lock_not_available", "55P03"),
+    ("not_null_violation", "23502"),
+    ("undefined_column", "42703"),
+    ("undefined_file", "58P01"),
+    ("undefined_function", "42883"),
+    ("undefined_object", "42704"),
+    ("undefined_table", "42P01"),
+    ("unique_violation", "23505"),
+];
+
+/// Resolves `continue_on` entries, condition names or five-character SQLSTATE
+/// codes, to codes.
+pub fn parse_continue_on<S: AsRef<str>>(entries: &[S]) -> Result<Vec<String>, String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let entry = entry.as_ref();
+            if let Some((_, code)) = CONDITIONS.iter().find(|(name, _)| *name == entry) {
+                return Ok(code.to_string());
+            }
+            if entry.len() == 5
+                && entry
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+            {
+                return Ok(entry.to_string());
+            }
+            Err(format!(
+                "Invalid continue_on entry \"{entry}\", expected a SQLSTATE code such as 58P01 or a condition name such as undefined_file"
+            ))
+        })
+        .collect()
+}
+
+/// Whether `error` is a server error with one of the `codes`.
+fn continues(codes: &[String], error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| codes.iter().any(|c| *c == code))
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Transactional(Vec<&'a str>),
+    Standalone(&'a str),
+    /// Consecutive column type changes to one table, applied by a single rebuild.
+    Rebuild {
+        table: &'a str,
+        clauses: Vec<&'a str>,
+    },
+}
+
+/// Whether a statement may run inside a transaction block. Function bodies,
+/// literals and comments don't count, so a function that refreshes a view
+/// `CONCURRENTLY` is still created in the transaction. Enum values are added
+/// on their own too: a value can't be used in the transaction that added it.
+/// So are constraint validations, whose table scan shouldn't run while the
+/// transaction still holds the locks of the statements before it. Quoted
+/// identifiers don't count either, so a column named `"concurrently"` doesn't
+/// take a statement out of its transaction.
+pub fn is_transactional(statement: &str) -> bool {
+    static NON_TRANSACTIONAL: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(
+            r"(?is)\bCONCURRENTLY\b|^\s*(VACUUM|CREATE\s+DATABASE|DROP\s+DATABASE|ALTER\s+SYSTEM)\b|^\s*ALTER\s+TYPE\b.*\bADD\s+VALUE\b|^\s*ALTER\s+TABLE\b.*\bVALIDATE\s+CONSTRAINT\b",
+        )
+        .unwrap()
+    });
+    !NON_TRANSACTIONAL.is_match(&crate::statements::keywords_only(statement))
+}
+
+/// Reports a negative `concurrent_index_retries` on the attribute.
+pub fn check_concurrent_index_retries(diags: &mut Diagnostics, retries: Option<i64>) -> Option<()> {
+    if retries.is_some_and(|n| n < 0) {
+        diags.error_short(
+            "concurrent_index_retries must not be negative",
+            AttributePath::new("concurrent_index_retries"),
+        );
+        return None;
+    }
+    Some(())
+}
+
+/// The index a `CREATE INDEX CONCURRENTLY` statement builds, qualified with
+/// its table's schema if that is given, as `to_regclass` takes it. `None` for
+/// other statements and for indexes left for the server to name.
+fn concurrent_index(statement: &str) -> Option<String> {
+    const IDENT: &str = r#""(?:[^"]|"")+"|[A-Za-z_][A-Za-z0-9_$]*"#;
+    let re = regex::Regex::new(&format!(
+        r"(?is)^\s*CREATE\s+(?:UNIQUE\s+)?INDEX\s+CONCURRENTLY\s+(?:IF\s+NOT\s+EXISTS\s+)?({IDENT})\s+ON\s+(?:ONLY\s+)?(?:({IDENT})\s*\.\s*)?(?:{IDENT})"
+    ))
+    .unwrap();
+    let code = crate::statements::code_only(statement);
+    let caps = re.captures(&code)?;
+    // ON is a reserved word, so an unnamed index can't match
+    Some(match caps.get(2) {
+        Some(schema) => format!("{}.{}", schema.as_str(), &caps[1]),
+        None => caps[1].to_string(),
+    })
+}
+
+/// Drops `index` if a failed concurrent build left it invalid, returning
+/// whether it did. An invalid index still has to be maintained on writes and
+/// makes a retried `CREATE INDEX` fail on its name.
+async fn drop_invalid_index(pool: &PgPool, index: &str) -> sqlx::Result<bool> {
+    let (invalid,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM pg_index WHERE indexrelid = to_regclass($1) AND NOT indisvalid)",
+    )
+    .bind(index)
+    .fetch_one(pool)
+    .await?;
+    if invalid {
+        sqlx::raw_sql(&format!("DROP INDEX CONCURRENTLY IF EXISTS {index}"))
+            .execute(pool)
+            .await?;
+    }
+    Ok(invalid)
+}
+
+/// Runs a statement outside a transaction. A concurrent index build that
+/// fails has its invalid index dropped, and is retried up to `retries` times.
+async fn run_standalone(
+    pool: &PgPool,
+    statement: &str,
+    index: usize,
+    retries: u32,
+) -> anyhow::Result<()> {
+    for attempt in 0.. {
+        let mut connection = pool.acquire().await?;
+        let pid = if is_long_running(statement) {
+            Some(backend_pid(&mut connection).await?)
+        } else {
+            None
+        };
+        let Err(e) = track(pool, pid, index, run(&mut connection, statement)).await else {
+            return Ok(());
+        };
+        drop(connection);
+        let Some(name) = concurrent_index(statement) else {
+            return Err(anyhow::anyhow!("{e} (statement: {statement})"));
+        };
+        match drop_invalid_index(pool, &name).await {
+            Ok(true) if attempt < retries => {
+                tracing::warn!(
+                    index = name,
+                    attempt = attempt + 1,
+                    error = %e,
+                    "concurrent index build failed, dropped the invalid index and retrying"
+                );
+                tokio::time::sleep(std::time::Duration::from_secs((attempt + 1).into())).await;
+            }
+            Ok(_) => return Err(anyhow::anyhow!("{e} (statement: {statement})")),
+            Err(cleanup) => {
+                return Err(anyhow::anyhow!(
+                    "{e} (statement: {statement}); dropping the invalid index {name} it left failed: {cleanup}"
+                ))
+            }
+        }
+    }
+    unreachable!("the loop returns once attempts run out")
+}
+
+/// Groups consecutive transactional statements; anything else runs on its own.
+/// With `rebuild`, column type changes are taken out to be applied by a rebuild.
+fn segments(statements: &[String], rebuild: bool) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut current = Vec::new();
+
+    for statement in statements {
+        if let Some(change) = rebuild
+            .then(|| crate::rebuild::TypeChange::parse(statement))
+            .flatten()
+        {
+            if !current.is_empty() {
+                segments.push(Segment::Transactional(std::mem::take(&mut current)));
+            }
+            match segments.last_mut() {
+                Some(Segment::Rebuild { table, clauses }) if *table == change.table => {
+                    clauses.push(change.clause)
+                }
+                _ => segments.push(Segment::Rebuild {
+                    table: change.table,
+                    clauses: vec![change.clause],
+                }),
+            }
+        } else if is_transactional(statement) {
+            current.push(statement.as_str());
+        } else {
+            if !current.is_empty() {
+                segments.push(Segment::Transactional(std::mem::take(&mut current)));
+            }
+            segments.push(Segment::Standalone(statement.as_str()));
+        }
+    }
+    if !current.is_empty() {
+        segments.push(Segment::Transactional(current));
+    }
+
+    segments
+}
+
+/// Splits transactional segments into transactions of at most `chunk_size` statements.
+fn chunked(segments: Vec<Segment<'_>>, chunk_size: usize) -> Vec<Segment<'_>> {
+    segments
+        .into_iter()
+        .flat_map(|segment| match segment {
+            Segment::Transactional(batch) => batch
+                .chunks(chunk_size)
+                .map(|chunk| Segment::Transactional(chunk.to_vec()))
+                .collect(),
+            other => vec![other],
+        })
+        .collect()
+}
+
+fn terminate(statement: &str) -> String {
+    let statement = statement.trim_end();
+    if crate::statements::code_only(statement)
+        .trim_end()
+        .ends_with(';')
+    {
+        statement.to_string()
+    } else if crate::statements::ends_in_line_comment(statement) {
+        format!("{statement}\n;")
+    } else {
+        format!("{statement};")
+    }
+}
+
+fn has_copy_data(statement: &str) -> bool {
+    crate::statements::split(statement)
+        .iter()
+        .any(|s| s.copy_data.is_some())
+}
+
+/// Executes one generated statement. Inline `COPY ... FROM STDIN` data goes
+/// through the copy protocol; anything else, dollar-quoted bodies and `DO`
+/// blocks included, is sent as is for the server to parse.
+async fn run(connection: &mut PgConnection, statement: &str) -> sqlx::Result<()> {
+    if !has_copy_data(statement) {
+        sqlx::raw_sql(statement).execute(connection).await?;
+        return Ok(());
+    }
+    for part in crate::statements::split(statement) {
+        match part.copy_data {
+            Some(data) => {
+                let mut copy = connection.copy_in_raw(&part.sql).await?;
+                copy.send(data.as_bytes()).await?;
+                copy.finish().await?;
+            }
+            None => {
+                sqlx::raw_sql(&part.sql).execute(&mut *connection).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the SQL of an injected failure in place of the statement at `index`.
+async fn inject(
+    connection: &mut PgConnection,
+    index: usize,
+    failure: crate::failpoints::Failure,
+) -> sqlx::Result<()> {
+    tracing::warn!(statement_index = index, ?failure, "failpoint triggered");
+    sqlx::raw_sql(&failure.sql(index))
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+/// Runs a statement in an open transaction, or the failure injected for it.
+async fn run_in_transaction(
+    pool: &PgPool,
+    connection: &mut PgConnection,
+    statement: &str,
+    index: usize,
+    failpoints: &crate::failpoints::Failpoints,
+) -> sqlx::Result<()> {
+    if let Some((at, failure)) = failpoints.within(index..index + 1) {
+        inject(connection, at, failure).await?;
+    }
+    let pid = if is_long_running(statement) {
+        Some(backend_pid(connection).await?)
+    } else {
+        None
+    };
+    track(pool, pid, index, run(connection, statement)).await
+}
+
+/// Executes generated statements. Transactional statements run inside a transaction, either one
+/// round trip per statement or batched through the simple query protocol; statements that cannot
+/// run in a transaction (e.g. `CREATE INDEX CONCURRENTLY`) run on their own. Progress of index
+/// builds and table rewrites is logged while they run.
+pub async fn execute_statements(
+    pool: &PgPool,
+    statements: &[String],
+    options: &ExecuteOptions,
+) -> anyhow::Result<()> {
+    execute_with_progress(pool, statements, options, &mut 0, &mut Vec::new()).await
+}
+
+/// [`execute_statements`], counting in `committed` the statements that took
+/// effect, so callers can report how far a failed run got, and collecting in
+/// `skipped` those `continue_on` let through.
+pub async fn execute_with_progress(
+    pool: &PgPool,
+    statements: &[String],
+    options: &ExecuteOptions,
+    committed: &mut usize,
+    skipped: &mut Vec<SkippedStatement>,
+) -> anyhow::Result<()> {
+    let mut segments = if options.standalone {
+        statements
+            .iter()
+            .map(|s| Segment::Standalone(s.as_str()))
+            .collect()
+    } else {
+        segments(statements, options.rebuild.is_some())
+    };
+    if let Some(chunk_size) = options.chunk_size {
+        segments = chunked(segments, chunk_size.max(1));
+    }
+
+    let mut index = 0;
+    for segment in segments {
+        match segment {
+            Segment::Transactional(batch) => {
+                let mut tx = pool.begin().await?;
+                // COPY data can't be batched through the simple query protocol,
+                // and savepoints need a round trip per statement
+                let savepoints = !options.continue_on.is_empty();
+                if options.batch && !savepoints && !batch.iter().any(|s| has_copy_data(s)) {
+                    tracing::debug!(
+                        statement_index = index,
+                        statements = batch.len(),
+                        "executing batch"
+                    );
+                    if let Some((at, failure)) =
+                        options.failpoints.within(index..index + batch.len())
+                    {
+                        inject(&mut tx, at, failure).await?;
+                    }
+                    let pid = if batch.iter().any(|s| is_long_running(s)) {
+                        Some(backend_pid(&mut tx).await?)
+                    } else {
+                        None
+                    };
+                    let sql = batch
+                        .iter()
+                        .map(|s| terminate(s))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    track(pool, pid, index, sqlx::raw_sql(&sql).execute(&mut *tx)).await?;
+                    index += batch.len();
+                } else {
+                    for statement in batch {
+                        tracing::debug!(statement_index = index, statement, "executing statement");
+                        if savepoints {
+                            sqlx::raw_sql("SAVEPOINT pgmold_statement")
+                                .execute(&mut *tx)
+                                .await?;
+                        }
+                        let result = run_in_transaction(
+                            pool,
+                            &mut tx,
+                            statement,
+                            index,
+                            &options.failpoints,
+                        )
+                        .await;
+                        match result {
+                            Ok(()) if savepoints => {
+                                sqlx::raw_sql("RELEASE SAVEPOINT pgmold_statement")
+                                    .execute(&mut *tx)
+                                    .await?;
+                            }
+                            Ok(()) => {}
+                            Err(e) if savepoints && continues(&options.continue_on, &e) => {
+                                sqlx::raw_sql("ROLLBACK TO SAVEPOINT pgmold_statement")
+                                    .execute(&mut *tx)
+                                    .await?;
+                                tracing::warn!(
+                                    statement_index = index,
+                                    statement,
+                                    error = %e,
+                                    "statement failed with a continue_on error, skipped"
+                                );
+                                skipped.push(SkippedStatement {
+                                    statement: statement.to_string(),
+                                    error: e.to_string(),
+                                });
+                            }
+                            Err(e) => return Err(anyhow::anyhow!("{e} (statement: {statement})")),
+                        }
+                        index += 1;
+                    }
+                }
+                tx.commit().await?;
+                *committed = index;
+                if options.chunk_size.is_some() {
+                    tracing::info!(
+                        committed = index,
+                        total = statements.len(),
+                        "committed chunk"
+                    );
+                }
+            }
+            Segment::Standalone(statement) => {
+                tracing::debug!(
+                    statement_index = index,
+                    statement,
+                    "executing statement outside a transaction"
+                );
+                if let Some((at, failure)) = options.failpoints.within(index..index + 1) {
+                    let mut connection = pool.acquire().await?;
+                    inject(&mut connection, at, failure)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{e} (statement: {statement})"))?;
+                }
+                run_standalone(pool, statement, index, options.concurrent_index_retries).await?;
+                index += 1;
+                *committed = index;
+            }
+            Segment::Rebuild { table, clauses } => {
+                tracing::debug!(
+                    statement_index = index,
+                    table,
+                    "rebuilding table through a shadow table"
+                );
+                if let Some((at, failure)) = options.failpoints.within(index..index + clauses.len())
+                {
+                    let mut connection = pool.acquire().await?;
+                    inject(&mut connection, at, failure)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{e} (table: {table})"))?;
+                }
+                let rebuild = options
+                    .rebuild
+                    .as_ref()
+                    .expect("rebuild segments require rebuild options");
+                crate::rebuild::rebuild_table(pool, table, &clauses, rebuild)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e} (table: {table})"))?;
+                index += clauses.len();
+                *committed = index;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Schemas from `schemas` that don't exist in the database, in input order.
+pub async fn missing_schemas(pool: &PgPool, schemas: &[String]) -> sqlx::Result<Vec<String>> {
+    let existing: Vec<(String,)> =
+        sqlx::query_as("SELECT nspname FROM pg_namespace WHERE nspname = ANY($1)")
+            .bind(schemas)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(schemas
+        .iter()
+        .filter(|s| !existing.iter().any(|(name,)| name == *s))
+        .cloned()
+        .collect())
+}
+
+pub async fn create_schemas(pool: &PgPool, schemas: &[String]) -> sqlx::Result<()> {
+    for schema in schemas {
+        let sql = format!(
+            "CREATE SCHEMA IF NOT EXISTS {}",
+            crate::functions::quote::quote_ident(schema)
+        );
+        sqlx::raw_sql(&sql).execute(pool).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_continue_on_resolves_condition_names() {
+        assert_eq!(
+            parse_continue_on(&["undefined_file", "42P07"]).unwrap(),
+            vec!["58P01".to_string(), "42P07".to_string()]
+        );
+        assert!(parse_continue_on(&["missing_extension"]).is_err());
+        assert!(parse_continue_on(&["42p07"]).is_err());
+    }
+
+    #[test]
+    fn concurrent_index_is_not_transactional() {
+        assert!(!is_transactional(
+            "CREATE INDEX CONCURRENTLY users_email_idx ON users (email)"
+        ));
+        assert!(is_transactional(
+            "CREATE INDEX users_email_idx ON users (email)"
+        ));
+    }
+
+    #[test]
+    fn concurrent_index_names_the_index_to_clean_up() {
+        assert_eq!(
+            concurrent_index("CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS \"Email_idx\" ON \"app\".users (email)"),
+            Some("\"app\".\"Email_idx\"".to_string())
+        );
+        assert_eq!(
+            concurrent_index("CREATE INDEX CONCURRENTLY users_email_idx ON users (email)"),
+            Some("users_email_idx".to_string())
+        );
+        assert_eq!(
+            concurrent_index("CREATE INDEX CONCURRENTLY ON users (email)"),
+            None
+        );
+        assert_eq!(
+            concurrent_index("CREATE INDEX users_email_idx ON users (email)"),
+            None
+        );
+    }
+
+    #[test]
+    fn segments_split_around_standalone_statements() {
+        let statements = vec![
+            "CREATE TABLE a (id INT)".to_string(),
+            "CREATE TABLE b (id INT)".to_string(),
+            "CREATE INDEX CONCURRENTLY a_idx ON a (id)".to_string(),
+            "CREATE TABLE c (id INT)".to_string(),
+        ];
+
+        assert_eq!(
+            segments(&statements, false),
+            vec![
+                Segment::Transactional(vec!["CREATE TABLE a (id INT)", "CREATE TABLE b (id INT)"]),
+                Segment::Standalone("CREATE INDEX CONCURRENTLY a_idx ON a (id)"),
+                Segment::Transactional(vec!["CREATE TABLE c (id INT)"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn chunked_splits_transactions() {
+        let mut statements: Vec<String> = (0..5)
+            .map(|i| format!("CREATE TABLE t{i} (id INT)"))
+            .collect();
+        statements.insert(3, "CREATE INDEX CONCURRENTLY a_idx ON t0 (id)".to_string());
+
+        let sizes: Vec<usize> = chunked(segments(&statements, false), 2)
+            .iter()
+            .map(|segment| match segment {
+                Segment::Transactional(batch) => batch.len(),
+                _ => 0,
+            })
+            .collect();
+        assert_eq!(sizes, vec![2, 1, 0, 2]);
+    }
+
+    #[test]
+    fn segments_group_type_changes_into_rebuilds() {
+        let statements = vec![
+            "ALTER TABLE users ALTER COLUMN score TYPE BIGINT".to_string(),
+            "ALTER TABLE users ALTER COLUMN rank TYPE BIGINT".to_string(),
+            "CREATE TABLE a (id INT)".to_string(),
+        ];
+
+        assert_eq!(
+            segments(&statements, true),
+            vec![
+                Segment::Rebuild {
+                    table: "users",
+                    clauses: vec![
+                        "ALTER COLUMN score TYPE BIGINT",
+                        "ALTER COLUMN rank TYPE BIGINT"
+                    ],
+                },
+                Segment::Transactional(vec!["CREATE TABLE a (id INT)"]),
+            ]
+        );
+        assert_eq!(segments(&statements, false).len(), 1);
+    }
+
+    #[test]
+    fn enum_value_additions_are_not_transactional() {
+        assert!(!is_transactional(
+            "ALTER TYPE \"public\".\"status\" ADD VALUE IF NOT EXISTS 'archived' AFTER 'active'"
+        ));
+        assert!(is_transactional(
+            "ALTER TYPE status RENAME VALUE 'archived' TO 'add value'"
+        ));
+    }
+
+    #[test]
+    fn constraint_validation_is_not_transactional() {
+        assert!(!is_transactional(
+            "ALTER TABLE public.users VALIDATE CONSTRAINT users_org_fk"
+        ));
+    }
+
+    #[test]
+    fn concurrently_in_function_body_is_transactional() {
+        assert!(is_transactional(
+            "CREATE FUNCTION refresh() RETURNS void AS $$ REFRESH MATERIALIZED VIEW CONCURRENTLY stats $$ LANGUAGE sql"
+        ));
+        assert!(is_transactional(
+            "DO $$ BEGIN EXECUTE 'CREATE INDEX CONCURRENTLY i ON t (a)'; END $$"
+        ));
+    }
+
+    #[test]
+    fn quoted_concurrently_identifier_is_transactional() {
+        assert!(is_transactional(
+            r#"CREATE INDEX "concurrently" ON public.jobs ("concurrently")"#
+        ));
+        assert!(is_transactional(
+            r#"ALTER TABLE "concurrently" ADD COLUMN "validate" INT"#
+        ));
+        assert!(!is_transactional(
+            r#"CREATE INDEX CONCURRENTLY "jobs_idx" ON "concurrently" (a)"#
+        ));
+    }
+
+    #[test]
+    fn terminate_respects_comments_and_bodies() {
+        assert_eq!(terminate("SELECT 1 -- one"), "SELECT 1 -- one\n;");
+        assert_eq!(terminate("SELECT 1; -- one"), "SELECT 1; -- one");
+        assert_eq!(
+            terminate("DO $$ BEGIN PERFORM 1; END $$"),
+            "DO $$ BEGIN PERFORM 1; END $$;"
+        );
+    }
+
+    #[test]
+    fn copy_data_is_detected() {
+        assert!(has_copy_data(
+            "COPY countries (code) FROM STDIN;\nfr\n\\.\n"
+        ));
+        assert!(!has_copy_data(
+            "COPY countries (code) FROM '/tmp/countries.csv'"
+        ));
+        assert!(!has_copy_data(
+            "DO $$ BEGIN RAISE NOTICE 'COPY t FROM STDIN;'; END $$"
+        ));
+    }
+
+    #[test]
+    fn terminate_adds_missing_semicolon() {
+        assert_eq!(terminate("SELECT 1"), "SELECT 1;");
+        assert_eq!(terminate("SELECT 1;\n"), "SELECT 1;");
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::Write;
+
+use serde::Serialize;
+use tf_provider::{AttributePath, Diagnostics};
+
+use crate::pipeline::{LintEntry, MigrationPlan, PhaseTimings};
+use crate::run_metadata::RunMetadata;
+
+/// One line of the `execution_log_path` file, describing a single create or update.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionLogEntry {
+    pub timestamp: String,
+    pub resource: &'static str,
+    pub operation: &'static str,
+    pub id: String,
+    pub schema_hash: String,
+    /// `success` or `failure`.
+    pub outcome: &'static str,
+    pub errors: Vec<String>,
+    pub operations: Vec<String>,
+    pub statements: Vec<String>,
+    pub lint_results: Vec<LintEntry>,
+    pub timings: PhaseTimings,
+    /// Workspace, run and commit of the Terraform run, as `workspace`,
+    /// `run_id` and `git_commit`.
+    #[serde(flatten)]
+    pub run: RunMetadata,
+}
+
+impl ExecutionLogEntry {
+    pub fn new(
+        resource: &'static str,
+        operation: &'static str,
+        id: &str,
+        schema_hash: &str,
+    ) -> Self {
+        ExecutionLogEntry {
+            timestamp: crate::clock::now().to_rfc3339(),
+            resource,
+            operation,
+            id: id.to_string(),
+            schema_hash: schema_hash.to_string(),
+            outcome: "failure",
+            errors: Vec::new(),
+            operations: Vec::new(),
+            statements: Vec::new(),
+            lint_results: Vec::new(),
+            timings: PhaseTimings::new(),
+            run: RunMetadata::default(),
+        }
+    }
+
+    /// Copies what was planned; call before executing so failed runs still log it.
+    pub fn record_plan(&mut self, plan: &MigrationPlan) {
+        self.operations = plan.operations.iter().map(|op| format!("{op:?}")).collect();
+        self.statements = plan.statements.clone();
+        self.lint_results = crate::pipeline::lint_entries(&plan.lint_results);
+        self.timings = plan.timings.clone();
+    }
+
+    /// Sets the outcome, taking error summaries from `diags`.
+    pub fn finish(&mut self, diags: &Diagnostics, success: bool) {
+        self.outcome = if success { "success" } else { "failure" };
+        self.errors = diags.errors.iter().map(|e| e.summary.to_string()).collect();
+    }
+}
+
+/// Appends `entry` as a JSON line to `path`, if set. Failing to write the log
+/// doesn't undo the run it describes, so it is reported as a warning.
+pub fn append(diags: &mut Diagnostics, path: Option<&str>, entry: &ExecutionLogEntry) {
+    let Some(path) = path else {
+        return;
+    };
+    if let Err(e) = write_line(path, entry) {
+        diags.warning(
+            "Failed to write execution log",
+            format!("{path}: {e}"),
+            AttributePath::new("execution_log_path"),
+        );
+    }
+}
+
+fn write_line(path: &str, entry: &ExecutionLogEntry) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_writes_one_json_line_per_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("runs.jsonl");
+        let path = path.to_str().unwrap();
+
+        let mut diags = Diagnostics::default();
+        let mut entry = ExecutionLogEntry::new("pgmold_schema", "create", "pgmold-app", "abc");
+        entry.statements = vec!["CREATE TABLE users (id INT);".to_string()];
+        entry.run.git_commit = Some("1a2b3c".to_string());
+        entry.finish(&diags, true);
+        append(&mut diags, Some(path), &entry);
+        append(&mut diags, Some(path), &entry);
+
+        let content = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["outcome"], "success");
+        assert_eq!(lines[0]["statements"][0], "CREATE TABLE users (id INT);");
+        assert_eq!(lines[0]["git_commit"], "1a2b3c");
+        assert_eq!(lines[0]["workspace"], serde_json::Value::Null);
+        assert!(diags.warnings.is_empty());
+    }
+}
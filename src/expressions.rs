@@ -0,0 +1,243 @@
+//! Compares expressions the way PostgreSQL stores them. The catalog keeps
+//! generated column expressions, defaults, index expressions and partial
+//! index predicates in its own deparsed form, e.g. `lower((email)::text)` for
+//! `lower(email)`, so comparing them with the schema file as written reports
+//! the same object as changed on every plan.
+
+use pgmold::model::Schema;
+use regex::Regex;
+
+/// Gives columns and indexes of `target` the definition they have in
+/// `current` when the two only differ in how their expressions are written:
+/// case, whitespace, redundant parentheses and casts.
+pub fn align(current: &Schema, target: &mut Schema) {
+    let normalizer = Normalizer::new();
+    for (key, table) in target.tables.iter_mut() {
+        let Some(live) = current.tables.get(key) else {
+            continue;
+        };
+        for (name, column) in table.columns.iter_mut() {
+            if let Some(live) = live.columns.get(name) {
+                if normalizer.same(&format!("{live:?}"), &format!("{column:?}")) {
+                    *column = live.clone();
+                }
+            }
+        }
+        for index in table.indexes.iter_mut() {
+            if let Some(live) = live.indexes.iter().find(|i| i.name == index.name) {
+                if normalizer.same(&format!("{live:?}"), &format!("{index:?}")) {
+                    *index = live.clone();
+                }
+            }
+        }
+    }
+}
+
+struct Normalizer {
+    string: Regex,
+    cast: Regex,
+    space: Regex,
+}
+
+impl Normalizer {
+    fn new() -> Self {
+        Normalizer {
+            // String fields in `{:?}` output, which hold the expressions
+            string: Regex::new(r#""((?:[^"\\]|\\.)*)""#).unwrap(),
+            cast: Regex::new(
+                r"::\s*(?:double\s+precision|character\s+varying|bit\s+varying|(?:timestamp|time)\s+with(?:out)?\s+time\s+zone|[a-z_][a-z0-9_]*(?:\.[a-z_][a-z0-9_]*)?)(?:\s*\(\s*\d+(?:\s*,\s*\d+)?\s*\))?(?:\s*\[\])*",
+            )
+            .unwrap(),
+            // Whitespace only matters between two words
+            space: Regex::new(r"\s+").unwrap(),
+        }
+    }
+
+    /// Whether the `{:?}` output of two objects matches once the expressions
+    /// in them are normalized.
+    fn same(&self, a: &str, b: &str) -> bool {
+        a == b || self.normalize_debug(a) == self.normalize_debug(b)
+    }
+
+    fn normalize_debug(&self, debug: &str) -> String {
+        self.string
+            .replace_all(debug, |caps: &regex::Captures| {
+                format!("\"{}\"", self.normalize(&caps[1]))
+            })
+            .into_owned()
+    }
+
+    /// Lower case and without casts outside string literals, whitespace only
+    /// between words, and without parentheses that don't change the meaning.
+    fn normalize(&self, expr: &str) -> String {
+        let mut out = String::with_capacity(expr.len());
+        for (i, part) in expr.split('\'').enumerate() {
+            if i > 0 {
+                out.push('\'');
+            }
+            if i % 2 == 1 {
+                // Inside a literal; '' splits it into two, which stays as is
+                out.push_str(part);
+                continue;
+            }
+            let code = part.to_lowercase();
+            let code = self.cast.replace_all(&code, "");
+            let code = self.space.replace_all(&code, " ");
+            let words: Vec<&str> = code.split(' ').collect();
+            for (j, word) in words.iter().enumerate() {
+                if j > 0 && word_end(words[j - 1]) && word_start(word) {
+                    out.push(' ');
+                }
+                out.push_str(word);
+            }
+        }
+
+        while let Some((open, close)) = redundant_parens(&out) {
+            for i in [close, open] {
+                // Keep the words on either side apart
+                let apart = word_end(&out[..i]) && word_start(&out[i + 1..]);
+                out.replace_range(i..=i, if apart { " " } else { "" });
+            }
+        }
+        out
+    }
+}
+
+fn is_word(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || !b.is_ascii()
+}
+
+fn word_end(s: &str) -> bool {
+    s.bytes().last().is_some_and(is_word)
+}
+
+fn word_start(s: &str) -> bool {
+    s.bytes().next().is_some_and(is_word)
+}
+
+/// Whether `expr` has an `and` or `or` outside parentheses and literals.
+fn has_boolean_operator(expr: &str) -> bool {
+    let bytes = expr.as_bytes();
+    let mut depth = 0;
+    let mut quoted = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'\'' => quoted = !quoted,
+            b'(' if !quoted => depth += 1,
+            b')' if !quoted => depth -= 1,
+            _ if !quoted && depth == 0 && (i == 0 || !is_word(bytes[i - 1])) => {
+                let rest = &expr[i..];
+                for keyword in ["and", "or"] {
+                    if rest.starts_with(keyword)
+                        && !rest[keyword.len()..].bytes().next().is_some_and(is_word)
+                    {
+                        return true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Whether `text` ends with a boolean operator, `(` or `,`, after which any
+/// expression without `and` or `or` can stand without parentheses.
+fn opens_operand(text: &str) -> bool {
+    text.is_empty()
+        || text.ends_with(['(', ','])
+        || ["and", "or", "not"].iter().any(|k| {
+            text.strip_suffix(k)
+                .is_some_and(|rest| !rest.bytes().last().is_some_and(is_word))
+        })
+}
+
+/// Whether `text` starts with a boolean operator, `)` or `,`.
+fn closes_operand(text: &str) -> bool {
+    text.is_empty()
+        || text.starts_with([')', ','])
+        || ["and", "or"].iter().any(|k| {
+            text.strip_prefix(k)
+                .is_some_and(|rest| !rest.bytes().next().is_some_and(is_word))
+        })
+}
+
+/// The first pair of parentheses that only groups what would group anyway:
+/// a single operand, the whole expression, or an expression without `and` or
+/// `or` between boolean operators, commas or other parentheses. Parentheses
+/// right after a word hold a call's arguments or a list and are kept.
+fn redundant_parens(expr: &str) -> Option<(usize, usize)> {
+    let bytes = expr.as_bytes();
+    let mut quoted = false;
+    for (open, &b) in bytes.iter().enumerate() {
+        if b == b'\'' {
+            quoted = !quoted;
+        }
+        if quoted || b != b'(' {
+            continue;
+        }
+        let before = &expr[..open];
+        if word_end(before) && !opens_operand(before) {
+            continue;
+        }
+        let mut depth = 0;
+        let mut inner_quoted = false;
+        let close = (open..bytes.len()).find(|&i| {
+            match bytes[i] {
+                b'\'' => inner_quoted = !inner_quoted,
+                b'(' if !inner_quoted => depth += 1,
+                b')' if !inner_quoted => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })?;
+        let inner = &expr[open + 1..close];
+        let operand = !inner.is_empty()
+            && (inner
+                .bytes()
+                .all(|b| is_word(b) || b == b'.' || b == b'"' || b == b'\\')
+                || (inner.len() > 1
+                    && inner.starts_with('\'')
+                    && inner.ends_with('\'')
+                    && !inner[1..inner.len() - 1].contains('\'')));
+        let whole = open == 0 && close == bytes.len() - 1;
+        let grouped = !has_boolean_operator(inner)
+            && opens_operand(before)
+            && closes_operand(&expr[close + 1..]);
+        if operand || whole || grouped {
+            return Some((open, close));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_matches_catalog_forms() {
+        let n = Normalizer::new();
+        for (written, stored) in [
+            ("lower(email)", "lower((email)::text)"),
+            ("price * qty", "(price * qty)"),
+            (
+                "status = 'active' AND deleted_at IS NULL",
+                "((status = 'active'::text) AND (deleted_at IS NULL))",
+            ),
+            ("amount::numeric(10, 2)", "(amount)::numeric(10,2)"),
+        ] {
+            assert_eq!(n.normalize(written), n.normalize(stored), "{written}");
+        }
+
+        assert_ne!(n.normalize("(a + b) * c"), n.normalize("a + (b * c)"));
+        assert_ne!(
+            n.normalize("name = 'Active'"),
+            n.normalize("name = 'active'")
+        );
+        assert!(n.same(
+            r#"Index { name: "users_email_idx", columns: ["lower(email)"] }"#,
+            r#"Index { name: "users_email_idx", columns: ["lower((email)::text)"] }"#,
+        ));
+    }
+}
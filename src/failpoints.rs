@@ -0,0 +1,108 @@
+//! Failures injected into applies on purpose, so CI can check how a
+//! configuration recovers from them (`apply_chunk_size`,
+//! `resume_interrupted_apply`, ...) without waiting for a real outage. They
+//! only take effect with [`ENABLE_VAR`] set, so a stray provider setting can't
+//! break production applies.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Environment variable that has to be `1` or `true` for failpoints to fire.
+pub const ENABLE_VAR: &str = "PGMOLD_ENABLE_FAILPOINTS";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Failure {
+    /// The server ends the session, as when the connection drops.
+    ConnectionLost,
+    /// The statement fails to get its lock within `lock_timeout`.
+    LockTimeout,
+    /// The statement fails with an error.
+    Error,
+}
+
+impl Failure {
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "connection_lost" => Some(Failure::ConnectionLost),
+            "lock_timeout" => Some(Failure::LockTimeout),
+            "error" => Some(Failure::Error),
+            _ => None,
+        }
+    }
+
+    /// SQL that fails the way the real failure does, with the same SQLSTATE,
+    /// on the session that runs it.
+    pub fn sql(self, index: usize) -> String {
+        match self {
+            Failure::ConnectionLost => "SELECT pg_terminate_backend(pg_backend_pid())".to_string(),
+            Failure::LockTimeout => format!(
+                "DO $pgmold$ BEGIN RAISE EXCEPTION USING \
+                 ERRCODE = 'lock_not_available', \
+                 MESSAGE = 'canceling statement due to lock timeout', \
+                 HINT = 'Injected by a failpoint at statement {index}.'; END $pgmold$"
+            ),
+            Failure::Error => format!(
+                "DO $pgmold$ BEGIN RAISE EXCEPTION 'failpoint at statement {index}'; END $pgmold$"
+            ),
+        }
+    }
+}
+
+/// Failures keyed by the index of the statement they replace, counted from 0
+/// over the statements an apply runs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Failpoints {
+    at: BTreeMap<usize, Failure>,
+}
+
+impl Failpoints {
+    /// Parses `kind:index` entries, where kind is `connection_lost`,
+    /// `lock_timeout` or `error`.
+    pub fn parse<S: AsRef<str>>(specs: &[S]) -> Result<Self, String> {
+        let mut at = BTreeMap::new();
+        for spec in specs {
+            let spec = spec.as_ref();
+            let parsed = spec.split_once(':').and_then(|(kind, index)| {
+                Some((Failure::parse(kind)?, index.parse::<usize>().ok()?))
+            });
+            let Some((failure, index)) = parsed else {
+                return Err(format!(
+                    "Invalid failpoint \"{spec}\", expected connection_lost, lock_timeout or error followed by a statement index, e.g. lock_timeout:3"
+                ));
+            };
+            at.insert(index, failure);
+        }
+        Ok(Failpoints { at })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.at.is_empty()
+    }
+
+    /// The first failure among the statements in `range`, with its index.
+    pub fn within(&self, range: Range<usize>) -> Option<(usize, Failure)> {
+        self.at.range(range).next().map(|(&i, &f)| (i, f))
+    }
+}
+
+/// Whether [`ENABLE_VAR`] turns failpoints on.
+pub fn enabled() -> bool {
+    std::env::var(ENABLE_VAR).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_indexes_failures_by_statement() {
+        let failpoints = Failpoints::parse(&["lock_timeout:3", "connection_lost:7"]).unwrap();
+
+        assert_eq!(failpoints.within(0..3), None);
+        assert_eq!(failpoints.within(2..8), Some((3, Failure::LockTimeout)));
+        assert_eq!(failpoints.within(4..8), Some((7, Failure::ConnectionLost)));
+        assert!(Failpoints::parse(&["timeout:1"]).is_err());
+        assert!(Failpoints::parse(&["error"]).is_err());
+        assert!(Failpoints::parse::<&str>(&[]).unwrap().is_empty());
+    }
+}
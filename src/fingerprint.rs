@@ -0,0 +1,136 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use sqlx::PgPool;
+
+/// Definition hash per object, keyed by `schema.name`. Objects of different
+/// kinds that share a name share an entry, and triggers count towards their
+/// table.
+pub type Fingerprints = BTreeMap<String, String>;
+
+/// Catalog definitions of the tables (columns, constraints, indexes,
+/// triggers), views, functions, sequences, enums and domains in `$1`.
+const OBJECT_DEFINITIONS_QUERY: &str = r#"
+SELECT name, string_agg(COALESCE(definition, ''), E'\n' ORDER BY definition)
+  FROM (
+    SELECT n.nspname || '.' || c.relname AS name,
+           concat_ws(E'\n',
+               (SELECT string_agg(
+                           a.attname || ' ' || format_type(a.atttypid, a.atttypmod)
+                           || CASE WHEN a.attnotnull THEN ' NOT NULL' ELSE '' END
+                           || COALESCE(' DEFAULT ' || pg_get_expr(d.adbin, d.adrelid), ''),
+                           E'\n' ORDER BY a.attnum)
+                  FROM pg_attribute a
+                  LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+                 WHERE a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped),
+               (SELECT string_agg(co.conname || ' ' || pg_get_constraintdef(co.oid), E'\n' ORDER BY co.conname)
+                  FROM pg_constraint co
+                 WHERE co.conrelid = c.oid),
+               (SELECT string_agg(pg_get_indexdef(i.indexrelid), E'\n' ORDER BY i.indexrelid::regclass::text)
+                  FROM pg_index i
+                 WHERE i.indrelid = c.oid),
+               (SELECT string_agg(pg_get_triggerdef(t.oid), E'\n' ORDER BY t.tgname)
+                  FROM pg_trigger t
+                 WHERE t.tgrelid = c.oid AND NOT t.tgisinternal)) AS definition
+      FROM pg_class c
+      JOIN pg_namespace n ON n.oid = c.relnamespace
+     WHERE c.relkind IN ('r', 'p')
+       AND n.nspname = ANY($1)
+    UNION ALL
+    SELECT n.nspname || '.' || c.relname, c.relkind || ' ' || pg_get_viewdef(c.oid)
+      FROM pg_class c
+      JOIN pg_namespace n ON n.oid = c.relnamespace
+     WHERE c.relkind IN ('v', 'm')
+       AND n.nspname = ANY($1)
+    UNION ALL
+    SELECT n.nspname || '.' || p.proname, pg_get_functiondef(p.oid)
+      FROM pg_proc p
+      JOIN pg_namespace n ON n.oid = p.pronamespace
+     WHERE p.prokind IN ('f', 'p')
+       AND n.nspname = ANY($1)
+    UNION ALL
+    SELECT n.nspname || '.' || c.relname,
+           concat_ws(' ', format_type(s.seqtypid, NULL), s.seqstart, s.seqincrement,
+                     s.seqmin, s.seqmax, s.seqcache, s.seqcycle)
+      FROM pg_sequence s
+      JOIN pg_class c ON c.oid = s.seqrelid
+      JOIN pg_namespace n ON n.oid = c.relnamespace
+     WHERE n.nspname = ANY($1)
+    UNION ALL
+    SELECT n.nspname || '.' || t.typname,
+           (SELECT string_agg(e.enumlabel, E'\n' ORDER BY e.enumsortorder)
+              FROM pg_enum e
+             WHERE e.enumtypid = t.oid)
+      FROM pg_type t
+      JOIN pg_namespace n ON n.oid = t.typnamespace
+     WHERE t.typtype = 'e'
+       AND n.nspname = ANY($1)
+    UNION ALL
+    SELECT n.nspname || '.' || t.typname,
+           concat_ws(E'\n',
+               format_type(t.typbasetype, t.typtypmod)
+               || CASE WHEN t.typnotnull THEN ' NOT NULL' ELSE '' END
+               || COALESCE(' DEFAULT ' || t.typdefault, ''),
+               (SELECT string_agg(co.conname || ' ' || pg_get_constraintdef(co.oid), E'\n' ORDER BY co.conname)
+                  FROM pg_constraint co
+                 WHERE co.contypid = t.oid))
+      FROM pg_type t
+      JOIN pg_namespace n ON n.oid = t.typnamespace
+     WHERE t.typtype = 'd'
+       AND n.nspname = ANY($1)
+  ) definitions
+ GROUP BY name
+"#;
+
+/// Hashes the catalog definition of every object pgmold diffs in `schemas`.
+pub async fn object_fingerprints(pool: &PgPool, schemas: &[String]) -> sqlx::Result<Fingerprints> {
+    let rows: Vec<(String, String)> = sqlx::query_as(OBJECT_DEFINITIONS_QUERY)
+        .bind(schemas)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, definition)| (name, crate::util::compute_content_hash(&definition)))
+        .collect())
+}
+
+/// Objects that were added, removed or redefined between two fingerprint sets.
+pub fn changed_objects(stored: &Fingerprints, live: &Fingerprints) -> BTreeSet<String> {
+    stored
+        .keys()
+        .chain(live.keys())
+        .filter(|name| stored.get(*name) != live.get(*name))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprints(entries: &[(&str, &str)]) -> Fingerprints {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn changed_objects_empty_when_identical() {
+        let stored = fingerprints(&[("public.users", "a"), ("public.orders", "b")]);
+        assert!(changed_objects(&stored, &stored.clone()).is_empty());
+    }
+
+    #[test]
+    fn changed_objects_reports_modified_added_and_removed() {
+        let stored = fingerprints(&[("public.users", "a"), ("public.orders", "b")]);
+        let live = fingerprints(&[("public.users", "a2"), ("public.items", "c")]);
+
+        let changed: Vec<String> = changed_objects(&stored, &live).into_iter().collect();
+
+        assert_eq!(
+            changed,
+            vec!["public.items", "public.orders", "public.users"]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Description, FunctionSchema, Parameter, Type},
+    Diagnostics, Function,
+};
+
+use crate::operations::{classify, operation_kind};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffSqlArgs {
+    pub current_sql: String,
+    pub target_sql: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiffOperation {
+    pub kind: String,
+    pub class: String,
+    pub description: String,
+}
+
+pub struct DiffSqlFunction;
+
+#[async_trait]
+impl Function for DiffSqlFunction {
+    type Input<'a> = DiffSqlArgs;
+    type Output<'a> = Vec<DiffOperation>;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<FunctionSchema> {
+        Some(FunctionSchema {
+            parameters: vec![
+                Parameter {
+                    name: "current_sql".into(),
+                    param_type: Type::String,
+                    description: Description::plain("SQL describing the current schema"),
+                    ..Default::default()
+                },
+                Parameter {
+                    name: "target_sql".into(),
+                    param_type: Type::String,
+                    description: Description::plain("SQL describing the desired schema"),
+                    ..Default::default()
+                },
+            ],
+            return_type: Type::List(Box::new(Type::Object(map! {
+                "kind" => Type::String,
+                "class" => Type::String,
+                "description" => Type::String
+            }))),
+            summary: "Diff two schema definitions".into(),
+            description: Description::plain(
+                "Returns the operations pgmold would run to turn current_sql into target_sql",
+            ),
+            ..Default::default()
+        })
+    }
+
+    async fn call<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        params: Self::Input<'a>,
+    ) -> Option<Self::Output<'a>> {
+        let current = match pgmold::parser::parse_sql_string(&params.current_sql) {
+            Ok(s) => s,
+            Err(e) => {
+                diags.function_error(0, format!("Failed to parse current_sql: {e}"));
+                return None;
+            }
+        };
+        let target = match pgmold::parser::parse_sql_string(&params.target_sql) {
+            Ok(s) => s,
+            Err(e) => {
+                diags.function_error(1, format!("Failed to parse target_sql: {e}"));
+                return None;
+            }
+        };
+
+        let operations = pgmold::diff::compute_diff(&current, &target);
+
+        Some(
+            operations
+                .iter()
+                .map(|op| DiffOperation {
+                    kind: operation_kind(op),
+                    class: classify(op).as_str().to_string(),
+                    description: format!("{op:?}"),
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn diff_sql_reports_added_table() {
+        let mut diags = Diagnostics::default();
+        let operations = DiffSqlFunction
+            .call(
+                &mut diags,
+                DiffSqlArgs {
+                    current_sql: String::new(),
+                    target_sql: "CREATE TABLE users (id INT PRIMARY KEY);".to_string(),
+                },
+            )
+            .await
+            .expect("call should succeed");
+
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].kind, "CreateTable");
+        assert_eq!(operations[0].class, "additive");
+    }
+
+    #[tokio::test]
+    async fn diff_sql_reports_dropped_table_as_destructive() {
+        let mut diags = Diagnostics::default();
+        let operations = DiffSqlFunction
+            .call(
+                &mut diags,
+                DiffSqlArgs {
+                    current_sql: "CREATE TABLE users (id INT PRIMARY KEY);".to_string(),
+                    target_sql: String::new(),
+                },
+            )
+            .await
+            .expect("call should succeed");
+
+        assert!(operations.iter().any(|op| op.class == "destructive"));
+    }
+
+    #[tokio::test]
+    async fn diff_sql_identical_schemas_is_empty() {
+        let sql = "CREATE TABLE users (id INT PRIMARY KEY);".to_string();
+        let mut diags = Diagnostics::default();
+        let operations = DiffSqlFunction
+            .call(
+                &mut diags,
+                DiffSqlArgs {
+                    current_sql: sql.clone(),
+                    target_sql: sql,
+                },
+            )
+            .await
+            .expect("call should succeed");
+
+        assert!(operations.is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+pub mod database_url;
+pub mod diff_sql;
+pub mod parse_ddl;
+pub mod quote;
+pub mod redact_url;
+pub mod schema_hash;
+
+pub use database_url::DatabaseUrlFunction;
+pub use diff_sql::DiffSqlFunction;
+pub use parse_ddl::ParseDdlFunction;
+pub use quote::{QuoteIdentFunction, QuoteLiteralFunction};
+pub use redact_url::RedactUrlFunction;
+pub use schema_hash::SchemaHashFunction;
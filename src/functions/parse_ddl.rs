@@ -0,0 +1,182 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Description, FunctionSchema, Parameter, Type},
+    Diagnostics, Function,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParseDdlArgs {
+    pub sql: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParsedColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: String,
+    pub nullable: bool,
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParsedIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParsedTable {
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<ParsedColumn>,
+    pub indexes: Vec<ParsedIndex>,
+}
+
+pub struct ParseDdlFunction;
+
+#[async_trait]
+impl Function for ParseDdlFunction {
+    type Input<'a> = ParseDdlArgs;
+    type Output<'a> = Vec<ParsedTable>;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<FunctionSchema> {
+        Some(FunctionSchema {
+            parameters: vec![Parameter {
+                name: "sql".into(),
+                param_type: Type::String,
+                description: Description::plain("SQL DDL to parse, e.g. from file()"),
+                ..Default::default()
+            }],
+            return_type: Type::List(Box::new(Type::Object(map! {
+                "schema" => Type::String,
+                "name" => Type::String,
+                "columns" => Type::List(Box::new(Type::Object(map! {
+                    "name" => Type::String,
+                    "type" => Type::String,
+                    "nullable" => Type::Bool,
+                    "default" => Type::String
+                }))),
+                "indexes" => Type::List(Box::new(Type::Object(map! {
+                    "name" => Type::String,
+                    "columns" => Type::List(Box::new(Type::String)),
+                    "unique" => Type::Bool
+                })))
+            }))),
+            summary: "Parse SQL DDL".into(),
+            description: Description::plain(
+                "Parses SQL DDL with pgmold and returns its tables with their columns and indexes",
+            ),
+            ..Default::default()
+        })
+    }
+
+    async fn call<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        params: Self::Input<'a>,
+    ) -> Option<Self::Output<'a>> {
+        match parse_tables(&params.sql) {
+            Ok(tables) => Some(tables),
+            Err(e) => {
+                diags.function_error(0, format!("Failed to parse SQL: {e}"));
+                None
+            }
+        }
+    }
+}
+
+pub fn parse_tables(sql: &str) -> anyhow::Result<Vec<ParsedTable>> {
+    let schema = pgmold::parser::parse_sql_string(sql)?;
+
+    Ok(schema
+        .tables
+        .values()
+        .map(|table| ParsedTable {
+            schema: table.schema.clone(),
+            name: table.name.clone(),
+            columns: table
+                .columns
+                .values()
+                .map(|column| ParsedColumn {
+                    name: column.name.clone(),
+                    data_type: crate::schema_docs::type_name(&column.data_type),
+                    nullable: column.nullable,
+                    default: column.default.clone(),
+                })
+                .collect(),
+            indexes: table
+                .indexes
+                .iter()
+                .map(|index| ParsedIndex {
+                    name: index.name.clone(),
+                    columns: index.columns.clone(),
+                    unique: index.unique,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tables_returns_columns_and_indexes() {
+        let tables = parse_tables(
+            "CREATE TABLE users (id INT PRIMARY KEY, email TEXT NOT NULL);\n\
+             CREATE UNIQUE INDEX users_email_idx ON users (email);",
+        )
+        .unwrap();
+
+        assert_eq!(tables.len(), 1);
+        let users = &tables[0];
+        assert_eq!(users.name, "users");
+        assert_eq!(users.columns.len(), 2);
+        assert!(users
+            .columns
+            .iter()
+            .any(|c| c.name == "email" && !c.nullable));
+        assert!(users
+            .columns
+            .iter()
+            .any(|c| c.name == "id" && c.data_type == "integer"));
+        assert_eq!(users.indexes.len(), 1);
+        assert!(users.indexes[0].unique);
+        assert_eq!(users.indexes[0].columns, vec!["email".to_string()]);
+    }
+
+    #[test]
+    fn parse_tables_renders_parameterized_types() {
+        let tables =
+            parse_tables("CREATE TABLE accounts (name VARCHAR(255) NOT NULL, bio VARCHAR);")
+                .unwrap();
+
+        let types: Vec<(&str, &str)> = tables[0]
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.as_str()))
+            .collect();
+        assert!(types.contains(&("name", "varchar(255)")));
+        assert!(types.contains(&("bio", "varchar")));
+    }
+
+    #[tokio::test]
+    async fn parse_ddl_reports_invalid_sql_on_argument() {
+        let mut diags = Diagnostics::default();
+        let result = ParseDdlFunction
+            .call(
+                &mut diags,
+                ParseDdlArgs {
+                    sql: "CREATE TABLE (".to_string(),
+                },
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(diags.errors.len(), 1);
+    }
+}
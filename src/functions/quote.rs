@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    schema::{Description, FunctionSchema, Parameter, Type},
+    Diagnostics, Function,
+};
+
+/// Quotes an identifier, doubling embedded double quotes. Identifiers are always quoted so
+/// reserved words and mixed-case names are safe.
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes a string literal like PostgreSQL's `quote_literal`, switching to an `E''` literal
+/// when the value contains backslashes.
+pub fn quote_literal(value: &str) -> String {
+    let escaped = value.replace('\'', "''");
+    if escaped.contains('\\') {
+        format!("E'{}'", escaped.replace('\\', "\\\\"))
+    } else {
+        format!("'{escaped}'")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteIdentArgs {
+    pub name: String,
+}
+
+pub struct QuoteIdentFunction;
+
+#[async_trait]
+impl Function for QuoteIdentFunction {
+    type Input<'a> = QuoteIdentArgs;
+    type Output<'a> = String;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<FunctionSchema> {
+        Some(FunctionSchema {
+            parameters: vec![Parameter {
+                name: "name".into(),
+                param_type: Type::String,
+                description: Description::plain("Identifier to quote"),
+                ..Default::default()
+            }],
+            return_type: Type::String,
+            summary: "Quote a SQL identifier".into(),
+            description: Description::plain(
+                "Wraps an identifier in double quotes, escaping embedded quotes",
+            ),
+            ..Default::default()
+        })
+    }
+
+    async fn call<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        params: Self::Input<'a>,
+    ) -> Option<Self::Output<'a>> {
+        Some(quote_ident(&params.name))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteLiteralArgs {
+    pub value: String,
+}
+
+pub struct QuoteLiteralFunction;
+
+#[async_trait]
+impl Function for QuoteLiteralFunction {
+    type Input<'a> = QuoteLiteralArgs;
+    type Output<'a> = String;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<FunctionSchema> {
+        Some(FunctionSchema {
+            parameters: vec![Parameter {
+                name: "value".into(),
+                param_type: Type::String,
+                description: Description::plain("Value to quote as a string literal"),
+                ..Default::default()
+            }],
+            return_type: Type::String,
+            summary: "Quote a SQL string literal".into(),
+            description: Description::plain(
+                "Wraps a value in single quotes, escaping quotes and backslashes",
+            ),
+            ..Default::default()
+        })
+    }
+
+    async fn call<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        params: Self::Input<'a>,
+    ) -> Option<Self::Output<'a>> {
+        Some(quote_literal(&params.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_ident_wraps_plain_names() {
+        assert_eq!(quote_ident("users"), "\"users\"");
+    }
+
+    #[test]
+    fn quote_ident_escapes_double_quotes() {
+        assert_eq!(quote_ident("we\"ird"), "\"we\"\"ird\"");
+    }
+
+    #[test]
+    fn quote_literal_escapes_single_quotes() {
+        assert_eq!(quote_literal("O'Brien"), "'O''Brien'");
+    }
+
+    #[test]
+    fn quote_literal_uses_escape_string_for_backslashes() {
+        assert_eq!(quote_literal("C:\\temp"), "E'C:\\\\temp'");
+    }
+}
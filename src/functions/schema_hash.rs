@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    schema::{Description, FunctionSchema, Parameter, Type},
+    Diagnostics, Function,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaHashArgs {
+    pub content: String,
+}
+
+pub struct SchemaHashFunction;
+
+#[async_trait]
+impl Function for SchemaHashFunction {
+    type Input<'a> = SchemaHashArgs;
+    type Output<'a> = String;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<FunctionSchema> {
+        Some(FunctionSchema {
+            parameters: vec![Parameter {
+                name: "content".into(),
+                param_type: Type::String,
+                description: Description::plain("SQL schema content, e.g. from file()"),
+                ..Default::default()
+            }],
+            return_type: Type::String,
+            summary: "Hash schema content".into(),
+            description: Description::plain(
+                "Computes the same SHA256 hash that pgmold resources store in schema_hash",
+            ),
+            ..Default::default()
+        })
+    }
+
+    async fn call<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        params: Self::Input<'a>,
+    ) -> Option<Self::Output<'a>> {
+        Some(crate::util::compute_content_hash(
+            &crate::util::normalize_schema_text(&params.content),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn schema_hash_matches_resource_hash() {
+        let content = "CREATE TABLE users (id INT PRIMARY KEY);\n";
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{content}").unwrap();
+
+        let mut diags = Diagnostics::default();
+        let hash = SchemaHashFunction
+            .call(
+                &mut diags,
+                SchemaHashArgs {
+                    content: content.to_string(),
+                },
+            )
+            .await
+            .expect("call should succeed");
+
+        assert_eq!(hash, crate::util::compute_schema_hash(file.path()).unwrap());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha384};
+use sqlx::PgPool;
+use tf_provider::{AttributePath, Diagnostics};
+
+/// Migration runners whose history tables can reveal out-of-band changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryRunner {
+    Flyway,
+    Liquibase,
+    Sqlx,
+}
+
+impl HistoryRunner {
+    pub const NAMES: [&'static str; 3] = ["flyway", "liquibase", "sqlx"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "flyway" => Some(HistoryRunner::Flyway),
+            "liquibase" => Some(HistoryRunner::Liquibase),
+            "sqlx" => Some(HistoryRunner::Sqlx),
+            _ => None,
+        }
+    }
+
+    pub fn table(self) -> &'static str {
+        match self {
+            HistoryRunner::Flyway => "flyway_schema_history",
+            HistoryRunner::Liquibase => "databasechangelog",
+            HistoryRunner::Sqlx => "_sqlx_migrations",
+        }
+    }
+
+    /// When a migration ran, as a `timestamptz`. Flyway and Liquibase record it
+    /// as a `timestamp` without time zone, in the local time of the runner's
+    /// session; that is taken to be UTC, so the comparison doesn't depend on
+    /// the TimeZone of the provider's own session.
+    fn applied_at_expression(self) -> &'static str {
+        match self {
+            HistoryRunner::Flyway => "installed_on AT TIME ZONE 'UTC'",
+            HistoryRunner::Liquibase => "dateexecuted AT TIME ZONE 'UTC'",
+            HistoryRunner::Sqlx => "installed_on",
+        }
+    }
+}
+
+/// Counts migrations the runner recorded after `since` (RFC 3339). Returns 0 when
+/// the history table doesn't exist on the search path.
+pub async fn applied_since(pool: &PgPool, runner: HistoryRunner, since: &str) -> sqlx::Result<i64> {
+    let (exists,): (bool,) = sqlx::query_as("SELECT to_regclass($1) IS NOT NULL")
+        .bind(runner.table())
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(0);
+    }
+
+    let sql = format!(
+        "SELECT count(*) FROM {} WHERE {} > $1::timestamptz",
+        runner.table(),
+        runner.applied_at_expression()
+    );
+    let (count,): (i64,) = sqlx::query_as(&sql).bind(since).fetch_one(pool).await?;
+    Ok(count)
+}
+
+/// Checksums the runner stored for applied migrations, keyed like
+/// [`migration_key`]. `None` when the history table doesn't exist, or the
+/// runner's checksums don't cover whole files (Liquibase hashes change sets).
+pub async fn applied_checksums(
+    pool: &PgPool,
+    runner: HistoryRunner,
+) -> sqlx::Result<Option<BTreeMap<String, String>>> {
+    let sql = match runner {
+        HistoryRunner::Flyway => {
+            "SELECT script, checksum::text FROM flyway_schema_history WHERE success AND checksum IS NOT NULL"
+        }
+        HistoryRunner::Sqlx => {
+            "SELECT version::text, encode(checksum, 'hex') FROM _sqlx_migrations WHERE success"
+        }
+        HistoryRunner::Liquibase => return Ok(None),
+    };
+    let (exists,): (bool,) = sqlx::query_as("SELECT to_regclass($1) IS NOT NULL")
+        .bind(runner.table())
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(None);
+    }
+    let rows: Vec<(String, String)> = sqlx::query_as(sql).fetch_all(pool).await?;
+    Ok(Some(rows.into_iter().collect()))
+}
+
+/// How the runner identifies a migration file in its history: Flyway by file
+/// name, sqlx by the version number the name starts with.
+pub fn migration_key(runner: HistoryRunner, file_name: &str) -> Option<String> {
+    match runner {
+        HistoryRunner::Flyway => Some(file_name.to_string()),
+        HistoryRunner::Sqlx => file_name
+            .split('_')
+            .next()
+            .and_then(|version| version.parse::<i64>().ok())
+            .map(|version| version.to_string()),
+        HistoryRunner::Liquibase => None,
+    }
+}
+
+/// The checksum the runner would store for a migration file with `content`.
+pub fn file_checksum(runner: HistoryRunner, content: &str) -> Option<String> {
+    match runner {
+        // CRC32 over the lines without their terminators, as a signed int
+        HistoryRunner::Flyway => {
+            let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+            let mut crc = Crc32::default();
+            for line in content
+                .split_inclusive('\n')
+                .flat_map(|l| l.split_inclusive('\r'))
+            {
+                crc.update(line.trim_end_matches(['\r', '\n']).as_bytes());
+            }
+            Some((crc.finish() as i32).to_string())
+        }
+        HistoryRunner::Sqlx => Some(format!("{:x}", Sha384::digest(content.as_bytes()))),
+        HistoryRunner::Liquibase => None,
+    }
+}
+
+/// Applied migrations among `files` (name and content) whose content no longer
+/// matches the checksum in the history.
+pub fn changed_migrations(
+    runner: HistoryRunner,
+    files: &[(String, String)],
+    applied: &BTreeMap<String, String>,
+) -> Vec<String> {
+    files
+        .iter()
+        .filter(|(name, content)| {
+            let stored = migration_key(runner, name).and_then(|key| applied.get(&key));
+            stored.is_some_and(|stored| file_checksum(runner, content).as_ref() != Some(stored))
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[derive(Default)]
+struct Crc32(u32);
+
+impl Crc32 {
+    fn update(&mut self, bytes: &[u8]) {
+        let mut crc = !self.0;
+        for &byte in bytes {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+            }
+        }
+        self.0 = !crc;
+    }
+
+    fn finish(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Reports an unknown `migration_history` value on the attribute.
+pub fn check_migration_history(diags: &mut Diagnostics, runner: Option<&str>) -> Option<()> {
+    match runner {
+        Some(name) if HistoryRunner::parse(name).is_none() => {
+            diags.error_short(
+                format!(
+                    "Unknown migration_history \"{name}\", expected one of: {}",
+                    HistoryRunner::NAMES.join(", ")
+                ),
+                AttributePath::new("migration_history"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_known_runners() {
+        for name in HistoryRunner::NAMES {
+            assert!(HistoryRunner::parse(name).is_some(), "{name}");
+        }
+        assert_eq!(HistoryRunner::parse("alembic"), None);
+    }
+
+    #[test]
+    fn file_checksum_matches_runners() {
+        // CRC32 of "123456789", the standard check value, as Flyway stores it
+        assert_eq!(
+            file_checksum(HistoryRunner::Flyway, "123456789").as_deref(),
+            Some("-873187034")
+        );
+        // Line endings and a BOM don't change Flyway checksums
+        assert_eq!(
+            file_checksum(HistoryRunner::Flyway, "\u{feff}SELECT 1;\r\nSELECT 2;\r\n"),
+            file_checksum(HistoryRunner::Flyway, "SELECT 1;\nSELECT 2;")
+        );
+        assert_eq!(
+            file_checksum(HistoryRunner::Sqlx, "").as_deref(),
+            Some("38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b")
+        );
+        assert_eq!(file_checksum(HistoryRunner::Liquibase, ""), None);
+    }
+
+    #[test]
+    fn changed_migrations_finds_edited_files() {
+        let files = vec![
+            (
+                "0001_20240101.sql".to_string(),
+                "CREATE TABLE a ();".to_string(),
+            ),
+            (
+                "0002_20240102.sql".to_string(),
+                "CREATE TABLE b ();".to_string(),
+            ),
+            (
+                "0003_20240103.sql".to_string(),
+                "CREATE TABLE c ();".to_string(),
+            ),
+        ];
+        let applied = BTreeMap::from([
+            (
+                "1".to_string(),
+                file_checksum(HistoryRunner::Sqlx, "CREATE TABLE a ();").unwrap(),
+            ),
+            (
+                "2".to_string(),
+                file_checksum(HistoryRunner::Sqlx, "CREATE TABLE b (id int);").unwrap(),
+            ),
+        ]);
+        assert_eq!(
+            changed_migrations(HistoryRunner::Sqlx, &files, &applied),
+            vec!["0002_20240102.sql"]
+        );
+    }
+
+    #[test]
+    fn runner_tables() {
+        assert_eq!(HistoryRunner::Flyway.table(), "flyway_schema_history");
+        assert_eq!(HistoryRunner::Liquibase.table(), "databasechangelog");
+        assert_eq!(HistoryRunner::Sqlx.table(), "_sqlx_migrations");
+    }
+}
@@ -1,6 +1,49 @@
+pub mod backfill;
+pub mod backup;
+pub mod claims;
+pub mod clock;
+pub mod confirmation;
+pub mod connections;
+pub mod data_sources;
+pub mod dialect;
+pub mod encryption;
+pub mod enums;
+pub mod estimate;
+pub mod execute;
+pub mod execution_log;
+pub mod expressions;
+pub mod failpoints;
+pub mod fingerprint;
+pub mod functions;
+pub mod history;
+pub mod lint_rules;
+pub mod logging;
+pub mod masking;
+pub mod object_filter;
+pub mod operation_policy;
+pub mod operations;
+pub mod ordering;
+pub mod ownership;
+pub mod pipeline;
+pub mod plan_output;
+pub mod progress;
 mod provider;
+pub mod rebuild;
+pub mod recreation;
 pub mod resources;
+pub mod run_metadata;
+pub mod schema_docs;
+pub mod seed;
+pub mod server_version;
+pub mod signing;
+pub mod statements;
+pub mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod throttle;
 pub mod util;
+pub mod validation;
+pub mod window;
 
 pub use provider::{PgmoldProvider, ProviderConfig};
 pub use resources::SchemaResource;
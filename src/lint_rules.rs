@@ -0,0 +1,174 @@
+//! House lint rules configured on the provider, such as "no new columns of
+//! type money". Each matches generated statements or diff operations with a
+//! regex and fires alongside pgmold's built-in rules, with the same effect:
+//! errors block the apply, warnings are reported in `lint_results`.
+
+use std::collections::HashMap;
+
+use pgmold::diff::MigrationOp;
+use pgmold::lint::{LintResult, LintSeverity};
+use regex::Regex;
+
+const KEYS: &[&str] = &["name", "severity", "message", "statement", "operation"];
+
+/// Longest excerpt of the matching statement or operation in a message.
+const EXCERPT_CHARS: usize = 120;
+
+#[derive(Debug, Clone)]
+pub struct LintRule {
+    name: String,
+    error: bool,
+    message: String,
+    /// Matched against each generated SQL statement.
+    statement: Option<Regex>,
+    /// Matched against each operation as `plan_output_path` details it, e.g.
+    /// `AddColumn { table: "users", ... }`.
+    operation: Option<Regex>,
+}
+
+impl LintRule {
+    fn parse(rule: &HashMap<String, String>) -> Result<Self, String> {
+        if let Some(key) = rule.keys().find(|k| !KEYS.contains(&k.as_str())) {
+            return Err(format!(
+                "Unknown lint rule key \"{key}\", expected one of {}",
+                KEYS.join(", ")
+            ));
+        }
+        let Some(name) = rule.get("name").filter(|n| !n.is_empty()) else {
+            return Err("Every lint rule needs a name".to_string());
+        };
+        let error = match rule.get("severity").map(String::as_str) {
+            None | Some("error") => true,
+            Some("warning") => false,
+            Some(other) => {
+                return Err(format!(
+                    "Invalid severity \"{other}\" for lint rule {name}, expected error or warning"
+                ))
+            }
+        };
+        let regex = |key: &str| {
+            rule.get(key)
+                .map(|pattern| {
+                    Regex::new(pattern)
+                        .map_err(|e| format!("Invalid {key} pattern for lint rule {name}: {e}"))
+                })
+                .transpose()
+        };
+        let statement = regex("statement")?;
+        let operation = regex("operation")?;
+        if statement.is_none() && operation.is_none() {
+            return Err(format!(
+                "Lint rule {name} needs a statement or operation pattern"
+            ));
+        }
+        Ok(LintRule {
+            name: name.clone(),
+            error,
+            message: rule
+                .get("message")
+                .cloned()
+                .unwrap_or_else(|| format!("Matches lint rule {name}")),
+            statement,
+            operation,
+        })
+    }
+
+    fn result(&self, matched: &str) -> LintResult {
+        let mut excerpt: String = matched.chars().take(EXCERPT_CHARS).collect();
+        if excerpt.len() < matched.len() {
+            excerpt.push('…');
+        }
+        LintResult {
+            rule: self.name.clone(),
+            severity: if self.error {
+                LintSeverity::Error
+            } else {
+                LintSeverity::Warning
+            },
+            message: format!("{}: {excerpt}", self.message),
+        }
+    }
+}
+
+/// Parses the provider's `lint_rules`.
+pub fn parse(rules: &[HashMap<String, String>]) -> Result<Vec<LintRule>, String> {
+    rules.iter().map(LintRule::parse).collect()
+}
+
+/// One result per operation and per statement each rule matches.
+pub fn evaluate(
+    rules: &[LintRule],
+    operations: &[MigrationOp],
+    statements: &[String],
+) -> Vec<LintResult> {
+    let mut results = Vec::new();
+    for rule in rules {
+        if let Some(pattern) = &rule.operation {
+            for op in operations {
+                let detail = format!("{op:?}");
+                if pattern.is_match(&detail) {
+                    results.push(rule.result(&detail));
+                }
+            }
+        }
+        if let Some(pattern) = &rule.statement {
+            for statement in statements {
+                let code = crate::statements::code_only(statement);
+                if pattern.is_match(&code) {
+                    results.push(rule.result(statement.trim()));
+                }
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn evaluate_reports_matching_statements() {
+        let rules = parse(&[rule(&[
+            ("name", "no_money"),
+            ("message", "Use numeric for amounts"),
+            ("statement", r"(?i)\bmoney\b"),
+        ])])
+        .unwrap();
+        let statements = vec![
+            "ALTER TABLE orders ADD COLUMN total money".to_string(),
+            "-- money is tracked elsewhere\nCREATE TABLE refunds (id bigint)".to_string(),
+        ];
+
+        let results = evaluate(&rules, &[], &statements);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].rule, "no_money");
+        assert_eq!(results[0].severity, LintSeverity::Error);
+        assert_eq!(
+            results[0].message,
+            "Use numeric for amounts: ALTER TABLE orders ADD COLUMN total money"
+        );
+    }
+
+    #[test]
+    fn parse_rejects_incomplete_rules() {
+        assert!(parse(&[rule(&[("statement", "money")])]).is_err());
+        assert!(parse(&[rule(&[("name", "no_money")])]).is_err());
+        assert!(parse(&[rule(&[("name", "x"), ("statement", "(")])]).is_err());
+        assert!(parse(&[rule(&[("name", "x"), ("sql", "money")])]).is_err());
+        assert!(parse(&[rule(&[
+            ("name", "x"),
+            ("operation", "^DropTable"),
+            ("severity", "critical")
+        ])])
+        .is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Installs a stderr logger when `TF_LOG_PROVIDER` or `TF_LOG` asks for it. Lines
+/// use the JSON format Terraform's plugin host parses, so levels and fields survive.
+/// `PLUGIN_LOG_FILE` is left to tf-provider, which installs its own subscriber.
+pub fn init() {
+    if std::env::var_os("PLUGIN_LOG_FILE").is_some() {
+        return;
+    }
+    let Some(level) = std::env::var("TF_LOG_PROVIDER")
+        .or_else(|_| std::env::var("TF_LOG"))
+        .ok()
+        .and_then(|value| parse_level(&value))
+    else {
+        return;
+    };
+
+    let _ = tracing_subscriber::fmt()
+        .event_format(HclogFormat)
+        .with_writer(std::io::stderr)
+        .with_max_level(level)
+        .try_init();
+}
+
+/// Maps a `TF_LOG` value to a level filter. `JSON` is Terraform's alias for trace.
+pub fn parse_level(value: &str) -> Option<LevelFilter> {
+    match value.to_ascii_uppercase().as_str() {
+        "TRACE" | "JSON" => Some(LevelFilter::TRACE),
+        "DEBUG" => Some(LevelFilter::DEBUG),
+        "INFO" => Some(LevelFilter::INFO),
+        "WARN" => Some(LevelFilter::WARN),
+        "ERROR" => Some(LevelFilter::ERROR),
+        "OFF" => Some(LevelFilter::OFF),
+        _ => None,
+    }
+}
+
+/// Formats events as `{"@level", "@message", "@module", "@timestamp", ...fields}`.
+struct HclogFormat;
+
+impl<S, N> FormatEvent<S, N> for HclogFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut record = serde_json::Map::new();
+        record.insert("@level".into(), level_name(*metadata.level()).into());
+        record.insert("@message".into(), "".into());
+        record.insert(
+            "@module".into(),
+            format!("pgmold.{}", metadata.target().replace("::", ".")).into(),
+        );
+        record.insert(
+            "@timestamp".into(),
+            chrono::Utc::now()
+                .format("%Y-%m-%dT%H:%M:%S%.6f%:z")
+                .to_string()
+                .into(),
+        );
+        event.record(&mut JsonVisitor(&mut record));
+
+        let line = serde_json::to_string(&record).map_err(|_| fmt::Error)?;
+        writeln!(writer, "{line}")
+    }
+}
+
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::TRACE => "trace",
+        Level::DEBUG => "debug",
+        Level::INFO => "info",
+        Level::WARN => "warn",
+        Level::ERROR => "error",
+    }
+}
+
+struct JsonVisitor<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl JsonVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        let key = match field.name() {
+            "message" => "@message",
+            name => name,
+        };
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{value:?}").into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn parse_level_accepts_terraform_values() {
+        assert_eq!(parse_level("debug"), Some(LevelFilter::DEBUG));
+        assert_eq!(parse_level("JSON"), Some(LevelFilter::TRACE));
+        assert_eq!(parse_level("verbose"), None);
+    }
+
+    #[test]
+    fn events_are_formatted_as_hclog_json() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .event_format(HclogFormat)
+            .with_writer(move || SharedWriter(writer.clone()))
+            .with_max_level(LevelFilter::DEBUG)
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(statement_index = 3, "executing statement");
+        });
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        let record: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(record["@level"], "debug");
+        assert_eq!(record["@message"], "executing statement");
+        assert_eq!(record["statement_index"], 3);
+    }
+
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
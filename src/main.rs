@@ -2,6 +2,7 @@ use terraform_provider_pgmold::PgmoldProvider;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> anyhow::Result<()> {
+    terraform_provider_pgmold::logging::init();
     tf_provider::serve("pgmold", PgmoldProvider::default()).await?;
     Ok(())
 }
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+use tf_provider::{AttributePath, Diagnostics};
+
+use crate::functions::quote::{quote_ident, quote_literal};
+
+const FIRST_NAMES: [&str; 8] = [
+    "Alex", "Sam", "Jordan", "Taylor", "Morgan", "Casey", "Riley", "Jamie",
+];
+const LAST_NAMES: [&str; 8] = [
+    "Silva", "Smith", "Garcia", "Chen", "Novak", "Kowalski", "Ibrahim", "Tanaka",
+];
+
+/// How a masked column's values are replaced. Generated values derive from a
+/// SHA256 of the original, so equal inputs mask to equal outputs and joins on
+/// masked columns still line up. NULLs stay NULL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskStrategy {
+    Null,
+    /// Hex SHA256 of the value.
+    Hash,
+    /// `user_<hash prefix>@example.com`.
+    Email,
+    /// A first and last name picked by the hash.
+    Name,
+    /// `+1-555-` followed by seven digits.
+    Phone,
+}
+
+impl MaskStrategy {
+    pub const NAMES: [&'static str; 5] = ["null", "hash", "email", "name", "phone"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "null" => Some(MaskStrategy::Null),
+            "hash" => Some(MaskStrategy::Hash),
+            "email" => Some(MaskStrategy::Email),
+            "name" => Some(MaskStrategy::Name),
+            "phone" => Some(MaskStrategy::Phone),
+            _ => None,
+        }
+    }
+
+    /// Masks one value the way [`MaskStrategy::sql`] does in the database.
+    pub fn mask(&self, value: Option<&str>) -> Option<String> {
+        let hash = format!("{:x}", Sha256::digest(value?.as_bytes()));
+        let hex = |range: std::ops::Range<usize>| u32::from_str_radix(&hash[range], 16).unwrap();
+        match self {
+            MaskStrategy::Null => None,
+            MaskStrategy::Hash => Some(hash),
+            MaskStrategy::Email => Some(format!("user_{}@example.com", &hash[..12])),
+            MaskStrategy::Name => Some(format!(
+                "{} {}",
+                FIRST_NAMES[hex(0..1) as usize % 8],
+                LAST_NAMES[hex(1..2) as usize % 8]
+            )),
+            MaskStrategy::Phone => Some(format!("+1-555-{:07}", hex(0..7) % 10_000_000)),
+        }
+    }
+
+    /// SQL expression computing the masked value of `column`.
+    fn sql(&self, column: &str) -> String {
+        let hash = format!("encode(sha256(convert_to({column}::text, 'UTF8')), 'hex')");
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|n| quote_literal(n))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            MaskStrategy::Null => "NULL".to_string(),
+            MaskStrategy::Hash => hash,
+            MaskStrategy::Email => format!("'user_' || left({hash}, 12) || '@example.com'"),
+            MaskStrategy::Name => format!(
+                "(ARRAY[{}])[('x' || substr({hash}, 1, 1))::bit(4)::int % 8 + 1] || ' ' || \
+                 (ARRAY[{}])[('x' || substr({hash}, 2, 1))::bit(4)::int % 8 + 1]",
+                names(&FIRST_NAMES),
+                names(&LAST_NAMES)
+            ),
+            MaskStrategy::Phone => format!(
+                "'+1-555-' || lpad((('x' || substr({hash}, 1, 7))::bit(28)::int % 10000000)::text, 7, '0')"
+            ),
+        }
+    }
+}
+
+/// One `masking` entry: a column of a `schema.table` and its strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskingRule {
+    pub table: String,
+    pub column: String,
+    pub strategy: MaskStrategy,
+}
+
+/// Parses `masking`, keyed by `table.column` (in `public`) or
+/// `schema.table.column`, with a strategy name as the value.
+pub fn parse_rules(masking: &BTreeMap<String, String>) -> Result<Vec<MaskingRule>, String> {
+    masking
+        .iter()
+        .map(|(key, strategy)| {
+            let strategy = MaskStrategy::parse(strategy).ok_or_else(|| {
+                format!(
+                    "Unknown masking strategy \"{strategy}\" for {key}, expected one of: {}",
+                    MaskStrategy::NAMES.join(", ")
+                )
+            })?;
+            let (table, column) = match key.split('.').collect::<Vec<_>>()[..] {
+                [table, column] => (format!("public.{table}"), column),
+                [schema, table, column] => (format!("{schema}.{table}"), column),
+                _ => {
+                    return Err(format!(
+                    "Invalid masking key \"{key}\", expected table.column or schema.table.column"
+                ))
+                }
+            };
+            Ok(MaskingRule {
+                table,
+                column: column.to_string(),
+                strategy,
+            })
+        })
+        .collect()
+}
+
+/// Reports an invalid `masking` entry on the attribute.
+pub fn check_masking(
+    diags: &mut Diagnostics,
+    masking: Option<&BTreeMap<String, String>>,
+) -> Option<()> {
+    if let Err(e) = masking.map(parse_rules).transpose() {
+        diags.error_short(e, AttributePath::new("masking"));
+        return None;
+    }
+    Some(())
+}
+
+/// `UPDATE` statements masking the rules' columns in place, one per table.
+pub fn update_statements(rules: &[MaskingRule]) -> Vec<String> {
+    let mut by_table: BTreeMap<&str, Vec<&MaskingRule>> = BTreeMap::new();
+    for rule in rules {
+        by_table.entry(&rule.table).or_default().push(rule);
+    }
+    by_table
+        .into_iter()
+        .map(|(table, rules)| {
+            let table = table
+                .split('.')
+                .map(quote_ident)
+                .collect::<Vec<_>>()
+                .join(".");
+            let assignments = rules
+                .iter()
+                .map(|rule| {
+                    let column = quote_ident(&rule.column);
+                    format!("{column} = {}", rule.strategy.sql(&column))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("UPDATE {table} SET {assignments}")
+        })
+        .collect()
+}
+
+/// Masks CSV rows of `table` whose header names a masked column. Returns
+/// whether anything was masked.
+pub fn mask_rows(
+    table: &str,
+    header: &[String],
+    rows: &mut [Vec<Option<String>>],
+    rules: &[MaskingRule],
+) -> bool {
+    let masked: Vec<(usize, MaskStrategy)> = rules
+        .iter()
+        .filter(|rule| rule.table == table)
+        .filter_map(|rule| {
+            let index = header.iter().position(|c| *c == rule.column)?;
+            Some((index, rule.strategy))
+        })
+        .collect();
+    for row in rows.iter_mut() {
+        for (index, strategy) in &masked {
+            if let Some(value) = row.get_mut(*index) {
+                *value = strategy.mask(value.as_deref());
+            }
+        }
+    }
+    !masked.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules_qualifies_tables() {
+        let masking = BTreeMap::from([
+            ("users.email".to_string(), "email".to_string()),
+            ("crm.contacts.phone".to_string(), "phone".to_string()),
+        ]);
+        assert_eq!(
+            parse_rules(&masking).unwrap(),
+            vec![
+                MaskingRule {
+                    table: "crm.contacts".to_string(),
+                    column: "phone".to_string(),
+                    strategy: MaskStrategy::Phone,
+                },
+                MaskingRule {
+                    table: "public.users".to_string(),
+                    column: "email".to_string(),
+                    strategy: MaskStrategy::Email,
+                },
+            ]
+        );
+        let unknown = BTreeMap::from([("users.email".to_string(), "scramble".to_string())]);
+        assert!(parse_rules(&unknown).unwrap_err().contains("scramble"));
+        let bare = BTreeMap::from([("email".to_string(), "null".to_string())]);
+        assert!(parse_rules(&bare).is_err());
+    }
+
+    #[test]
+    fn mask_is_deterministic_and_keeps_nulls() {
+        let email = MaskStrategy::Email.mask(Some("ana@corp.pt")).unwrap();
+        assert_eq!(
+            MaskStrategy::Email.mask(Some("ana@corp.pt")).unwrap(),
+            email
+        );
+        assert!(email.starts_with("user_") && email.ends_with("@example.com"));
+        assert_eq!(
+            MaskStrategy::Hash.mask(Some("abc")).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(MaskStrategy::Null.mask(Some("abc")), None);
+        assert_eq!(MaskStrategy::Name.mask(None), None);
+        assert_eq!(
+            MaskStrategy::Phone.mask(Some("abc")).unwrap().len(),
+            "+1-555-".len() + 7
+        );
+    }
+
+    #[test]
+    fn update_statements_group_columns_by_table() {
+        let rules = vec![
+            MaskingRule {
+                table: "public.users".to_string(),
+                column: "email".to_string(),
+                strategy: MaskStrategy::Email,
+            },
+            MaskingRule {
+                table: "public.users".to_string(),
+                column: "notes".to_string(),
+                strategy: MaskStrategy::Null,
+            },
+        ];
+        let statements = update_statements(&rules);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].starts_with("UPDATE \"public\".\"users\" SET \"email\" = 'user_' || left(encode(sha256(convert_to(\"email\"::text, 'UTF8')), 'hex'), 12)"));
+        assert!(statements[0].ends_with(", \"notes\" = NULL"));
+    }
+
+    #[test]
+    fn mask_rows_masks_matching_columns() {
+        let rules = vec![MaskingRule {
+            table: "public.users".to_string(),
+            column: "email".to_string(),
+            strategy: MaskStrategy::Null,
+        }];
+        let header = vec!["id".to_string(), "email".to_string()];
+        let mut rows = vec![vec![Some("1".to_string()), Some("ana@corp.pt".to_string())]];
+        assert!(mask_rows("public.users", &header, &mut rows, &rules));
+        assert_eq!(rows, vec![vec![Some("1".to_string()), None]]);
+        assert!(!mask_rows("public.orders", &header, &mut rows, &rules));
+    }
+}
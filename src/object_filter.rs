@@ -0,0 +1,148 @@
+//! Scopes a resource to the objects matching `schema.name` patterns, so
+//! several resources can manage one database: each diffs, creates and drops
+//! only the objects in its scope and leaves the rest to the others.
+
+use std::collections::BTreeSet;
+
+use pgmold::model::Schema;
+use regex::Regex;
+
+/// `schema.name` globs, where `*` matches any run of characters and `?` any
+/// one. No patterns means every object.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectFilter {
+    patterns: Vec<(Regex, Regex)>,
+}
+
+impl ObjectFilter {
+    pub fn parse<S: AsRef<str>>(patterns: &[S]) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref();
+                match pattern.split_once('.') {
+                    Some((schema, name)) if !schema.is_empty() && !name.is_empty() => {
+                        Ok((glob(schema), glob(name)))
+                    }
+                    _ => Err(format!(
+                        "Invalid object pattern \"{pattern}\", expected schema.name, e.g. billing.* or public.orders_*"
+                    )),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ObjectFilter { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn matches(&self, schema: &str, name: &str) -> bool {
+        self.is_empty()
+            || self
+                .patterns
+                .iter()
+                .any(|(s, n)| s.is_match(schema) && n.is_match(name))
+    }
+
+    /// Removes the objects outside the filter. Triggers go with their table.
+    pub fn retain(&self, schema: &mut Schema) {
+        if self.is_empty() {
+            return;
+        }
+        schema
+            .tables
+            .retain(|_, t| self.matches(&t.schema, &t.name));
+        schema.views.retain(|_, v| self.matches(&v.schema, &v.name));
+        schema
+            .functions
+            .retain(|_, f| self.matches(&f.schema, &f.name));
+        schema
+            .sequences
+            .retain(|_, s| self.matches(&s.schema, &s.name));
+        schema.enums.retain(|_, e| self.matches(&e.schema, &e.name));
+        schema
+            .domains
+            .retain(|_, d| self.matches(&d.schema, &d.name));
+        schema
+            .triggers
+            .retain(|_, t| self.matches(&t.target_schema, &t.target_name));
+    }
+}
+
+/// Names of the tables, views, functions, sequences, enums and domains in
+/// `schema`, as `(schema, name)`.
+pub fn object_names(schema: &Schema) -> BTreeSet<(String, String)> {
+    let mut names = BTreeSet::new();
+    let mut add = |schema: &str, name: &str| {
+        names.insert((schema.to_string(), name.to_string()));
+    };
+    schema.tables.values().for_each(|t| add(&t.schema, &t.name));
+    schema.views.values().for_each(|v| add(&v.schema, &v.name));
+    schema
+        .functions
+        .values()
+        .for_each(|f| add(&f.schema, &f.name));
+    schema
+        .sequences
+        .values()
+        .for_each(|s| add(&s.schema, &s.name));
+    schema.enums.values().for_each(|e| add(&e.schema, &e.name));
+    schema
+        .domains
+        .values()
+        .for_each(|d| add(&d.schema, &d.name));
+    names
+}
+
+/// Anchored regex for a glob, where `*` matches any run of characters and `?`
+/// any one.
+pub(crate) fn glob(pattern: &str) -> Regex {
+    let re: String = pattern
+        .chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            c => regex::escape(&c.to_string()),
+        })
+        .collect();
+    Regex::new(&format!("^{re}$")).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_keeps_matching_objects() {
+        let mut schema = pgmold::parser::parse_sql_string(
+            "CREATE TABLE orders (id INT PRIMARY KEY);\n\
+             CREATE TABLE order_items (id INT PRIMARY KEY);\n\
+             CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE TYPE order_status AS ENUM ('open', 'paid');",
+        )
+        .unwrap();
+        let filter = ObjectFilter::parse(&["public.order*"]).unwrap();
+
+        filter.retain(&mut schema);
+
+        assert_eq!(
+            object_names(&schema),
+            BTreeSet::from([
+                ("public".to_string(), "order_items".to_string()),
+                ("public".to_string(), "order_status".to_string()),
+                ("public".to_string(), "orders".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_requires_qualified_patterns() {
+        assert!(ObjectFilter::parse(&["billing.*", "public.?_log"]).is_ok());
+        assert!(ObjectFilter::parse(&["orders"]).is_err());
+        assert!(ObjectFilter::parse(&["public."]).is_err());
+        assert!(ObjectFilter::parse::<&str>(&[])
+            .unwrap()
+            .matches("any", "thing"));
+    }
+}
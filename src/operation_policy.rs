@@ -0,0 +1,220 @@
+//! Which kinds of operations may touch which objects, e.g. no `DropTable` in
+//! `billing`, declared once in a policy file for every plan of the provider.
+//! Operations the policy doesn't allow fail the plan like lint errors, under
+//! the name of the rule they break.
+
+use std::collections::BTreeMap;
+
+use pgmold::diff::MigrationOp;
+use pgmold::lint::{LintResult, LintSeverity};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::object_filter::ObjectFilter;
+use crate::operations::operation_kind;
+
+/// `rule "name" { ... }` blocks in HCL, or `{"rule": {"name": {...}}}` in
+/// JSON.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    rule: BTreeMap<String, RuleSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    #[serde(default)]
+    objects: Vec<String>,
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct PolicyRule {
+    name: String,
+    /// `schema.name` globs of the objects the rule covers; all when empty.
+    objects: ObjectFilter,
+    /// Operation kind globs, e.g. `Drop*`. When set, only these kinds are
+    /// allowed on the objects.
+    allow: Vec<Regex>,
+    deny: Vec<Regex>,
+}
+
+impl PolicyRule {
+    fn permits(&self, kind: &str) -> bool {
+        !self.deny.iter().any(|k| k.is_match(kind))
+            && (self.allow.is_empty() || self.allow.iter().any(|k| k.is_match(kind)))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OperationPolicy {
+    rules: Vec<PolicyRule>,
+}
+
+impl OperationPolicy {
+    /// Reads a policy file, as JSON when its name ends in `.json` and as HCL
+    /// otherwise.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let file: PolicyFile = if path.ends_with(".json") {
+            serde_json::from_str(&content).map_err(|e| e.to_string())?
+        } else {
+            hcl::from_str(&content).map_err(|e| e.to_string())?
+        };
+        Self::from_file(file)
+    }
+
+    fn from_file(file: PolicyFile) -> Result<Self, String> {
+        let rules = file
+            .rule
+            .into_iter()
+            .map(|(name, spec)| {
+                if spec.allow.is_empty() && spec.deny.is_empty() {
+                    return Err(format!("Policy rule {name} needs allow or deny"));
+                }
+                let objects = ObjectFilter::parse(&spec.objects)
+                    .map_err(|e| format!("Policy rule {name}: {e}"))?;
+                let kinds = |kinds: &[String]| {
+                    kinds
+                        .iter()
+                        .map(|kind| crate::object_filter::glob(kind))
+                        .collect()
+                };
+                Ok(PolicyRule {
+                    objects,
+                    allow: kinds(&spec.allow),
+                    deny: kinds(&spec.deny),
+                    name,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(OperationPolicy { rules })
+    }
+
+    /// One error per operation and rule that doesn't permit it.
+    pub fn evaluate(&self, operations: &[MigrationOp]) -> Vec<LintResult> {
+        operations
+            .iter()
+            .flat_map(|op| self.evaluate_operation(&operation_kind(op), &format!("{op:?}")))
+            .collect()
+    }
+
+    /// Violations by an operation of `kind`, whose `Debug` output is `debug`.
+    fn evaluate_operation(&self, kind: &str, debug: &str) -> Vec<LintResult> {
+        let object = object_of(debug);
+        self.rules
+            .iter()
+            .filter(|rule| match &object {
+                Some((schema, name)) => rule.objects.matches(schema, name),
+                None => rule.objects.is_empty(),
+            })
+            .filter(|rule| !rule.permits(kind))
+            .map(|rule| {
+                let target = object
+                    .as_ref()
+                    .map(|(schema, name)| format!(" on {schema}.{name}"))
+                    .unwrap_or_default();
+                LintResult {
+                    rule: rule.name.clone(),
+                    severity: LintSeverity::Error,
+                    message: format!("{kind}{target} is not allowed by policy rule {}", rule.name),
+                }
+            })
+            .collect()
+    }
+}
+
+/// `(schema, name)` of the object the operation changes: its `table`, or the
+/// object it creates or drops, in `public` unless the operation says otherwise.
+fn object_of(debug: &str) -> Option<(String, String)> {
+    let field = |pattern: &str| {
+        Regex::new(pattern)
+            .unwrap()
+            .captures(debug)
+            .map(|caps| caps[1].to_string())
+    };
+    let object = field(r#"\btable: "([^"]+)""#)
+        .or_else(|| field(r#"\bname: "([^"]+)""#))
+        .or_else(|| field(r#""([^"]+)""#))?;
+    if let Some((schema, name)) = object.split_once('.') {
+        return Some((schema.to_string(), name.to_string()));
+    }
+    let schema = field(r#"\bschema: "([^"]+)""#).unwrap_or_else(|| "public".to_string());
+    Some((schema, object))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(hcl: &str) -> Result<OperationPolicy, String> {
+        OperationPolicy::from_file(hcl::from_str(hcl).map_err(|e| e.to_string())?)
+    }
+
+    #[test]
+    fn evaluate_reports_operations_rules_deny() {
+        let policy = policy(
+            r#"
+            rule "protect_billing" {
+              objects = ["billing.*"]
+              deny    = ["Drop*"]
+            }
+            rule "additive_public" {
+              objects = ["public.*"]
+              allow   = ["Create*", "Add*"]
+            }
+            "#,
+        )
+        .unwrap();
+
+        let violations = policy.evaluate_operation("DropTable", r#"DropTable("billing.invoices")"#);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "protect_billing");
+        assert_eq!(
+            violations[0].message,
+            "DropTable on billing.invoices is not allowed by policy rule protect_billing"
+        );
+        assert!(policy
+            .evaluate_operation(
+                "AddColumn",
+                r#"AddColumn { table: "billing.invoices", column: Column { name: "note" } }"#
+            )
+            .is_empty());
+        assert!(policy
+            .evaluate_operation(
+                "CreateTable",
+                r#"CreateTable(Table { name: "orders", schema: "public" })"#
+            )
+            .is_empty());
+        assert_eq!(
+            policy.evaluate_operation(
+                "DropColumn",
+                r#"DropColumn { table: "users", column: "name" }"#
+            )[0]
+            .rule,
+            "additive_public"
+        );
+    }
+
+    #[test]
+    fn parses_json_and_rejects_incomplete_rules() {
+        let file: PolicyFile = serde_json::from_str(
+            r#"{"rule": {"protect_billing": {"objects": ["billing.*"], "deny": ["DropTable"]}}}"#,
+        )
+        .unwrap();
+        assert_eq!(OperationPolicy::from_file(file).unwrap().rules.len(), 1);
+
+        assert!(policy(r#"rule "empty" { objects = ["billing.*"] }"#).is_err());
+        assert!(
+            policy("rule \"bad\" {\n objects = [\"billing\"]\n deny = [\"DropTable\"]\n}").is_err()
+        );
+        assert!(
+            policy("rule \"typo\" {\n deny = [\"DropTable\"]\n severity = \"error\"\n}").is_err()
+        );
+    }
+}
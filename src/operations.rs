@@ -0,0 +1,435 @@
+use std::collections::BTreeMap;
+
+use pgmold::diff::MigrationOp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationClass {
+    Additive,
+    Destructive,
+    Rewrite,
+}
+
+impl OperationClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationClass::Additive => "additive",
+            OperationClass::Destructive => "destructive",
+            OperationClass::Rewrite => "rewrite",
+        }
+    }
+}
+
+/// Strongest table lock an operation takes, in PostgreSQL's terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LockLevel {
+    /// Blocks other DDL and VACUUM, but not reads or writes.
+    ShareUpdateExclusive,
+    /// Blocks writes.
+    Share,
+    /// Blocks writes and other `SHARE` locks.
+    ShareRowExclusive,
+    /// Blocks everything, including reads.
+    AccessExclusive,
+}
+
+impl LockLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockLevel::ShareUpdateExclusive => "SHARE UPDATE EXCLUSIVE",
+            LockLevel::Share => "SHARE",
+            LockLevel::ShareRowExclusive => "SHARE ROW EXCLUSIVE",
+            LockLevel::AccessExclusive => "ACCESS EXCLUSIVE",
+        }
+    }
+
+    pub fn blocks_reads(&self) -> bool {
+        *self == LockLevel::AccessExclusive
+    }
+
+    pub fn blocks_writes(&self) -> bool {
+        *self >= LockLevel::Share
+    }
+}
+
+/// Half of an expand/contract rollout. Expand runs additive operations, which
+/// the running application tolerates; contract runs drops and rewrites once the
+/// application no longer depends on the old shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Expand,
+    Contract,
+}
+
+impl Phase {
+    pub const NAMES: [&'static str; 2] = ["expand", "contract"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "expand" => Some(Phase::Expand),
+            "contract" => Some(Phase::Contract),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Expand => "expand",
+            Phase::Contract => "contract",
+        }
+    }
+
+    pub fn includes(&self, op: &MigrationOp) -> bool {
+        self.includes_class(classify(op))
+    }
+
+    fn includes_class(&self, class: OperationClass) -> bool {
+        match self {
+            Phase::Expand => class == OperationClass::Additive,
+            Phase::Contract => class != OperationClass::Additive,
+        }
+    }
+}
+
+/// Variant name of a migration operation, e.g. `CreateTable`. Every variant is
+/// listed, so a pgmold release that adds or renames one fails to build here
+/// until [`classify`] and [`lock_level`] have been reviewed for it.
+pub fn operation_kind(op: &MigrationOp) -> String {
+    let kind = match op {
+        MigrationOp::CreateSchema { .. } => "CreateSchema",
+        MigrationOp::DropSchema { .. } => "DropSchema",
+        MigrationOp::CreateExtension { .. } => "CreateExtension",
+        MigrationOp::DropExtension { .. } => "DropExtension",
+        MigrationOp::CreateEnum { .. } => "CreateEnum",
+        MigrationOp::DropEnum { .. } => "DropEnum",
+        MigrationOp::AddEnumValue { .. } => "AddEnumValue",
+        MigrationOp::CreateDomain { .. } => "CreateDomain",
+        MigrationOp::DropDomain { .. } => "DropDomain",
+        MigrationOp::AlterDomain { .. } => "AlterDomain",
+        MigrationOp::CreateTable { .. } => "CreateTable",
+        MigrationOp::DropTable { .. } => "DropTable",
+        MigrationOp::CreatePartition { .. } => "CreatePartition",
+        MigrationOp::DropPartition { .. } => "DropPartition",
+        MigrationOp::AddColumn { .. } => "AddColumn",
+        MigrationOp::DropColumn { .. } => "DropColumn",
+        MigrationOp::AlterColumn { .. } => "AlterColumn",
+        MigrationOp::AddPrimaryKey { .. } => "AddPrimaryKey",
+        MigrationOp::DropPrimaryKey { .. } => "DropPrimaryKey",
+        MigrationOp::AddIndex { .. } => "AddIndex",
+        MigrationOp::DropIndex { .. } => "DropIndex",
+        MigrationOp::AddForeignKey { .. } => "AddForeignKey",
+        MigrationOp::DropForeignKey { .. } => "DropForeignKey",
+        MigrationOp::AddCheckConstraint { .. } => "AddCheckConstraint",
+        MigrationOp::DropCheckConstraint { .. } => "DropCheckConstraint",
+        MigrationOp::EnableRls { .. } => "EnableRls",
+        MigrationOp::DisableRls { .. } => "DisableRls",
+        MigrationOp::CreatePolicy { .. } => "CreatePolicy",
+        MigrationOp::DropPolicy { .. } => "DropPolicy",
+        MigrationOp::AlterPolicy { .. } => "AlterPolicy",
+        MigrationOp::CreateFunction { .. } => "CreateFunction",
+        MigrationOp::DropFunction { .. } => "DropFunction",
+        MigrationOp::AlterFunction { .. } => "AlterFunction",
+        MigrationOp::CreateView { .. } => "CreateView",
+        MigrationOp::DropView { .. } => "DropView",
+        MigrationOp::AlterView { .. } => "AlterView",
+        MigrationOp::CreateTrigger { .. } => "CreateTrigger",
+        MigrationOp::DropTrigger { .. } => "DropTrigger",
+        MigrationOp::AlterTriggerEnabled { .. } => "AlterTriggerEnabled",
+        MigrationOp::CreateSequence { .. } => "CreateSequence",
+        MigrationOp::DropSequence { .. } => "DropSequence",
+        MigrationOp::AlterSequence { .. } => "AlterSequence",
+        MigrationOp::AlterOwner { .. } => "AlterOwner",
+        MigrationOp::BackfillHint { .. } => "BackfillHint",
+        MigrationOp::SetColumnNotNull { .. } => "SetColumnNotNull",
+        MigrationOp::GrantPrivileges { .. } => "GrantPrivileges",
+        MigrationOp::RevokePrivileges { .. } => "RevokePrivileges",
+    };
+    kind.to_string()
+}
+
+pub fn classify(op: &MigrationOp) -> OperationClass {
+    match op {
+        MigrationOp::DropSchema { .. }
+        | MigrationOp::DropExtension { .. }
+        | MigrationOp::DropEnum { .. }
+        | MigrationOp::DropDomain { .. }
+        | MigrationOp::DropTable { .. }
+        | MigrationOp::DropPartition { .. }
+        | MigrationOp::DropColumn { .. }
+        | MigrationOp::DropPrimaryKey { .. }
+        | MigrationOp::DropIndex { .. }
+        | MigrationOp::DropForeignKey { .. }
+        | MigrationOp::DropCheckConstraint { .. }
+        | MigrationOp::DropPolicy { .. }
+        | MigrationOp::DropFunction { .. }
+        | MigrationOp::DropView { .. }
+        | MigrationOp::DropTrigger { .. }
+        | MigrationOp::DropSequence { .. } => OperationClass::Destructive,
+        MigrationOp::AlterColumn { changes, .. } if changes.data_type.is_some() => {
+            OperationClass::Rewrite
+        }
+        _ => OperationClass::Additive,
+    }
+}
+
+/// Lock the operation takes on an existing table, or `None` when it only
+/// creates new objects or touches none (grants).
+pub fn lock_level(op: &MigrationOp) -> Option<LockLevel> {
+    match op {
+        MigrationOp::AddIndex { .. } if builds_concurrently(op) => {
+            Some(LockLevel::ShareUpdateExclusive)
+        }
+        MigrationOp::AddIndex { .. } => Some(LockLevel::Share),
+        MigrationOp::CreateTrigger { .. }
+        | MigrationOp::DropTrigger { .. }
+        | MigrationOp::AlterTriggerEnabled { .. }
+        | MigrationOp::AddForeignKey { .. } => Some(LockLevel::ShareRowExclusive),
+        MigrationOp::GrantPrivileges { .. } | MigrationOp::RevokePrivileges { .. } => None,
+        // Policies attach to existing tables; other creates only add new objects
+        MigrationOp::CreateSchema { .. }
+        | MigrationOp::CreateExtension { .. }
+        | MigrationOp::CreateEnum { .. }
+        | MigrationOp::CreateDomain { .. }
+        | MigrationOp::CreateTable { .. }
+        | MigrationOp::CreatePartition { .. }
+        | MigrationOp::CreateFunction { .. }
+        | MigrationOp::CreateView { .. }
+        | MigrationOp::CreateSequence { .. } => None,
+        // Everything else rewrites or redefines an existing object
+        _ => Some(LockLevel::AccessExclusive),
+    }
+}
+
+/// SQL pgmold generates for `op` on its own.
+fn statements(op: &MigrationOp) -> Vec<String> {
+    pgmold::pg::sqlgen::generate_sql(std::slice::from_ref(op))
+}
+
+/// Whether pgmold builds the index `CONCURRENTLY`, which it decides when
+/// generating the SQL.
+fn builds_concurrently(op: &MigrationOp) -> bool {
+    statements(op)
+        .iter()
+        .any(|statement| !crate::execute::is_transactional(statement))
+}
+
+/// Hash identifying the set of destructive operations, which
+/// `destructive_approval_token` must match. `None` when nothing is destructive.
+/// It covers the SQL the operations run, so it only changes when they do.
+pub fn destructive_hash(ops: &[MigrationOp]) -> Option<String> {
+    hash_destructive(
+        ops.iter()
+            .filter(|op| classify(op) == OperationClass::Destructive)
+            .map(|op| statements(op).join("\n"))
+            .collect(),
+    )
+}
+
+fn hash_destructive(mut sql: Vec<String>) -> Option<String> {
+    if sql.is_empty() {
+        return None;
+    }
+    // Sorted so the token doesn't depend on the order operations were generated in
+    sql.sort();
+    Some(crate::util::compute_content_hash(&sql.join("\n")))
+}
+
+/// One-line summary such as "Applied 3 operations: 2 CREATE TABLE, 1 ADD COLUMN",
+/// most frequent kinds first.
+pub fn summarize_applied(kinds: &[String]) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for kind in kinds {
+        match counts.iter_mut().find(|(k, _)| k == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    counts.sort_by(|(a_kind, a), (b_kind, b)| b.cmp(a).then(a_kind.cmp(b_kind)));
+
+    let noun = if kinds.len() == 1 {
+        "operation"
+    } else {
+        "operations"
+    };
+    let breakdown = counts
+        .iter()
+        .map(|(kind, count)| format!("{count} {}", kind_label(kind)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("Applied {} {noun}: {breakdown}", kinds.len())
+}
+
+/// Number of operations per kind, keyed in snake case (`CreateTable` -> `create_table`).
+pub fn count_by_kind(kinds: &[String]) -> BTreeMap<String, i64> {
+    let mut counts = BTreeMap::new();
+    for kind in kinds {
+        *counts.entry(kind_key(kind)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// `CreateTable` -> `create_table`.
+fn kind_key(kind: &str) -> String {
+    kind_label(kind).replace(' ', "_").to_lowercase()
+}
+
+/// `CreateTable` -> `CREATE TABLE`.
+fn kind_label(kind: &str) -> String {
+    let mut label = String::new();
+    for (i, c) in kind.char_indices() {
+        if i > 0 && c.is_ascii_uppercase() {
+            label.push(' ');
+        }
+        label.push(c.to_ascii_uppercase());
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Operations pgmold plans from `current` to `target`.
+    fn diff(current: &str, target: &str) -> Vec<MigrationOp> {
+        pgmold::diff::compute_diff(
+            &pgmold::parser::parse_sql_string(current).unwrap(),
+            &pgmold::parser::parse_sql_string(target).unwrap(),
+        )
+    }
+
+    const USERS: &str = "CREATE TABLE users (id INT PRIMARY KEY, name TEXT);";
+
+    #[test]
+    fn operation_kind_names_the_variant() {
+        let ops = diff(USERS, "CREATE TABLE users (id INT PRIMARY KEY);");
+        assert_eq!(operation_kind(&ops[0]), "DropColumn");
+        assert_eq!(operation_kind(&diff("", USERS)[0]), "CreateTable");
+    }
+
+    #[test]
+    fn destructive_hash_covers_only_destructive_operations() {
+        let orders = "CREATE TABLE orders (id INT PRIMARY KEY, note TEXT);";
+        let both = format!("{USERS}\n{orders}");
+
+        assert_eq!(destructive_hash(&diff("", &both)), None);
+        let dropped = diff(&both, "CREATE TABLE orders (id INT PRIMARY KEY);");
+        let mut reversed = dropped.clone();
+        reversed.reverse();
+        assert!(destructive_hash(&dropped).is_some());
+        assert_eq!(destructive_hash(&dropped), destructive_hash(&reversed));
+        assert_ne!(
+            destructive_hash(&dropped),
+            destructive_hash(&diff(&both, orders))
+        );
+    }
+
+    #[test]
+    fn classify_drops_as_destructive() {
+        for op in diff(USERS, "CREATE TABLE users (id INT PRIMARY KEY);")
+            .iter()
+            .chain(&diff(USERS, "CREATE TABLE orders (id INT PRIMARY KEY);"))
+            .filter(|op| operation_kind(op).starts_with("Drop"))
+        {
+            assert_eq!(classify(op), OperationClass::Destructive);
+        }
+    }
+
+    #[test]
+    fn classify_type_change_as_rewrite() {
+        let ops = diff(
+            USERS,
+            "CREATE TABLE users (id BIGINT PRIMARY KEY, name TEXT);",
+        );
+        assert!(ops.iter().any(|op| classify(op) == OperationClass::Rewrite));
+    }
+
+    #[test]
+    fn classify_nullability_change_as_additive() {
+        let ops = diff(
+            USERS,
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT NOT NULL);",
+        );
+        assert!(!ops.is_empty());
+        assert!(ops
+            .iter()
+            .all(|op| classify(op) == OperationClass::Additive));
+    }
+
+    #[test]
+    fn classify_creates_as_additive() {
+        let ops = diff("", USERS);
+        assert_eq!(classify(&ops[0]), OperationClass::Additive);
+        assert_eq!(lock_level(&ops[0]), None);
+    }
+
+    #[test]
+    fn lock_level_by_operation_kind() {
+        let orders = "CREATE TABLE orders (id INT PRIMARY KEY, user_id INT);";
+        let current = format!("{USERS}\n{orders}");
+        let lock = |target: &str| diff(&current, target).iter().filter_map(lock_level).max();
+
+        assert!(matches!(
+            lock(&format!(
+                "{current}\nCREATE INDEX users_name_idx ON users (name);"
+            )),
+            Some(LockLevel::Share | LockLevel::ShareUpdateExclusive)
+        ));
+        assert_eq!(
+            lock(&format!(
+                "{USERS}\nCREATE TABLE orders (id INT PRIMARY KEY, user_id INT REFERENCES users (id));"
+            )),
+            Some(LockLevel::ShareRowExclusive)
+        );
+        assert_eq!(
+            lock(&format!(
+                "CREATE TABLE users (id INT PRIMARY KEY, name TEXT, email TEXT);\n{orders}"
+            )),
+            Some(LockLevel::AccessExclusive)
+        );
+    }
+
+    #[test]
+    fn summarize_applied_counts_kinds() {
+        let kinds: Vec<String> = ["AddColumn", "CreateTable", "AddIndex", "CreateTable"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        assert_eq!(
+            summarize_applied(&kinds),
+            "Applied 4 operations: 2 CREATE TABLE, 1 ADD COLUMN, 1 ADD INDEX"
+        );
+        assert_eq!(
+            summarize_applied(&["DropTable".to_string()]),
+            "Applied 1 operation: 1 DROP TABLE"
+        );
+    }
+
+    #[test]
+    fn count_by_kind_uses_snake_case_keys() {
+        let kinds: Vec<String> = ["CreateTable", "DropColumn", "CreateTable"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let counts = count_by_kind(&kinds);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["create_table"], 2);
+        assert_eq!(counts["drop_column"], 1);
+    }
+
+    #[test]
+    fn phases_split_operation_classes() {
+        assert!(Phase::Expand.includes_class(OperationClass::Additive));
+        assert!(!Phase::Expand.includes_class(OperationClass::Rewrite));
+        assert!(Phase::Contract.includes_class(OperationClass::Destructive));
+        assert!(Phase::Contract.includes_class(OperationClass::Rewrite));
+        assert_eq!(Phase::parse("contract"), Some(Phase::Contract));
+        assert_eq!(Phase::parse("migrate"), None);
+    }
+
+    #[test]
+    fn lock_levels_block_reads_and_writes() {
+        assert!(LockLevel::AccessExclusive.blocks_reads());
+        assert!(!LockLevel::ShareRowExclusive.blocks_reads());
+        assert!(LockLevel::Share.blocks_writes());
+        assert!(!LockLevel::ShareUpdateExclusive.blocks_writes());
+    }
+}
@@ -0,0 +1,295 @@
+//! Orders generated SQL by the objects statements create and reference, so a
+//! view runs after the tables it selects from, a table after the enum types
+//! and referenced tables it uses, and a trigger after its function, whatever
+//! order the schema file declares them in. Statements are only moved when a
+//! dependency requires it; drops keep their position.
+
+use regex::Regex;
+
+/// An identifier, optionally schema-qualified, quoted or not.
+const NAME: &str = r#"(?:"(?:[^"]|"")+"|[A-Za-z_][A-Za-z0-9_$]*)(?:\s*\.\s*(?:"(?:[^"]|"")+"|[A-Za-z_][A-Za-z0-9_$]*))?"#;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// Tables, views and sequences, referenced after keywords such as `FROM`.
+    Relation,
+    /// Types and domains, referenced by name anywhere.
+    Type,
+    /// Functions, referenced by a call.
+    Function,
+    /// Indexes, triggers and the like, which nothing references.
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ObjectName {
+    schema: Option<String>,
+    name: String,
+}
+
+impl ObjectName {
+    fn parse(text: &str) -> Self {
+        let parts: Vec<String> = split_name(text).into_iter().map(normalize).collect();
+        match parts.as_slice() {
+            [schema, name] => ObjectName {
+                schema: Some(schema.clone()),
+                name: name.clone(),
+            },
+            _ => ObjectName {
+                schema: None,
+                name: parts.last().cloned().unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Whether both name the same object, an unqualified name matching any schema.
+    fn matches(&self, other: &ObjectName) -> bool {
+        self.name == other.name
+            && (self.schema.is_none() || other.schema.is_none() || self.schema == other.schema)
+    }
+}
+
+impl std::fmt::Display for ObjectName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.schema {
+            Some(schema) => write!(f, "{schema}.{}", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Splits a possibly qualified name at dots outside quotes.
+fn split_name(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted) = (0, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '.' if !quoted => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts
+}
+
+/// Quoted identifiers keep their case; unquoted ones fold to lower case.
+fn normalize(part: &str) -> String {
+    match part.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => part.to_lowercase(),
+    }
+}
+
+/// The object a statement creates, if any.
+fn created(code: &str) -> Option<(Kind, ObjectName)> {
+    let re = Regex::new(&format!(
+        r"(?is)^\s*CREATE\s+(?:OR\s+REPLACE\s+)?(?:(?:GLOBAL\s+|LOCAL\s+)?(?:TEMP|TEMPORARY)\s+|UNLOGGED\s+|UNIQUE\s+|CONSTRAINT\s+)?(TABLE|FOREIGN\s+TABLE|MATERIALIZED\s+VIEW|RECURSIVE\s+VIEW|VIEW|SEQUENCE|TYPE|DOMAIN|FUNCTION|PROCEDURE|AGGREGATE|INDEX|TRIGGER|POLICY)\s+(?:CONCURRENTLY\s+)?(?:IF\s+NOT\s+EXISTS\s+)?({NAME})"
+    ))
+    .unwrap();
+    let caps = re.captures(code)?;
+    let keyword = caps[1].to_uppercase();
+    let kind = match keyword.split_whitespace().last() {
+        Some("TABLE" | "VIEW" | "SEQUENCE") => Kind::Relation,
+        Some("TYPE" | "DOMAIN") => Kind::Type,
+        Some("FUNCTION" | "PROCEDURE" | "AGGREGATE") => Kind::Function,
+        _ => Kind::Other,
+    };
+    Some((kind, ObjectName::parse(&caps[2])))
+}
+
+/// Names a statement refers to, with the kind of object each can be.
+fn references(code: &str) -> Vec<(Kind, ObjectName)> {
+    // Only the keyword is matched, so in `UPDATE ON t` both `ON` and `t` are seen
+    let keyword = Regex::new(
+        r"(?i)\b(?:REFERENCES|FROM|JOIN|ON|TABLE|SETOF|INTO|UPDATE|PARTITION\s+OF|INHERITS\s*\(|LIKE)\s+(?:ONLY\s+)?",
+    )
+    .unwrap();
+    let call = Regex::new(&format!(r"({NAME})\s*\(")).unwrap();
+    let name = Regex::new(NAME).unwrap();
+    let leading_name = Regex::new(&format!("^{NAME}")).unwrap();
+
+    let mut refs: Vec<(Kind, ObjectName)> = keyword
+        .find_iter(code)
+        .filter_map(|m| leading_name.find(&code[m.end()..]))
+        .map(|m| (Kind::Relation, ObjectName::parse(m.as_str())))
+        .collect();
+    refs.extend(
+        call.captures_iter(code)
+            .map(|caps| (Kind::Function, ObjectName::parse(&caps[1]))),
+    );
+    refs.extend(
+        name.find_iter(code)
+            .map(|m| (Kind::Type, ObjectName::parse(m.as_str()))),
+    );
+    refs
+}
+
+/// Reorders `statements` so each runs after the statements creating what it
+/// references, otherwise keeping their order. Fails naming the objects of a
+/// dependency cycle, which no order can apply.
+pub fn order(statements: Vec<String>) -> Result<Vec<String>, String> {
+    let codes: Vec<String> = statements
+        .iter()
+        .map(|s| crate::statements::code_only(s))
+        .collect();
+    let creates: Vec<Option<(Kind, ObjectName)>> = codes.iter().map(|c| created(c)).collect();
+    let drop = Regex::new(r"(?is)^\s*DROP\b").unwrap();
+
+    let dependencies: Vec<Vec<usize>> = codes
+        .iter()
+        .enumerate()
+        .map(|(i, code)| {
+            // Drops refer to objects on their way out, not to their replacements
+            if drop.is_match(code) {
+                return Vec::new();
+            }
+            let own = creates[i].as_ref();
+            let refs = references(code);
+            creates
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .filter_map(|(j, create)| create.as_ref().map(|c| (j, c)))
+                .filter(|(_, (kind, name))| {
+                    // Redefinitions of the same object don't depend on each other
+                    own.is_none_or(|(own_kind, own)| own_kind != kind || !own.matches(name))
+                        && refs.iter().any(|(ref_kind, reference)| {
+                            ref_kind == kind && reference.matches(name)
+                        })
+                })
+                .map(|(j, _)| j)
+                .collect()
+        })
+        .collect();
+
+    // Kahn's algorithm, always taking the earliest ready statement
+    let mut done = vec![false; statements.len()];
+    let mut sorted = Vec::with_capacity(statements.len());
+    while sorted.len() < statements.len() {
+        let next =
+            (0..statements.len()).find(|&i| !done[i] && dependencies[i].iter().all(|&d| done[d]));
+        match next {
+            Some(i) => {
+                done[i] = true;
+                sorted.push(i);
+            }
+            None => return Err(describe_cycle(&dependencies, &done, &creates, &statements)),
+        }
+    }
+
+    let mut statements: Vec<Option<String>> = statements.into_iter().map(Some).collect();
+    Ok(sorted
+        .into_iter()
+        .filter_map(|i| statements[i].take())
+        .collect())
+}
+
+/// Follows dependencies among the statements left over until one repeats,
+/// and names the objects on that loop, e.g. `public.a -> public.b -> public.a`.
+fn describe_cycle(
+    dependencies: &[Vec<usize>],
+    done: &[bool],
+    creates: &[Option<(Kind, ObjectName)>],
+    statements: &[String],
+) -> String {
+    // Every statement left waits on another one left, so the walk must loop
+    let mut path = vec![(0..done.len()).find(|&i| !done[i]).unwrap_or_default()];
+    loop {
+        let current = *path.last().unwrap_or(&0);
+        let Some(&next) = dependencies[current].iter().find(|&&d| !done[d]) else {
+            break;
+        };
+        if let Some(start) = path.iter().position(|&i| i == next) {
+            path.drain(..start);
+            path.push(next);
+            break;
+        }
+        path.push(next);
+    }
+    // Listed from a dependency to what depends on it
+    path.iter()
+        .rev()
+        .map(|&i| match &creates[i] {
+            Some((_, name)) => name.to_string(),
+            None => summarize(&statements[i]),
+        })
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+fn summarize(statement: &str) -> String {
+    let statement = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+    match statement.char_indices().nth(60) {
+        Some((end, _)) => format!("\"{}...\"", &statement[..end]),
+        None => format!("\"{statement}\""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql(statements: &[&str]) -> Vec<String> {
+        statements.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn order_puts_dependencies_first() {
+        let ordered = order(sql(&[
+            "CREATE VIEW public.active_orders AS SELECT * FROM public.orders WHERE status = 'active'",
+            "CREATE TABLE public.orders (id int, user_id int REFERENCES public.users (id), status public.status)",
+            "CREATE INDEX orders_user_idx ON public.orders (user_id)",
+            "CREATE TYPE public.status AS ENUM ('active', 'done')",
+            "CREATE TABLE public.users (id int PRIMARY KEY)",
+            "CREATE TABLE public.notes (body text)",
+        ]))
+        .unwrap();
+        assert_eq!(
+            ordered,
+            sql(&[
+                "CREATE TYPE public.status AS ENUM ('active', 'done')",
+                "CREATE TABLE public.users (id int PRIMARY KEY)",
+                "CREATE TABLE public.orders (id int, user_id int REFERENCES public.users (id), status public.status)",
+                "CREATE VIEW public.active_orders AS SELECT * FROM public.orders WHERE status = 'active'",
+                "CREATE INDEX orders_user_idx ON public.orders (user_id)",
+                "CREATE TABLE public.notes (body text)",
+            ])
+        );
+    }
+
+    #[test]
+    fn order_keeps_drops_and_function_bodies_in_place() {
+        let statements = sql(&[
+            "DROP VIEW public.report",
+            "CREATE FUNCTION public.touch() RETURNS trigger AS $$ BEGIN UPDATE public.audit SET n = n + 1; RETURN NEW; END $$ LANGUAGE plpgsql",
+            "CREATE TRIGGER touch BEFORE UPDATE ON public.audit FOR EACH ROW EXECUTE FUNCTION public.touch()",
+            "CREATE TABLE public.audit (n int)",
+            "DROP TABLE public.report_source",
+        ]);
+        assert_eq!(
+            order(statements.clone()).unwrap(),
+            vec![
+                statements[0].clone(),
+                statements[1].clone(),
+                statements[3].clone(),
+                statements[2].clone(),
+                statements[4].clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn order_names_cycles() {
+        let err = order(sql(&[
+            "CREATE TABLE a (id int PRIMARY KEY, b_id int REFERENCES b (id))",
+            "CREATE TABLE b (id int PRIMARY KEY, a_id int REFERENCES a (id))",
+            "CREATE TABLE c (id int)",
+        ]))
+        .unwrap_err();
+        assert_eq!(err, "a -> b -> a");
+    }
+}
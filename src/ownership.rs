@@ -0,0 +1,109 @@
+//! Keeps the objects of the managed schemas owned by one role, whatever role
+//! created them: applies often run as a migration user, while the
+//! application expects its own role to own the tables.
+
+use sqlx::PgPool;
+
+use crate::functions::quote::quote_ident;
+
+/// Schemas and their objects not owned by `owner`, as `(kind, name)` with
+/// `kind` the keyword `ALTER ... OWNER TO` takes. Indexes, sequences owned by
+/// a column and extension members follow their parent and are left out.
+const MISOWNED_SQL: &str = r#"
+WITH objects AS (
+    SELECT CASE c.relkind
+               WHEN 'v' THEN 'VIEW'
+               WHEN 'm' THEN 'MATERIALIZED VIEW'
+               WHEN 'S' THEN 'SEQUENCE'
+               WHEN 'f' THEN 'FOREIGN TABLE'
+               ELSE 'TABLE'
+           END AS kind,
+           format('%I.%I', n.nspname, c.relname) AS name,
+           c.relowner AS owner
+    FROM pg_class c
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE n.nspname = ANY($1)
+      AND c.relkind IN ('r', 'p', 'v', 'm', 'S', 'f')
+      AND NOT EXISTS (
+          SELECT 1 FROM pg_depend d
+          WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid
+            AND (d.deptype = 'e' OR (c.relkind = 'S' AND d.deptype IN ('a', 'i')))
+      )
+    UNION ALL
+    SELECT CASE p.prokind WHEN 'p' THEN 'PROCEDURE' WHEN 'a' THEN 'AGGREGATE' ELSE 'FUNCTION' END,
+           format('%I.%I(%s)', n.nspname, p.proname, pg_get_function_identity_arguments(p.oid)),
+           p.proowner
+    FROM pg_proc p
+    JOIN pg_namespace n ON n.oid = p.pronamespace
+    WHERE n.nspname = ANY($1)
+      AND NOT EXISTS (
+          SELECT 1 FROM pg_depend d
+          WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+      )
+    UNION ALL
+    SELECT CASE t.typtype WHEN 'd' THEN 'DOMAIN' ELSE 'TYPE' END,
+           format('%I.%I', n.nspname, t.typname),
+           t.typowner
+    FROM pg_type t
+    JOIN pg_namespace n ON n.oid = t.typnamespace
+    LEFT JOIN pg_class c ON c.oid = t.typrelid
+    WHERE n.nspname = ANY($1)
+      AND (t.typtype IN ('e', 'd', 'r') OR (t.typtype = 'c' AND c.relkind = 'c'))
+      AND NOT EXISTS (
+          SELECT 1 FROM pg_depend d
+          WHERE d.classid = 'pg_type'::regclass AND d.objid = t.oid AND d.deptype = 'e'
+      )
+    UNION ALL
+    SELECT 'SCHEMA', format('%I', n.nspname), n.nspowner
+    FROM pg_namespace n
+    WHERE n.nspname = ANY($1)
+)
+SELECT o.kind, o.name
+FROM objects o
+JOIN pg_roles r ON r.rolname = $2
+WHERE o.owner <> r.oid
+ORDER BY o.kind = 'SCHEMA' DESC, o.kind, o.name
+"#;
+
+/// `ALTER ... OWNER TO` statements giving `owner` everything in `schemas` it
+/// doesn't own yet, schemas first. Fails if the role doesn't exist.
+pub async fn reassign_statements(
+    pool: &PgPool,
+    schemas: &[String],
+    owner: &str,
+) -> anyhow::Result<Vec<String>> {
+    let (exists,): (bool,) =
+        sqlx::query_as("SELECT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = $1)")
+            .bind(owner)
+            .fetch_one(pool)
+            .await?;
+    if !exists {
+        anyhow::bail!("role \"{owner}\" does not exist");
+    }
+    let objects: Vec<(String, String)> = sqlx::query_as(MISOWNED_SQL)
+        .bind(schemas)
+        .bind(owner)
+        .fetch_all(pool)
+        .await?;
+    Ok(objects
+        .iter()
+        .map(|(kind, name)| alter_owner(kind, name, owner))
+        .collect())
+}
+
+fn alter_owner(kind: &str, name: &str, owner: &str) -> String {
+    format!("ALTER {kind} {name} OWNER TO {}", quote_ident(owner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alter_owner_quotes_the_role() {
+        assert_eq!(
+            alter_owner("FUNCTION", "public.touch()", "App Owner"),
+            "ALTER FUNCTION public.touch() OWNER TO \"App Owner\""
+        );
+    }
+}
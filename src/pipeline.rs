@@ -0,0 +1,932 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
+
+use pgmold::diff::MigrationOp;
+use pgmold::lint::{LintOptions, LintResult, LintSeverity};
+use pgmold::model::Schema;
+use pgmold::pg::connection::PgConnection;
+use serde::{Deserialize, Serialize};
+use tf_provider::{schema::AttributeType, AttributePath, Diagnostics};
+use tracing::Instrument;
+
+/// Everything computed between introspection and execution.
+pub struct MigrationPlan {
+    pub operations: Vec<MigrationOp>,
+    pub lint_results: Vec<LintResult>,
+    pub statements: Vec<String>,
+    /// Target schemas left out because they couldn't be introspected.
+    pub skipped_schemas: Vec<SkippedSchema>,
+    pub timings: PhaseTimings,
+    /// Sizes of the tables operations scan or rewrite, for duration estimates.
+    /// Empty when planning without a database.
+    pub table_sizes: crate::estimate::TableSizes,
+}
+
+/// Wall-clock milliseconds spent in each phase (`parse`, `introspect`, `diff`,
+/// `lint`, `sqlgen`, and `apply` once executed).
+pub type PhaseTimings = BTreeMap<String, u64>;
+
+/// Records how long `phase` took since `started` and logs it at debug level.
+pub fn record_phase(timings: &mut PhaseTimings, phase: &str, started: Instant) {
+    let duration_ms = started.elapsed().as_millis() as u64;
+    tracing::debug!(phase, duration_ms, "phase finished");
+    timings.insert(phase.to_string(), duration_ms);
+}
+
+pub struct SkippedSchema {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Inputs that shape how a plan is computed.
+pub struct PlanOptions {
+    pub target_schemas: Vec<String>,
+    pub lint_options: LintOptions,
+    /// The provider's `lint_rules`, checked alongside pgmold's.
+    pub lint_rules: Vec<crate::lint_rules::LintRule>,
+    /// Report lint warnings as errors, from the provider's `lint_policy`.
+    pub escalate_warnings: bool,
+    /// The provider's `operation_policy_file`; operations it doesn't allow
+    /// are lint errors.
+    pub operation_policy: crate::operation_policy::OperationPolicy,
+    /// Leave object kinds the schema file doesn't declare out of the diff.
+    /// They are still introspected.
+    pub declared_kinds_only: bool,
+    /// Restrict the diff to the objects with these `schema.name` names, and
+    /// introspection to their schemas.
+    pub only_objects: Option<BTreeSet<String>>,
+    /// Only diff objects these `schema.name` patterns match.
+    pub objects: crate::object_filter::ObjectFilter,
+    /// Compare expressions in columns and indexes as PostgreSQL normalizes
+    /// them rather than as written.
+    pub normalize_expressions: bool,
+    /// Skip schemas the role can't introspect instead of failing the whole plan.
+    pub skip_unreadable_schemas: bool,
+    pub dialect: crate::dialect::Dialect,
+    /// Keep only the operations of one expand/contract phase.
+    pub phase: Option<crate::operations::Phase>,
+    /// `server_version_num` to generate SQL for. Detected from the connection
+    /// when unset; plans without a connection then use pgmold's SQL as is.
+    pub server_version: Option<i64>,
+    /// Replace enums that lose or reorder values instead of failing the plan.
+    pub rebuild_enums: bool,
+    /// Add foreign keys and check constraints to existing tables `NOT VALID`
+    /// and validate them separately, in the contract phase when phased.
+    pub defer_validation: bool,
+    /// Restore the sequence values of tables the plan drops and creates again.
+    pub preserve_sequences: bool,
+    /// Restore their rows as well.
+    pub preserve_data: bool,
+}
+
+#[derive(Debug)]
+pub enum PlanError {
+    Parse(String),
+    Introspect(String),
+    /// The schema file needs a feature the target server doesn't have.
+    Unsupported(String),
+    /// Enums lose or reorder values and `rebuild_enums` isn't set.
+    EnumValues(String),
+    /// Statements depend on each other in a loop, so no order applies them.
+    Cycle(String),
+}
+
+impl PlanError {
+    /// The resource attribute a diagnostic for this error should point at.
+    pub fn attribute(&self) -> AttributePath {
+        match self {
+            PlanError::Parse(_)
+            | PlanError::Unsupported(_)
+            | PlanError::EnumValues(_)
+            | PlanError::Cycle(_) => AttributePath::new("schema_file"),
+            PlanError::Introspect(_) => AttributePath::new("database_url"),
+        }
+    }
+}
+
+impl std::fmt::Display for PlanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanError::Parse(e) => write!(f, "Failed to parse schema file: {e}"),
+            PlanError::Introspect(e) => write!(f, "Failed to introspect database: {e}"),
+            PlanError::Unsupported(e) => write!(f, "Unsupported by the target server: {e}"),
+            PlanError::EnumValues(e) => write!(
+                f,
+                "Enum values can't be removed or reordered in place ({e}). Rename values \
+                 with ALTER TYPE ... RENAME VALUE first, or set rebuild_enums = true to \
+                 replace the type and convert the columns using it"
+            ),
+            PlanError::Cycle(e) => write!(
+                f,
+                "Dependency cycle between {e}: each needs the next to exist first. Break it \
+                 in the schema file, e.g. by dropping one of the references"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+pub async fn plan_migration(
+    connection: &PgConnection,
+    schema_file: &str,
+    options: &PlanOptions,
+) -> Result<MigrationPlan, PlanError> {
+    // Parse first so a broken schema file fails before touching the database
+    let mut timings = PhaseTimings::new();
+    let target = parse_timed(schema_file, &mut timings)?;
+    plan_towards(connection, target, timings, options).await
+}
+
+/// Plans the migration from a snapshot of the database, as the provider's mock
+/// mode does, instead of introspecting a live server.
+pub fn plan_from_snapshot(
+    snapshot: &Schema,
+    schema_file: &str,
+    options: &PlanOptions,
+) -> Result<MigrationPlan, PlanError> {
+    let mut timings = PhaseTimings::new();
+    let target = parse_timed(schema_file, &mut timings)?;
+    let current = snapshot_subset(snapshot, &options.target_schemas);
+    diff_plan(
+        current,
+        target,
+        timings,
+        Vec::new(),
+        options.server_version,
+        options,
+    )
+}
+
+/// The part of a mock-mode snapshot standing in for introspecting `schemas`.
+pub fn snapshot_subset(snapshot: &Schema, schemas: &[String]) -> Schema {
+    let mut subset = snapshot.clone();
+    retain_schemas(&mut subset, |schema| schemas.iter().any(|s| s == schema));
+    subset
+}
+
+fn parse_timed(schema_file: &str, timings: &mut PhaseTimings) -> Result<Schema, PlanError> {
+    tracing::debug!(schema_file, "parsing schema file");
+    let started = Instant::now();
+    let target = tracing::debug_span!("parse", schema_file)
+        .in_scope(|| parse_schema_file(schema_file))
+        .map_err(|e| PlanError::Parse(e.to_string()))?;
+    record_phase(timings, "parse", started);
+    Ok(target)
+}
+
+/// Plans the migration from the database to `target`, e.g. a schema
+/// introspected from another database rather than parsed from a file.
+pub async fn plan_to_schema(
+    connection: &PgConnection,
+    target: Schema,
+    options: &PlanOptions,
+) -> Result<MigrationPlan, PlanError> {
+    plan_towards(connection, target, PhaseTimings::new(), options).await
+}
+
+async fn plan_towards(
+    connection: &PgConnection,
+    mut target: Schema,
+    mut timings: PhaseTimings,
+    options: &PlanOptions,
+) -> Result<MigrationPlan, PlanError> {
+    let schemas = introspected_schemas(&options.target_schemas, options.only_objects.as_ref());
+    tracing::debug!(schemas = ?schemas, "introspecting database");
+    let started = Instant::now();
+    let mut skipped_schemas = Vec::new();
+    let introspect_span = tracing::debug_span!("introspect");
+    let current = if options.skip_unreadable_schemas {
+        let current = introspect_readable(connection, &schemas, &mut skipped_schemas)
+            .instrument(introspect_span)
+            .await?;
+        // Nothing in a skipped schema can be compared, so nothing in it is planned
+        let skipped: BTreeSet<&str> = skipped_schemas.iter().map(|s| s.name.as_str()).collect();
+        retain_schemas(&mut target, |schema| !skipped.contains(schema));
+        current
+    } else {
+        introspect(connection, &schemas)
+            .instrument(introspect_span)
+            .await?
+    };
+    record_phase(&mut timings, "introspect", started);
+
+    let server_version = match options.server_version {
+        Some(version) => Some(version),
+        None => match crate::server_version::server_version_num(connection.pool()).await {
+            Ok(version) => Some(version),
+            Err(e) => {
+                tracing::debug!(error = %e, "failed to read server version");
+                None
+            }
+        },
+    };
+    let mut plan = diff_plan(
+        current,
+        target,
+        timings,
+        skipped_schemas,
+        server_version,
+        options,
+    )?;
+    if options.defer_validation && options.phase == Some(crate::operations::Phase::Contract) {
+        let pending = crate::validation::pending(connection.pool(), &options.target_schemas)
+            .await
+            .map_err(|e| PlanError::Introspect(e.to_string()))?;
+        plan.statements.extend(pending);
+    }
+    if plan.operations.iter().any(crate::estimate::scans_table) {
+        // Estimates are advisory, so a failed lookup only drops them
+        match crate::estimate::table_sizes(connection.pool(), &options.target_schemas).await {
+            Ok(sizes) => plan.table_sizes = sizes,
+            Err(e) => tracing::debug!(error = %e, "failed to read table sizes"),
+        }
+    }
+    Ok(plan)
+}
+
+/// Diffs, lints and generates SQL once both sides of the plan are known.
+fn diff_plan(
+    mut current: Schema,
+    mut target: Schema,
+    mut timings: PhaseTimings,
+    skipped_schemas: Vec<SkippedSchema>,
+    server_version: Option<i64>,
+    options: &PlanOptions,
+) -> Result<MigrationPlan, PlanError> {
+    if options.declared_kinds_only {
+        prune_undeclared(&mut current, &target);
+    }
+
+    if let Some(only_objects) = &options.only_objects {
+        retain_objects(&mut current, only_objects);
+        retain_objects(&mut target, only_objects);
+    }
+    options.objects.retain(&mut current);
+    options.objects.retain(&mut target);
+    if options.normalize_expressions {
+        crate::expressions::align(&current, &mut target);
+    }
+
+    let started = Instant::now();
+    let mut enums = crate::enums::plan(&mut current, &target, options.rebuild_enums)
+        .map_err(PlanError::EnumValues)?;
+    let mut operations =
+        tracing::debug_span!("diff").in_scope(|| pgmold::diff::compute_diff(&current, &target));
+    if let Some(phase) = options.phase {
+        operations.retain(|op| phase.includes(op));
+        // Added values are additive; replacing a type rewrites its columns
+        match phase {
+            crate::operations::Phase::Expand => enums.rebuilds.clear(),
+            crate::operations::Phase::Contract => enums.additions.clear(),
+        }
+    }
+    record_phase(&mut timings, "diff", started);
+    tracing::debug!(operations = operations.len(), "computed diff");
+
+    let started = Instant::now();
+    let mut lint_results = tracing::debug_span!("lint")
+        .in_scope(|| pgmold::lint::lint_migration_plan(&operations, &options.lint_options));
+    record_phase(&mut timings, "lint", started);
+    for lint in &lint_results {
+        tracing::debug!(severity = ?lint.severity, message = %lint.message, "lint result");
+    }
+
+    let started = Instant::now();
+    // Enum changes come first, so the rest of the plan can use the new values
+    let mut statements: Vec<String> = tracing::debug_span!("sqlgen").in_scope(|| {
+        enums
+            .additions
+            .into_iter()
+            .chain(enums.rebuilds)
+            .chain(pgmold::pg::sqlgen::generate_sql(&operations))
+            .map(|statement| options.dialect.adapt(&statement))
+            .collect()
+    });
+    statements = crate::ordering::order(statements).map_err(PlanError::Cycle)?;
+    if options.defer_validation {
+        let (deferred, validations) = crate::validation::defer(statements);
+        statements = deferred;
+        // Phased rollouts validate whatever is pending in the contract phase
+        if options.phase.is_none() {
+            statements.extend(validations);
+        }
+    }
+    if options.preserve_sequences || options.preserve_data {
+        statements = crate::recreation::preserve(statements, options.preserve_data);
+    }
+    if let Some(version) = server_version {
+        statements =
+            crate::server_version::adapt(statements, version).map_err(PlanError::Unsupported)?;
+    }
+    record_phase(&mut timings, "sqlgen", started);
+
+    // House rules can match the final SQL, so they run once it's generated
+    let mut custom = crate::lint_rules::evaluate(&options.lint_rules, &operations, &statements);
+    custom.extend(options.operation_policy.evaluate(&operations));
+    for lint in &custom {
+        tracing::debug!(severity = ?lint.severity, message = %lint.message, "lint result");
+    }
+    lint_results.extend(custom);
+    if options.escalate_warnings {
+        for lint in &mut lint_results {
+            if lint.severity == LintSeverity::Warning {
+                lint.severity = LintSeverity::Error;
+            }
+        }
+    }
+
+    Ok(MigrationPlan {
+        operations,
+        lint_results,
+        statements,
+        skipped_schemas,
+        timings,
+        table_sizes: Default::default(),
+    })
+}
+
+pub async fn introspect(
+    connection: &PgConnection,
+    schemas: &[String],
+) -> Result<Schema, PlanError> {
+    pgmold::pg::introspect::introspect_schema(connection, &schemas.to_vec(), false)
+        .await
+        .map_err(|e| PlanError::Introspect(e.to_string()))
+}
+
+/// CREATE statements for every object in `schema`, in pgmold's canonical form.
+pub fn canonical_sql(schema: &Schema) -> String {
+    // Diffing against an empty schema yields the CREATE statements for every object
+    let operations = pgmold::diff::compute_diff(&Schema::default(), schema);
+    pgmold::pg::sqlgen::generate_sql(&operations).join("\n")
+}
+
+/// Introspects the subset of `schemas` the connected role can read, recording the
+/// rest in `skipped`. Schemas without USAGE are dropped up front; if introspection
+/// still fails, each schema is introspected on its own and the readable ones merged.
+async fn introspect_readable(
+    connection: &PgConnection,
+    schemas: &[String],
+    skipped: &mut Vec<SkippedSchema>,
+) -> Result<Schema, PlanError> {
+    let denied: Vec<(String,)> = sqlx::query_as(
+        "SELECT nspname FROM pg_namespace \
+         WHERE nspname = ANY($1) AND NOT has_schema_privilege(oid, 'USAGE')",
+    )
+    .bind(schemas)
+    .fetch_all(connection.pool())
+    .await
+    .map_err(|e| PlanError::Introspect(e.to_string()))?;
+
+    let mut readable = Vec::new();
+    for schema in schemas {
+        if denied.iter().any(|(name,)| name == schema) {
+            tracing::warn!(schema, "skipping schema without USAGE privilege");
+            skipped.push(SkippedSchema {
+                name: schema.clone(),
+                reason: "missing USAGE privilege".to_string(),
+            });
+        } else {
+            readable.push(schema.clone());
+        }
+    }
+
+    if let Ok(current) = introspect(connection, &readable).await {
+        return Ok(current);
+    }
+
+    let mut merged: Option<Schema> = None;
+    for schema in readable {
+        match introspect(connection, std::slice::from_ref(&schema)).await {
+            Ok(current) => match &mut merged {
+                Some(merged) => merge_schema(merged, current),
+                None => merged = Some(current),
+            },
+            Err(e) => skipped.push(SkippedSchema {
+                name: schema,
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(merged.unwrap_or_default())
+}
+
+/// Adds the objects of `other`, introspected from different schemas, to `schema`.
+fn merge_schema(schema: &mut Schema, other: Schema) {
+    schema.tables.extend(other.tables);
+    schema.views.extend(other.views);
+    schema.functions.extend(other.functions);
+    schema.triggers.extend(other.triggers);
+    schema.sequences.extend(other.sequences);
+    schema.enums.extend(other.enums);
+    schema.domains.extend(other.domains);
+}
+
+/// Reports skipped schemas as warnings on `target_schemas`.
+pub fn report_skipped_schemas(diags: &mut Diagnostics, skipped: &[SkippedSchema]) {
+    for schema in skipped {
+        diags.warning(
+            format!("Skipped schema {}: {}", schema.name, schema.reason),
+            "Objects in this schema are excluded from the diff. Grant the role access to manage them.",
+            AttributePath::new("target_schemas"),
+        );
+    }
+}
+
+/// Drops object kinds from `current` that `target` doesn't declare at all, so a
+/// tables-only schema file never diffs (or drops) the functions, views and
+/// triggers of a large database.
+fn prune_undeclared(current: &mut Schema, target: &Schema) {
+    if target.functions.is_empty() {
+        current.functions.clear();
+    }
+    if target.views.is_empty() {
+        current.views.clear();
+    }
+    if target.triggers.is_empty() {
+        current.triggers.clear();
+    }
+    if target.sequences.is_empty() {
+        current.sequences.clear();
+    }
+    if target.enums.is_empty() {
+        current.enums.clear();
+    }
+    if target.domains.is_empty() {
+        current.domains.clear();
+    }
+}
+
+/// The target schemas holding one of `only_objects`, or all of them.
+fn introspected_schemas(
+    target_schemas: &[String],
+    only_objects: Option<&BTreeSet<String>>,
+) -> Vec<String> {
+    let Some(only_objects) = only_objects else {
+        return target_schemas.to_vec();
+    };
+    target_schemas
+        .iter()
+        .filter(|schema| {
+            only_objects
+                .iter()
+                .any(|name| name.split_once('.').is_some_and(|(s, _)| s == *schema))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Keeps the objects named in `names`, with triggers following their table.
+fn retain_objects(schema: &mut Schema, names: &BTreeSet<String>) {
+    retain_where(schema, |schema, name| {
+        names.contains(&format!("{schema}.{name}"))
+    });
+}
+
+/// Keeps the objects of every kind in the schemas `keep` accepts.
+fn retain_schemas(schema: &mut Schema, keep: impl Fn(&str) -> bool) {
+    retain_where(schema, |schema, _| keep(schema));
+}
+
+/// Keeps the objects of every kind for which `keep(schema, name)` holds, with
+/// triggers following their table.
+fn retain_where(schema: &mut Schema, keep: impl Fn(&str, &str) -> bool) {
+    schema.tables.retain(|_, t| keep(&t.schema, &t.name));
+    schema.views.retain(|_, v| keep(&v.schema, &v.name));
+    schema.functions.retain(|_, f| keep(&f.schema, &f.name));
+    schema.sequences.retain(|_, s| keep(&s.schema, &s.name));
+    schema.enums.retain(|_, e| keep(&e.schema, &e.name));
+    schema.domains.retain(|_, d| keep(&d.schema, &d.name));
+    schema
+        .triggers
+        .retain(|_, t| keep(&t.target_schema, &t.target_name));
+}
+
+/// Fails when the server's `server_version_num` is outside `[min, max]`.
+pub async fn check_server_version(
+    diags: &mut Diagnostics,
+    connection: &PgConnection,
+    min: Option<i64>,
+    max: Option<i64>,
+) -> Option<()> {
+    if min.is_none() && max.is_none() {
+        return Some(());
+    }
+
+    let version = match crate::server_version::server_version_num(connection.pool()).await {
+        Ok(version) => version,
+        Err(e) => {
+            diags.root_error_short(format!("Failed to read server version: {e}"));
+            return None;
+        }
+    };
+
+    if let Some(min) = min.filter(|min| version < *min) {
+        diags.error(
+            format!("Server version {version} is below min_server_version {min}"),
+            "The schema file relies on features this server doesn't support. Upgrade the server or lower min_server_version.",
+            AttributePath::new("min_server_version"),
+        );
+        return None;
+    }
+    if let Some(max) = max.filter(|max| version > *max) {
+        diags.error(
+            format!("Server version {version} is above max_server_version {max}"),
+            "The schema file hasn't been verified against this server version. Raise max_server_version once it has.",
+            AttributePath::new("max_server_version"),
+        );
+        return None;
+    }
+    Some(())
+}
+
+/// Rejects a `min_server_version` greater than `max_server_version`.
+pub fn check_version_range(
+    diags: &mut Diagnostics,
+    min: Option<i64>,
+    max: Option<i64>,
+) -> Option<()> {
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => {
+            diags.error_short(
+                format!("min_server_version {min} is greater than max_server_version {max}"),
+                AttributePath::new("min_server_version"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+/// Parses a schema file after normalizing its encoding and line endings with
+/// [`crate::util::read_schema_file`].
+pub fn parse_schema_file(schema_file: &str) -> anyhow::Result<Schema> {
+    let content = crate::util::read_schema_file(std::path::Path::new(schema_file))?;
+    Ok(pgmold::parser::parse_sql_string(&content)?)
+}
+
+/// Parses the schema file locally so syntax errors surface at plan time, with
+/// their location, instead of in the middle of apply. Returns the parsed schema
+/// for the rest of the plan to use.
+pub fn check_schema_syntax(diags: &mut Diagnostics, schema_file: &str) -> Option<Schema> {
+    match parse_schema_file(schema_file) {
+        Ok(schema) => Some(schema),
+        Err(e) => {
+            report_parse_error(diags, schema_file, &e.to_string());
+            None
+        }
+    }
+}
+
+/// Reads the schema file once for plan time, returning the parsed schema, as
+/// [`check_schema_syntax`] does, and the hash [`crate::util::compute_schema_hash`]
+/// would compute, so a large file isn't read again for each.
+pub fn load_schema_file(diags: &mut Diagnostics, schema_file: &str) -> Option<(Schema, String)> {
+    let content = match crate::util::read_schema_file(std::path::Path::new(schema_file)) {
+        Ok(content) => content,
+        Err(e) => {
+            diags.error_short(
+                format!("Failed to read schema file: {e}"),
+                AttributePath::new("schema_file"),
+            );
+            return None;
+        }
+    };
+    let hash = crate::util::compute_content_hash(&content);
+    match pgmold::parser::parse_sql_string(&content) {
+        Ok(schema) => Some((schema, hash)),
+        Err(e) => {
+            report_parse_error(diags, schema_file, &e.to_string());
+            None
+        }
+    }
+}
+
+fn report_parse_error(diags: &mut Diagnostics, schema_file: &str, message: &str) {
+    let summary = match error_location(message) {
+        Some((line, column)) => {
+            format!("Failed to parse {schema_file} at line {line}, column {column}")
+        }
+        None => format!("Failed to parse {schema_file}"),
+    };
+    diags.error(
+        summary,
+        message.to_string(),
+        AttributePath::new("schema_file"),
+    );
+}
+
+/// Rejects schema files that declare no objects, only comments or statements
+/// like `SET` and `GRANT`, which would otherwise plan a drop of everything in the
+/// target schemas, unless `allow_empty` opts into that teardown.
+pub fn check_schema_not_empty(
+    diags: &mut Diagnostics,
+    schema_file: &str,
+    schema: &Schema,
+    allow_empty: bool,
+) -> Option<()> {
+    if allow_empty || !crate::object_filter::object_names(schema).is_empty() {
+        return Some(());
+    }
+    diags.error(
+        format!("{schema_file} declares no objects"),
+        "A schema file without objects drops every object in target_schemas. Set allow_empty = true if that is intended.",
+        AttributePath::new("schema_file"),
+    );
+    None
+}
+
+/// Extracts the `Line: N, Column: M` suffix that the SQL parser appends to errors.
+fn error_location(message: &str) -> Option<(u64, u64)> {
+    let re = regex::Regex::new(r"Line: (\d+), Column:? (\d+)").unwrap();
+    let captures = re.captures(message)?;
+    Some((captures[1].parse().ok()?, captures[2].parse().ok()?))
+}
+
+/// A lint check that fired, as exposed in `lint_results` and the execution log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LintEntry {
+    pub rule: String,
+    /// `error` or `warning`.
+    pub severity: String,
+    pub message: String,
+    /// Object the message names, e.g. `public.users`, when there is one.
+    pub object: Option<String>,
+}
+
+impl LintEntry {
+    /// Terraform type of a `lint_results` element.
+    pub fn attribute_type() -> AttributeType {
+        AttributeType::Object(
+            ["rule", "severity", "message", "object"]
+                .into_iter()
+                .map(|name| (name.to_string(), AttributeType::String))
+                .collect(),
+        )
+    }
+}
+
+pub fn lint_entries(lint_results: &[LintResult]) -> Vec<LintEntry> {
+    lint_results
+        .iter()
+        .map(|lint| LintEntry {
+            rule: lint.rule.to_string(),
+            severity: format!("{:?}", lint.severity).to_lowercase(),
+            message: lint.message.to_string(),
+            object: lint_object(&lint.message),
+        })
+        .collect()
+}
+
+/// First quoted or schema-qualified name in a lint message.
+fn lint_object(message: &str) -> Option<String> {
+    let re = regex::Regex::new(r#""([^"]+)"|\b([A-Za-z_]\w*(?:\.[A-Za-z_]\w*)+)\b"#).unwrap();
+    let captures = re.captures(message)?;
+    captures
+        .get(1)
+        .or_else(|| captures.get(2))
+        .map(|m| m.as_str().to_string())
+}
+
+/// Reports error-level lint results as diagnostics. Returns `true` if any were found.
+pub fn report_lint_errors(diags: &mut Diagnostics, lint_results: &[LintResult]) -> bool {
+    if !pgmold::lint::has_errors(lint_results) {
+        return false;
+    }
+    for lint in lint_results {
+        if lint.severity == LintSeverity::Error {
+            diags.root_error_short(lint.message.to_string());
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_object_takes_first_qualified_name() {
+        assert_eq!(
+            lint_object("Dropping column public.users.email loses data").as_deref(),
+            Some("public.users.email")
+        );
+        assert_eq!(
+            lint_object("Table \"orders\" has no primary key").as_deref(),
+            Some("orders")
+        );
+        assert_eq!(lint_object("Migration drops data"), None);
+    }
+
+    #[test]
+    fn prune_undeclared_keeps_declared_kinds() {
+        let current = pgmold::parser::parse_sql_string(
+            "CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE VIEW active_users AS SELECT id FROM users;\n\
+             CREATE FUNCTION one() RETURNS INT LANGUAGE sql AS 'SELECT 1';",
+        )
+        .unwrap();
+        let target = pgmold::parser::parse_sql_string(
+            "CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE VIEW active_users AS SELECT id FROM users;",
+        )
+        .unwrap();
+
+        let mut pruned = current.clone();
+        prune_undeclared(&mut pruned, &target);
+
+        assert_eq!(pruned.tables.len(), current.tables.len());
+        assert_eq!(pruned.views.len(), 1);
+        assert!(pruned.functions.is_empty());
+    }
+
+    #[test]
+    fn plan_error_points_at_attribute() {
+        assert_eq!(
+            PlanError::Parse("bad".to_string()).attribute(),
+            AttributePath::new("schema_file")
+        );
+        assert_eq!(
+            PlanError::Introspect("bad".to_string()).to_string(),
+            "Failed to introspect database: bad"
+        );
+    }
+
+    #[test]
+    fn record_phase_stores_elapsed_milliseconds() {
+        let mut timings = PhaseTimings::new();
+        let started = Instant::now() - std::time::Duration::from_millis(25);
+        record_phase(&mut timings, "introspect", started);
+        assert!(timings["introspect"] >= 25);
+    }
+
+    #[test]
+    fn check_schema_not_empty_rejects_statements_without_objects() {
+        let schema =
+            pgmold::parser::parse_sql_string("-- nothing yet\nSET search_path TO public;").unwrap();
+
+        let mut diags = Diagnostics::default();
+        assert!(check_schema_not_empty(&mut diags, "schema.sql", &schema, false).is_none());
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("schema_file"));
+
+        let mut diags = Diagnostics::default();
+        assert!(check_schema_not_empty(&mut diags, "schema.sql", &schema, true).is_some());
+        let tables = pgmold::parser::parse_sql_string("CREATE TABLE t (id INT);").unwrap();
+        assert!(check_schema_not_empty(&mut diags, "schema.sql", &tables, false).is_some());
+        assert!(diags.errors.is_empty());
+    }
+
+    #[test]
+    fn check_version_range_rejects_inverted_bounds() {
+        let mut diags = Diagnostics::default();
+        assert!(check_version_range(&mut diags, Some(160000), Some(150000)).is_none());
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("min_server_version")
+        );
+
+        let mut diags = Diagnostics::default();
+        assert!(check_version_range(&mut diags, Some(150000), None).is_some());
+        assert!(diags.errors.is_empty());
+    }
+
+    #[test]
+    fn error_location_parses_parser_suffix() {
+        assert_eq!(
+            error_location("Expected: ), found: ; at Line: 3, Column: 17"),
+            Some((3, 17))
+        );
+        assert_eq!(
+            error_location("Expected ), found: ; at Line: 3, Column 17"),
+            Some((3, 17))
+        );
+        assert_eq!(error_location("unexpected end of input"), None);
+    }
+
+    #[test]
+    fn load_schema_file_hashes_like_compute_schema_hash() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"\xEF\xBB\xBFCREATE TABLE users (\r\n  id INT PRIMARY KEY\r\n);\r\n")
+            .unwrap();
+        let path = file.path().to_string_lossy().to_string();
+
+        let mut diags = Diagnostics::default();
+        let (schema, hash) = load_schema_file(&mut diags, &path).unwrap();
+
+        assert_eq!(schema.tables.len(), 1);
+        assert_eq!(hash, crate::util::compute_schema_hash(file.path()).unwrap());
+    }
+
+    #[test]
+    fn check_schema_syntax_reports_schema_file_attribute() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "CREATE TABLE users (\n  id INT PRIMARY KEY,\n  name TEXT NOT\n;"
+        )
+        .unwrap();
+
+        let mut diags = Diagnostics::default();
+        let result = check_schema_syntax(&mut diags, &file.path().to_string_lossy());
+
+        assert!(result.is_none());
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("schema_file"));
+    }
+
+    #[test]
+    fn retain_objects_keeps_named_objects_of_every_kind() {
+        let mut schema = pgmold::parser::parse_sql_string(
+            "CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE TABLE orders (id INT PRIMARY KEY);\n\
+             CREATE VIEW active_users AS SELECT id FROM users;\n\
+             CREATE FUNCTION one() RETURNS INT LANGUAGE sql AS 'SELECT 1';",
+        )
+        .unwrap();
+
+        retain_objects(
+            &mut schema,
+            &BTreeSet::from([
+                "public.orders".to_string(),
+                "public.active_users".to_string(),
+            ]),
+        );
+
+        assert_eq!(schema.tables.len(), 1);
+        assert!(schema.tables.values().all(|t| t.name == "orders"));
+        assert_eq!(schema.views.len(), 1);
+        assert!(schema.functions.is_empty());
+    }
+
+    #[test]
+    fn snapshot_subset_filters_every_kind_to_target_schemas() {
+        let snapshot = pgmold::parser::parse_sql_string(
+            "CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE TABLE billing.invoices (id INT PRIMARY KEY);\n\
+             CREATE VIEW billing.open_invoices AS SELECT id FROM billing.invoices;\n\
+             CREATE TYPE billing.status AS ENUM ('open');",
+        )
+        .unwrap();
+
+        let subset = snapshot_subset(&snapshot, &["public".to_string()]);
+
+        assert_eq!(subset.tables.len(), 1);
+        assert!(subset.views.is_empty());
+        assert!(subset.enums.is_empty());
+    }
+
+    #[test]
+    fn retain_schemas_drops_every_kind_in_other_schemas() {
+        let mut schema = pgmold::parser::parse_sql_string(
+            "CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE TABLE secret.tokens (id INT PRIMARY KEY);\n\
+             CREATE VIEW secret.token_ids AS SELECT id FROM secret.tokens;\n\
+             CREATE FUNCTION secret.token_count() RETURNS INT LANGUAGE sql AS 'SELECT 1';",
+        )
+        .unwrap();
+
+        retain_schemas(&mut schema, |schema| schema != "secret");
+
+        assert_eq!(schema.tables.len(), 1);
+        assert!(schema.views.is_empty());
+        assert!(schema.functions.is_empty());
+    }
+
+    #[test]
+    fn merge_schema_keeps_objects_of_both_schemas() {
+        let mut schema = pgmold::parser::parse_sql_string(
+            "CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE VIEW user_ids AS SELECT id FROM users;",
+        )
+        .unwrap();
+        let billing = pgmold::parser::parse_sql_string(
+            "CREATE TABLE billing.invoices (id INT PRIMARY KEY);\n\
+             CREATE TYPE billing.status AS ENUM ('open');",
+        )
+        .unwrap();
+
+        merge_schema(&mut schema, billing);
+
+        assert_eq!(schema.tables.len(), 2);
+        assert_eq!(schema.views.len(), 1);
+        assert_eq!(schema.enums.len(), 1);
+    }
+
+    #[test]
+    fn introspected_schemas_only_holds_changed_objects() {
+        let schemas = vec!["public".to_string(), "billing".to_string()];
+        assert_eq!(introspected_schemas(&schemas, None), schemas);
+        assert_eq!(
+            introspected_schemas(
+                &schemas,
+                Some(&BTreeSet::from(["billing.invoices".to_string()]))
+            ),
+            vec!["billing".to_string()]
+        );
+    }
+}
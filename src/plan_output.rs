@@ -0,0 +1,243 @@
+use serde::Serialize;
+use tf_provider::{AttributePath, Diagnostics};
+
+use crate::operations::OperationClass;
+use crate::pipeline::{LintEntry, MigrationPlan};
+
+/// The `plan_output_path` document: what apply would do, for approval tooling
+/// such as OPA, Sentinel or Atlantis to check before apply is allowed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanOutput {
+    pub generated_at: String,
+    pub resource: &'static str,
+    pub schema_hash: String,
+    /// Whether any operation drops objects or data.
+    pub destructive: bool,
+    /// Value `destructive_approval_token` must be set to for these operations to apply.
+    pub destructive_hash: Option<String>,
+    /// Sum of the operations' `estimated_seconds`.
+    pub estimated_seconds: u64,
+    /// Whether any operation blocks reads of an existing table while it runs.
+    pub blocks_reads: bool,
+    /// Whether any operation blocks writes to an existing table while it runs.
+    pub blocks_writes: bool,
+    pub operations: Vec<PlannedOperation>,
+    pub statements: Vec<String>,
+    pub lint_results: Vec<LintEntry>,
+    /// `hmac-sha256:<hex>` signature of `statements` under the provider's
+    /// `plan_signing_key`; null without one.
+    pub signature: Option<String>,
+    /// Hash of the statements the last apply ran, when these differ from them.
+    pub previous_statements_hash: Option<String>,
+    /// Unified diff from the statements the last apply ran to these, so
+    /// reviewers see what changed since the plan they approved before.
+    pub sql_diff: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedOperation {
+    /// Operation kind, e.g. `CreateTable`.
+    pub kind: String,
+    /// `additive`, `destructive` or `rewrite`.
+    pub class: &'static str,
+    pub destructive: bool,
+    /// Table lock taken, e.g. `ACCESS EXCLUSIVE`; null when no existing table is locked.
+    pub lock: Option<&'static str>,
+    pub blocks_reads: bool,
+    pub blocks_writes: bool,
+    /// Rough duration of operations that scan or rewrite an existing table,
+    /// from its size; null for the rest.
+    pub estimated_seconds: Option<u64>,
+    /// Rows in the table such an operation scans.
+    pub estimated_rows: Option<i64>,
+    pub detail: String,
+}
+
+impl PlanOutput {
+    /// A plan with nothing to do.
+    pub fn empty(resource: &'static str, schema_hash: &str) -> Self {
+        PlanOutput {
+            generated_at: crate::clock::now().to_rfc3339(),
+            resource,
+            schema_hash: schema_hash.to_string(),
+            destructive: false,
+            destructive_hash: None,
+            estimated_seconds: 0,
+            blocks_reads: false,
+            blocks_writes: false,
+            operations: Vec::new(),
+            statements: Vec::new(),
+            lint_results: Vec::new(),
+            signature: None,
+            previous_statements_hash: None,
+            sql_diff: None,
+        }
+    }
+
+    /// Signs the statements with `key`, if any.
+    pub fn signed(mut self, key: Option<&str>) -> Self {
+        self.signature = key.map(|key| crate::signing::sign(key, &self.statements));
+        self
+    }
+
+    /// Adds the diff from `previous`, the statements of an earlier plan with
+    /// hash `previous_hash`.
+    pub fn diffed(mut self, previous_hash: &str, previous: &[String]) -> Self {
+        self.previous_statements_hash = Some(previous_hash.to_string());
+        self.sql_diff = Some(sql_diff(previous, &self.statements));
+        self
+    }
+
+    pub fn new(resource: &'static str, schema_hash: &str, plan: &MigrationPlan) -> Self {
+        let operations: Vec<PlannedOperation> = plan
+            .operations
+            .iter()
+            .map(|op| {
+                let class = crate::operations::classify(op);
+                let lock = crate::operations::lock_level(op);
+                let estimate = crate::estimate::estimate(op, &plan.table_sizes);
+                PlannedOperation {
+                    kind: crate::operations::operation_kind(op),
+                    class: class.as_str(),
+                    destructive: class == OperationClass::Destructive,
+                    lock: lock.map(|l| l.as_str()),
+                    blocks_reads: lock.is_some_and(|l| l.blocks_reads()),
+                    blocks_writes: lock.is_some_and(|l| l.blocks_writes()),
+                    estimated_seconds: estimate.map(|e| e.seconds),
+                    estimated_rows: estimate.map(|e| e.rows),
+                    detail: format!("{op:?}"),
+                }
+            })
+            .collect();
+        PlanOutput {
+            destructive: operations.iter().any(|op| op.destructive),
+            destructive_hash: crate::operations::destructive_hash(&plan.operations),
+            estimated_seconds: operations
+                .iter()
+                .filter_map(|op| op.estimated_seconds)
+                .sum(),
+            blocks_reads: operations.iter().any(|op| op.blocks_reads),
+            blocks_writes: operations.iter().any(|op| op.blocks_writes),
+            operations,
+            statements: plan.statements.clone(),
+            lint_results: crate::pipeline::lint_entries(&plan.lint_results),
+            ..PlanOutput::empty(resource, schema_hash)
+        }
+    }
+}
+
+/// Statements as a script, one per line ending in `;`, so both sides of the
+/// diff read the same whether or not a statement carried its own.
+fn script(statements: &[String]) -> String {
+    statements
+        .iter()
+        .map(|s| format!("{};\n", s.trim_end().trim_end_matches(';')))
+        .collect()
+}
+
+fn sql_diff(previous: &[String], planned: &[String]) -> String {
+    similar::TextDiff::from_lines(&script(previous), &script(planned))
+        .unified_diff()
+        .context_radius(3)
+        .header("previous", "planned")
+        .to_string()
+}
+
+/// Writes `output` as pretty-printed JSON to `path`, if set, replacing any
+/// previous plan. Approval tooling relies on the file, so failures are errors.
+pub fn write(diags: &mut Diagnostics, path: Option<&str>, output: &PlanOutput) -> Option<()> {
+    let Some(path) = path else {
+        return Some(());
+    };
+    if let Err(e) = write_json(path, output) {
+        diags.error(
+            "Failed to write plan output",
+            format!("{path}: {e}"),
+            AttributePath::new("plan_output_path"),
+        );
+        return None;
+    }
+    Some(())
+}
+
+fn write_json(path: &str, output: &PlanOutput) -> anyhow::Result<()> {
+    let mut json = serde_json::to_string_pretty(output)?;
+    json.push('\n');
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_replaces_previous_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plan.json");
+        let path = path.to_str().unwrap();
+
+        let mut diags = Diagnostics::default();
+        let mut output = PlanOutput::empty("pgmold_schema", "abc");
+        output.statements = vec!["DROP TABLE users;".to_string()];
+        write(&mut diags, Some(path), &output).unwrap();
+        write(
+            &mut diags,
+            Some(path),
+            &PlanOutput::empty("pgmold_schema", "def"),
+        )
+        .unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(written["schema_hash"], "def");
+        assert_eq!(written["statements"], serde_json::json!([]));
+        assert_eq!(written["destructive"], false);
+        assert_eq!(written["blocks_writes"], false);
+        assert_eq!(written["signature"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn signed_covers_statements() {
+        let mut output = PlanOutput::empty("pgmold_schema", "abc");
+        output.statements = vec!["DROP TABLE users;".to_string()];
+        let output = output.signed(Some("key"));
+        let signature = output.signature.as_deref().unwrap();
+        assert!(crate::signing::verify("key", &output.statements, signature));
+    }
+
+    #[test]
+    fn diffed_shows_changed_statements() {
+        let mut output = PlanOutput::empty("pgmold_schema", "abc");
+        output.statements = vec![
+            "CREATE TABLE users (id bigint)".to_string(),
+            "CREATE INDEX users_email_idx ON users (email);".to_string(),
+        ];
+        let previous = vec![
+            "CREATE TABLE users (id int);".to_string(),
+            "CREATE INDEX users_email_idx ON users (email)".to_string(),
+        ];
+
+        let output = output.diffed("old", &previous);
+
+        assert_eq!(output.previous_statements_hash.as_deref(), Some("old"));
+        assert_eq!(
+            output.sql_diff.unwrap(),
+            "--- previous\n\
+             +++ planned\n\
+             @@ -1,2 +1,2 @@\n\
+             -CREATE TABLE users (id int);\n\
+             +CREATE TABLE users (id bigint);\n \
+             CREATE INDEX users_email_idx ON users (email);\n"
+        );
+    }
+
+    #[test]
+    fn write_reports_unwritable_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut diags = Diagnostics::default();
+        let output = PlanOutput::empty("pgmold_schema", "abc");
+        assert!(write(&mut diags, dir.path().to_str(), &output).is_none());
+        assert_eq!(diags.errors.len(), 1);
+    }
+}
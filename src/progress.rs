@@ -0,0 +1,119 @@
+use std::future::Future;
+use std::time::Duration;
+
+use sqlx::PgPool;
+
+/// How often a long-running statement's progress is logged.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Progress of index builds (including those run by `ALTER TABLE`) and of
+/// `CLUSTER` / `VACUUM FULL` rewrites, for one backend.
+const PROGRESS_QUERY: &str = "\
+    SELECT command, phase, blocks_done::bigint, blocks_total::bigint, \
+           tuples_done::bigint, tuples_total::bigint \
+    FROM pg_stat_progress_create_index WHERE pid = $1 \
+    UNION ALL \
+    SELECT command, phase, heap_blks_scanned::bigint, heap_blks_total::bigint, \
+           heap_tuples_scanned::bigint, 0::bigint \
+    FROM pg_stat_progress_cluster WHERE pid = $1";
+
+/// Whether a statement can run long enough to be worth reporting progress for:
+/// index builds, and table rewrites that rebuild indexes.
+pub fn is_long_running(statement: &str) -> bool {
+    let re = regex::Regex::new(
+        r"(?is)^\s*(CREATE\s+(UNIQUE\s+)?INDEX|REINDEX|CLUSTER|VACUUM\s*(\([^)]*\bFULL\b|FULL))\b|^\s*ALTER\s+TABLE\b.*\b(TYPE|PRIMARY\s+KEY|UNIQUE)\b",
+    )
+    .unwrap();
+    re.is_match(statement)
+}
+
+/// Backend process id of a connection, as reported in `pg_stat_progress_*`.
+pub async fn backend_pid(connection: &mut sqlx::PgConnection) -> sqlx::Result<i32> {
+    sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(connection)
+        .await
+}
+
+/// Awaits `statement`, logging the progress of backend `pid` every few seconds
+/// until it finishes. Without a `pid` the statement is simply awaited.
+pub async fn track<F: Future>(
+    pool: &PgPool,
+    pid: Option<i32>,
+    statement_index: usize,
+    statement: F,
+) -> F::Output {
+    let Some(pid) = pid else {
+        return statement.await;
+    };
+    let poller = tokio::spawn(poll(pool.clone(), pid, statement_index));
+    let output = statement.await;
+    poller.abort();
+    output
+}
+
+async fn poll(pool: PgPool, pid: i32, statement_index: usize) {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let rows: Vec<(String, String, i64, i64, i64, i64)> = match sqlx::query_as(PROGRESS_QUERY)
+            .bind(pid)
+            .fetch_all(&pool)
+            .await
+        {
+            Ok(rows) => rows,
+            // e.g. engines without these views; the statement itself is unaffected
+            Err(e) => {
+                tracing::debug!(error = %e, "progress reporting unavailable");
+                return;
+            }
+        };
+        for (command, phase, blocks_done, blocks_total, tuples_done, tuples_total) in rows {
+            match percent(blocks_done, blocks_total).or(percent(tuples_done, tuples_total)) {
+                Some(percent) => tracing::info!(
+                    statement_index,
+                    command,
+                    phase,
+                    percent,
+                    "statement progress: {command} {phase} {percent}%"
+                ),
+                None => tracing::info!(
+                    statement_index,
+                    command,
+                    phase,
+                    "statement progress: {command} {phase}"
+                ),
+            }
+        }
+    }
+}
+
+/// Whole percentage of `done` out of `total`, or `None` while the total is unknown.
+pub(crate) fn percent(done: i64, total: i64) -> Option<i64> {
+    (total > 0).then(|| (done * 100 / total).clamp(0, 100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_builds_and_rewrites_are_long_running() {
+        assert!(is_long_running(
+            "CREATE INDEX CONCURRENTLY users_email_idx ON users (email)"
+        ));
+        assert!(is_long_running(
+            "CREATE UNIQUE INDEX users_email_key ON users (email)"
+        ));
+        assert!(is_long_running(
+            "ALTER TABLE users ALTER COLUMN id TYPE BIGINT"
+        ));
+        assert!(is_long_running("VACUUM (FULL, VERBOSE) users"));
+        assert!(!is_long_running("CREATE TABLE users (id INT)"));
+        assert!(!is_long_running("ALTER TABLE users ADD COLUMN name TEXT"));
+    }
+
+    #[test]
+    fn percent_needs_a_known_total() {
+        assert_eq!(percent(25, 200), Some(12));
+        assert_eq!(percent(10, 0), None);
+    }
+}
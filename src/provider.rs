@@ -5,21 +5,61 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tf_provider::{
     schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
-    Diagnostics, DynamicResource, Provider,
+    AttributePath, Diagnostics, DynamicDataSource, DynamicFunction, DynamicResource, Provider,
 };
 use tokio::sync::RwLock;
 
-use crate::resources::{MigrationResource, SchemaResource};
+use crate::claims::ClaimRegistry;
+use crate::connections::ConnectionCache;
+use crate::data_sources::{
+    IndexesDataSource, PendingOperationsDataSource, ProviderInfoDataSource, QueryDataSource,
+    SchemaDumpDataSource, ServerInfoDataSource, TablesDataSource,
+};
+use crate::functions::{
+    DatabaseUrlFunction, DiffSqlFunction, ParseDdlFunction, QuoteIdentFunction,
+    QuoteLiteralFunction, RedactUrlFunction, SchemaHashFunction,
+};
+use crate::resources::{
+    BackupResource, MigrationResource, RestoreResource, SchemaCloneResource, SchemaPhaseResource,
+    SchemaResource, SchemaSwapResource, TenantSchemasResource,
+};
+use crate::telemetry::Telemetry;
+use crate::throttle::ApplyLimiter;
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub database_url: Option<String>,
     pub target_schemas: Option<Vec<String>>,
+    pub max_parallel_applies: Option<i64>,
+    pub tcp_keepalives_idle: Option<i64>,
+    pub redact_hosts: Option<bool>,
+    pub otlp_endpoint: Option<String>,
+    pub otlp_headers: Option<HashMap<String, String>>,
+    pub mock: Option<bool>,
+    pub mock_snapshot: Option<String>,
+    pub target_server_version: Option<i64>,
+    pub plan_signing_key: Option<String>,
+    pub schema_decryption_key: Option<String>,
+    pub require_confirmation: Option<bool>,
+    pub confirmation_token: Option<String>,
+    pub introspection_timeout: Option<String>,
+    pub workspace: Option<String>,
+    pub run_id: Option<String>,
+    pub git_commit: Option<String>,
+    pub lint_rules: Option<Vec<HashMap<String, String>>>,
+    pub environment: Option<String>,
+    pub lint_policy: Option<HashMap<String, String>>,
+    pub operation_policy_file: Option<String>,
+    pub failpoints: Option<Vec<String>>,
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct PgmoldProvider {
     pub config: Arc<RwLock<Option<ProviderConfig>>>,
+    pub apply_limiter: ApplyLimiter,
+    pub connections: ConnectionCache,
+    pub telemetry: Telemetry,
+    pub claims: ClaimRegistry,
 }
 
 #[async_trait]
@@ -51,6 +91,264 @@ impl Provider for PgmoldProvider {
             },
         );
 
+        attributes.insert(
+            "max_parallel_applies".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Maximum number of pgmold resources applied concurrently (default: unlimited)",
+                ),
+                attr_type: AttributeType::Number,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "tcp_keepalives_idle".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Seconds of inactivity after which the server sends TCP keepalives (default: server setting)",
+                ),
+                attr_type: AttributeType::Number,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "redact_hosts".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Mask database hosts and ports in connection errors (default: false)",
+                ),
+                attr_type: AttributeType::Bool,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "introspection_timeout".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "statement_timeout of the read-only sessions plans and refreshes introspect through, e.g. 30s or 500ms (default: 30s)",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "otlp_endpoint".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "OTLP/HTTP collector URL (e.g. http://collector:4318) to export a span and metrics for each resource operation to (default: disabled)",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "otlp_headers".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Headers sent with every OTLP export, e.g. for collector authentication",
+                ),
+                attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                constraint: AttributeConstraint::Optional,
+                sensitive: true,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "mock".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Plan against mock_snapshot instead of connecting to databases; applies fail (default: false)",
+                ),
+                attr_type: AttributeType::Bool,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "mock_snapshot".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "SQL file describing the database in mock mode, e.g. a pgmold_schema_dump output (default: an empty database)",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "plan_signing_key".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "HMAC key that signs plan_output_path documents and generated migrations, and verifies approved_plan_signature",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                sensitive: true,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "workspace".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Terraform workspace recorded in generated migrations and execution logs (default: from TF_WORKSPACE, TFC_WORKSPACE_NAME or Atlantis' WORKSPACE)",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "run_id".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Pipeline run recorded in generated migrations and execution logs (default: from TFC_RUN_ID, Atlantis' PULL_URL, GITHUB_RUN_ID or CI_PIPELINE_ID)",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "git_commit".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Git commit recorded in generated migrations and execution logs (default: from TFC_CONFIGURATION_VERSION_GIT_COMMIT_SHA, Atlantis' HEAD_COMMIT, GITHUB_SHA or CI_COMMIT_SHA)",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "target_server_version".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "server_version_num to generate SQL for, e.g. 130000, instead of the version of the server each plan connects to",
+                ),
+                attr_type: AttributeType::Number,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "lint_rules".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "House lint rules checked alongside pgmold's on every plan. Each has a name, a statement and/or operation regex, a severity (error or warning, default error) and a message",
+                ),
+                attr_type: AttributeType::List(Box::new(AttributeType::Map(Box::new(
+                    AttributeType::String,
+                )))),
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "environment".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Environment the provider deploys to, e.g. production, which selects the lint_policy entry",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "lint_policy".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Lowest lint severity per environment: error turns lint warnings into errors that block the apply, warning keeps them as warnings",
+                ),
+                attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "operation_policy_file".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "HCL or JSON file of rules allowing or denying operation kinds per object pattern; operations a rule doesn't allow fail the plan",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "schema_decryption_key".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "age identities (AGE-SECRET-KEY-1... lines) that decrypt schema files encrypted with age or SOPS. Defaults to the PGMOLD_AGE_KEY environment variable",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                sensitive: true,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "require_confirmation".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Only run destructive operations when the apply is confirmed with PGMOLD_CONFIRM=yes or a matching confirmation_token, on top of allow_destructive (default: false)",
+                ),
+                attr_type: AttributeType::Bool,
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "confirmation_token".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Confirms a destructive apply under require_confirmation when it equals the destructive_operations_hash of the plan. Set it per pipeline, e.g. from a CI variable",
+                ),
+                attr_type: AttributeType::String,
+                constraint: AttributeConstraint::Optional,
+                sensitive: true,
+                ..Default::default()
+            },
+        );
+
+        attributes.insert(
+            "failpoints".to_string(),
+            Attribute {
+                description: Description::plain(
+                    "Testing only: failures to inject into applies, e.g. lock_timeout:3. Ignored unless PGMOLD_ENABLE_FAILPOINTS=1",
+                ),
+                attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                constraint: AttributeConstraint::Optional,
+                ..Default::default()
+            },
+        );
+
         Some(Schema {
             version: 1,
             block: Block {
@@ -64,10 +362,201 @@ impl Provider for PgmoldProvider {
 
     async fn configure<'a>(
         &self,
-        _diags: &mut Diagnostics,
+        diags: &mut Diagnostics,
         _terraform_version: String,
         config: Self::Config<'a>,
     ) -> Option<()> {
+        let max_parallel_applies = match config.max_parallel_applies {
+            Some(n) if n < 1 => {
+                diags.error_short(
+                    "max_parallel_applies must be at least 1",
+                    AttributePath::new("max_parallel_applies"),
+                );
+                return None;
+            }
+            n => n.map(|n| n as usize),
+        };
+        self.apply_limiter.set_limit(max_parallel_applies).await;
+
+        let tcp_keepalives_idle = match config.tcp_keepalives_idle {
+            Some(n) if n < 1 => {
+                diags.error_short(
+                    "tcp_keepalives_idle must be at least 1",
+                    AttributePath::new("tcp_keepalives_idle"),
+                );
+                return None;
+            }
+            n => n.map(|n| n as u32),
+        };
+        self.connections
+            .set_keepalives_idle(tcp_keepalives_idle)
+            .await;
+        self.connections
+            .set_redact_hosts(config.redact_hosts.unwrap_or(false))
+            .await;
+
+        // Goes into the connection URL, so only plain durations are accepted
+        let duration = regex::Regex::new(r"^[0-9]+(us|ms|s|min|h|d)?$").unwrap();
+        if let Some(timeout) = config
+            .introspection_timeout
+            .as_deref()
+            .filter(|t| !duration.is_match(t))
+        {
+            diags.error(
+                format!("Invalid introspection_timeout {timeout:?}"),
+                "introspection_timeout is a duration such as 30s, 500ms or 2min.",
+                AttributePath::new("introspection_timeout"),
+            );
+            return None;
+        }
+        self.connections
+            .set_introspection_timeout(config.introspection_timeout.clone())
+            .await;
+
+        let snapshot = match (config.mock.unwrap_or(false), &config.mock_snapshot) {
+            (false, None) => None,
+            (false, Some(_)) => {
+                diags.error_short(
+                    "mock_snapshot requires mock = true",
+                    AttributePath::new("mock_snapshot"),
+                );
+                return None;
+            }
+            (true, None) => Some(pgmold::model::Schema::default()),
+            (true, Some(path)) => match crate::pipeline::parse_schema_file(path) {
+                Ok(snapshot) => Some(snapshot),
+                Err(e) => {
+                    diags.error_short(
+                        format!("Failed to load mock_snapshot {path}: {e}"),
+                        AttributePath::new("mock_snapshot"),
+                    );
+                    return None;
+                }
+            },
+        };
+        if snapshot.is_some() {
+            tracing::info!("mock mode: planning against a snapshot, no database connections");
+        }
+        self.connections.set_mock(snapshot).await;
+
+        // Catches a major version such as 15 given instead of a server_version_num
+        if let Some(version) = config.target_server_version.filter(|v| *v < 90600) {
+            diags.error(
+                format!("Invalid target_server_version {version}"),
+                "target_server_version is a server_version_num, e.g. 150004 for PostgreSQL 15.4 or 90624 for 9.6.24.",
+                AttributePath::new("target_server_version"),
+            );
+            return None;
+        }
+        self.connections
+            .set_target_server_version(config.target_server_version)
+            .await;
+        self.connections
+            .set_plan_signing_key(config.plan_signing_key.clone().filter(|k| !k.is_empty()))
+            .await;
+        crate::encryption::set_key(config.schema_decryption_key.clone());
+        self.connections
+            .set_resource_defaults(crate::connections::ResourceDefaults {
+                database_url: config.database_url.clone().filter(|u| !u.is_empty()),
+                target_schemas: config.target_schemas.clone().filter(|s| !s.is_empty()),
+            })
+            .await;
+        self.connections
+            .set_confirmation(crate::confirmation::Confirmation {
+                required: config.require_confirmation.unwrap_or(false),
+                token: config.confirmation_token.clone().filter(|t| !t.is_empty()),
+            })
+            .await;
+        self.connections
+            .set_run_metadata(crate::run_metadata::RunMetadata::resolve(
+                config.workspace.clone(),
+                config.run_id.clone(),
+                config.git_commit.clone(),
+            ))
+            .await;
+
+        let rules = config.lint_rules.clone().unwrap_or_default();
+        let lint_rules = match crate::lint_rules::parse(&rules) {
+            Ok(rules) => rules,
+            Err(e) => {
+                diags.error_short(e, AttributePath::new("lint_rules"));
+                return None;
+            }
+        };
+        self.connections.set_lint_rules(lint_rules).await;
+
+        let policy = config.lint_policy.clone().unwrap_or_default();
+        if let Some((environment, severity)) = policy
+            .iter()
+            .find(|(_, severity)| !matches!(severity.as_str(), "error" | "warning"))
+        {
+            diags.error_short(
+                format!("Invalid lint_policy severity \"{severity}\" for {environment}, expected error or warning"),
+                AttributePath::new("lint_policy"),
+            );
+            return None;
+        }
+        let escalate = config
+            .environment
+            .as_ref()
+            .and_then(|environment| policy.get(environment))
+            .is_some_and(|severity| severity == "error");
+        self.connections.set_escalate_warnings(escalate).await;
+
+        let operation_policy = match &config.operation_policy_file {
+            None => Default::default(),
+            Some(path) => match crate::operation_policy::OperationPolicy::load(path) {
+                Ok(policy) => policy,
+                Err(e) => {
+                    diags.error_short(
+                        format!("Failed to load operation_policy_file {path}: {e}"),
+                        AttributePath::new("operation_policy_file"),
+                    );
+                    return None;
+                }
+            },
+        };
+        self.connections
+            .set_operation_policy(operation_policy)
+            .await;
+
+        let specs = config.failpoints.clone().unwrap_or_default();
+        let failpoints = match crate::failpoints::Failpoints::parse(&specs) {
+            Ok(failpoints) => failpoints,
+            Err(e) => {
+                diags.error_short(e, AttributePath::new("failpoints"));
+                return None;
+            }
+        };
+        let failpoints = if failpoints.is_empty() || crate::failpoints::enabled() {
+            failpoints
+        } else {
+            diags.root_warning_short(format!(
+                "failpoints are ignored unless {}=1 is set",
+                crate::failpoints::ENABLE_VAR
+            ));
+            Default::default()
+        };
+        if !failpoints.is_empty() {
+            tracing::warn!(
+                ?failpoints,
+                "failpoints enabled: applies will fail on purpose"
+            );
+        }
+        self.connections.set_failpoints(failpoints).await;
+
+        if let Some(endpoint) = &config.otlp_endpoint {
+            let headers = config.otlp_headers.clone().unwrap_or_default();
+            if let Err(e) = self.telemetry.configure(endpoint, headers).await {
+                diags.error(
+                    "Failed to set up OTLP export",
+                    e.to_string(),
+                    AttributePath::new("otlp_endpoint"),
+                );
+                return None;
+            }
+        }
+
         let mut guard = self.config.write().await;
         *guard = Some(config);
         Some(())
@@ -78,10 +567,113 @@ impl Provider for PgmoldProvider {
         _diags: &mut Diagnostics,
     ) -> Option<HashMap<String, Box<dyn DynamicResource>>> {
         let mut resources: HashMap<String, Box<dyn DynamicResource>> = HashMap::new();
-        resources.insert("schema".to_string(), Box::new(SchemaResource));
-        resources.insert("migration".to_string(), Box::new(MigrationResource));
+        resources.insert(
+            "schema".to_string(),
+            Box::new(SchemaResource {
+                apply_limiter: self.apply_limiter.clone(),
+                connections: self.connections.clone(),
+                telemetry: self.telemetry.clone(),
+                claims: self.claims.clone(),
+            }),
+        );
+        resources.insert(
+            "migration".to_string(),
+            Box::new(MigrationResource {
+                apply_limiter: self.apply_limiter.clone(),
+                connections: self.connections.clone(),
+                telemetry: self.telemetry.clone(),
+            }),
+        );
+        resources.insert(
+            "schema_phase".to_string(),
+            Box::new(SchemaPhaseResource {
+                apply_limiter: self.apply_limiter.clone(),
+                connections: self.connections.clone(),
+                telemetry: self.telemetry.clone(),
+            }),
+        );
+        resources.insert(
+            "schema_swap".to_string(),
+            Box::new(SchemaSwapResource {
+                apply_limiter: self.apply_limiter.clone(),
+                connections: self.connections.clone(),
+                telemetry: self.telemetry.clone(),
+            }),
+        );
+        resources.insert(
+            "schema_clone".to_string(),
+            Box::new(SchemaCloneResource {
+                apply_limiter: self.apply_limiter.clone(),
+                connections: self.connections.clone(),
+                telemetry: self.telemetry.clone(),
+            }),
+        );
+        resources.insert(
+            "tenant_schemas".to_string(),
+            Box::new(TenantSchemasResource {
+                apply_limiter: self.apply_limiter.clone(),
+                connections: self.connections.clone(),
+                telemetry: self.telemetry.clone(),
+            }),
+        );
+        resources.insert(
+            "backup".to_string(),
+            Box::new(BackupResource {
+                telemetry: self.telemetry.clone(),
+            }),
+        );
+        resources.insert(
+            "restore".to_string(),
+            Box::new(RestoreResource {
+                connections: self.connections.clone(),
+                telemetry: self.telemetry.clone(),
+            }),
+        );
         Some(resources)
     }
+
+    fn get_data_sources(
+        &self,
+        _diags: &mut Diagnostics,
+    ) -> Option<HashMap<String, Box<dyn DynamicDataSource>>> {
+        let mut data_sources: HashMap<String, Box<dyn DynamicDataSource>> = HashMap::new();
+        data_sources.insert("server_info".to_string(), Box::new(ServerInfoDataSource));
+        data_sources.insert("tables".to_string(), Box::new(TablesDataSource));
+        data_sources.insert(
+            "schema_dump".to_string(),
+            Box::new(SchemaDumpDataSource {
+                connections: self.connections.clone(),
+            }),
+        );
+        data_sources.insert(
+            "pending_operations".to_string(),
+            Box::new(PendingOperationsDataSource {
+                connections: self.connections.clone(),
+            }),
+        );
+        data_sources.insert("query".to_string(), Box::new(QueryDataSource));
+        data_sources.insert(
+            "provider_info".to_string(),
+            Box::new(ProviderInfoDataSource),
+        );
+        data_sources.insert("indexes".to_string(), Box::new(IndexesDataSource));
+        Some(data_sources)
+    }
+
+    fn get_functions(
+        &self,
+        _diags: &mut Diagnostics,
+    ) -> Option<HashMap<String, Box<dyn DynamicFunction>>> {
+        let mut functions: HashMap<String, Box<dyn DynamicFunction>> = HashMap::new();
+        functions.insert("schema_hash".to_string(), Box::new(SchemaHashFunction));
+        functions.insert("parse_ddl".to_string(), Box::new(ParseDdlFunction));
+        functions.insert("quote_ident".to_string(), Box::new(QuoteIdentFunction));
+        functions.insert("quote_literal".to_string(), Box::new(QuoteLiteralFunction));
+        functions.insert("database_url".to_string(), Box::new(DatabaseUrlFunction));
+        functions.insert("diff_sql".to_string(), Box::new(DiffSqlFunction));
+        functions.insert("redact_url".to_string(), Box::new(RedactUrlFunction));
+        Some(functions)
+    }
 }
 
 #[cfg(test)]
@@ -118,6 +710,277 @@ mod tests {
         assert!(matches!(attr.attr_type, AttributeType::List(_)));
     }
 
+    #[test]
+    fn provider_schema_has_max_parallel_applies() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+        let schema = provider.schema(&mut diags).expect("schema should exist");
+
+        let attr = schema
+            .block
+            .attributes
+            .get("max_parallel_applies")
+            .expect("max_parallel_applies attribute should exist");
+
+        assert_eq!(attr.constraint, AttributeConstraint::Optional);
+    }
+
+    #[tokio::test]
+    async fn configure_rejects_zero_max_parallel_applies() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let result = provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    max_parallel_applies: Some(0),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert!(!diags.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn configure_pins_target_server_version() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let rejected = provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    target_server_version: Some(15),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(rejected.is_none());
+
+        let mut diags = Diagnostics::default();
+        provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    target_server_version: Some(130000),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("configure should succeed");
+        assert_eq!(
+            provider.connections.target_server_version().await,
+            Some(130000)
+        );
+    }
+
+    #[tokio::test]
+    async fn configure_rejects_invalid_introspection_timeout() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let result = provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    introspection_timeout: Some("30 s&sslmode=disable".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("introspection_timeout")
+        );
+    }
+
+    #[tokio::test]
+    async fn configure_rejects_invalid_lint_rules() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let result = provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    lint_rules: Some(vec![HashMap::from([(
+                        "statement".to_string(),
+                        r"\bmoney\b".to_string(),
+                    )])]),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("lint_rules"));
+    }
+
+    #[tokio::test]
+    async fn configure_applies_lint_policy_of_environment() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+        let policy = HashMap::from([
+            ("production".to_string(), "error".to_string()),
+            ("staging".to_string(), "warning".to_string()),
+        ]);
+
+        for (environment, escalate) in [("production", true), ("staging", false), ("dev", false)] {
+            provider
+                .configure(
+                    &mut diags,
+                    String::new(),
+                    ProviderConfig {
+                        environment: Some(environment.to_string()),
+                        lint_policy: Some(policy.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(provider.connections.escalate_warnings().await, escalate);
+        }
+
+        let rejected = provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    lint_policy: Some(HashMap::from([(
+                        "production".to_string(),
+                        "fatal".to_string(),
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(rejected.is_none());
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("lint_policy"));
+    }
+
+    #[tokio::test]
+    async fn configure_rejects_invalid_failpoints() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let result = provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    failpoints: Some(vec!["lock_timeout".to_string()]),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("failpoints"));
+    }
+
+    #[tokio::test]
+    async fn configure_requires_mock_for_mock_snapshot() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let result = provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    mock_snapshot: Some("snapshot.sql".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("mock_snapshot")
+        );
+    }
+
+    #[tokio::test]
+    async fn configure_mock_disables_connections() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+        provider
+            .configure(
+                &mut diags,
+                String::new(),
+                ProviderConfig {
+                    mock: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("configure should succeed");
+
+        assert!(provider.connections.snapshot().await.is_some());
+        let connection = provider
+            .connections
+            .connect(&mut diags, "postgres://db.internal/app")
+            .await;
+        assert!(connection.is_none());
+        assert_eq!(diags.errors.len(), 1);
+    }
+
+    #[test]
+    fn provider_schema_has_tcp_keepalives_idle() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+        let schema = provider.schema(&mut diags).expect("schema should exist");
+
+        let attr = schema
+            .block
+            .attributes
+            .get("tcp_keepalives_idle")
+            .expect("tcp_keepalives_idle attribute should exist");
+
+        assert_eq!(attr.constraint, AttributeConstraint::Optional);
+    }
+
+    #[test]
+    fn provider_schema_marks_otlp_headers_sensitive() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+        let schema = provider.schema(&mut diags).expect("schema should exist");
+
+        assert!(schema.block.attributes.contains_key("otlp_endpoint"));
+        let attr = schema
+            .block
+            .attributes
+            .get("otlp_headers")
+            .expect("otlp_headers attribute should exist");
+        assert!(attr.sensitive);
+    }
+
+    #[test]
+    fn provider_schema_has_redact_hosts() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+        let schema = provider.schema(&mut diags).expect("schema should exist");
+
+        let attr = schema
+            .block
+            .attributes
+            .get("redact_hosts")
+            .expect("redact_hosts attribute should exist");
+
+        assert!(matches!(attr.attr_type, AttributeType::Bool));
+    }
+
     #[test]
     fn provider_returns_schema_resource() {
         let provider = PgmoldProvider::default();
@@ -146,5 +1009,223 @@ mod tests {
             resources.contains_key("migration"),
             "should have migration resource"
         );
+        assert!(
+            resources.contains_key("schema_phase"),
+            "should have schema_phase resource"
+        );
+        assert!(
+            resources.contains_key("schema_swap"),
+            "should have schema_swap resource"
+        );
+        assert!(
+            resources.contains_key("schema_clone"),
+            "should have schema_clone resource"
+        );
+        assert!(
+            resources.contains_key("tenant_schemas"),
+            "should have tenant_schemas resource"
+        );
+        assert!(
+            resources.contains_key("backup"),
+            "should have backup resource"
+        );
+        assert!(
+            resources.contains_key("restore"),
+            "should have restore resource"
+        );
+    }
+
+    #[test]
+    fn provider_returns_server_info_data_source() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let data_sources = provider
+            .get_data_sources(&mut diags)
+            .expect("data sources should exist");
+
+        assert!(
+            data_sources.contains_key("server_info"),
+            "should have server_info data source"
+        );
+    }
+
+    #[test]
+    fn provider_returns_tables_data_source() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let data_sources = provider
+            .get_data_sources(&mut diags)
+            .expect("data sources should exist");
+
+        assert!(
+            data_sources.contains_key("tables"),
+            "should have tables data source"
+        );
+    }
+
+    #[test]
+    fn provider_returns_schema_dump_data_source() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let data_sources = provider
+            .get_data_sources(&mut diags)
+            .expect("data sources should exist");
+
+        assert!(
+            data_sources.contains_key("schema_dump"),
+            "should have schema_dump data source"
+        );
+    }
+
+    #[test]
+    fn provider_returns_pending_operations_data_source() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let data_sources = provider
+            .get_data_sources(&mut diags)
+            .expect("data sources should exist");
+
+        assert!(
+            data_sources.contains_key("pending_operations"),
+            "should have pending_operations data source"
+        );
+    }
+
+    #[test]
+    fn provider_returns_query_data_source() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let data_sources = provider
+            .get_data_sources(&mut diags)
+            .expect("data sources should exist");
+
+        assert!(
+            data_sources.contains_key("query"),
+            "should have query data source"
+        );
+    }
+
+    #[test]
+    fn provider_returns_indexes_data_source() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let data_sources = provider
+            .get_data_sources(&mut diags)
+            .expect("data sources should exist");
+
+        assert!(
+            data_sources.contains_key("indexes"),
+            "should have indexes data source"
+        );
+    }
+
+    #[test]
+    fn provider_returns_provider_info_data_source() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let data_sources = provider
+            .get_data_sources(&mut diags)
+            .expect("data sources should exist");
+
+        assert!(
+            data_sources.contains_key("provider_info"),
+            "should have provider_info data source"
+        );
+    }
+
+    #[test]
+    fn provider_returns_schema_hash_function() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let functions = provider
+            .get_functions(&mut diags)
+            .expect("functions should exist");
+
+        assert!(
+            functions.contains_key("schema_hash"),
+            "should have schema_hash function"
+        );
+    }
+
+    #[test]
+    fn provider_returns_parse_ddl_function() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let functions = provider
+            .get_functions(&mut diags)
+            .expect("functions should exist");
+
+        assert!(
+            functions.contains_key("parse_ddl"),
+            "should have parse_ddl function"
+        );
+    }
+
+    #[test]
+    fn provider_returns_quote_functions() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let functions = provider
+            .get_functions(&mut diags)
+            .expect("functions should exist");
+
+        for name in ["quote_ident", "quote_literal"] {
+            assert!(functions.contains_key(name), "missing: {name}");
+        }
+    }
+
+    #[test]
+    fn provider_returns_database_url_function() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let functions = provider
+            .get_functions(&mut diags)
+            .expect("functions should exist");
+
+        assert!(
+            functions.contains_key("database_url"),
+            "should have database_url function"
+        );
+    }
+
+    #[test]
+    fn provider_returns_diff_sql_function() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let functions = provider
+            .get_functions(&mut diags)
+            .expect("functions should exist");
+
+        assert!(
+            functions.contains_key("diff_sql"),
+            "should have diff_sql function"
+        );
+    }
+
+    #[test]
+    fn provider_returns_redact_url_function() {
+        let provider = PgmoldProvider::default();
+        let mut diags = Diagnostics::default();
+
+        let functions = provider
+            .get_functions(&mut diags)
+            .expect("functions should exist");
+
+        assert!(
+            functions.contains_key("redact_url"),
+            "should have redact_url function"
+        );
     }
 }
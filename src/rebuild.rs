@@ -0,0 +1,689 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tf_provider::{AttributePath, Diagnostics};
+
+use crate::functions::quote::{quote_ident, quote_literal};
+
+/// Name of the trigger that mirrors writes into the shadow table.
+const SYNC_TRIGGER: &str = "_pgmold_sync";
+
+/// SQLSTATE of `lock_not_available`, raised when `lock_timeout` expires.
+const LOCK_NOT_AVAILABLE: &str = "55P03";
+
+/// How column type changes, which rewrite the whole table, are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RewriteStrategy {
+    /// `ALTER TABLE ... TYPE`, holding an exclusive lock for the whole rewrite.
+    #[default]
+    InPlace,
+    /// Build a copy with the new type while writes are mirrored into it, then swap.
+    ShadowTable,
+}
+
+impl RewriteStrategy {
+    pub const NAMES: [&'static str; 2] = ["in_place", "shadow_table"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "in_place" => Some(RewriteStrategy::InPlace),
+            "shadow_table" => Some(RewriteStrategy::ShadowTable),
+            _ => None,
+        }
+    }
+}
+
+/// Reports an unknown `rewrite_strategy` value on the attribute.
+pub fn check_rewrite_strategy(diags: &mut Diagnostics, strategy: Option<&str>) -> Option<()> {
+    match strategy {
+        Some(name) if RewriteStrategy::parse(name).is_none() => {
+            diags.error_short(
+                format!(
+                    "Unknown rewrite_strategy \"{name}\", expected one of: {}",
+                    RewriteStrategy::NAMES.join(", ")
+                ),
+                AttributePath::new("rewrite_strategy"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+/// Reports a `rewrite_cutover_lock_timeout` PostgreSQL wouldn't accept as a
+/// `lock_timeout`, e.g. `500ms`, `5s` or `1min`.
+pub fn check_lock_timeout(diags: &mut Diagnostics, timeout: Option<&str>) -> Option<()> {
+    let re = regex::Regex::new(r"^\d+\s*(ms|s|min|h)?$").unwrap();
+    match timeout {
+        Some(timeout) if !re.is_match(timeout) => {
+            diags.error_short(
+                format!("Invalid rewrite_cutover_lock_timeout \"{timeout}\", expected e.g. 500ms, 5s or 1min"),
+                AttributePath::new("rewrite_cutover_lock_timeout"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+/// Settings for [`RewriteStrategy::ShadowTable`].
+#[derive(Debug, Clone)]
+pub struct RebuildOptions {
+    /// Heap pages copied per backfill batch; writers wait while a batch runs.
+    pub batch_pages: i64,
+    /// `lock_timeout` for the exclusive lock taken at cutover.
+    pub cutover_lock_timeout: String,
+    /// Cutover attempts before giving up when the lock can't be taken in time.
+    pub cutover_retries: u32,
+}
+
+impl Default for RebuildOptions {
+    fn default() -> Self {
+        RebuildOptions {
+            batch_pages: 1000,
+            cutover_lock_timeout: "5s".to_string(),
+            cutover_retries: 3,
+        }
+    }
+}
+
+/// An `ALTER TABLE ... ALTER COLUMN ... TYPE` statement the shadow table
+/// strategy can take over. `USING` expressions aren't supported, since rows are
+/// copied with assignment casts.
+#[derive(Debug, PartialEq)]
+pub struct TypeChange<'a> {
+    pub table: &'a str,
+    /// `ALTER COLUMN ... TYPE ...`, applied to the shadow table.
+    pub clause: &'a str,
+}
+
+impl<'a> TypeChange<'a> {
+    pub fn parse(statement: &'a str) -> Option<Self> {
+        let re = regex::Regex::new(
+            r#"(?is)^\s*ALTER\s+TABLE\s+(?:ONLY\s+)?((?:"[^"]*"|[\w$]+)(?:\.(?:"[^"]*"|[\w$]+))?)\s+(ALTER\s+(?:COLUMN\s+)?(?:"[^"]*"|[\w$]+)\s+(?:SET\s+DATA\s+)?TYPE\s+.*?)\s*;?\s*$"#,
+        )
+        .unwrap();
+        let using = regex::Regex::new(r"(?i)\bUSING\b").unwrap();
+        let captures = re.captures(statement)?;
+        let clause = captures.get(2)?.as_str();
+        if using.is_match(clause) {
+            return None;
+        }
+        Some(TypeChange {
+            table: captures.get(1)?.as_str(),
+            clause,
+        })
+    }
+}
+
+/// What the rebuild needs to know about the table being rewritten.
+#[derive(Debug, Default)]
+struct Table {
+    schema: String,
+    name: String,
+    /// Columns copied into the shadow table; generated columns are recomputed.
+    columns: Vec<String>,
+    primary_key: Vec<String>,
+    identity_columns: Vec<String>,
+    /// `(sequence, column)` for serial columns, whose sequence moves to the shadow table.
+    owned_sequences: Vec<(String, String)>,
+    /// `(name, definition)`; `LIKE` doesn't copy foreign keys.
+    foreign_keys: Vec<(String, String)>,
+    /// `(grantee, privilege)`; `LIKE` doesn't copy privileges either.
+    grants: Vec<(String, String)>,
+    /// `(name, definition)`, used to give the shadow table's indexes their names.
+    indexes: Vec<(String, String)>,
+}
+
+impl Table {
+    fn qualified(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+    }
+
+    fn shadow_name(&self) -> String {
+        format!("_pgmold_shadow_{}", self.name)
+    }
+
+    fn shadow(&self) -> String {
+        format!(
+            "{}.{}",
+            quote_ident(&self.schema),
+            quote_ident(&self.shadow_name())
+        )
+    }
+
+    fn sync_function(&self) -> String {
+        format!(
+            "{}.{}",
+            quote_ident(&self.schema),
+            quote_ident(&format!("_pgmold_sync_{}", self.name))
+        )
+    }
+
+    fn column_list(&self, prefix: &str) -> String {
+        self.columns
+            .iter()
+            .map(|c| format!("{prefix}{}", quote_ident(c)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Removes what an earlier, failed rebuild left behind.
+    fn cleanup_sql(&self) -> Vec<String> {
+        vec![
+            format!(
+                "DROP TRIGGER IF EXISTS {} ON {}",
+                quote_ident(SYNC_TRIGGER),
+                self.qualified()
+            ),
+            format!("DROP FUNCTION IF EXISTS {}()", self.sync_function()),
+            format!("DROP TABLE IF EXISTS {}", self.shadow()),
+        ]
+    }
+
+    /// Creates the shadow table with the new column types and starts mirroring
+    /// writes into it.
+    fn setup_sql(&self, clauses: &[&str]) -> Vec<String> {
+        let shadow = self.shadow();
+        let mut statements = self.cleanup_sql();
+        statements.push(format!(
+            "CREATE TABLE {shadow} (LIKE {} INCLUDING ALL)",
+            self.qualified()
+        ));
+        statements.push(format!("ALTER TABLE {shadow} {}", clauses.join(", ")));
+        for (name, definition) in &self.foreign_keys {
+            let definition = definition.trim_end_matches(" NOT VALID");
+            statements.push(format!(
+                "ALTER TABLE {shadow} ADD CONSTRAINT {} {definition} NOT VALID",
+                quote_ident(name)
+            ));
+        }
+        for (grantee, privilege) in &self.grants {
+            let grantee = if grantee == "PUBLIC" {
+                grantee.clone()
+            } else {
+                quote_ident(grantee)
+            };
+            statements.push(format!("GRANT {privilege} ON {shadow} TO {grantee}"));
+        }
+        statements.push(self.sync_function_sql());
+        statements.push(format!(
+            "CREATE TRIGGER {} AFTER INSERT OR UPDATE OR DELETE ON {} FOR EACH ROW EXECUTE FUNCTION {}()",
+            quote_ident(SYNC_TRIGGER),
+            self.qualified(),
+            self.sync_function()
+        ));
+        statements
+    }
+
+    fn sync_function_sql(&self) -> String {
+        let key = |prefix: &str| {
+            self.primary_key
+                .iter()
+                .map(|c| format!("{prefix}{}", quote_ident(c)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!(
+            "CREATE FUNCTION {}() RETURNS trigger LANGUAGE plpgsql AS $pgmold$\n\
+             BEGIN\n\
+             \x20 IF TG_OP IN ('UPDATE', 'DELETE') THEN\n\
+             \x20   DELETE FROM {shadow} WHERE ({key}) = ({old_key});\n\
+             \x20 END IF;\n\
+             \x20 IF TG_OP IN ('INSERT', 'UPDATE') THEN\n\
+             \x20   INSERT INTO {shadow} ({columns}) OVERRIDING SYSTEM VALUE SELECT {new_columns};\n\
+             \x20 END IF;\n\
+             \x20 RETURN NULL;\n\
+             END\n\
+             $pgmold$",
+            self.sync_function(),
+            shadow = self.shadow(),
+            key = key(""),
+            old_key = key("OLD."),
+            columns = self.column_list(""),
+            new_columns = self.column_list("NEW."),
+        )
+    }
+
+    /// Copies the rows stored in heap pages `start..end`. Rows the trigger has
+    /// already mirrored are skipped.
+    fn backfill_sql(&self, start: i64, end: i64) -> String {
+        let columns = self.column_list("");
+        format!(
+            "INSERT INTO {} ({columns}) OVERRIDING SYSTEM VALUE \
+             SELECT {columns} FROM {} WHERE ctid >= '({start},0)'::tid AND ctid < '({end},0)'::tid \
+             ON CONFLICT DO NOTHING",
+            self.shadow(),
+            self.qualified()
+        )
+    }
+
+    /// Replaces the table with the shadow table, under `lock_timeout`.
+    fn cutover_sql(&self, lock_timeout: &str, index_renames: &[(String, String)]) -> Vec<String> {
+        let table = self.qualified();
+        let shadow = self.shadow();
+        let mut statements = vec![
+            format!("SET LOCAL lock_timeout = {}", quote_literal(lock_timeout)),
+            format!("LOCK TABLE {table} IN ACCESS EXCLUSIVE MODE"),
+            format!("DROP TRIGGER {} ON {table}", quote_ident(SYNC_TRIGGER)),
+            format!("DROP FUNCTION {}()", self.sync_function()),
+        ];
+        for (sequence, column) in &self.owned_sequences {
+            statements.push(format!(
+                "ALTER SEQUENCE {sequence} OWNED BY {shadow}.{}",
+                quote_ident(column)
+            ));
+        }
+        statements.push(format!("DROP TABLE {table}"));
+        statements.push(format!(
+            "ALTER TABLE {shadow} RENAME TO {}",
+            quote_ident(&self.name)
+        ));
+        for (shadow_index, index) in index_renames {
+            statements.push(format!(
+                "ALTER INDEX {}.{} RENAME TO {}",
+                quote_ident(&self.schema),
+                quote_ident(shadow_index),
+                quote_ident(index)
+            ));
+        }
+        // Identity columns got a fresh sequence from LIKE; continue after the copied values
+        for column in &self.identity_columns {
+            statements.push(format!(
+                "SELECT setval(pg_get_serial_sequence({}, {}), COALESCE(max({}), 0) + 1, false) FROM {table}",
+                quote_literal(&table),
+                quote_literal(column),
+                quote_ident(column)
+            ));
+        }
+        statements
+    }
+}
+
+/// Pairs each shadow index with the original index of the same definition, as
+/// `(shadow name, original name)`.
+fn index_renames(
+    original: &[(String, String)],
+    shadow: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut unmatched: Vec<&(String, String)> = original.iter().collect();
+    let mut renames = Vec::new();
+    for (shadow_name, shadow_definition) in shadow {
+        let key = index_key(shadow_definition);
+        if let Some(position) = unmatched
+            .iter()
+            .position(|(_, definition)| index_key(definition) == key)
+        {
+            let (name, _) = unmatched.remove(position);
+            renames.push((shadow_name.clone(), name.clone()));
+        }
+    }
+    renames
+}
+
+/// `pg_get_indexdef` output without the index and table names.
+fn index_key(definition: &str) -> String {
+    let re = regex::Regex::new(
+        r#"(?i)^CREATE\s+(UNIQUE\s+)?INDEX\s+(?:"[^"]*"|[^\s"])+\s+ON\s+(?:ONLY\s+)?(?:"[^"]*"|[^\s"])+"#,
+    )
+    .unwrap();
+    re.replace(definition, "$1").to_string()
+}
+
+/// Rewrites `table` with `clauses` applied through a shadow table: writes are
+/// mirrored into it by a trigger while existing rows are copied in batches,
+/// then the two are swapped under a short exclusive lock.
+pub async fn rebuild_table(
+    pool: &PgPool,
+    table: &str,
+    clauses: &[&str],
+    options: &RebuildOptions,
+) -> anyhow::Result<()> {
+    let table = load_table(pool, table).await?;
+    let qualified = table.qualified();
+    tracing::info!(table = qualified, "rebuilding table through a shadow table");
+
+    let result = copy_and_swap(pool, &table, clauses, options).await;
+    if result.is_err() {
+        let mut tx = pool.begin().await?;
+        for statement in table.cleanup_sql() {
+            sqlx::raw_sql(&statement).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+    }
+    result
+}
+
+async fn copy_and_swap(
+    pool: &PgPool,
+    table: &Table,
+    clauses: &[&str],
+    options: &RebuildOptions,
+) -> anyhow::Result<()> {
+    let qualified = table.qualified();
+    let mut tx = pool.begin().await?;
+    for statement in table.setup_sql(clauses) {
+        sqlx::raw_sql(&statement).execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+
+    let pages: i64 = sqlx::query_scalar(
+        "SELECT pg_relation_size($1::regclass) / current_setting('block_size')::bigint",
+    )
+    .bind(&qualified)
+    .fetch_one(pool)
+    .await?;
+    let batch_pages = options.batch_pages.max(1);
+    let mut start = 0;
+    while start < pages {
+        let end = start + batch_pages;
+        let mut tx = pool.begin().await?;
+        // Writers wait for the batch, so the trigger and the copy can't interleave
+        sqlx::raw_sql(&format!("LOCK TABLE {qualified} IN SHARE MODE"))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::raw_sql(&table.backfill_sql(start, end))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        start = end;
+        let percent = crate::progress::percent(start.min(pages), pages).unwrap_or(100);
+        tracing::info!(
+            table = qualified,
+            percent,
+            "rebuild progress: {qualified} {percent}%"
+        );
+    }
+
+    let shadow_indexes = indexes(pool, &table.shadow()).await?;
+    let cutover = table.cutover_sql(
+        &options.cutover_lock_timeout,
+        &index_renames(&table.indexes, &shadow_indexes),
+    );
+    cut_over(pool, &qualified, &cutover, options).await?;
+
+    for (name, _) in &table.foreign_keys {
+        sqlx::raw_sql(&format!(
+            "ALTER TABLE {qualified} VALIDATE CONSTRAINT {}",
+            quote_ident(name)
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Runs the cutover, retrying with a growing pause when the exclusive lock
+/// can't be taken within the lock timeout.
+async fn cut_over(
+    pool: &PgPool,
+    table: &str,
+    statements: &[String],
+    options: &RebuildOptions,
+) -> anyhow::Result<()> {
+    let attempts = options.cutover_retries.max(1);
+    for attempt in 1..=attempts {
+        match try_cut_over(pool, statements).await {
+            Ok(()) => {
+                tracing::info!(table, attempt, "rebuild cutover complete");
+                return Ok(());
+            }
+            Err(e)
+                if attempt < attempts
+                    && e.as_database_error().and_then(|d| d.code()).as_deref()
+                        == Some(LOCK_NOT_AVAILABLE) =>
+            {
+                tracing::warn!(
+                    table,
+                    attempt,
+                    "rebuild cutover timed out waiting for lock, retrying"
+                );
+                tokio::time::sleep(Duration::from_secs(attempt.into())).await;
+            }
+            Err(e) => return Err(anyhow::anyhow!("cutover of {table} failed: {e}")),
+        }
+    }
+    unreachable!("the last attempt returns")
+}
+
+async fn try_cut_over(pool: &PgPool, statements: &[String]) -> sqlx::Result<()> {
+    let mut tx = pool.begin().await?;
+    for statement in statements {
+        sqlx::raw_sql(statement).execute(&mut *tx).await?;
+    }
+    tx.commit().await
+}
+
+async fn load_table(pool: &PgPool, table: &str) -> anyhow::Result<Table> {
+    let (schema, name): (String, String) = sqlx::query_as(
+        "SELECT n.nspname::text, c.relname::text FROM pg_class c \
+         JOIN pg_namespace n ON n.oid = c.relnamespace WHERE c.oid = $1::regclass",
+    )
+    .bind(table)
+    .fetch_one(pool)
+    .await?;
+    let mut info = Table {
+        schema,
+        name,
+        ..Default::default()
+    };
+    let qualified = info.qualified();
+
+    let blockers: Vec<(String,)> = sqlx::query_as(
+        "SELECT 'it is partitioned' FROM pg_class WHERE oid = $1::regclass AND relkind = 'p' \
+         UNION ALL SELECT 'foreign key ' || conname || ' references it' \
+         FROM pg_constraint WHERE confrelid = $1::regclass \
+         UNION ALL SELECT 'it has trigger ' || tgname FROM pg_trigger \
+         WHERE tgrelid = $1::regclass AND NOT tgisinternal AND tgname <> $2 \
+         UNION ALL SELECT 'it has row-level security policy ' || polname \
+         FROM pg_policy WHERE polrelid = $1::regclass \
+         UNION ALL SELECT DISTINCT 'view ' || r.ev_class::regclass::text || ' depends on it' \
+         FROM pg_depend d JOIN pg_rewrite r ON r.oid = d.objid \
+         WHERE d.refobjid = $1::regclass AND r.ev_class <> $1::regclass",
+    )
+    .bind(&qualified)
+    .bind(SYNC_TRIGGER)
+    .fetch_all(pool)
+    .await?;
+
+    let columns: Vec<(String, bool)> = sqlx::query_as(
+        "SELECT attname::text, attidentity <> '' FROM pg_attribute \
+         WHERE attrelid = $1::regclass AND attnum > 0 AND NOT attisdropped AND attgenerated = '' \
+         ORDER BY attnum",
+    )
+    .bind(&qualified)
+    .fetch_all(pool)
+    .await?;
+    for (column, identity) in columns {
+        if identity {
+            info.identity_columns.push(column.clone());
+        }
+        info.columns.push(column);
+    }
+
+    info.primary_key = sqlx::query_scalar(
+        "SELECT a.attname::text FROM pg_index i \
+         JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey) \
+         WHERE i.indrelid = $1::regclass AND i.indisprimary \
+         ORDER BY array_position(i.indkey::int2[], a.attnum)",
+    )
+    .bind(&qualified)
+    .fetch_all(pool)
+    .await?;
+
+    let mut reasons: Vec<String> = blockers.into_iter().map(|(reason,)| reason).collect();
+    if info.primary_key.is_empty() {
+        reasons.push("it has no primary key".to_string());
+    }
+    if !reasons.is_empty() {
+        anyhow::bail!(
+            "{qualified} can't be rebuilt through a shadow table: {}",
+            reasons.join(", ")
+        );
+    }
+
+    info.owned_sequences = sqlx::query_as(
+        "SELECT s.oid::regclass::text, a.attname::text FROM pg_depend d \
+         JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S' \
+         JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid \
+         WHERE d.refobjid = $1::regclass AND d.deptype = 'a'",
+    )
+    .bind(&qualified)
+    .fetch_all(pool)
+    .await?;
+    info.foreign_keys = sqlx::query_as(
+        "SELECT conname::text, pg_get_constraintdef(oid) FROM pg_constraint \
+         WHERE conrelid = $1::regclass AND contype = 'f'",
+    )
+    .bind(&qualified)
+    .fetch_all(pool)
+    .await?;
+    info.grants = sqlx::query_as(
+        "SELECT grantee::text, privilege_type::text FROM information_schema.role_table_grants \
+         WHERE table_schema = $1 AND table_name = $2",
+    )
+    .bind(&info.schema)
+    .bind(&info.name)
+    .fetch_all(pool)
+    .await?;
+    info.indexes = indexes(pool, &qualified).await?;
+    Ok(info)
+}
+
+async fn indexes(pool: &PgPool, table: &str) -> sqlx::Result<Vec<(String, String)>> {
+    sqlx::query_as(
+        "SELECT c.relname::text, pg_get_indexdef(i.indexrelid) FROM pg_index i \
+         JOIN pg_class c ON c.oid = i.indexrelid WHERE i.indrelid = $1::regclass ORDER BY c.relname",
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users() -> Table {
+        Table {
+            schema: "public".to_string(),
+            name: "users".to_string(),
+            columns: vec!["id".to_string(), "score".to_string()],
+            primary_key: vec!["id".to_string()],
+            identity_columns: vec!["id".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn type_change_parses_column_type_statements() {
+        assert_eq!(
+            TypeChange::parse(
+                "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"score\" TYPE BIGINT;"
+            ),
+            Some(TypeChange {
+                table: "\"public\".\"users\"",
+                clause: "ALTER COLUMN \"score\" TYPE BIGINT",
+            })
+        );
+        assert!(TypeChange::parse(
+            "ALTER TABLE users ALTER COLUMN score TYPE INT USING score::int"
+        )
+        .is_none());
+        assert!(TypeChange::parse("ALTER TABLE users ALTER COLUMN score SET NOT NULL").is_none());
+    }
+
+    #[test]
+    fn setup_mirrors_writes_into_the_shadow_table() {
+        let statements = users().setup_sql(&["ALTER COLUMN \"score\" TYPE BIGINT"]);
+        assert!(statements.contains(
+            &"CREATE TABLE \"public\".\"_pgmold_shadow_users\" (LIKE \"public\".\"users\" INCLUDING ALL)"
+                .to_string()
+        ));
+        assert!(statements.contains(
+            &"ALTER TABLE \"public\".\"_pgmold_shadow_users\" ALTER COLUMN \"score\" TYPE BIGINT"
+                .to_string()
+        ));
+        assert!(statements
+            .last()
+            .unwrap()
+            .starts_with("CREATE TRIGGER \"_pgmold_sync\" AFTER INSERT OR UPDATE OR DELETE ON \"public\".\"users\""));
+        assert!(users().sync_function_sql().contains(
+            "DELETE FROM \"public\".\"_pgmold_shadow_users\" WHERE (\"id\") = (OLD.\"id\");"
+        ));
+    }
+
+    #[test]
+    fn backfill_copies_a_page_range() {
+        assert_eq!(
+            users().backfill_sql(0, 1000),
+            "INSERT INTO \"public\".\"_pgmold_shadow_users\" (\"id\", \"score\") OVERRIDING SYSTEM VALUE \
+             SELECT \"id\", \"score\" FROM \"public\".\"users\" WHERE ctid >= '(0,0)'::tid AND ctid < '(1000,0)'::tid \
+             ON CONFLICT DO NOTHING"
+        );
+    }
+
+    #[test]
+    fn cutover_swaps_tables_under_lock_timeout() {
+        let renames = vec![(
+            "_pgmold_shadow_users_pkey".to_string(),
+            "users_pkey".to_string(),
+        )];
+        let statements = users().cutover_sql("5s", &renames);
+        assert_eq!(statements[0], "SET LOCAL lock_timeout = '5s'");
+        assert!(statements.contains(
+            &"ALTER TABLE \"public\".\"_pgmold_shadow_users\" RENAME TO \"users\"".to_string()
+        ));
+        assert!(statements.contains(
+            &"ALTER INDEX \"public\".\"_pgmold_shadow_users_pkey\" RENAME TO \"users_pkey\""
+                .to_string()
+        ));
+        assert!(statements.last().unwrap().starts_with("SELECT setval("));
+    }
+
+    #[test]
+    fn index_renames_match_by_definition() {
+        let original = vec![
+            (
+                "users_email_idx".to_string(),
+                "CREATE INDEX users_email_idx ON public.users USING btree (email)".to_string(),
+            ),
+            (
+                "users_pkey".to_string(),
+                "CREATE UNIQUE INDEX users_pkey ON public.users USING btree (id)".to_string(),
+            ),
+        ];
+        let shadow = vec![
+            (
+                "_pgmold_shadow_users_email_idx".to_string(),
+                "CREATE INDEX _pgmold_shadow_users_email_idx ON public._pgmold_shadow_users USING btree (email)".to_string(),
+            ),
+            (
+                "_pgmold_shadow_users_pkey".to_string(),
+                "CREATE UNIQUE INDEX _pgmold_shadow_users_pkey ON public._pgmold_shadow_users USING btree (id)".to_string(),
+            ),
+        ];
+        assert_eq!(
+            index_renames(&original, &shadow),
+            vec![
+                (
+                    "_pgmold_shadow_users_email_idx".to_string(),
+                    "users_email_idx".to_string()
+                ),
+                (
+                    "_pgmold_shadow_users_pkey".to_string(),
+                    "users_pkey".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lock_timeout_accepts_postgres_durations() {
+        let mut diags = Diagnostics::default();
+        assert!(check_lock_timeout(&mut diags, Some("500ms")).is_some());
+        assert!(check_lock_timeout(&mut diags, Some("5s")).is_some());
+        assert!(check_lock_timeout(&mut diags, Some("five seconds")).is_none());
+        assert_eq!(diags.errors.len(), 1);
+    }
+}
@@ -0,0 +1,209 @@
+//! Keeps what recreating a table would lose. When a plan drops a table and
+//! creates it again, the sequences behind its serial and identity columns
+//! start over, so new rows collide with keys handed out before. The sequence
+//! values, and optionally the rows, are copied into helper tables before the
+//! drop and restored once the rest of the plan has run.
+
+use regex::Regex;
+
+use crate::functions::quote::{quote_ident, quote_literal};
+
+const IDENT: &str = r#""(?:[^"]|"")+"|[A-Za-z_][A-Za-z0-9_$]*"#;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Table {
+    schema: Option<String>,
+    name: String,
+}
+
+impl Table {
+    fn parse(caps: &regex::Captures) -> Self {
+        Table {
+            schema: caps.get(1).map(|s| unquote(s.as_str())),
+            name: unquote(&caps[2]),
+        }
+    }
+
+    /// Whether both name the same table, an unqualified name matching any schema.
+    fn matches(&self, other: &Table) -> bool {
+        self.name == other.name
+            && (self.schema.is_none() || other.schema.is_none() || self.schema == other.schema)
+    }
+
+    fn qualify(&self, name: &str) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", quote_ident(schema), quote_ident(name)),
+            None => quote_ident(name),
+        }
+    }
+
+    /// The helper table holding what `kind` keeps of this table.
+    fn helper(&self, kind: &str) -> String {
+        self.qualify(&format!("_pgmold_{kind}_{}", self.name))
+    }
+}
+
+/// Quoted identifiers keep their case; unquoted ones fold to lower case.
+fn unquote(ident: &str) -> String {
+    match ident.strip_prefix('"').and_then(|i| i.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => ident.to_lowercase(),
+    }
+}
+
+/// Wraps each `DROP TABLE` of a table the plan creates again later: its
+/// sequence values, and with `data` its rows, are copied aside right before
+/// the drop and restored after the last statement.
+pub fn preserve(statements: Vec<String>, data: bool) -> Vec<String> {
+    let drop = Regex::new(&format!(
+        r"(?is)^\s*DROP\s+TABLE\s+(?:IF\s+EXISTS\s+)?(?:({IDENT})\s*\.\s*)?({IDENT})"
+    ))
+    .unwrap();
+    let create = Regex::new(&format!(
+        r"(?is)^\s*CREATE\s+(?:UNLOGGED\s+)?TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?(?:({IDENT})\s*\.\s*)?({IDENT})"
+    ))
+    .unwrap();
+    let codes: Vec<String> = statements
+        .iter()
+        .map(|s| crate::statements::code_only(s))
+        .collect();
+    let created: Vec<(usize, Table)> = codes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, code)| create.captures(code).map(|caps| (i, Table::parse(&caps))))
+        .collect();
+
+    let mut preserved = Vec::with_capacity(statements.len());
+    let mut restores = Vec::new();
+    for (i, statement) in statements.into_iter().enumerate() {
+        let recreated = drop
+            .captures(&codes[i])
+            .map(|caps| Table::parse(&caps))
+            .filter(|table| created.iter().any(|(j, t)| *j > i && t.matches(table)));
+        if let Some(table) = recreated {
+            preserved.push(capture_sequences(&table));
+            if data {
+                preserved.push(format!(
+                    "CREATE TABLE {} AS TABLE {}",
+                    table.helper("rows"),
+                    table.qualify(&table.name)
+                ));
+                restores.push(restore_rows(&table));
+            }
+            restores.push(restore_sequences(&table));
+            restores.push(format!("DROP TABLE {}", table.helper("sequences")));
+        }
+        preserved.push(statement);
+    }
+    preserved.extend(restores);
+    preserved
+}
+
+/// Records the last value of each column's owned sequence. Creating the helper
+/// fails if a failed apply left one behind, rather than overwriting it.
+fn capture_sequences(table: &Table) -> String {
+    let name = quote_literal(&table.qualify(&table.name));
+    format!(
+        "CREATE TABLE {} AS \
+         SELECT a.attname::text AS column_name, s.last_value \
+         FROM pg_attribute a \
+         JOIN pg_sequences s ON format('%I.%I', s.schemaname, s.sequencename) = pg_get_serial_sequence({name}, a.attname) \
+         WHERE a.attrelid = {name}::regclass AND a.attnum > 0 AND NOT a.attisdropped",
+        table.helper("sequences")
+    )
+}
+
+/// Sets the recreated table's sequences to their recorded values, skipping
+/// columns that no longer have one and sequences that were never used.
+fn restore_sequences(table: &Table) -> String {
+    let name = quote_literal(&table.qualify(&table.name));
+    format!(
+        "SELECT setval(pg_get_serial_sequence({name}, k.column_name), k.last_value) \
+         FROM {} k \
+         WHERE k.last_value IS NOT NULL AND pg_get_serial_sequence({name}, k.column_name) IS NOT NULL",
+        table.helper("sequences")
+    )
+}
+
+/// Copies the kept rows back into the columns the old and new table share,
+/// keeping identity values as they were.
+fn restore_rows(table: &Table) -> String {
+    let target = table.qualify(&table.name);
+    let helper = table.helper("rows");
+    format!(
+        r#"DO $pgmold$
+DECLARE
+    columns text;
+BEGIN
+    SELECT string_agg(quote_ident(a.attname), ', ' ORDER BY a.attnum) INTO columns
+    FROM pg_attribute a
+    JOIN pg_attribute k ON k.attrelid = {helper_literal}::regclass AND k.attname = a.attname
+                       AND k.attnum > 0 AND NOT k.attisdropped
+    WHERE a.attrelid = {target_literal}::regclass AND a.attnum > 0 AND NOT a.attisdropped
+      AND a.attgenerated = '';
+    IF columns IS NOT NULL THEN
+        EXECUTE format('INSERT INTO %s (%s) OVERRIDING SYSTEM VALUE SELECT %s FROM %s',
+                       {target_literal}, columns, columns, {helper_literal});
+    END IF;
+    DROP TABLE {helper};
+END
+$pgmold$"#,
+        helper_literal = quote_literal(&helper),
+        target_literal = quote_literal(&target),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql(statements: &[&str]) -> Vec<String> {
+        statements.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn preserve_wraps_recreated_tables_only() {
+        let statements = sql(&[
+            "DROP TABLE public.audit",
+            "DROP TABLE IF EXISTS \"public\".\"Users\" CASCADE",
+            "CREATE TABLE public.\"Users\" (id bigint GENERATED BY DEFAULT AS IDENTITY) PARTITION BY HASH (id)",
+            "CREATE INDEX users_id_idx ON public.\"Users\" (id)",
+        ]);
+
+        let preserved = preserve(statements.clone(), false);
+
+        assert_eq!(preserved.len(), 7);
+        assert_eq!(preserved[0], statements[0]);
+        assert!(preserved[1]
+            .starts_with("CREATE TABLE \"public\".\"_pgmold_sequences_Users\" AS SELECT"));
+        assert!(preserved[1].contains("pg_get_serial_sequence('\"public\".\"Users\"', a.attname)"));
+        assert_eq!(preserved[2..5], statements[1..4]);
+        assert!(preserved[5].starts_with("SELECT setval("));
+        assert_eq!(
+            preserved[6],
+            "DROP TABLE \"public\".\"_pgmold_sequences_Users\""
+        );
+    }
+
+    #[test]
+    fn preserve_copies_rows_with_data() {
+        let preserved = preserve(
+            sql(&[
+                "DROP TABLE orders",
+                "CREATE TABLE orders (id serial PRIMARY KEY, total numeric)",
+            ]),
+            true,
+        );
+
+        assert_eq!(preserved.len(), 7);
+        assert_eq!(
+            preserved[1],
+            "CREATE TABLE \"_pgmold_rows_orders\" AS TABLE \"orders\""
+        );
+        assert_eq!(preserved[2], "DROP TABLE orders");
+        // Rows go back before the sequences are set past them
+        assert!(preserved[4].starts_with("DO $pgmold$"));
+        assert!(preserved[4].contains("OVERRIDING SYSTEM VALUE"));
+        assert!(preserved[5].starts_with("SELECT setval("));
+    }
+}
@@ -0,0 +1,376 @@
+use std::borrow::Cow;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::{Value, ValueEmpty, ValueList, ValueMap, ValueNumber, ValueString},
+    AttributePath, Diagnostics, Resource,
+};
+
+use crate::backup::{DumpContent, DumpFormat, DumpOptions};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BackupResourceState<'a> {
+    #[serde(borrow)]
+    pub id: ValueString<'a>,
+    #[serde(borrow)]
+    pub database_url: ValueString<'a>,
+    #[serde(borrow)]
+    pub destination: ValueString<'a>,
+    #[serde(borrow)]
+    pub format: ValueString<'a>,
+    #[serde(borrow)]
+    pub content: ValueString<'a>,
+    #[serde(borrow)]
+    pub target_schemas: ValueList<ValueString<'a>>,
+    #[serde(borrow)]
+    pub pg_dump_path: ValueString<'a>,
+    #[serde(borrow)]
+    pub triggers: ValueMap<'a, ValueString<'a>>,
+    #[serde(borrow)]
+    pub location: ValueString<'a>,
+    #[serde(borrow)]
+    pub checksum: ValueString<'a>,
+    pub size_bytes: ValueNumber,
+    #[serde(borrow)]
+    pub created_at: ValueString<'a>,
+}
+
+/// Takes a `pg_dump` of a database when created, e.g. before the schema
+/// resources that depend on it apply. Any change takes a new backup.
+#[derive(Debug, Default, Clone)]
+pub struct BackupResource {
+    pub telemetry: crate::telemetry::Telemetry,
+}
+
+/// Attributes that, when changed, take a new backup.
+const INPUTS: [&str; 7] = [
+    "database_url",
+    "destination",
+    "format",
+    "content",
+    "target_schemas",
+    "pg_dump_path",
+    "triggers",
+];
+
+#[async_trait]
+impl Resource for BackupResource {
+    type State<'a> = BackupResourceState<'a>;
+    type PrivateState<'a> = ValueEmpty;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Dumps a database with pg_dump to a local path or S3",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Resource identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "destination" => Attribute {
+                        description: Description::plain("Local path or s3://bucket/key to write the dump to; {timestamp} is replaced with the backup time"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "format" => Attribute {
+                        description: Description::plain("Dump format: custom (for pg_restore) or plain (SQL) (default: custom)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "content" => Attribute {
+                        description: Description::plain("What to dump: all, schema or data (default: all)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "target_schemas" => Attribute {
+                        description: Description::plain("PostgreSQL schemas to dump (default: the whole database)"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "pg_dump_path" => Attribute {
+                        description: Description::plain("pg_dump executable (default: pg_dump on the PATH)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "triggers" => Attribute {
+                        description: Description::plain("Arbitrary values that take a new backup when changed, e.g. the schema file hash"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "location" => Attribute {
+                        description: Description::plain("Where the dump was written"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "checksum" => Attribute {
+                        description: Description::plain("SHA256 of the dump file"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "size_bytes" => Attribute {
+                        description: Description::plain("Size of the dump file in bytes"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "created_at" => Attribute {
+                        description: Description::plain("Timestamp of the backup"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        crate::util::check_database_url(
+            diags,
+            config.database_url.as_ref_option().map(|u| u.as_ref()),
+        );
+        crate::backup::check_destination(
+            diags,
+            config.destination.as_ref_option().map(|d| d.as_ref()),
+        );
+        crate::backup::check_format(diags, config.format.as_ref_option().map(|f| f.as_ref()));
+        crate::backup::check_content(diags, config.content.as_ref_option().map(|c| c.as_ref()));
+        Some(())
+    }
+
+    async fn read<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        state: Self::State<'a>,
+        private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        Some((state, private_state))
+    }
+
+    async fn plan_create<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let mut state = proposed_state;
+        state.id = Value::Unknown;
+        state.location = Value::Unknown;
+        state.checksum = Value::Unknown;
+        state.size_bytes = Value::Unknown;
+        state.created_at = Value::Unknown;
+        Some((state, Default::default()))
+    }
+
+    async fn plan_update<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
+        let requires_replace = changed_inputs(&prior_state, &proposed_state)
+            .into_iter()
+            .map(AttributePath::new)
+            .collect();
+
+        let mut state = proposed_state;
+        state.id = prior_state.id;
+        state.location = prior_state.location;
+        state.checksum = prior_state.checksum;
+        state.size_bytes = prior_state.size_bytes;
+        state.created_at = prior_state.created_at;
+        Some((state, prior_private_state, requires_replace))
+    }
+
+    async fn plan_destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::PrivateState<'a>> {
+        Some(prior_private_state)
+    }
+
+    async fn create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let started = std::time::SystemTime::now();
+        let backed_up = backup(diags, planned_state).await;
+        self.telemetry
+            .record("pgmold_backup", "create", started, backed_up.is_some())
+            .await;
+        Some((backed_up?, planned_private_state))
+    }
+
+    async fn update<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        // Every input forces replacement, so there is nothing to update
+        Some((planned_state, planned_private_state))
+    }
+
+    /// Backups outlive the resource; the artifact is left in place.
+    async fn destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        Some(())
+    }
+}
+
+fn changed_inputs(
+    prior: &BackupResourceState,
+    proposed: &BackupResourceState,
+) -> Vec<&'static str> {
+    let changed = [
+        prior.database_url != proposed.database_url,
+        prior.destination != proposed.destination,
+        prior.format != proposed.format,
+        prior.content != proposed.content,
+        prior.target_schemas != proposed.target_schemas,
+        prior.pg_dump_path != proposed.pg_dump_path,
+        prior.triggers != proposed.triggers,
+    ];
+    INPUTS
+        .into_iter()
+        .zip(changed)
+        .filter_map(|(name, changed)| changed.then_some(name))
+        .collect()
+}
+
+async fn backup<'a>(
+    diags: &mut Diagnostics,
+    state: BackupResourceState<'a>,
+) -> Option<BackupResourceState<'a>> {
+    let now = crate::clock::now();
+    let destination = crate::backup::expand_destination(state.destination.as_str(), now);
+    let options = DumpOptions {
+        format: state
+            .format
+            .as_ref_option()
+            .and_then(|f| DumpFormat::parse(f))
+            .unwrap_or_default(),
+        content: state
+            .content
+            .as_ref_option()
+            .and_then(|c| DumpContent::parse(c))
+            .unwrap_or_default(),
+        schemas: state
+            .target_schemas
+            .iter()
+            .flatten()
+            .filter_map(|s| s.as_ref_option().map(|s| s.to_string()))
+            .collect(),
+    };
+    let pg_dump = state
+        .pg_dump_path
+        .as_ref_option()
+        .map_or("pg_dump", |p| p.as_ref());
+
+    tracing::info!(resource = "pgmold_backup", destination, "taking backup");
+    let artifact = match crate::backup::run(
+        pg_dump,
+        state.database_url.as_str(),
+        &destination,
+        &options,
+    )
+    .await
+    {
+        Ok(artifact) => artifact,
+        Err(e) => {
+            diags.root_error_short(format!("Backup failed: {e}"));
+            return None;
+        }
+    };
+
+    let mut state = state;
+    state.id = Value::Value(Cow::Owned(format!(
+        "pgmold-backup-{}",
+        &artifact.checksum[..12]
+    )));
+    state.location = Value::Value(Cow::Owned(artifact.location));
+    state.checksum = Value::Value(Cow::Owned(artifact.checksum));
+    state.size_bytes = Value::Value(artifact.size_bytes);
+    state.created_at = Value::Value(Cow::Owned(now.to_rfc3339()));
+    Some(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_inputs_lists_attributes_forcing_a_new_backup() {
+        let prior = BackupResourceState {
+            destination: Value::Value(Cow::Borrowed("backups/app.dump")),
+            ..Default::default()
+        };
+        let mut proposed = prior.clone();
+        assert!(changed_inputs(&prior, &proposed).is_empty());
+
+        proposed.format = Value::Value(Cow::Borrowed("plain"));
+        assert_eq!(changed_inputs(&prior, &proposed), vec!["format"]);
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_unknown_format() {
+        let config = BackupResourceState {
+            database_url: Value::Value(Cow::Borrowed("postgres://db/app")),
+            destination: Value::Value(Cow::Borrowed("s3://backups/app.dump")),
+            format: Value::Value(Cow::Borrowed("tar")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        BackupResource::default().validate(&mut diags, config).await;
+
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("format"));
+    }
+}
@@ -12,20 +12,59 @@ pub struct MigrationResourceState {
     pub schema_file: String,
     pub database_url: Option<String>,
     pub output_dir: String,
+    pub base_dir: Option<String>,
     pub prefix: Option<String>,
     pub target_schemas: Option<Vec<String>>,
+    pub plan_with_database: Option<bool>,
+    pub declared_kinds_only: Option<bool>,
+    pub skip_unreadable_schemas: Option<bool>,
+    pub dialect: Option<String>,
+    pub allow_empty: Option<bool>,
+    pub min_server_version: Option<i64>,
+    pub max_server_version: Option<i64>,
+    pub execution_log_path: Option<String>,
+    pub plan_output_path: Option<String>,
+    pub migration_history: Option<String>,
+    pub warn_on_checksum_mismatch: Option<bool>,
+    pub seed_files: Option<std::collections::BTreeMap<String, String>>,
+    pub seed_format: Option<String>,
+    pub seed_environment: Option<String>,
+    pub seed_variables: Option<std::collections::BTreeMap<String, String>>,
+    pub masking: Option<std::collections::BTreeMap<String, String>>,
+    pub backfill_templates: Option<bool>,
     pub schema_hash: Option<String>,
+    pub seed_hash: Option<String>,
     pub migration_file: Option<String>,
+    pub migration_files: Option<Vec<String>>,
     pub migration_number: Option<u32>,
     pub operations: Option<Vec<String>>,
+    pub operation_counts: Option<std::collections::BTreeMap<String, i64>>,
+    pub lint_results: Option<Vec<crate::pipeline::LintEntry>>,
 }
 
-pub struct MigrationResource;
+/// Result of the last output directory scan, reused while the directory is unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MigrationPrivateState {
+    /// Output directory mtime (nanoseconds since the epoch) after the last write.
+    pub dir_mtime: u64,
+    /// Highest migration number in the directory at that time.
+    pub max_number: u32,
+    /// Last migration file generated by this resource.
+    pub generated_file: String,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MigrationResource {
+    pub apply_limiter: crate::throttle::ApplyLimiter,
+    pub connections: crate::connections::ConnectionCache,
+    pub telemetry: crate::telemetry::Telemetry,
+}
 
 #[async_trait]
 impl Resource for MigrationResource {
     type State<'a> = MigrationResourceState;
-    type PrivateState<'a> = ValueEmpty;
+    // Optional because private state is empty for resources created before the cache existed
+    type PrivateState<'a> = Option<MigrationPrivateState>;
     type ProviderMetaState<'a> = ValueEmpty;
 
     fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
@@ -56,9 +95,11 @@ impl Resource for MigrationResource {
                     (
                         "database_url",
                         Attribute {
-                            description: Description::plain("PostgreSQL connection URL"),
+                            description: Description::plain(
+                                "PostgreSQL connection URL (default: the provider's database_url)",
+                            ),
                             attr_type: AttributeType::String,
-                            constraint: AttributeConstraint::Required,
+                            constraint: AttributeConstraint::OptionalComputed,
                             sensitive: true,
                             ..Default::default()
                         },
@@ -72,6 +113,17 @@ impl Resource for MigrationResource {
                             ..Default::default()
                         },
                     ),
+                    (
+                        "base_dir",
+                        Attribute {
+                            description: Description::plain(
+                                "Directory relative schema_file and output_dir paths resolve against, e.g. path.module (default: working directory)",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
                     (
                         "prefix",
                         Attribute {
@@ -85,9 +137,196 @@ impl Resource for MigrationResource {
                         "target_schemas",
                         Attribute {
                             description: Description::plain(
-                                "PostgreSQL schemas to introspect (default: public)",
+                                "PostgreSQL schemas to introspect (default: the provider's target_schemas, or public)",
                             ),
                             attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                            constraint: AttributeConstraint::OptionalComputed,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "plan_with_database",
+                        Attribute {
+                            description: Description::plain(
+                                "Connect during plan to diff and lint (default: false)",
+                            ),
+                            attr_type: AttributeType::Bool,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "declared_kinds_only",
+                        Attribute {
+                            description: Description::plain(
+                                "Leave object kinds the schema file doesn't declare, e.g. functions and triggers when it declares only tables, out of the diff, so they are never changed or dropped. A filter on the diff: introspection still reads every kind (default: false)",
+                            ),
+                            attr_type: AttributeType::Bool,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "skip_unreadable_schemas",
+                        Attribute {
+                            description: Description::plain(
+                                "Skip target schemas the role can't introspect, with a warning, instead of failing (default: false)",
+                            ),
+                            attr_type: AttributeType::Bool,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "dialect",
+                        Attribute {
+                            description: Description::plain(
+                                "Target engine: postgres, cockroachdb or aurora (default: postgres)",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "allow_empty",
+                        Attribute {
+                            description: Description::plain(
+                                "Allow a schema file that declares no objects, generating a migration that drops everything (default: false)",
+                            ),
+                            attr_type: AttributeType::Bool,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "min_server_version",
+                        Attribute {
+                            description: Description::plain(
+                                "Lowest server_version_num (e.g. 150000) the schema file supports, checked at apply time",
+                            ),
+                            attr_type: AttributeType::Number,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "max_server_version",
+                        Attribute {
+                            description: Description::plain(
+                                "Highest server_version_num the schema file supports, checked at apply time",
+                            ),
+                            attr_type: AttributeType::Number,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "seed_files",
+                        Attribute {
+                            description: Description::plain(
+                                "CSV files with a header row, keyed by table, loaded in the migration that creates the table",
+                            ),
+                            attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "seed_format",
+                        Attribute {
+                            description: Description::plain(
+                                "How seed data is written: copy (COPY ... FROM STDIN) or insert (default: copy)",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "seed_environment",
+                        Attribute {
+                            description: Description::plain(
+                                "Environment whose seed overlays apply: countries.<environment>.csv next to countries.csv replaces rows with the same first column and adds the rest",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "seed_variables",
+                        Attribute {
+                            description: Description::plain(
+                                "Values for ${name} placeholders in seed files",
+                            ),
+                            attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "masking",
+                        Attribute {
+                            description: Description::plain(
+                                "Columns of seeded tables to mask, as table.column or schema.table.column = null, hash, email, name or phone",
+                            ),
+                            attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "backfill_templates",
+                        Attribute {
+                            description: Description::plain(
+                                "Write NOT NULL column additions without a default as a nullable add plus commented backfill and SET NOT NULL steps (default: false)",
+                            ),
+                            attr_type: AttributeType::Bool,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "execution_log_path",
+                        Attribute {
+                            description: Description::plain(
+                                "File that each create and update appends a JSON line to, with operations, SQL, lint results, phase timings and outcome",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "plan_output_path",
+                        Attribute {
+                            description: Description::plain(
+                                "File that plan overwrites with the pending operations, SQL, lint results and destructive flags as JSON, for approval tooling. Requires plan_with_database",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "migration_history",
+                        Attribute {
+                            description: Description::plain(
+                                "Migration runner that applies the generated files: flyway, liquibase or sqlx. Checksums in its history table are compared with the files in output_dir, catching edits to applied migrations",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Optional,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "warn_on_checksum_mismatch",
+                        Attribute {
+                            description: Description::plain(
+                                "Warn instead of failing when an applied migration no longer matches its checksum (default: false)",
+                            ),
+                            attr_type: AttributeType::Bool,
                             constraint: AttributeConstraint::Optional,
                             ..Default::default()
                         },
@@ -101,6 +340,17 @@ impl Resource for MigrationResource {
                             ..Default::default()
                         },
                     ),
+                    (
+                        "seed_hash",
+                        Attribute {
+                            description: Description::plain(
+                                "SHA256 hash of the seed data after overlays and variables, or null without seed_files",
+                            ),
+                            attr_type: AttributeType::String,
+                            constraint: AttributeConstraint::Computed,
+                            ..Default::default()
+                        },
+                    ),
                     (
                         "migration_file",
                         Attribute {
@@ -110,6 +360,17 @@ impl Resource for MigrationResource {
                             ..Default::default()
                         },
                     ),
+                    (
+                        "migration_files",
+                        Attribute {
+                            description: Description::plain(
+                                "Paths to the generated migration files, in the order to run them. More than one when some statements can't run in a transaction",
+                            ),
+                            attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                            constraint: AttributeConstraint::Computed,
+                            ..Default::default()
+                        },
+                    ),
                     (
                         "migration_number",
                         Attribute {
@@ -128,6 +389,30 @@ impl Resource for MigrationResource {
                             ..Default::default()
                         },
                     ),
+                    (
+                        "operation_counts",
+                        Attribute {
+                            description: Description::plain(
+                                "Number of operations in the migration per kind, e.g. create_table",
+                            ),
+                            attr_type: AttributeType::Map(Box::new(AttributeType::Number)),
+                            constraint: AttributeConstraint::Computed,
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "lint_results",
+                        Attribute {
+                            description: Description::plain(
+                                "Lint checks that fired for the migration, with rule, severity, message and object",
+                            ),
+                            attr_type: AttributeType::List(Box::new(
+                                crate::pipeline::LintEntry::attribute_type(),
+                            )),
+                            constraint: AttributeConstraint::Computed,
+                            ..Default::default()
+                        },
+                    ),
                 ]
                 .into_iter()
                 .map(|(k, v)| (k.to_string(), v))
@@ -137,6 +422,36 @@ impl Resource for MigrationResource {
         })
     }
 
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        crate::util::check_database_url(diags, config.database_url.as_deref());
+        crate::dialect::check_dialect(diags, config.dialect.as_deref());
+        crate::pipeline::check_version_range(
+            diags,
+            config.min_server_version,
+            config.max_server_version,
+        );
+        check_prefix(diags, config.prefix.as_deref());
+        check_output_dir(diags, &config);
+        crate::seed::check_seed_format(diags, config.seed_format.as_deref());
+        crate::masking::check_masking(diags, config.masking.as_ref());
+        crate::history::check_migration_history(diags, config.migration_history.as_deref());
+        if config.migration_history.as_deref() == Some("liquibase") {
+            diags.warning(
+                "Liquibase checksums can't be verified",
+                "Liquibase checksums cover change sets rather than files, so edits to applied migrations aren't detected.",
+                AttributePath::new("migration_history"),
+            );
+        }
+        if config.plan_output_path.is_some() && !config.plan_with_database.unwrap_or(false) {
+            diags.warning(
+                "plan_output_path has no effect",
+                "The plan is only computed, and written, when plan_with_database is enabled.",
+                AttributePath::new("plan_output_path"),
+            );
+        }
+        Some(())
+    }
+
     async fn read<'a>(
         &self,
         _diags: &mut Diagnostics,
@@ -151,59 +466,124 @@ impl Resource for MigrationResource {
         &self,
         diags: &mut Diagnostics,
         proposed_state: Self::State<'a>,
-        _config_state: Self::State<'a>,
+        config_state: Self::State<'a>,
         _provider_meta_state: Self::ProviderMetaState<'a>,
     ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let mut proposed_state = proposed_state;
+        self.inherit_provider_defaults(&mut proposed_state, &config_state)
+            .await;
         if proposed_state.database_url.is_none() {
-            diags.root_error_short("database_url is required");
+            diags.error_short(
+                "database_url is required (either at resource or provider level)",
+                AttributePath::new("database_url"),
+            );
             return None;
         }
 
-        let schema_path = std::path::Path::new(&proposed_state.schema_file);
+        let schema_file = resolved_schema_file(&proposed_state);
+        let schema_path = std::path::Path::new(&schema_file);
         if !schema_path.exists() {
-            diags.root_error_short(format!(
-                "schema_file not found: {}",
-                proposed_state.schema_file
-            ));
+            diags.error_short(
+                format!("schema_file not found: {schema_file}"),
+                AttributePath::new("schema_file"),
+            );
             return None;
         }
-
-        let output_dir = std::path::Path::new(&proposed_state.output_dir);
+        let (schema, schema_hash) = crate::pipeline::load_schema_file(diags, &schema_file)?;
+        crate::pipeline::check_schema_not_empty(
+            diags,
+            &schema_file,
+            &schema,
+            proposed_state.allow_empty.unwrap_or(false),
+        )?;
+        crate::dialect::check_dialect(diags, proposed_state.dialect.as_deref())?;
+        crate::pipeline::check_version_range(
+            diags,
+            proposed_state.min_server_version,
+            proposed_state.max_server_version,
+        )?;
+
+        let output_dir = resolved_output_dir(&proposed_state);
+        let output_dir = std::path::Path::new(&output_dir);
         if let Some(parent) = output_dir.parent() {
             if !parent.exists() {
-                diags.root_error_short(format!(
-                    "output_dir parent does not exist: {}",
-                    parent.display()
-                ));
+                diags.error_short(
+                    format!("output_dir parent does not exist: {}", parent.display()),
+                    AttributePath::new("output_dir"),
+                );
                 return None;
             }
         }
 
-        let schema_hash = match crate::util::compute_schema_hash(schema_path) {
-            Ok(h) => h,
-            Err(e) => {
-                diags.root_error_short(format!("Failed to read schema file: {e}"));
-                return None;
-            }
-        };
+        if proposed_state.plan_with_database.unwrap_or(false) {
+            let plan = self.plan_operations(diags, &proposed_state).await?;
+            self.verify_checksums(diags, &proposed_state).await?;
+            let key = self.connections.plan_signing_key().await;
+            write_plan_output(diags, &proposed_state, &schema_hash, &plan, key.as_deref())?;
+        }
 
+        let seeds = effective_seeds(diags, &proposed_state)?;
         let mut state = proposed_state;
         state.id = format!("pgmold-migration-{}", &schema_hash[..8]);
         state.schema_hash = Some(schema_hash);
+        state.seed_hash = seed_hash(&seeds);
 
         Some((state, Default::default()))
     }
 
     async fn plan_update<'a>(
         &self,
-        _diags: &mut Diagnostics,
-        _prior_state: Self::State<'a>,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
         proposed_state: Self::State<'a>,
-        _config_state: Self::State<'a>,
-        _prior_private_state: Self::PrivateState<'a>,
+        config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
         _provider_meta_state: Self::ProviderMetaState<'a>,
     ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
-        Some((proposed_state, Default::default(), vec![]))
+        let mut proposed_state = proposed_state;
+        self.inherit_provider_defaults(&mut proposed_state, &config_state)
+            .await;
+        let schema_file = resolved_schema_file(&proposed_state);
+        let (schema, schema_hash) = crate::pipeline::load_schema_file(diags, &schema_file)?;
+        crate::pipeline::check_schema_not_empty(
+            diags,
+            &schema_file,
+            &schema,
+            proposed_state.allow_empty.unwrap_or(false),
+        )?;
+        crate::dialect::check_dialect(diags, proposed_state.dialect.as_deref())?;
+        crate::pipeline::check_version_range(
+            diags,
+            proposed_state.min_server_version,
+            proposed_state.max_server_version,
+        )?;
+
+        // Network access during plan is strictly opt-in so air-gapped plans keep working
+        if proposed_state.plan_with_database.unwrap_or(false) {
+            let plan = self.plan_operations(diags, &proposed_state).await?;
+            self.verify_checksums(diags, &proposed_state).await?;
+            let key = self.connections.plan_signing_key().await;
+            write_plan_output(diags, &proposed_state, &schema_hash, &plan, key.as_deref())?;
+        }
+
+        // Files already written belong to the old directory and naming scheme,
+        // so these start a new migration sequence instead of updating in place
+        let mut requires_replace = vec![];
+        if resolved_output_dir(&prior_state) != resolved_output_dir(&proposed_state) {
+            requires_replace.push(AttributePath::new("output_dir"));
+        }
+        if prior_state.prefix != proposed_state.prefix {
+            requires_replace.push(AttributePath::new("prefix"));
+        }
+        if prior_state.target_schemas != proposed_state.target_schemas {
+            requires_replace.push(AttributePath::new("target_schemas"));
+        }
+
+        let seeds = effective_seeds(diags, &proposed_state)?;
+        let mut state = proposed_state;
+        state.seed_hash = seed_hash(&seeds);
+
+        Some((state, prior_private_state, requires_replace))
     }
 
     async fn plan_destroy<'a>(
@@ -224,216 +604,606 @@ impl Resource for MigrationResource {
         _planned_private_state: Self::PrivateState<'a>,
         _provider_meta_state: Self::ProviderMetaState<'a>,
     ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
-        let db_url = planned_state.database_url.as_ref()?;
-        let output_dir = std::path::Path::new(&planned_state.output_dir);
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let mut log = crate::execution_log::ExecutionLogEntry::new(
+            "pgmold_migration",
+            "create",
+            &planned_state.id,
+            planned_state.schema_hash.as_deref().unwrap_or_default(),
+        );
+        log.run = self.connections.run_metadata().await;
+        let log_path = planned_state.execution_log_path.clone();
+        let generated = self
+            .generate_migration(diags, planned_state, None, None, &mut log)
+            .await;
+        self.telemetry
+            .record("pgmold_migration", "create", started, generated.is_some())
+            .await;
+        log.finish(diags, generated.is_some());
+        crate::execution_log::append(diags, log_path.as_deref(), &log);
+        generated
+    }
+
+    async fn update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let mut log = crate::execution_log::ExecutionLogEntry::new(
+            "pgmold_migration",
+            "update",
+            &planned_state.id,
+            planned_state.schema_hash.as_deref().unwrap_or_default(),
+        );
+        log.run = self.connections.run_metadata().await;
+        let log_path = planned_state.execution_log_path.clone();
+        let generated = self
+            .generate_migration(
+                diags,
+                planned_state,
+                &prior_files(&prior_state),
+                planned_private_state,
+                &mut log,
+            )
+            .await;
+        self.telemetry
+            .record("pgmold_migration", "update", started, generated.is_some())
+            .await;
+        log.finish(diags, generated.is_some());
+        crate::execution_log::append(diags, log_path.as_deref(), &log);
+        generated
+    }
 
-        let connection = match pgmold::pg::connection::PgConnection::new(db_url).await {
-            Ok(c) => c,
+    async fn destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        Some(())
+    }
+}
+
+impl MigrationResource {
+    /// Fills in the provider's `database_url` and `target_schemas` where the
+    /// configuration leaves them out.
+    async fn inherit_provider_defaults(
+        &self,
+        state: &mut MigrationResourceState,
+        config: &MigrationResourceState,
+    ) {
+        let defaults = self.connections.resource_defaults().await;
+        if config.database_url.is_none() {
+            state.database_url = defaults.database_url;
+        }
+        if config.target_schemas.is_none() {
+            state.target_schemas = defaults.target_schemas;
+        }
+    }
+
+    /// Connects, diffs and lints without writing anything.
+    async fn plan_operations(
+        &self,
+        diags: &mut Diagnostics,
+        state: &MigrationResourceState,
+    ) -> Option<crate::pipeline::MigrationPlan> {
+        let Some(db_url) = state.database_url.as_deref() else {
+            diags.error_short(
+                "database_url is required (either at resource or provider level)",
+                AttributePath::new("database_url"),
+            );
+            return None;
+        };
+        let options = crate::pipeline::PlanOptions {
+            target_schemas: state
+                .target_schemas
+                .clone()
+                .unwrap_or_else(|| vec!["public".to_string()]),
+            lint_rules: self.connections.lint_rules().await,
+            escalate_warnings: self.connections.escalate_warnings().await,
+            operation_policy: self.connections.operation_policy().await,
+            lint_options: pgmold::lint::LintOptions {
+                allow_destructive: false,
+                is_production: false,
+            },
+            declared_kinds_only: state.declared_kinds_only.unwrap_or(false),
+            only_objects: None,
+            objects: Default::default(),
+            normalize_expressions: false,
+            skip_unreadable_schemas: state.skip_unreadable_schemas.unwrap_or(false),
+            dialect: state
+                .dialect
+                .as_deref()
+                .and_then(crate::dialect::Dialect::parse)
+                .unwrap_or_default(),
+            phase: None,
+            server_version: self.connections.target_server_version().await,
+            rebuild_enums: false,
+            defer_validation: false,
+            preserve_sequences: false,
+            preserve_data: false,
+        };
+
+        let schema_file = resolved_schema_file(state);
+        let planned = match self.connections.snapshot().await {
+            Some(snapshot) => {
+                crate::pipeline::plan_from_snapshot(&snapshot, &schema_file, &options)
+            }
+            None => {
+                let connection = self.connections.connect_read_only(diags, db_url).await?;
+                crate::pipeline::check_server_version(
+                    diags,
+                    &connection,
+                    state.min_server_version,
+                    state.max_server_version,
+                )
+                .await?;
+                crate::pipeline::plan_migration(&connection, &schema_file, &options).await
+            }
+        };
+        let mut plan = match planned {
+            Ok(p) => p,
             Err(e) => {
-                let sanitized = crate::util::sanitize_db_error(&format!("{e}"));
-                diags.root_error_short(format!("Failed to connect to database: {sanitized}"));
+                diags.error_short(format!("{e}"), e.attribute());
                 return None;
             }
         };
 
-        let target_schemas = planned_state
-            .target_schemas
-            .clone()
-            .unwrap_or_else(|| vec!["public".to_string()]);
-
-        let current =
-            match pgmold::pg::introspect::introspect_schema(&connection, &target_schemas, false)
-                .await
-            {
-                Ok(s) => s,
-                Err(e) => {
-                    diags.root_error_short(format!("Failed to introspect database: {e}"));
-                    return None;
-                }
-            };
+        crate::pipeline::report_skipped_schemas(diags, &plan.skipped_schemas);
+        if state.backfill_templates.unwrap_or(false) {
+            crate::backfill::apply_templates(&mut plan);
+        }
+
+        if !plan.operations.is_empty()
+            && crate::pipeline::report_lint_errors(diags, &plan.lint_results)
+        {
+            return None;
+        }
 
-        let target = match pgmold::parser::parse_sql_file(&planned_state.schema_file) {
-            Ok(s) => s,
+        Some(plan)
+    }
+
+    /// Compares the migration files in `output_dir` with the checksums the
+    /// runner recorded when it applied them.
+    async fn verify_checksums(
+        &self,
+        diags: &mut Diagnostics,
+        state: &MigrationResourceState,
+    ) -> Option<()> {
+        let Some(runner) = state
+            .migration_history
+            .as_deref()
+            .and_then(crate::history::HistoryRunner::parse)
+        else {
+            return Some(());
+        };
+        // Mock mode has no history table to compare against
+        if self.connections.snapshot().await.is_some() {
+            return Some(());
+        }
+        let db_url = state.database_url.as_deref()?;
+        let connection = self.connections.connect_read_only(diags, db_url).await?;
+        let applied = match crate::history::applied_checksums(connection.pool(), runner).await {
+            Ok(Some(applied)) => applied,
+            Ok(None) => return Some(()),
             Err(e) => {
-                diags.root_error_short(format!("Failed to parse schema file: {e}"));
+                diags.error(
+                    "Failed to read migration history",
+                    format!("{}: {e}", runner.table()),
+                    AttributePath::new("migration_history"),
+                );
                 return None;
             }
         };
 
-        let operations = pgmold::diff::compute_diff(&current, &target);
+        let files = migration_files(
+            std::path::Path::new(&resolved_output_dir(state)),
+            state.prefix.as_deref(),
+        );
+        let changed = crate::history::changed_migrations(runner, &files, &applied);
+        if changed.is_empty() {
+            return Some(());
+        }
+        let summary = format!("Applied migrations were edited: {}", changed.join(", "));
+        let detail = format!(
+            "The checksums {} recorded when these files ran no longer match them. Restore the files, or repair the history with the migration runner.",
+            runner.table()
+        );
+        if state.warn_on_checksum_mismatch.unwrap_or(false) {
+            diags.warning(summary, detail, AttributePath::new("migration_history"));
+            return Some(());
+        }
+        diags.error(summary, detail, AttributePath::new("migration_history"));
+        None
+    }
 
-        if operations.is_empty() {
+    /// Writes the next numbered migration files, replacing `prior_files`.
+    async fn generate_migration(
+        &self,
+        diags: &mut Diagnostics,
+        planned_state: MigrationResourceState,
+        prior_files: &[String],
+        cache: Option<MigrationPrivateState>,
+        log: &mut crate::execution_log::ExecutionLogEntry,
+    ) -> Option<(MigrationResourceState, Option<MigrationPrivateState>)> {
+        let plan = self.plan_operations(diags, &planned_state).await?;
+        self.verify_checksums(diags, &planned_state).await?;
+        log.record_plan(&plan);
+
+        if plan.operations.is_empty() {
             let mut state = planned_state;
             state.operations = Some(vec![]);
-            return Some((state, Default::default()));
+            state.operation_counts = Some(Default::default());
+            state.lint_results = Some(log.lint_results.clone());
+            return Some((state, cache));
         }
 
-        let lint_results = pgmold::lint::lint_migration_plan(
-            &operations,
-            &pgmold::lint::LintOptions {
-                allow_destructive: false,
-                is_production: false,
-            },
-        );
+        let output_dir = resolved_output_dir(&planned_state);
+        let output_dir = std::path::Path::new(&output_dir);
 
-        if pgmold::lint::has_errors(&lint_results) {
-            for lint in &lint_results {
-                if lint.severity == pgmold::lint::LintSeverity::Error {
-                    diags.root_error_short(lint.message.to_string());
-                }
+        // Must be checked before removing the prior files, which bumps the directory mtime
+        let cached_number = cache
+            .as_ref()
+            .and_then(|c| cached_migration_number(output_dir, c, prior_files));
+
+        for old_file in prior_files {
+            if std::path::Path::new(old_file).exists() {
+                let _ = std::fs::remove_file(old_file);
             }
-            return None;
+            let _ = std::fs::remove_file(crate::signing::signature_path(old_file));
         }
 
-        let migration_number =
-            find_next_migration_number(output_dir, planned_state.prefix.as_deref());
+        let migration_number = cached_number.unwrap_or_else(|| {
+            find_next_migration_number(output_dir, planned_state.prefix.as_deref())
+        });
 
-        let sql = pgmold::pg::sqlgen::generate_sql(&operations);
-        let op_summaries: Vec<String> = operations.iter().map(|op| format!("{op:?}")).collect();
+        let op_summaries: Vec<String> =
+            plan.operations.iter().map(|op| format!("{op:?}")).collect();
+        let op_kinds: Vec<String> = plan
+            .operations
+            .iter()
+            .map(crate::operations::operation_kind)
+            .collect();
 
         if let Err(e) = std::fs::create_dir_all(output_dir) {
-            diags.root_error_short(format!("Failed to create output directory: {e}"));
+            diags.error_short(
+                format!("Failed to create output directory: {e}"),
+                AttributePath::new("output_dir"),
+            );
             return None;
         }
 
         let prefix = planned_state.prefix.as_deref().unwrap_or("");
-        let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
-        let filename = format!("{prefix}{migration_number:04}_{timestamp}.sql");
-        let filepath = output_dir.join(&filename);
+        let timestamp = crate::clock::now().format("%Y%m%d%H%M%S");
+        let seeds = seed_sections(diags, &planned_state, &plan.operations)?;
+        let parts = split_by_transaction(&plan.statements, seeds);
+        let split = parts.iter().any(|part| !part.transactional);
+        let signing_key = self.connections.plan_signing_key().await;
+        let mut generated_files = Vec::new();
+        for (number, part) in (migration_number..).zip(&parts) {
+            let suffix = if part.transactional {
+                ""
+            } else {
+                "_nontransactional"
+            };
+            let filename = format!("{prefix}{number:04}_{timestamp}{suffix}.sql");
+            tracing::info!(
+                resource = "pgmold_migration",
+                operations = plan.operations.len(),
+                statements = part.statements.len(),
+                filename = %filename,
+                "writing migration"
+            );
+            let filepath = output_dir.join(&filename);
 
-        if let Err(e) = std::fs::write(&filepath, sql.join("\n")) {
-            diags.root_error_short(format!("Failed to write migration file: {e}"));
-            return None;
+            // The headers aren't SQL, so the signature covers the statements alone
+            let mut lines: Vec<String> = Vec::new();
+            if split {
+                lines.push(part.header().to_string());
+            }
+            lines.extend(log.run.header());
+            lines.push(part.statements.join("\n"));
+            if let Err(e) = std::fs::write(&filepath, lines.join("\n")) {
+                diags.error_short(
+                    format!("Failed to write migration file: {e}"),
+                    AttributePath::new("output_dir"),
+                );
+                return None;
+            }
+
+            let generated_file = filepath.to_string_lossy().to_string();
+            if let Some(key) = &signing_key {
+                let signature = crate::signing::sign(key, &part.statements);
+                if let Err(e) = std::fs::write(
+                    crate::signing::signature_path(&generated_file),
+                    format!("{signature}\n"),
+                ) {
+                    diags.error_short(
+                        format!("Failed to write migration signature: {e}"),
+                        AttributePath::new("output_dir"),
+                    );
+                    return None;
+                }
+            }
+            generated_files.push(generated_file);
         }
+        let last_number = migration_number + parts.len() as u32 - 1;
+        let private_state = dir_mtime(output_dir).map(|dir_mtime| MigrationPrivateState {
+            dir_mtime,
+            max_number: last_number,
+            generated_file: generated_files.last().cloned().unwrap_or_default(),
+        });
 
         let mut state = planned_state;
-        state.migration_file = Some(filepath.to_string_lossy().to_string());
+        state.migration_file = generated_files.first().cloned();
+        state.migration_files = Some(generated_files);
         state.migration_number = Some(migration_number);
         state.operations = Some(op_summaries);
+        state.operation_counts = Some(crate::operations::count_by_kind(&op_kinds));
+        state.lint_results = Some(log.lint_results.clone());
 
-        Some((state, Default::default()))
+        Some((state, private_state))
     }
+}
 
-    async fn update<'a>(
-        &self,
-        diags: &mut Diagnostics,
-        prior_state: Self::State<'a>,
-        planned_state: Self::State<'a>,
-        _config_state: Self::State<'a>,
-        _planned_private_state: Self::PrivateState<'a>,
-        _provider_meta_state: Self::ProviderMetaState<'a>,
-    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
-        let db_url = planned_state.database_url.as_ref()?;
-        let output_dir = std::path::Path::new(&planned_state.output_dir);
+/// Seed data for the tables this migration creates. Seeds for tables that
+/// already exist are skipped, since their migration has been written.
+fn seed_sections(
+    diags: &mut Diagnostics,
+    state: &MigrationResourceState,
+    operations: &[pgmold::diff::MigrationOp],
+) -> Option<Vec<String>> {
+    let format = state
+        .seed_format
+        .as_deref()
+        .and_then(crate::seed::SeedFormat::parse)
+        .unwrap_or_default();
+    let created = crate::seed::created_tables(operations);
+    let masking = match state.masking.as_ref().map(crate::masking::parse_rules) {
+        Some(Ok(rules)) => rules,
+        Some(Err(e)) => {
+            diags.error_short(e, AttributePath::new("masking"));
+            return None;
+        }
+        None => vec![],
+    };
 
-        let connection = match pgmold::pg::connection::PgConnection::new(db_url).await {
-            Ok(c) => c,
+    let mut sections = vec![];
+    for (table, csv) in effective_seeds(diags, state)? {
+        if !created.contains(&table) {
+            continue;
+        }
+        match crate::seed::seed_sql(&table, &csv, format, &masking) {
+            Ok(sql) if sql.is_empty() => {}
+            Ok(sql) => sections.push(sql),
             Err(e) => {
-                let sanitized = crate::util::sanitize_db_error(&format!("{e}"));
-                diags.root_error_short(format!("Failed to connect to database: {sanitized}"));
+                diags.error(
+                    format!("Failed to read seed data for {table}"),
+                    e.to_string(),
+                    AttributePath::new("seed_files"),
+                );
                 return None;
             }
-        };
-
-        let target_schemas = planned_state
-            .target_schemas
-            .clone()
-            .unwrap_or_else(|| vec!["public".to_string()]);
-
-        let current =
-            match pgmold::pg::introspect::introspect_schema(&connection, &target_schemas, false)
-                .await
-            {
-                Ok(s) => s,
-                Err(e) => {
-                    diags.root_error_short(format!("Failed to introspect database: {e}"));
-                    return None;
-                }
-            };
+        }
+    }
+    Some(sections)
+}
 
-        let target = match pgmold::parser::parse_sql_file(&planned_state.schema_file) {
-            Ok(s) => s,
+/// Seed data per qualified table, with `seed_environment` overlays and
+/// `seed_variables` applied.
+fn effective_seeds(
+    diags: &mut Diagnostics,
+    state: &MigrationResourceState,
+) -> Option<std::collections::BTreeMap<String, String>> {
+    let Some(seed_files) = &state.seed_files else {
+        return Some(Default::default());
+    };
+    let variables = state.seed_variables.clone().unwrap_or_default();
+    let mut seeds = std::collections::BTreeMap::new();
+    for (table, file) in seed_files {
+        let table = crate::seed::qualified_table(table);
+        let path = crate::util::resolve_path(state.base_dir.as_deref(), file);
+        match crate::seed::effective_csv(&path, state.seed_environment.as_deref(), &variables) {
+            Ok(csv) => {
+                seeds.insert(table, csv);
+            }
             Err(e) => {
-                diags.root_error_short(format!("Failed to parse schema file: {e}"));
+                diags.error(
+                    format!("Failed to read seed data for {table}"),
+                    format!("{path}: {e}"),
+                    AttributePath::new("seed_files"),
+                );
                 return None;
             }
-        };
+        }
+    }
+    Some(seeds)
+}
 
-        let operations = pgmold::diff::compute_diff(&current, &target);
+fn seed_hash(seeds: &std::collections::BTreeMap<String, String>) -> Option<String> {
+    if seeds.is_empty() {
+        return None;
+    }
+    let data: String = seeds
+        .iter()
+        .map(|(table, csv)| format!("{table}\0{csv}\0"))
+        .collect();
+    Some(crate::util::compute_content_hash(&data))
+}
 
-        if operations.is_empty() {
-            let mut state = planned_state;
-            state.operations = Some(vec![]);
-            return Some((state, Default::default()));
-        }
+fn write_plan_output(
+    diags: &mut Diagnostics,
+    state: &MigrationResourceState,
+    schema_hash: &str,
+    plan: &crate::pipeline::MigrationPlan,
+    signing_key: Option<&str>,
+) -> Option<()> {
+    crate::plan_output::write(
+        diags,
+        state.plan_output_path.as_deref(),
+        &crate::plan_output::PlanOutput::new("pgmold_migration", schema_hash, plan)
+            .signed(signing_key),
+    )
+}
 
-        let lint_results = pgmold::lint::lint_migration_plan(
-            &operations,
-            &pgmold::lint::LintOptions {
-                allow_destructive: false,
-                is_production: false,
-            },
-        );
+fn resolved_schema_file(state: &MigrationResourceState) -> String {
+    crate::util::resolve_path(state.base_dir.as_deref(), &state.schema_file)
+}
 
-        if pgmold::lint::has_errors(&lint_results) {
-            for lint in &lint_results {
-                if lint.severity == pgmold::lint::LintSeverity::Error {
-                    diags.root_error_short(lint.message.to_string());
-                }
-            }
-            return None;
-        }
+fn resolved_output_dir(state: &MigrationResourceState) -> String {
+    crate::util::resolve_path(state.base_dir.as_deref(), &state.output_dir)
+}
 
-        if let Some(old_file) = &prior_state.migration_file {
-            if std::path::Path::new(old_file).exists() {
-                let _ = std::fs::remove_file(old_file);
-            }
+/// The prefix becomes part of every file name, so it can't contain path
+/// separators or characters migration runners reject.
+fn check_prefix(diags: &mut Diagnostics, prefix: Option<&str>) -> Option<()> {
+    let valid = regex::Regex::new(r"^[A-Za-z0-9_.-]*$").unwrap();
+    match prefix {
+        Some(prefix) if !valid.is_match(prefix) => {
+            diags.error(
+                format!("Invalid prefix \"{prefix}\""),
+                "prefix may only contain letters, digits, '_', '.' and '-'",
+                AttributePath::new("prefix"),
+            );
+            None
         }
+        _ => Some(()),
+    }
+}
 
-        let migration_number =
-            find_next_migration_number(output_dir, planned_state.prefix.as_deref());
-
-        let sql = pgmold::pg::sqlgen::generate_sql(&operations);
-        let op_summaries: Vec<String> = operations.iter().map(|op| format!("{op:?}")).collect();
+fn check_output_dir(diags: &mut Diagnostics, state: &MigrationResourceState) -> Option<()> {
+    if state.output_dir.trim().is_empty() {
+        diags.error_short(
+            "output_dir must not be empty",
+            AttributePath::new("output_dir"),
+        );
+        return None;
+    }
+    let output_dir = resolved_output_dir(state);
+    if std::path::Path::new(&output_dir).is_file() {
+        diags.error_short(
+            format!("output_dir {output_dir} is a file, not a directory"),
+            AttributePath::new("output_dir"),
+        );
+        return None;
+    }
+    Some(())
+}
 
-        if let Err(e) = std::fs::create_dir_all(output_dir) {
-            diags.root_error_short(format!("Failed to create output directory: {e}"));
-            return None;
-        }
+fn dir_mtime(dir: &std::path::Path) -> Option<u64> {
+    let modified = std::fs::metadata(dir).ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_nanos() as u64)
+}
 
-        let prefix = planned_state.prefix.as_deref().unwrap_or("");
-        let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
-        let filename = format!("{prefix}{migration_number:04}_{timestamp}.sql");
-        let filepath = output_dir.join(&filename);
+/// Next migration number from the cached scan, or `None` if the directory changed
+/// since it was recorded and must be rescanned.
+fn cached_migration_number(
+    output_dir: &std::path::Path,
+    cache: &MigrationPrivateState,
+    prior_files: &[String],
+) -> Option<u32> {
+    if dir_mtime(output_dir)? != cache.dir_mtime {
+        return None;
+    }
+    match prior_files {
+        // Replacing our own file, which holds the highest number
+        [prior] if *prior == cache.generated_file => Some(cache.max_number),
+        // Replacing another file, or several whose first number the rescan finds
+        [_, ..] => None,
+        [] => Some(cache.max_number + 1),
+    }
+}
 
-        if let Err(e) = std::fs::write(&filepath, sql.join("\n")) {
-            diags.root_error_short(format!("Failed to write migration file: {e}"));
-            return None;
-        }
+/// Files generated for `state`, which has `migration_files` unless it was
+/// written before migrations were split.
+fn prior_files(state: &MigrationResourceState) -> Vec<String> {
+    state
+        .migration_files
+        .clone()
+        .unwrap_or_else(|| state.migration_file.iter().cloned().collect())
+}
 
-        let mut state = planned_state;
-        state.migration_file = Some(filepath.to_string_lossy().to_string());
-        state.migration_number = Some(migration_number);
-        state.operations = Some(op_summaries);
+/// Consecutive statements of a migration that can, or can't, run in a
+/// transaction block. Each is written to its own file.
+#[derive(Debug, PartialEq)]
+struct MigrationPart {
+    statements: Vec<String>,
+    transactional: bool,
+}
 
-        Some((state, Default::default()))
+impl MigrationPart {
+    fn header(&self) -> &'static str {
+        if self.transactional {
+            "-- pgmold: transactional. Apply this file in a single transaction."
+        } else {
+            "-- pgmold: non-transactional. Apply each statement outside a transaction block; runners that wrap files in a transaction must not wrap this one."
+        }
     }
+}
 
-    async fn destroy<'a>(
-        &self,
-        _diags: &mut Diagnostics,
-        _prior_state: Self::State<'a>,
-        _prior_private_state: Self::PrivateState<'a>,
-        _provider_meta_state: Self::ProviderMetaState<'a>,
-    ) -> Option<()> {
-        Some(())
+/// Splits a migration where statements such as `CREATE INDEX CONCURRENTLY`
+/// start or end, so runners enforcing single-transaction applies can run
+/// every file. Statements keep their order across the parts; seed data,
+/// which loads into tables the migration creates, comes last. There is
+/// always at least one part.
+fn split_by_transaction(statements: &[String], seeds: Vec<String>) -> Vec<MigrationPart> {
+    let mut parts: Vec<MigrationPart> = Vec::new();
+    let classified = statements
+        .iter()
+        .map(|s| (s.clone(), crate::execute::is_transactional(s)))
+        .chain(seeds.into_iter().map(|s| (s, true)));
+    for (statement, transactional) in classified {
+        match parts.last_mut() {
+            Some(part) if part.transactional == transactional => part.statements.push(statement),
+            _ => parts.push(MigrationPart {
+                statements: vec![statement],
+                transactional,
+            }),
+        }
+    }
+    if parts.is_empty() {
+        parts.push(MigrationPart {
+            statements: vec![],
+            transactional: true,
+        });
     }
+    parts
 }
 
-fn find_next_migration_number(output_dir: &std::path::Path, prefix: Option<&str>) -> u32 {
+fn migration_file_pattern(prefix: Option<&str>) -> regex::Regex {
     let prefix = prefix.unwrap_or("");
-    let pattern = format!(r"{}(\d{{4}})_.*\.sql$", regex::escape(prefix));
-    let re = regex::Regex::new(&pattern).unwrap();
+    regex::Regex::new(&format!(r"{}(\d{{4}})_.*\.sql$", regex::escape(prefix))).unwrap()
+}
+
+/// Names and contents of the numbered migration files in `output_dir`.
+fn migration_files(output_dir: &std::path::Path, prefix: Option<&str>) -> Vec<(String, String)> {
+    let re = migration_file_pattern(prefix);
+    let mut files: Vec<(String, String)> = std::fs::read_dir(output_dir)
+        .ok()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| re.is_match(&e.file_name().to_string_lossy()))
+        .filter_map(|e| {
+            let content = std::fs::read_to_string(e.path()).ok()?;
+            Some((e.file_name().to_string_lossy().to_string(), content))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn find_next_migration_number(output_dir: &std::path::Path, prefix: Option<&str>) -> u32 {
+    let re = migration_file_pattern(prefix);
 
     std::fs::read_dir(output_dir)
         .ok()
@@ -457,12 +1227,208 @@ mod tests {
     use std::io::Write;
     use tempfile::TempDir;
 
+    #[tokio::test]
+    async fn validate_rejects_bad_prefix_and_output_dir() {
+        let output_file = tempfile::NamedTempFile::new().unwrap();
+        let config = MigrationResourceState {
+            schema_file: "schema.sql".to_string(),
+            output_dir: output_file.path().to_string_lossy().to_string(),
+            prefix: Some("V/1".to_string()),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        MigrationResource::default()
+            .validate(&mut diags, config)
+            .await;
+
+        let attributes: Vec<_> = diags.errors.iter().map(|e| e.attribute.clone()).collect();
+        assert_eq!(
+            attributes,
+            vec![
+                AttributePath::new("prefix"),
+                AttributePath::new("output_dir")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn plan_update_replaces_on_prefix_change() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+        let output_dir = TempDir::new().unwrap();
+
+        let prior = MigrationResourceState {
+            schema_file: schema_file.path().to_string_lossy().to_string(),
+            output_dir: output_dir.path().to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let proposed = MigrationResourceState {
+            prefix: Some("V".to_string()),
+            ..prior.clone()
+        };
+
+        let mut diags = Diagnostics::default();
+        let (_, _, requires_replace) = MigrationResource::default()
+            .plan_update(
+                &mut diags,
+                prior,
+                proposed.clone(),
+                proposed,
+                None,
+                ValueEmpty::default(),
+            )
+            .await
+            .expect("plan_update should succeed");
+
+        assert_eq!(requires_replace, vec![AttributePath::new("prefix")]);
+    }
+
+    #[tokio::test]
+    async fn plan_create_inherits_provider_database_url() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+        let output_dir = TempDir::new().unwrap();
+
+        let resource = MigrationResource::default();
+        resource
+            .connections
+            .set_resource_defaults(crate::connections::ResourceDefaults {
+                database_url: Some("postgres://provider/app".to_string()),
+                target_schemas: Some(vec!["app".to_string()]),
+            })
+            .await;
+        let proposed = MigrationResourceState {
+            schema_file: schema_file.path().to_string_lossy().to_string(),
+            output_dir: output_dir.path().to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        let (state, _) = resource
+            .plan_create(
+                &mut diags,
+                proposed.clone(),
+                proposed,
+                ValueEmpty::default(),
+            )
+            .await
+            .expect("plan_create should succeed");
+
+        assert_eq!(
+            state.database_url.as_deref(),
+            Some("postgres://provider/app")
+        );
+        assert_eq!(state.target_schemas, Some(vec!["app".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn plan_create_requires_database_url() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+        let output_dir = TempDir::new().unwrap();
+        let proposed = MigrationResourceState {
+            schema_file: schema_file.path().to_string_lossy().to_string(),
+            output_dir: output_dir.path().to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        let result = MigrationResource::default()
+            .plan_create(
+                &mut diags,
+                proposed.clone(),
+                proposed,
+                ValueEmpty::default(),
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("database_url")
+        );
+    }
+
     #[test]
     fn migration_state_has_default_empty_prefix() {
         let state = MigrationResourceState::default();
         assert!(state.prefix.is_none());
     }
 
+    #[test]
+    fn migration_state_defaults_plan_with_database_unset() {
+        let state = MigrationResourceState::default();
+        assert!(state.plan_with_database.is_none());
+    }
+
+    #[test]
+    fn cached_migration_number_reuses_scan_when_dir_unchanged() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("0007_20240101.sql");
+        std::fs::write(&file, "").unwrap();
+        let cache = MigrationPrivateState {
+            dir_mtime: dir_mtime(dir.path()).unwrap(),
+            max_number: 7,
+            generated_file: file.to_string_lossy().to_string(),
+        };
+
+        assert_eq!(
+            cached_migration_number(dir.path(), &cache, &[cache.generated_file.clone()]),
+            Some(7)
+        );
+        assert_eq!(cached_migration_number(dir.path(), &cache, &[]), Some(8));
+        assert_eq!(
+            cached_migration_number(dir.path(), &cache, &["other.sql".to_string()]),
+            None
+        );
+    }
+
+    #[test]
+    fn cached_migration_number_rescans_when_dir_changed() {
+        let dir = TempDir::new().unwrap();
+        let cache = MigrationPrivateState {
+            dir_mtime: 0,
+            max_number: 7,
+            generated_file: String::new(),
+        };
+
+        assert_eq!(cached_migration_number(dir.path(), &cache, &[]), None);
+    }
+
+    #[test]
+    fn split_by_transaction_keeps_order_across_files() {
+        let statements: Vec<String> = [
+            "CREATE TABLE users (id INT PRIMARY KEY, email TEXT)",
+            "CREATE INDEX CONCURRENTLY users_email_idx ON users (email)",
+            "ALTER TYPE status ADD VALUE 'archived'",
+            "ALTER TABLE users ADD COLUMN status status",
+        ]
+        .map(String::from)
+        .to_vec();
+        let seeds = vec!["INSERT INTO users (id) VALUES (1)".to_string()];
+
+        let parts = split_by_transaction(&statements, seeds);
+
+        assert_eq!(
+            parts
+                .iter()
+                .map(|p| (p.transactional, p.statements.len()))
+                .collect::<Vec<_>>(),
+            vec![(true, 1), (false, 2), (true, 2)]
+        );
+        assert_eq!(parts[2].statements[1], "INSERT INTO users (id) VALUES (1)");
+        assert_eq!(split_by_transaction(&statements[..1], vec![]).len(), 1);
+        assert_eq!(
+            split_by_transaction(&[], vec![]),
+            vec![MigrationPart {
+                statements: vec![],
+                transactional: true
+            }]
+        );
+    }
+
     #[test]
     fn find_next_migration_number_empty_dir() {
         let dir = TempDir::new().unwrap();
@@ -494,6 +1460,48 @@ mod tests {
         assert_eq!(find_next_migration_number(dir.path(), None), 2);
     }
 
+    #[test]
+    fn migration_files_reads_numbered_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("0002_20240102.sql"), "SELECT 2;").unwrap();
+        std::fs::write(dir.path().join("0001_20240101.sql"), "SELECT 1;").unwrap();
+        std::fs::write(dir.path().join("0001_20240101.sql.sig"), "").unwrap();
+        std::fs::write(dir.path().join("schema.sql"), "").unwrap();
+        assert_eq!(
+            migration_files(dir.path(), None),
+            vec![
+                ("0001_20240101.sql".to_string(), "SELECT 1;".to_string()),
+                ("0002_20240102.sql".to_string(), "SELECT 2;".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn seed_hash_covers_effective_seed_data() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("flags.csv"), "name,enabled\nbeta,${beta}\n").unwrap();
+        let mut state = MigrationResourceState {
+            base_dir: Some(dir.path().to_string_lossy().to_string()),
+            seed_files: Some([("flags".to_string(), "flags.csv".to_string())].into()),
+            seed_variables: Some([("beta".to_string(), "false".to_string())].into()),
+            ..Default::default()
+        };
+        let mut diags = Diagnostics::default();
+        let seeds = effective_seeds(&mut diags, &state).unwrap();
+        assert_eq!(seeds["public.flags"], "name,enabled\nbeta,false\n");
+        let staging_hash = seed_hash(&seeds);
+
+        state.seed_variables = Some([("beta".to_string(), "true".to_string())].into());
+        assert_ne!(
+            seed_hash(&effective_seeds(&mut diags, &state).unwrap()),
+            staging_hash
+        );
+
+        state.seed_variables = None;
+        assert!(effective_seeds(&mut diags, &state).is_none());
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("seed_files"));
+    }
+
     #[test]
     fn find_next_migration_number_skips_gaps() {
         let dir = TempDir::new().unwrap();
@@ -505,7 +1513,7 @@ mod tests {
 
     #[tokio::test]
     async fn migration_resource_has_required_attributes() {
-        let resource = MigrationResource;
+        let resource = MigrationResource::default();
         let mut diags = Diagnostics::default();
         let schema = resource.schema(&mut diags).expect("schema should exist");
 
@@ -519,16 +1527,20 @@ mod tests {
 
     #[tokio::test]
     async fn migration_resource_has_computed_attributes() {
-        let resource = MigrationResource;
+        let resource = MigrationResource::default();
         let mut diags = Diagnostics::default();
         let schema = resource.schema(&mut diags).expect("schema should exist");
 
         for name in [
             "id",
             "schema_hash",
+            "seed_hash",
             "migration_file",
+            "migration_files",
             "migration_number",
             "operations",
+            "operation_counts",
+            "lint_results",
         ] {
             assert!(
                 schema.block.attributes.contains_key(name),
@@ -542,7 +1554,7 @@ mod tests {
         let mut schema_file = tempfile::NamedTempFile::new().unwrap();
         writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
 
-        let resource = MigrationResource;
+        let resource = MigrationResource::default();
         let mut diags = Diagnostics::default();
 
         let proposed = MigrationResourceState {
@@ -575,7 +1587,7 @@ mod tests {
         let mut schema_file = tempfile::NamedTempFile::new().unwrap();
         writeln!(schema_file, "CREATE TABLE users (id INT);").unwrap();
 
-        let resource = MigrationResource;
+        let resource = MigrationResource::default();
         let mut diags = Diagnostics::default();
 
         let proposed = MigrationResourceState {
@@ -599,7 +1611,7 @@ mod tests {
 
     #[tokio::test]
     async fn plan_create_fails_with_nonexistent_schema_file() {
-        let resource = MigrationResource;
+        let resource = MigrationResource::default();
         let mut diags = Diagnostics::default();
 
         let proposed = MigrationResourceState {
@@ -620,4 +1632,32 @@ mod tests {
 
         assert!(result.is_none() || !diags.errors.is_empty());
     }
+
+    #[tokio::test]
+    async fn plan_create_reports_missing_output_dir_parent_on_attribute() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT);").unwrap();
+
+        let resource = MigrationResource::default();
+        let mut diags = Diagnostics::default();
+
+        let proposed = MigrationResourceState {
+            schema_file: schema_file.path().to_string_lossy().to_string(),
+            database_url: Some("postgres://test".to_string()),
+            output_dir: "/nonexistent/parent/migrations".to_string(),
+            ..Default::default()
+        };
+
+        let result = resource
+            .plan_create(
+                &mut diags,
+                proposed.clone(),
+                proposed,
+                ValueEmpty::default(),
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("output_dir"));
+    }
 }
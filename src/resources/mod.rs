@@ -1,5 +1,17 @@
+pub mod backup;
 pub mod migration;
+pub mod restore;
 pub mod schema;
+pub mod schema_clone;
+pub mod schema_phase;
+pub mod schema_swap;
+pub mod tenant_schemas;
 
+pub use backup::BackupResource;
 pub use migration::MigrationResource;
+pub use restore::RestoreResource;
 pub use schema::SchemaResource;
+pub use schema_clone::SchemaCloneResource;
+pub use schema_phase::SchemaPhaseResource;
+pub use schema_swap::SchemaSwapResource;
+pub use tenant_schemas::TenantSchemasResource;
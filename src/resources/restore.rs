@@ -0,0 +1,510 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::{Value, ValueBool, ValueEmpty, ValueMap, ValueString},
+    AttributePath, Diagnostics, Resource,
+};
+
+use crate::backup::RestoreOptions;
+use crate::functions::quote::quote_ident;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RestoreResourceState<'a> {
+    #[serde(borrow)]
+    pub id: ValueString<'a>,
+    #[serde(borrow)]
+    pub database_url: ValueString<'a>,
+    #[serde(borrow)]
+    pub source: ValueString<'a>,
+    #[serde(borrow)]
+    pub checksum: ValueString<'a>,
+    pub create_database: ValueBool,
+    pub clean: ValueBool,
+    pub no_owner: ValueBool,
+    #[serde(borrow)]
+    pub pg_restore_path: ValueString<'a>,
+    #[serde(borrow)]
+    pub psql_path: ValueString<'a>,
+    #[serde(borrow)]
+    pub masking: ValueMap<'a, ValueString<'a>>,
+    #[serde(borrow)]
+    pub triggers: ValueMap<'a, ValueString<'a>>,
+    #[serde(borrow)]
+    pub source_checksum: ValueString<'a>,
+    #[serde(borrow)]
+    pub restored_at: ValueString<'a>,
+}
+
+/// Restores a dump into a database when created, optionally creating the
+/// database first, e.g. to seed a preview environment from a production
+/// snapshot. Any change restores again.
+#[derive(Debug, Default, Clone)]
+pub struct RestoreResource {
+    pub connections: crate::connections::ConnectionCache,
+    pub telemetry: crate::telemetry::Telemetry,
+}
+
+/// Attributes that, when changed, restore again.
+const INPUTS: [&str; 10] = [
+    "database_url",
+    "source",
+    "checksum",
+    "create_database",
+    "clean",
+    "no_owner",
+    "pg_restore_path",
+    "psql_path",
+    "masking",
+    "triggers",
+];
+
+#[async_trait]
+impl Resource for RestoreResource {
+    type State<'a> = RestoreResourceState<'a>;
+    type PrivateState<'a> = ValueEmpty;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Restores a pg_dump artifact from a local path or S3 into a database",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Resource identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL of the database to restore into"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "source" => Attribute {
+                        description: Description::plain("Local path or s3://bucket/key of the dump, e.g. pgmold_backup.location"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "checksum" => Attribute {
+                        description: Description::plain("Expected SHA256 of the dump, e.g. pgmold_backup.checksum; the restore fails on a mismatch"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "create_database" => Attribute {
+                        description: Description::plain("Create the database before restoring, and drop it on destroy (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "clean" => Attribute {
+                        description: Description::plain("Drop existing objects before restoring a custom-format dump (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "no_owner" => Attribute {
+                        description: Description::plain("Skip restoring object ownership of a custom-format dump (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "pg_restore_path" => Attribute {
+                        description: Description::plain("pg_restore executable, for custom-format dumps (default: pg_restore on the PATH)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "psql_path" => Attribute {
+                        description: Description::plain("psql executable, for plain SQL dumps (default: psql on the PATH)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "masking" => Attribute {
+                        description: Description::plain("Columns to mask after restoring, as table.column or schema.table.column = null, hash, email, name or phone"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "triggers" => Attribute {
+                        description: Description::plain("Arbitrary values that restore again when changed"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "source_checksum" => Attribute {
+                        description: Description::plain("SHA256 of the restored dump"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "restored_at" => Attribute {
+                        description: Description::plain("Timestamp of the restore"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        let database_url = config.database_url.as_ref_option().map(|u| u.as_ref());
+        crate::util::check_database_url(diags, database_url);
+        if let Some(source) = config.source.as_ref_option() {
+            if let Err(e) = crate::backup::Destination::parse(source) {
+                diags.error_short(format!("Invalid source: {e}"), AttributePath::new("source"));
+            }
+        }
+        crate::masking::check_masking(diags, masking(&config).as_ref());
+        if config.create_database.unwrap_or(false)
+            && database_url.is_some_and(|url| crate::backup::maintenance_url(url).is_none())
+        {
+            diags.error(
+                "create_database needs a database name",
+                "database_url must name the database to create, e.g. postgres://host/preview_42",
+                AttributePath::new("database_url"),
+            );
+        }
+        Some(())
+    }
+
+    async fn read<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        state: Self::State<'a>,
+        private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        Some((state, private_state))
+    }
+
+    async fn plan_create<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let mut state = proposed_state;
+        state.id = Value::Unknown;
+        state.source_checksum = Value::Unknown;
+        state.restored_at = Value::Unknown;
+        Some((state, Default::default()))
+    }
+
+    async fn plan_update<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
+        let requires_replace = changed_inputs(&prior_state, &proposed_state)
+            .into_iter()
+            .map(AttributePath::new)
+            .collect();
+
+        let mut state = proposed_state;
+        state.id = prior_state.id;
+        state.source_checksum = prior_state.source_checksum;
+        state.restored_at = prior_state.restored_at;
+        Some((state, prior_private_state, requires_replace))
+    }
+
+    async fn plan_destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::PrivateState<'a>> {
+        Some(prior_private_state)
+    }
+
+    async fn create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let started = std::time::SystemTime::now();
+        let restored = self.restore(diags, planned_state).await;
+        self.telemetry
+            .record("pgmold_restore", "create", started, restored.is_some())
+            .await;
+        Some((restored?, planned_private_state))
+    }
+
+    async fn update<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        // Every input forces replacement, so there is nothing to update
+        Some((planned_state, planned_private_state))
+    }
+
+    /// Drops the database if this resource created it; otherwise the restored
+    /// data is left in place.
+    async fn destroy<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        if !prior_state.create_database.unwrap_or(false) {
+            return Some(());
+        }
+        if let Err(e) = self
+            .drop_database(diags, prior_state.database_url.as_str())
+            .await?
+        {
+            diags.root_error_short(format!("Failed to drop database: {e}"));
+            return None;
+        }
+        Some(())
+    }
+}
+
+impl RestoreResource {
+    async fn restore<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        state: RestoreResourceState<'a>,
+    ) -> Option<RestoreResourceState<'a>> {
+        let database_url = state.database_url.as_str();
+        let create_database = state.create_database.unwrap_or(false);
+        if create_database {
+            if let Err(e) = self.create_database(diags, database_url).await? {
+                diags.root_error_short(format!("Failed to create database: {e}"));
+                return None;
+            }
+        }
+
+        let options = RestoreOptions {
+            clean: state.clean.unwrap_or(false),
+            no_owner: state.no_owner.unwrap_or(false),
+            pg_restore: state
+                .pg_restore_path
+                .as_ref_option()
+                .map_or("pg_restore".to_string(), |p| p.to_string()),
+            psql: state
+                .psql_path
+                .as_ref_option()
+                .map_or("psql".to_string(), |p| p.to_string()),
+        };
+        tracing::info!(
+            resource = "pgmold_restore",
+            source = state.source.as_str(),
+            "restoring dump"
+        );
+        let restored = match crate::backup::restore(
+            database_url,
+            state.source.as_str(),
+            state.checksum.as_ref_option().map(|c| c.as_ref()),
+            &options,
+        )
+        .await
+        {
+            Ok(checksum) => self
+                .mask(diags, &state)
+                .await
+                .map_err(|e| anyhow::anyhow!("masking failed: {e}"))
+                .map(|()| checksum),
+            Err(e) => Err(e),
+        };
+        let checksum = match restored {
+            Ok(checksum) => checksum,
+            Err(e) => {
+                // A half-restored database would make the next create fail on CREATE DATABASE
+                if create_database {
+                    if let Some(Err(drop_error)) = self.drop_database(diags, database_url).await {
+                        tracing::warn!(error = %drop_error, "failed to drop database after failed restore");
+                    }
+                }
+                diags.root_error_short(format!("Restore failed: {e}"));
+                return None;
+            }
+        };
+
+        let mut state = state;
+        state.id = Value::Value(Cow::Owned(format!("pgmold-restore-{}", &checksum[..12])));
+        state.source_checksum = Value::Value(Cow::Owned(checksum));
+        state.restored_at = Value::Value(Cow::Owned(crate::clock::now().to_rfc3339()));
+        Some(state)
+    }
+
+    /// Applies `masking` to the restored data in one transaction.
+    async fn mask(
+        &self,
+        diags: &mut Diagnostics,
+        state: &RestoreResourceState<'_>,
+    ) -> anyhow::Result<()> {
+        let Some(masking) = masking(state) else {
+            return Ok(());
+        };
+        let rules = crate::masking::parse_rules(&masking).map_err(anyhow::Error::msg)?;
+        let Some(connection) = self
+            .connections
+            .connect(diags, state.database_url.as_str())
+            .await
+        else {
+            anyhow::bail!("could not connect to the restored database");
+        };
+        let statements = crate::masking::update_statements(&rules);
+        crate::execute::execute_statements(
+            connection.pool(),
+            &statements,
+            &crate::execute::ExecuteOptions::default(),
+        )
+        .await
+    }
+
+    /// Runs `statement` for the database named by `database_url` against the
+    /// server's `postgres` database. `None` when connecting failed, which is
+    /// already reported.
+    async fn on_maintenance_database(
+        &self,
+        diags: &mut Diagnostics,
+        database_url: &str,
+        statement: impl FnOnce(&str) -> String,
+    ) -> Option<sqlx::Result<()>> {
+        let Some((maintenance_url, name)) = crate::backup::maintenance_url(database_url) else {
+            diags.error_short(
+                "database_url doesn't name a database",
+                AttributePath::new("database_url"),
+            );
+            return None;
+        };
+        let connection = self.connections.connect(diags, &maintenance_url).await?;
+        let sql = statement(&quote_ident(&name));
+        Some(
+            sqlx::raw_sql(&sql)
+                .execute(connection.pool())
+                .await
+                .map(|_| ()),
+        )
+    }
+
+    async fn create_database(
+        &self,
+        diags: &mut Diagnostics,
+        database_url: &str,
+    ) -> Option<sqlx::Result<()>> {
+        self.on_maintenance_database(diags, database_url, |name| {
+            format!("CREATE DATABASE {name}")
+        })
+        .await
+    }
+
+    async fn drop_database(
+        &self,
+        diags: &mut Diagnostics,
+        database_url: &str,
+    ) -> Option<sqlx::Result<()>> {
+        // FORCE disconnects sessions still using the database (PostgreSQL 13+)
+        self.on_maintenance_database(diags, database_url, |name| {
+            format!("DROP DATABASE IF EXISTS {name} WITH (FORCE)")
+        })
+        .await
+    }
+}
+
+fn masking(state: &RestoreResourceState) -> Option<BTreeMap<String, String>> {
+    let masking = state.masking.as_ref_option()?;
+    Some(
+        masking
+            .iter()
+            .filter_map(|(key, strategy)| {
+                Some((key.to_string(), strategy.as_ref_option()?.to_string()))
+            })
+            .collect(),
+    )
+}
+
+fn changed_inputs(
+    prior: &RestoreResourceState,
+    proposed: &RestoreResourceState,
+) -> Vec<&'static str> {
+    let changed = [
+        prior.database_url != proposed.database_url,
+        prior.source != proposed.source,
+        prior.checksum != proposed.checksum,
+        prior.create_database != proposed.create_database,
+        prior.clean != proposed.clean,
+        prior.no_owner != proposed.no_owner,
+        prior.pg_restore_path != proposed.pg_restore_path,
+        prior.psql_path != proposed.psql_path,
+        prior.masking != proposed.masking,
+        prior.triggers != proposed.triggers,
+    ];
+    INPUTS
+        .into_iter()
+        .zip(changed)
+        .filter_map(|(name, changed)| changed.then_some(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_inputs_lists_attributes_forcing_a_new_restore() {
+        let prior = RestoreResourceState {
+            source: Value::Value(Cow::Borrowed("s3://backups/app.dump")),
+            ..Default::default()
+        };
+        let mut proposed = prior.clone();
+        assert!(changed_inputs(&prior, &proposed).is_empty());
+
+        proposed.source = Value::Value(Cow::Borrowed("s3://backups/app-2.dump"));
+        assert_eq!(changed_inputs(&prior, &proposed), vec!["source"]);
+    }
+
+    #[tokio::test]
+    async fn validate_requires_database_name_to_create() {
+        let config = RestoreResourceState {
+            database_url: Value::Value(Cow::Borrowed("postgres://db:5432")),
+            source: Value::Value(Cow::Borrowed("backups/app.dump")),
+            create_database: Value::Value(true),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        RestoreResource::default()
+            .validate(&mut diags, config)
+            .await;
+
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("database_url")
+        );
+    }
+}
@@ -5,35 +5,193 @@ use serde::{Deserialize, Serialize};
 use tf_provider::{
     map,
     schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
-    value::{Value, ValueBool, ValueEmpty, ValueList, ValueNumber, ValueString},
+    value::{Value, ValueBool, ValueEmpty, ValueList, ValueMap, ValueNumber, ValueString},
     AttributePath, Diagnostics, Resource,
 };
+use tracing::Instrument;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SchemaResourceState<'a> {
     #[serde(borrow)]
     pub id: ValueString<'a>,
     #[serde(borrow)]
+    pub name: ValueString<'a>,
+    #[serde(borrow)]
     pub schema_file: ValueString<'a>,
     #[serde(borrow)]
+    pub base_dir: ValueString<'a>,
+    #[serde(borrow)]
     pub database_url: ValueString<'a>,
     #[serde(borrow)]
+    pub database_url_env: ValueString<'a>,
+    #[serde(borrow)]
+    pub introspection_url: ValueString<'a>,
+    #[serde(borrow)]
     pub target_schemas: ValueList<ValueString<'a>>,
+    #[serde(borrow)]
+    pub object_patterns: ValueList<ValueString<'a>>,
     pub allow_destructive: ValueBool,
+    pub allow_empty: ValueBool,
     pub zero_downtime: ValueBool,
+    pub batch_statements: ValueBool,
+    pub apply_chunk_size: ValueNumber,
+    pub concurrent_index_retries: ValueNumber,
+    #[serde(borrow)]
+    pub continue_on: ValueList<ValueString<'a>>,
+    pub resume_interrupted_apply: ValueBool,
+    #[serde(borrow)]
+    pub rewrite_strategy: ValueString<'a>,
+    pub rewrite_batch_pages: ValueNumber,
+    #[serde(borrow)]
+    pub rewrite_cutover_lock_timeout: ValueString<'a>,
+    pub rewrite_cutover_retries: ValueNumber,
+    pub plan_with_database: ValueBool,
+    pub auto_remediate_drift: ValueBool,
+    pub declared_kinds_only: ValueBool,
+    pub skip_unreadable_schemas: ValueBool,
+    pub create_missing_schemas: ValueBool,
+    pub rebuild_enums: ValueBool,
+    pub defer_constraint_validation: ValueBool,
+    pub preserve_sequences: ValueBool,
+    pub preserve_data: ValueBool,
+    pub normalize_expressions: ValueBool,
+    #[serde(borrow)]
+    pub owner: ValueString<'a>,
+    #[serde(borrow)]
+    pub migration_history: ValueString<'a>,
+    #[serde(borrow)]
+    pub dialect: ValueString<'a>,
+    pub min_server_version: ValueNumber,
+    pub max_server_version: ValueNumber,
+    #[serde(borrow)]
+    pub execution_log_path: ValueString<'a>,
+    #[serde(borrow)]
+    pub plan_output_path: ValueString<'a>,
+    #[serde(borrow)]
+    pub docs_output_dir: ValueString<'a>,
+    #[serde(borrow)]
+    pub maintenance_window_cron: ValueString<'a>,
+    #[serde(borrow)]
+    pub maintenance_window_duration: ValueString<'a>,
+    #[serde(borrow)]
+    pub maintenance_window_start: ValueString<'a>,
+    #[serde(borrow)]
+    pub maintenance_window_end: ValueString<'a>,
+    #[serde(borrow)]
+    pub maintenance_window_timezone: ValueString<'a>,
+    pub maintenance_window_wait: ValueBool,
+    #[serde(borrow)]
+    pub destructive_approval_token: ValueString<'a>,
+    #[serde(borrow)]
+    pub approved_plan_signature: ValueString<'a>,
     #[serde(borrow)]
     pub schema_hash: ValueString<'a>,
     #[serde(borrow)]
     pub applied_at: ValueString<'a>,
     pub migration_count: ValueNumber,
+    #[serde(borrow)]
+    pub operation_counts: ValueMap<'a, ValueNumber>,
+    pub lint_results: ValueList<Value<crate::pipeline::LintEntry>>,
+    #[serde(borrow)]
+    pub destructive_operations_hash: ValueString<'a>,
+    #[serde(borrow)]
+    pub connection_fingerprint: ValueString<'a>,
+    #[serde(borrow)]
+    pub managed_objects: ValueList<ValueString<'a>>,
+    #[serde(borrow)]
+    pub database_fingerprint: ValueString<'a>,
+    #[serde(borrow)]
+    pub table_ddl: ValueMap<'a, ValueString<'a>>,
+}
+
+/// Catalog fingerprints and a short history recorded after each apply.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SchemaPrivateState {
+    pub schema_hash: String,
+    /// `None` when the dialect doesn't support fingerprints or recording them failed.
+    pub fingerprints: Option<crate::fingerprint::Fingerprints>,
+    #[serde(default)]
+    pub history: Vec<ApplyRecord>,
+    /// Set when the last apply failed after committing part of its statements.
+    #[serde(default)]
+    pub interrupted: Option<InterruptedApply>,
+    /// The last apply that ran statements, which later plans are diffed against.
+    #[serde(default)]
+    pub last_sql: Option<AppliedSql>,
+}
+
+/// Statements an apply ran, with their [`statements_hash`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct AppliedSql {
+    pub hash: String,
+    pub statements: Vec<String>,
 }
 
-pub struct SchemaResource;
+/// How far a failed apply got. The committed statements aren't rerun: the
+/// next apply diffs against the database again, which now includes them, or
+/// with `resume_interrupted_apply` runs the recorded statements after the
+/// `committed` cursor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct InterruptedApply {
+    pub schema_hash: String,
+    pub failed_at: String,
+    pub committed: usize,
+    pub statements: usize,
+    /// The plan being executed, and [`statements_hash`] of it to catch a
+    /// corrupted or edited private state before resuming.
+    #[serde(default)]
+    pub plan_statements: Vec<String>,
+    #[serde(default)]
+    pub plan_hash: String,
+    #[serde(default)]
+    pub operations: Vec<String>,
+    #[serde(default)]
+    pub destructive_hash: Option<String>,
+}
+
+impl InterruptedApply {
+    /// Whether the recorded plan is intact and has statements left to run.
+    fn resumable(&self) -> bool {
+        self.committed < self.plan_statements.len()
+            && self.plan_hash == statements_hash(&self.plan_statements)
+    }
+}
+
+fn statements_hash(statements: &[String]) -> String {
+    crate::util::compute_content_hash(&statements.join(";\n"))
+}
+
+/// One apply, most recent last in [`SchemaPrivateState::history`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ApplyRecord {
+    pub schema_hash: String,
+    pub applied_at: String,
+    /// Operation kinds, e.g. `CreateTable`.
+    pub operations: Vec<String>,
+    /// Milliseconds per phase, see [`crate::pipeline::PhaseTimings`].
+    #[serde(default)]
+    pub timings: crate::pipeline::PhaseTimings,
+    /// See [`crate::operations::destructive_hash`].
+    #[serde(default)]
+    pub destructive_hash: Option<String>,
+}
+
+/// Applies kept in private state; older records are dropped.
+const MAX_APPLY_HISTORY: usize = 10;
+
+#[derive(Debug, Default, Clone)]
+pub struct SchemaResource {
+    pub apply_limiter: crate::throttle::ApplyLimiter,
+    pub connections: crate::connections::ConnectionCache,
+    pub telemetry: crate::telemetry::Telemetry,
+    pub claims: crate::claims::ClaimRegistry,
+}
 
 #[async_trait]
 impl Resource for SchemaResource {
     type State<'a> = SchemaResourceState<'a>;
-    type PrivateState<'a> = ValueEmpty;
+    // Optional because private state is empty for resources created before fingerprints existed
+    type PrivateState<'a> = Option<SchemaPrivateState>;
     type ProviderMetaState<'a> = ValueEmpty;
 
     fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
@@ -49,21 +207,52 @@ impl Resource for SchemaResource {
                         constraint: AttributeConstraint::Computed,
                         ..Default::default()
                     },
+                    "name" => Attribute {
+                        description: Description::plain("Stable name used as the resource id. Defaults to an id derived from the database and target_schemas"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
                     "schema_file" => Attribute {
                         description: Description::plain("Path to SQL schema file"),
                         attr_type: AttributeType::String,
                         constraint: AttributeConstraint::Required,
                         ..Default::default()
                     },
+                    "base_dir" => Attribute {
+                        description: Description::plain("Directory relative schema_file paths resolve against, e.g. path.module (default: working directory)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
                     "database_url" => Attribute {
-                        description: Description::plain("PostgreSQL connection URL"),
+                        description: Description::plain("PostgreSQL connection URL (default: the provider's database_url, unless database_url_env is set)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::OptionalComputed,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "database_url_env" => Attribute {
+                        description: Description::plain("Environment variable holding the connection URL, read during plan and apply, so credentials never reach state. Conflicts with database_url"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "introspection_url" => Attribute {
+                        description: Description::plain("Connection URL of a read replica used for plan and refresh introspection. DDL, and the diff it runs, still use database_url"),
                         attr_type: AttributeType::String,
                         constraint: AttributeConstraint::Optional,
                         sensitive: true,
                         ..Default::default()
                     },
                     "target_schemas" => Attribute {
-                        description: Description::plain("PostgreSQL schemas to manage"),
+                        description: Description::plain("PostgreSQL schemas to manage (default: the provider's target_schemas, or public)"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::OptionalComputed,
+                        ..Default::default()
+                    },
+                    "object_patterns" => Attribute {
+                        description: Description::plain("schema.name patterns, with * and ? wildcards, limiting the objects in target_schemas this resource creates, changes and drops, e.g. billing.* or public.order_*"),
                         attr_type: AttributeType::List(Box::new(AttributeType::String)),
                         constraint: AttributeConstraint::Optional,
                         ..Default::default()
@@ -74,12 +263,228 @@ impl Resource for SchemaResource {
                         constraint: AttributeConstraint::Optional,
                         ..Default::default()
                     },
+                    "allow_empty" => Attribute {
+                        description: Description::plain("Allow a schema file that declares no objects, which drops everything in target_schemas (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
                     "zero_downtime" => Attribute {
                         description: Description::plain("Use expand/contract pattern"),
                         attr_type: AttributeType::Bool,
                         constraint: AttributeConstraint::Optional,
                         ..Default::default()
                     },
+                    "batch_statements" => Attribute {
+                        description: Description::plain("Send generated statements in batched round trips"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "apply_chunk_size" => Attribute {
+                        description: Description::plain("Commit every this many statements, so a failed apply keeps its progress and the next one continues from there"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "concurrent_index_retries" => Attribute {
+                        description: Description::plain("Times a failed CREATE INDEX CONCURRENTLY is retried after dropping the invalid index it left; the invalid index is dropped even without retries (default: 0)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "continue_on" => Attribute {
+                        description: Description::plain("Errors, as SQLSTATE codes or condition names such as undefined_file for a missing extension, with which a transactional statement is rolled back to a savepoint and skipped with a warning while the rest of the migration commits"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "resume_interrupted_apply" => Attribute {
+                        description: Description::plain("After an apply fails part way, run the rest of its recorded statements instead of diffing again (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "rewrite_strategy" => Attribute {
+                        description: Description::plain("How column type changes are applied: in_place or shadow_table (default: in_place)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "rewrite_batch_pages" => Attribute {
+                        description: Description::plain("Heap pages copied per batch into a shadow table (default: 1000)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "rewrite_cutover_lock_timeout" => Attribute {
+                        description: Description::plain("lock_timeout for swapping in a shadow table, e.g. 5s (default: 5s)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "rewrite_cutover_retries" => Attribute {
+                        description: Description::plain("Attempts at swapping in a shadow table when the lock times out (default: 3)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "plan_with_database" => Attribute {
+                        description: Description::plain("Connect during plan to diff and lint (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "auto_remediate_drift" => Attribute {
+                        description: Description::plain("Check the database for drift on every refresh and revert it on the next apply, even without plan_with_database (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "declared_kinds_only" => Attribute {
+                        description: Description::plain("Leave object kinds the schema file doesn't declare, e.g. functions and triggers when it declares only tables, out of the diff, so they are never changed or dropped. A filter on the diff: introspection still reads every kind (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "create_missing_schemas" => Attribute {
+                        description: Description::plain("Create target_schemas that don't exist during apply instead of failing (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "rebuild_enums" => Attribute {
+                        description: Description::plain("Replace enum types that lose or reorder values, converting the columns using them, instead of failing the plan. Added values never need this (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "defer_constraint_validation" => Attribute {
+                        description: Description::plain("Add foreign keys and check constraints to existing tables NOT VALID and validate them in separate statements at the end of the apply, so the table scan doesn't block writes (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "preserve_sequences" => Attribute {
+                        description: Description::plain("When the plan drops a table and creates it again, restore the values of its serial and identity sequences afterwards (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "preserve_data" => Attribute {
+                        description: Description::plain("When the plan drops a table and creates it again, copy its rows aside and back into the columns both versions share; implies preserve_sequences (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "normalize_expressions" => Attribute {
+                        description: Description::plain("Compare generated column expressions, defaults, index expressions and partial index predicates ignoring case, whitespace, casts and redundant parentheses, so the form PostgreSQL stores them in doesn't show up as a change on every plan (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "owner" => Attribute {
+                        description: Description::plain("Role that owns target_schemas and their objects after each apply, whichever role created them"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "migration_history" => Attribute {
+                        description: Description::plain("Migration runner whose history table is checked for out-of-band migrations: flyway, liquibase or sqlx"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "skip_unreadable_schemas" => Attribute {
+                        description: Description::plain("Skip target schemas the role can't introspect, with a warning, instead of failing (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "dialect" => Attribute {
+                        description: Description::plain("Target engine: postgres, cockroachdb or aurora (default: postgres)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "min_server_version" => Attribute {
+                        description: Description::plain("Lowest server_version_num (e.g. 150000) the schema file supports, checked at apply time"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "max_server_version" => Attribute {
+                        description: Description::plain("Highest server_version_num the schema file supports, checked at apply time"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "execution_log_path" => Attribute {
+                        description: Description::plain("File that each create and update appends a JSON line to, with operations, SQL, lint results, phase timings and outcome"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "plan_output_path" => Attribute {
+                        description: Description::plain("File that plan overwrites with the pending operations, SQL, lint results and destructive flags as JSON, for approval tooling. Requires plan_with_database"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "docs_output_dir" => Attribute {
+                        description: Description::plain("Directory each apply writes schema.md (tables, columns, keys, indexes, enums) and schema.mmd (Mermaid ER diagram) into, rendered from schema_file"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "maintenance_window_cron" => Attribute {
+                        description: Description::plain("Five-field cron schedule opening a recurring maintenance window, e.g. `0 22 * * mon-fri`. Applies outside the window fail. Requires maintenance_window_duration"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "maintenance_window_duration" => Attribute {
+                        description: Description::plain("How long each window opened by maintenance_window_cron lasts, e.g. 30min, 4h or 1d2h"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "maintenance_window_start" => Attribute {
+                        description: Description::plain("Start of a single maintenance window, RFC 3339 or a local time in maintenance_window_timezone. Requires maintenance_window_end"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "maintenance_window_end" => Attribute {
+                        description: Description::plain("End of the single maintenance window started by maintenance_window_start"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "maintenance_window_timezone" => Attribute {
+                        description: Description::plain("IANA timezone the maintenance window schedule and local timestamps are read in (default: UTC)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "maintenance_window_wait" => Attribute {
+                        description: Description::plain("Make applies outside the maintenance window wait for it to open instead of failing (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "destructive_approval_token" => Attribute {
+                        description: Description::plain("Acknowledges destructive operations: when set, apply only drops objects if this equals destructive_operations_hash"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "approved_plan_signature" => Attribute {
+                        description: Description::plain("Signature from a reviewed plan_output_path document: when set, apply only runs SQL whose signature under the provider's plan_signing_key matches"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
                     "schema_hash" => Attribute {
                         description: Description::plain("SHA256 hash of schema file"),
                         attr_type: AttributeType::String,
@@ -97,6 +502,48 @@ impl Resource for SchemaResource {
                         attr_type: AttributeType::Number,
                         constraint: AttributeConstraint::Computed,
                         ..Default::default()
+                    },
+                    "operation_counts" => Attribute {
+                        description: Description::plain("Number of operations applied per kind, e.g. create_table"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::Number)),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "connection_fingerprint" => Attribute {
+                        description: Description::plain("Hash of the target database's host, port and name, without credentials"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "managed_objects" => Attribute {
+                        description: Description::plain("Tables, views, functions, sequences, enums and domains the schema file declares within target_schemas and object_patterns, as schema.name"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "table_ddl" => Attribute {
+                        description: Description::plain("Canonical CREATE statements of each table the schema file declares within target_schemas and object_patterns, with its indexes and constraints, keyed by schema.table"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "database_fingerprint" => Attribute {
+                        description: Description::plain("Hash of the canonical SQL of target_schemas as introspected from the database on the last refresh or apply, as pgmold_schema_dump computes it. Requires plan_with_database or auto_remediate_drift, null otherwise"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "destructive_operations_hash" => Attribute {
+                        description: Description::plain("Hash of the pending destructive operations, null if there are none. Known at plan time with plan_with_database"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "lint_results" => Attribute {
+                        description: Description::plain("Lint checks that fired during the last apply, with rule, severity, message and object"),
+                        attr_type: AttributeType::List(Box::new(crate::pipeline::LintEntry::attribute_type())),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
                     }
                 },
                 ..Default::default()
@@ -104,54 +551,258 @@ impl Resource for SchemaResource {
         })
     }
 
-    async fn read<'a>(
-        &self,
-        _diags: &mut Diagnostics,
-        state: Self::State<'a>,
-        private_state: Self::PrivateState<'a>,
-        _provider_meta_state: Self::ProviderMetaState<'a>,
-    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
-        Some((state, private_state))
-    }
-
-    async fn plan_create<'a>(
-        &self,
-        diags: &mut Diagnostics,
-        proposed_state: Self::State<'a>,
-        _config_state: Self::State<'a>,
-        _provider_meta_state: Self::ProviderMetaState<'a>,
-    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
-        if proposed_state.database_url.is_null() {
-            diags.root_error_short(
-                "database_url is required (either at resource or provider level)",
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        let dialect = config.dialect.as_ref_option().map(|d| d.as_ref());
+        crate::util::check_database_url(
+            diags,
+            config.database_url.as_ref_option().map(|u| u.as_ref()),
+        );
+        if config.database_url.is_value() && config.database_url_env.is_value() {
+            diags.error(
+                "database_url conflicts with database_url_env",
+                "Set the connection URL either in configuration or through an environment variable, not both.",
+                AttributePath::new("database_url_env"),
             );
-            return None;
         }
+        check_introspection_url(
+            diags,
+            config.introspection_url.as_ref_option().map(|u| u.as_ref()),
+        );
+        crate::dialect::check_dialect(diags, dialect);
+        crate::history::check_migration_history(
+            diags,
+            config.migration_history.as_ref_option().map(|h| h.as_ref()),
+        );
+        crate::pipeline::check_version_range(
+            diags,
+            config.min_server_version.as_ref_option().copied(),
+            config.max_server_version.as_ref_option().copied(),
+        );
 
-        let schema_file_str = proposed_state.schema_file.as_str();
-        let schema_path = std::path::Path::new(schema_file_str);
-        if !schema_path.exists() {
-            diags.root_error_short(format!("schema_file not found: {schema_file_str}"));
-            return None;
+        // cockroachdb runs every statement on its own, so there is nothing to batch
+        if config.batch_statements.unwrap_or(false)
+            && dialect
+                .and_then(crate::dialect::Dialect::parse)
+                .is_some_and(|d| !d.transactional_ddl())
+        {
+            diags.error(
+                "batch_statements conflicts with dialect",
+                "Engines without transactional DDL execute statements one at a time. Remove batch_statements or change dialect.",
+                AttributePath::new("batch_statements"),
+            );
+        }
+        if config
+            .apply_chunk_size
+            .as_ref_option()
+            .is_some_and(|&n| n < 1)
+        {
+            diags.error_short(
+                "apply_chunk_size must be at least 1",
+                AttributePath::new("apply_chunk_size"),
+            );
+        }
+        crate::execute::check_concurrent_index_retries(
+            diags,
+            config.concurrent_index_retries.as_ref_option().copied(),
+        );
+        if let Err(e) = crate::execute::parse_continue_on(&continue_on(&config)) {
+            diags.error_short(e, AttributePath::new("continue_on"));
+        }
+        let rewrite_strategy = config.rewrite_strategy.as_ref_option().map(|s| s.as_ref());
+        crate::rebuild::check_rewrite_strategy(diags, rewrite_strategy);
+        crate::rebuild::check_lock_timeout(
+            diags,
+            config
+                .rewrite_cutover_lock_timeout
+                .as_ref_option()
+                .map(|t| t.as_ref()),
+        );
+        crate::window::check_window(diags, window_settings(&config));
+        if let Err(e) = crate::object_filter::ObjectFilter::parse(&object_patterns(&config)) {
+            diags.error_short(e, AttributePath::new("object_patterns"));
+        }
+        // Rebuilds rely on triggers and pg_catalog details only PostgreSQL engines share
+        if rewrite_strategy.and_then(crate::rebuild::RewriteStrategy::parse)
+            == Some(crate::rebuild::RewriteStrategy::ShadowTable)
+            && dialect
+                .and_then(crate::dialect::Dialect::parse)
+                .is_some_and(|d| !d.transactional_ddl())
+        {
+            diags.error(
+                "rewrite_strategy conflicts with dialect",
+                "Shadow table rebuilds need transactional DDL. Remove rewrite_strategy or change dialect.",
+                AttributePath::new("rewrite_strategy"),
+            );
+        }
+        if config.migration_history.is_value()
+            && matches!(config.plan_with_database, Value::Null | Value::Value(false))
+        {
+            diags.warning(
+                "migration_history has no effect",
+                "The history table is only checked when plan_with_database is enabled.",
+                AttributePath::new("migration_history"),
+            );
+        }
+        if config.plan_output_path.is_value()
+            && matches!(config.plan_with_database, Value::Null | Value::Value(false))
+        {
+            diags.warning(
+                "plan_output_path has no effect",
+                "The plan is only computed, and written, when plan_with_database is enabled.",
+                AttributePath::new("plan_output_path"),
+            );
         }
 
-        let schema_hash = match crate::util::compute_schema_hash(schema_path) {
-            Ok(h) => h,
-            Err(e) => {
-                diags.root_error_short(format!("Failed to read schema file: {e}"));
-                return None;
+        Some(())
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        state: Self::State<'a>,
+        private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let mut state = state;
+
+        // Ids used to be derived from the schema file's canonical path; move
+        // existing state over on refresh so plans don't show an id change
+        if let id @ Value::Value(_) = resource_id(&state) {
+            state.id = id;
+        }
+
+        // schema_hash records the file that was last applied. A file edited since
+        // nulls it, like drift does, so the refresh shows the pending apply. A file
+        // that can't be read is left to plan to report.
+        if let Value::Value(applied) = &state.schema_hash {
+            let path = schema_file_path(&state);
+            if let Ok(current) = crate::util::compute_schema_hash(std::path::Path::new(&path)) {
+                if current != applied.as_ref() {
+                    state.schema_hash = Value::Null;
+                    diags.warning_short(
+                        "schema_file changed since the last apply",
+                        AttributePath::new("schema_file"),
+                    );
+                }
             }
-        };
+        }
+
+        // Live drift is only visible by asking the database, which mock mode
+        // doesn't have.
+        let plan_with_database = state.plan_with_database.unwrap_or(false);
+        let auto_remediate = state.auto_remediate_drift.unwrap_or(false);
+        let live = (plan_with_database || auto_remediate)
+            && database_url(&state).is_value()
+            && self.connections.snapshot().await.is_none();
+        if live && self.drifted(diags, &state, private_state.as_ref()).await? {
+            // A null hash never matches the schema file, so plan_update schedules
+            // an apply that diffs and lints like any other
+            state.schema_hash = Value::Null;
+            if auto_remediate {
+                diags.warning(
+                    "Schema drift detected",
+                    "The database no longer matches the last applied schema. The next apply reverts the changes made outside Terraform.",
+                    AttributePath::new("auto_remediate_drift"),
+                );
+            }
+        }
 
-        let path_hash = crate::util::compute_path_hash(schema_path);
-        let id = format!("pgmold-{}", &path_hash[..8]);
+        if live && state.schema_hash.is_value() && self.ownership_drifted(diags, &state).await? {
+            state.schema_hash = Value::Null;
+            diags.warning(
+                "Ownership drift detected",
+                "Objects in target_schemas are owned by roles other than owner. The next apply reassigns them.",
+                AttributePath::new("owner"),
+            );
+        }
+
+        if live && plan_with_database {
+            self.warn_external_migrations(diags, &state).await?;
+        }
+
+        state.database_fingerprint = self.database_fingerprint(diags, &state).await?;
+
+        Some((state, private_state))
+    }
+
+    async fn plan_create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        proposed_state: Self::State<'a>,
+        config_state: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let mut proposed_state = proposed_state;
+        self.inherit_provider_defaults(&mut proposed_state, &config_state)
+            .await;
+        check_database_url_set(diags, &proposed_state)?;
+
+        let schema_file = schema_file_path(&proposed_state);
+        let schema_file_str = schema_file.as_str();
+        let schema_path = std::path::Path::new(schema_file_str);
+        if !schema_path.exists() {
+            diags.error_short(
+                format!("schema_file not found: {schema_file_str}"),
+                AttributePath::new("schema_file"),
+            );
+            return None;
+        }
+        let (schema, schema_hash) = crate::pipeline::load_schema_file(diags, schema_file_str)?;
+        crate::pipeline::check_schema_not_empty(
+            diags,
+            schema_file_str,
+            &schema,
+            proposed_state.allow_empty.unwrap_or(false),
+        )?;
+        crate::history::check_migration_history(
+            diags,
+            proposed_state
+                .migration_history
+                .as_ref_option()
+                .map(|m| m.as_ref()),
+        )?;
+        crate::pipeline::check_version_range(
+            diags,
+            proposed_state.min_server_version.as_ref_option().copied(),
+            proposed_state.max_server_version.as_ref_option().copied(),
+        )?;
+        crate::dialect::check_dialect(
+            diags,
+            proposed_state.dialect.as_ref_option().map(|d| d.as_ref()),
+        )?;
+
+        // Network access during plan is strictly opt-in so air-gapped plans keep working
+        let mut destructive_hash = Value::Unknown;
+        if proposed_state.plan_with_database.unwrap_or(false)
+            && database_url(&proposed_state).is_value()
+        {
+            let plan = self.plan_only(diags, &proposed_state, None).await?;
+            let key = self.connections.plan_signing_key().await;
+            write_plan_output(
+                diags,
+                &proposed_state,
+                &schema_hash,
+                Some(&plan),
+                None,
+                key.as_deref(),
+            )?;
+            destructive_hash =
+                optional_string(crate::operations::destructive_hash(&plan.operations));
+        }
 
         let mut state = proposed_state;
-        state.id = Value::Value(Cow::Owned(id));
+        state.id = resource_id(&state);
+        state.connection_fingerprint = connection_fingerprint(&state);
+        state.managed_objects = self.claim_objects(diags, &state, &schema).await?;
+        state.table_ddl = table_ddl(&state, &schema);
         state.schema_hash = Value::Value(Cow::Owned(schema_hash));
         // Mark computed fields as Unknown during plan so Terraform knows they'll be set during apply
         state.applied_at = Value::Unknown;
         state.migration_count = Value::Unknown;
+        state.operation_counts = Value::Unknown;
+        state.lint_results = Value::Unknown;
+        state.destructive_operations_hash = destructive_hash;
+        state.database_fingerprint = Value::Unknown;
 
         Some((state, Default::default()))
     }
@@ -159,38 +810,119 @@ impl Resource for SchemaResource {
     async fn plan_update<'a>(
         &self,
         diags: &mut Diagnostics,
-        _prior_state: Self::State<'a>,
+        prior_state: Self::State<'a>,
         proposed_state: Self::State<'a>,
-        _config_state: Self::State<'a>,
-        _prior_private_state: Self::PrivateState<'a>,
+        config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
         _provider_meta_state: Self::ProviderMetaState<'a>,
     ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
-        let schema_file_str = proposed_state.schema_file.as_str();
+        let mut proposed_state = proposed_state;
+        self.inherit_provider_defaults(&mut proposed_state, &config_state)
+            .await;
+        let schema_file = schema_file_path(&proposed_state);
+        let schema_file_str = schema_file.as_str();
         let schema_path = std::path::Path::new(schema_file_str);
         if !schema_path.exists() {
-            diags.root_error_short(format!("schema_file not found: {schema_file_str}"));
+            diags.error_short(
+                format!("schema_file not found: {schema_file_str}"),
+                AttributePath::new("schema_file"),
+            );
             return None;
         }
+        let (schema, schema_hash) = crate::pipeline::load_schema_file(diags, schema_file_str)?;
+        crate::pipeline::check_schema_not_empty(
+            diags,
+            schema_file_str,
+            &schema,
+            proposed_state.allow_empty.unwrap_or(false),
+        )?;
+        crate::history::check_migration_history(
+            diags,
+            proposed_state
+                .migration_history
+                .as_ref_option()
+                .map(|m| m.as_ref()),
+        )?;
+        crate::pipeline::check_version_range(
+            diags,
+            proposed_state.min_server_version.as_ref_option().copied(),
+            proposed_state.max_server_version.as_ref_option().copied(),
+        )?;
+        crate::dialect::check_dialect(
+            diags,
+            proposed_state.dialect.as_ref_option().map(|d| d.as_ref()),
+        )?;
 
-        let schema_hash = match crate::util::compute_schema_hash(schema_path) {
-            Ok(h) => h,
-            Err(e) => {
-                diags.root_error_short(format!("Failed to read schema file: {e}"));
-                return None;
-            }
-        };
+        if proposed_state.database_url_env.is_value() {
+            check_database_url_set(diags, &proposed_state)?;
+        }
+        let fingerprint = connection_fingerprint(&proposed_state);
 
-        let path_hash = crate::util::compute_path_hash(schema_path);
-        let id = format!("pgmold-{}", &path_hash[..8]);
+        // With database_url_env only the fingerprint shows the target moved
+        let mut needs_apply = prior_state.schema_hash.as_str() != schema_hash
+            || prior_state.schema_hash.is_null()
+            || prior_state.database_url != proposed_state.database_url
+            || (prior_state.connection_fingerprint.is_value()
+                && prior_state.connection_fingerprint != fingerprint)
+            || prior_state.target_schemas != proposed_state.target_schemas
+            || prior_state.object_patterns != proposed_state.object_patterns
+            || prior_state.owner != proposed_state.owner
+            || prior_state.docs_output_dir != proposed_state.docs_output_dir;
+
+        // Network access during plan is strictly opt-in so air-gapped plans keep working
+        let mut destructive_hash = Value::Unknown;
+        if proposed_state.plan_with_database.unwrap_or(false)
+            && database_url(&proposed_state).is_value()
+        {
+            let (pending, hash) = self
+                .plan_changed_objects(
+                    diags,
+                    &proposed_state,
+                    &schema_hash,
+                    prior_private_state.as_ref(),
+                )
+                .await?;
+            needs_apply |= pending;
+            destructive_hash = optional_string(hash);
+        }
+
+        // Objects in schemas dropped from target_schemas would otherwise be left
+        // behind unmanaged
+        let mut requires_replace = vec![];
+        if prior_state.target_schemas != proposed_state.target_schemas {
+            requires_replace.push(AttributePath::new("target_schemas"));
+        }
 
         let mut state = proposed_state;
-        state.id = Value::Value(Cow::Owned(id));
+        state.id = resource_id(&state);
+        state.connection_fingerprint = fingerprint;
+        state.managed_objects = self.claim_objects(diags, &state, &schema).await?;
+        state.table_ddl = table_ddl(&state, &schema);
         state.schema_hash = Value::Value(Cow::Owned(schema_hash));
-        // Mark computed fields as Unknown during plan so Terraform knows they'll be set during apply
-        state.applied_at = Value::Unknown;
-        state.migration_count = Value::Unknown;
+        if needs_apply {
+            if let Some(last) = prior_private_state.as_ref().and_then(|p| p.history.last()) {
+                diags.root_warning_short(format!(
+                    "Last apply ran {} operations at {}",
+                    last.operations.len(),
+                    last.applied_at
+                ));
+            }
+            // Mark computed fields as Unknown during plan so Terraform knows they'll be set during apply
+            state.applied_at = Value::Unknown;
+            state.migration_count = Value::Unknown;
+            state.operation_counts = Value::Unknown;
+            state.lint_results = Value::Unknown;
+            state.destructive_operations_hash = destructive_hash;
+            state.database_fingerprint = Value::Unknown;
+        } else {
+            state.applied_at = prior_state.applied_at;
+            state.migration_count = prior_state.migration_count;
+            state.operation_counts = prior_state.operation_counts;
+            state.lint_results = prior_state.lint_results;
+            state.destructive_operations_hash = prior_state.destructive_operations_hash;
+        }
 
-        Some((state, Default::default(), vec![]))
+        Some((state, prior_private_state, requires_replace))
     }
 
     async fn plan_destroy<'a>(
@@ -211,117 +943,1249 @@ impl Resource for SchemaResource {
         _planned_private_state: Self::PrivateState<'a>,
         _provider_meta_state: Self::ProviderMetaState<'a>,
     ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
-        let db_url = planned_state.database_url.as_str();
+        await_maintenance_window(diags, &planned_state).await?;
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let mut log = crate::execution_log::ExecutionLogEntry::new(
+            "pgmold_schema",
+            "create",
+            planned_state.id.as_str(),
+            planned_state.schema_hash.as_str(),
+        );
+        log.run = self.connections.run_metadata().await;
+        let mut interrupted = None;
+        let applied = self
+            .apply_schema(diags, &planned_state, &mut log, None, &mut interrupted)
+            .await;
+        self.telemetry
+            .record("pgmold_schema", "create", started, applied.is_some())
+            .await;
+        log.finish(diags, applied.is_some());
+        crate::execution_log::append(
+            diags,
+            planned_state
+                .execution_log_path
+                .as_ref_option()
+                .map(|p| p.as_ref()),
+            &log,
+        );
+        let Some((record, fingerprints)) = applied else {
+            let interrupted = interrupted?;
+            return Some(interrupted_state(planned_state, None, interrupted));
+        };
+
+        if !record.operations.is_empty() {
+            diags.root_warning_short(crate::operations::summarize_applied(&record.operations));
+        }
+
+        let mut state = planned_state;
+        state.applied_at = Value::Value(Cow::Owned(record.applied_at.clone()));
+        state.migration_count = Value::Value(record.operations.len() as i64);
+        state.operation_counts = operation_counts(&record.operations);
+        state.lint_results = Value::Value(log.lint_results.into_iter().map(Value::Value).collect());
+        if state.destructive_operations_hash.is_unknown() {
+            state.destructive_operations_hash = optional_string(record.destructive_hash.clone());
+        }
+        state.database_fingerprint = self.database_fingerprint(diags, &state).await?;
+
+        Some((
+            state,
+            Some(record_apply(None, record, fingerprints, &log.statements)),
+        ))
+    }
+
+    async fn update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        // Only non-schema settings changed; plan_update kept the prior apply results
+        if planned_state.applied_at.is_value() {
+            return Some((planned_state, planned_private_state));
+        }
+
+        await_maintenance_window(diags, &planned_state).await?;
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let mut log = crate::execution_log::ExecutionLogEntry::new(
+            "pgmold_schema",
+            "update",
+            planned_state.id.as_str(),
+            planned_state.schema_hash.as_str(),
+        );
+        log.run = self.connections.run_metadata().await;
+        let previous = planned_private_state
+            .as_ref()
+            .and_then(|p| p.interrupted.clone())
+            .filter(|i| i.schema_hash == planned_state.schema_hash.as_str());
+        let resume = previous
+            .clone()
+            .filter(|_| planned_state.resume_interrupted_apply.unwrap_or(false));
+        if let Some(previous) = previous.filter(|_| resume.is_none()) {
+            diags.root_warning_short(format!(
+                "Continuing the apply that failed at {}: {} of {} statements were already committed",
+                previous.failed_at, previous.committed, previous.statements
+            ));
+        }
+        let mut interrupted = None;
+        let applied = self
+            .apply_schema(
+                diags,
+                &planned_state,
+                &mut log,
+                resume.as_ref(),
+                &mut interrupted,
+            )
+            .await;
+        self.telemetry
+            .record("pgmold_schema", "update", started, applied.is_some())
+            .await;
+        log.finish(diags, applied.is_some());
+        crate::execution_log::append(
+            diags,
+            planned_state
+                .execution_log_path
+                .as_ref_option()
+                .map(|p| p.as_ref()),
+            &log,
+        );
+        let Some((record, fingerprints)) = applied else {
+            let interrupted = interrupted?;
+            return Some(interrupted_state(
+                planned_state,
+                planned_private_state,
+                interrupted,
+            ));
+        };
+
+        if !record.operations.is_empty() {
+            diags.root_warning_short(crate::operations::summarize_applied(&record.operations));
+        }
+
+        let mut state = planned_state;
+        state.applied_at = Value::Value(Cow::Owned(record.applied_at.clone()));
+        state.migration_count = Value::Value(record.operations.len() as i64);
+        state.operation_counts = operation_counts(&record.operations);
+        state.lint_results = Value::Value(log.lint_results.into_iter().map(Value::Value).collect());
+        if state.destructive_operations_hash.is_unknown() {
+            state.destructive_operations_hash = optional_string(record.destructive_hash.clone());
+        }
+        state.database_fingerprint = self.database_fingerprint(diags, &state).await?;
+
+        let private_state =
+            record_apply(planned_private_state, record, fingerprints, &log.statements);
+        Some((state, Some(private_state)))
+    }
+
+    async fn destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        Some(())
+    }
+}
+
+fn pending_changes(plan: &crate::pipeline::MigrationPlan) -> (bool, Option<String>) {
+    (
+        !plan.operations.is_empty(),
+        crate::operations::destructive_hash(&plan.operations),
+    )
+}
+
+fn optional_string<'a>(value: Option<String>) -> ValueString<'a> {
+    value.map_or(Value::Null, |v| Value::Value(Cow::Owned(v)))
+}
+
+/// With `destructive_approval_token` set, destructive operations only run when the
+/// token matches their hash, even if `allow_destructive` is on.
+fn check_destructive_approval(
+    diags: &mut Diagnostics,
+    state: &SchemaResourceState<'_>,
+    destructive_hash: Option<&str>,
+) -> Option<()> {
+    let (Value::Value(token), Some(hash)) = (&state.destructive_approval_token, destructive_hash)
+    else {
+        return Some(());
+    };
+    if token == hash {
+        return Some(());
+    }
+    diags.error(
+        "Destructive operations are not approved",
+        format!(
+            "The plan drops objects or data. Review the plan, then set destructive_approval_token = \"{hash}\" to apply it."
+        ),
+        AttributePath::new("destructive_approval_token"),
+    );
+    None
+}
+
+/// With the provider's `require_confirmation` set, destructive operations only
+/// run once the apply is confirmed, see [`crate::confirmation`].
+fn check_confirmation(
+    diags: &mut Diagnostics,
+    confirmation: &crate::confirmation::Confirmation,
+    destructive_hash: Option<&str>,
+) -> Option<()> {
+    let Some(hash) = destructive_hash else {
+        return Some(());
+    };
+    if confirmation.confirmed(hash) {
+        return Some(());
+    }
+    diags.root_error(
+        "Destructive apply is not confirmed",
+        format!(
+            "The plan drops objects or data and the provider sets require_confirmation. Run the apply with {}=yes, or set the provider's confirmation_token to \"{hash}\", the destructive_operations_hash of the reviewed plan, in the pipeline that should apply it.",
+            crate::confirmation::CONFIRM_VAR
+        ),
+    );
+    None
+}
+
+/// With `approved_plan_signature` set, apply only runs the statements a
+/// reviewer saw: their signature must match the one from the plan document.
+fn check_plan_signature(
+    diags: &mut Diagnostics,
+    state: &SchemaResourceState<'_>,
+    signing_key: Option<&str>,
+    statements: &[String],
+) -> Option<()> {
+    let Value::Value(approved) = &state.approved_plan_signature else {
+        return Some(());
+    };
+    let Some(key) = signing_key else {
+        diags.error(
+            "Cannot verify approved_plan_signature",
+            "Set plan_signing_key in the provider configuration to the key the plan was signed with.",
+            AttributePath::new("approved_plan_signature"),
+        );
+        return None;
+    };
+    if crate::signing::verify(key, statements, approved) {
+        return Some(());
+    }
+    // The expected signature isn't shown: copying it would bypass the review
+    diags.error(
+        "SQL differs from the approved plan",
+        "The statements apply would run don't match approved_plan_signature. The database or schema file changed since the plan was reviewed; review a new plan and approve its signature.",
+        AttributePath::new("approved_plan_signature"),
+    );
+    None
+}
+
+/// Writes the plan-time diff to `plan_output_path`; `None` means nothing is pending.
+fn write_plan_output(
+    diags: &mut Diagnostics,
+    state: &SchemaResourceState<'_>,
+    schema_hash: &str,
+    plan: Option<&crate::pipeline::MigrationPlan>,
+    previous: Option<&AppliedSql>,
+    signing_key: Option<&str>,
+) -> Option<()> {
+    let mut output = match plan {
+        Some(plan) => crate::plan_output::PlanOutput::new("pgmold_schema", schema_hash, plan),
+        None => crate::plan_output::PlanOutput::empty("pgmold_schema", schema_hash),
+    };
+    if let Some(previous) = previous
+        .filter(|p| !output.statements.is_empty() && p.hash != statements_hash(&output.statements))
+    {
+        output = output.diffed(&previous.hash, &previous.statements);
+    }
+    crate::plan_output::write(
+        diags,
+        state.plan_output_path.as_ref_option().map(|p| p.as_ref()),
+        &output.signed(signing_key),
+    )
+}
+
+/// `table_ddl`: the SQL that creates each table of `schema` in scope on its own,
+/// as `pgmold_schema_dump` would render it.
+fn table_ddl<'a>(
+    state: &SchemaResourceState,
+    schema: &pgmold::model::Schema,
+) -> ValueMap<'a, ValueString<'a>> {
+    let schemas = target_schemas(state);
+    let filter =
+        crate::object_filter::ObjectFilter::parse(&object_patterns(state)).unwrap_or_default();
+    Value::Value(
+        schema
+            .tables
+            .iter()
+            .filter(|(_, t)| schemas.contains(&t.schema) && filter.matches(&t.schema, &t.name))
+            .map(|(key, table)| {
+                let single = pgmold::model::Schema {
+                    tables: [(key.clone(), table.clone())].into_iter().collect(),
+                    ..Default::default()
+                };
+                (
+                    Cow::Owned(format!("{}.{}", table.schema, table.name)),
+                    Value::Value(Cow::Owned(crate::pipeline::canonical_sql(&single))),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn operation_counts<'a>(kinds: &[String]) -> ValueMap<'a, ValueNumber> {
+    Value::Value(
+        crate::operations::count_by_kind(kinds)
+            .into_iter()
+            .map(|(kind, count)| (Cow::Owned(kind), Value::Value(count)))
+            .collect(),
+    )
+}
+
+/// State saved after an apply that failed part way: the schema hash is
+/// cleared so the next plan applies again, and private state records the
+/// progress.
+fn interrupted_state<'a>(
+    mut state: SchemaResourceState<'a>,
+    prior: Option<SchemaPrivateState>,
+    interrupted: InterruptedApply,
+) -> (SchemaResourceState<'a>, Option<SchemaPrivateState>) {
+    state.schema_hash = Value::Null;
+    for computed in [
+        &mut state.applied_at,
+        &mut state.destructive_operations_hash,
+        &mut state.database_fingerprint,
+    ] {
+        if computed.is_unknown() {
+            *computed = Value::Null;
+        }
+    }
+    if state.migration_count.is_unknown() {
+        state.migration_count = Value::Null;
+    }
+    if state.operation_counts.is_unknown() {
+        state.operation_counts = Value::Null;
+    }
+    if state.lint_results.is_unknown() {
+        state.lint_results = Value::Null;
+    }
+    let mut private_state = prior.unwrap_or_default();
+    private_state.interrupted = Some(interrupted);
+    (state, Some(private_state))
+}
+
+/// Appends `record` to the prior apply history, keeping the most recent
+/// [`MAX_APPLY_HISTORY`] entries.
+fn record_apply(
+    prior: Option<SchemaPrivateState>,
+    record: ApplyRecord,
+    fingerprints: Option<crate::fingerprint::Fingerprints>,
+    statements: &[String],
+) -> SchemaPrivateState {
+    // An apply with nothing to do leaves the last reviewed SQL in place
+    let last_sql = if statements.is_empty() {
+        prior.as_ref().and_then(|p| p.last_sql.clone())
+    } else {
+        Some(AppliedSql {
+            hash: statements_hash(statements),
+            statements: statements.to_vec(),
+        })
+    };
+    let mut history = prior.map(|p| p.history).unwrap_or_default();
+    let excess = (history.len() + 1).saturating_sub(MAX_APPLY_HISTORY);
+    history.drain(..excess);
+    SchemaPrivateState {
+        schema_hash: record.schema_hash.clone(),
+        fingerprints,
+        history: history.into_iter().chain([record]).collect(),
+        interrupted: None,
+        last_sql,
+    }
+}
+
+/// Uses `name` when set, otherwise hashes the database identity (host, port,
+/// database) with the target schemas, so the id survives moving the checkout.
+fn resource_id(state: &SchemaResourceState) -> ValueString<'static> {
+    if let Value::Value(name) = &state.name {
+        return Value::Value(Cow::Owned(format!("pgmold-{name}")));
+    }
+    let Value::Value(database_url) = database_url(state) else {
+        return Value::Unknown;
+    };
+    if state.target_schemas.is_unknown() {
+        return Value::Unknown;
+    }
+
+    let mut schemas = target_schemas(state);
+    schemas.sort();
+    let identity = format!(
+        "{}|{}",
+        crate::util::database_identity(&database_url),
+        schemas.join(",")
+    );
+    let hash = crate::util::compute_content_hash(&identity);
+    Value::Value(Cow::Owned(format!("pgmold-{}", &hash[..8])))
+}
 
-        let connection = match pgmold::pg::connection::PgConnection::new(db_url).await {
-            Ok(c) => c,
+/// The primary's connection URL: `database_url`, or the environment variable
+/// `database_url_env` names. Null when neither yields one.
+fn database_url<'s>(state: &'s SchemaResourceState) -> ValueString<'s> {
+    match (&state.database_url, &state.database_url_env) {
+        (Value::Value(url), _) => Value::Value(Cow::Borrowed(url)),
+        (Value::Unknown, _) | (_, Value::Unknown) => Value::Unknown,
+        (_, Value::Value(name)) => std::env::var(name.as_ref())
+            .ok()
+            .filter(|url| !url.is_empty())
+            .map_or(Value::Null, |url| Value::Value(Cow::Owned(url))),
+        _ => Value::Null,
+    }
+}
+
+fn check_database_url_set(diags: &mut Diagnostics, state: &SchemaResourceState) -> Option<()> {
+    if !database_url(state).is_null() {
+        return Some(());
+    }
+    match &state.database_url_env {
+        Value::Value(name) => diags.error_short(
+            format!("Environment variable {name} is not set"),
+            AttributePath::new("database_url_env"),
+        ),
+        _ => diags.error_short(
+            "database_url is required (either at resource or provider level)",
+            AttributePath::new("database_url"),
+        ),
+    }
+    None
+}
+
+/// Identifies the target database without credentials, so state can track
+/// which database was migrated even when the URL itself isn't stored.
+fn connection_fingerprint(state: &SchemaResourceState) -> ValueString<'static> {
+    match database_url(state) {
+        Value::Value(url) => {
+            let hash = crate::util::compute_content_hash(&crate::util::database_identity(&url));
+            Value::Value(Cow::Owned(hash[..16].to_string()))
+        }
+        Value::Unknown => Value::Unknown,
+        Value::Null => Value::Null,
+    }
+}
+
+fn dialect(state: &SchemaResourceState) -> crate::dialect::Dialect {
+    state
+        .dialect
+        .as_ref_option()
+        .and_then(|d| crate::dialect::Dialect::parse(d))
+        .unwrap_or_default()
+}
+
+/// Shadow table settings when `rewrite_strategy` is `shadow_table`.
+fn rebuild_options(state: &SchemaResourceState) -> Option<crate::rebuild::RebuildOptions> {
+    let strategy = state
+        .rewrite_strategy
+        .as_ref_option()
+        .and_then(|s| crate::rebuild::RewriteStrategy::parse(s))
+        .unwrap_or_default();
+    if strategy != crate::rebuild::RewriteStrategy::ShadowTable {
+        return None;
+    }
+    let defaults = crate::rebuild::RebuildOptions::default();
+    Some(crate::rebuild::RebuildOptions {
+        batch_pages: state
+            .rewrite_batch_pages
+            .as_ref_option()
+            .copied()
+            .unwrap_or(defaults.batch_pages),
+        cutover_lock_timeout: state
+            .rewrite_cutover_lock_timeout
+            .as_ref_option()
+            .map(|t| t.to_string())
+            .unwrap_or(defaults.cutover_lock_timeout),
+        cutover_retries: state
+            .rewrite_cutover_retries
+            .as_ref_option()
+            .map(|r| (*r).max(1) as u32)
+            .unwrap_or(defaults.cutover_retries),
+    })
+}
+
+fn window_settings<'s>(state: &'s SchemaResourceState) -> crate::window::WindowSettings<'s> {
+    let setting = |value: &'s ValueString| value.as_ref_option().map(|v| v.as_ref());
+    crate::window::WindowSettings {
+        cron: setting(&state.maintenance_window_cron),
+        duration: setting(&state.maintenance_window_duration),
+        start: setting(&state.maintenance_window_start),
+        end: setting(&state.maintenance_window_end),
+        timezone: setting(&state.maintenance_window_timezone),
+    }
+}
+
+/// Holds an apply back until the maintenance window is open, failing instead
+/// unless `maintenance_window_wait` is set. Runs before taking an apply slot,
+/// so a waiting apply doesn't block others.
+async fn await_maintenance_window(
+    diags: &mut Diagnostics,
+    state: &SchemaResourceState<'_>,
+) -> Option<()> {
+    let Some(window) = crate::window::check_window(diags, window_settings(state))? else {
+        return Some(());
+    };
+    let now = crate::clock::now();
+    let opening = window.next_opening(now);
+    if opening == Some(now) {
+        return Some(());
+    }
+    let Some(opening) = opening else {
+        diags.root_error(
+            "Apply outside the maintenance window",
+            "The maintenance window won't open again. Update the maintenance_window_* settings to apply.",
+        );
+        return None;
+    };
+    if !state.maintenance_window_wait.unwrap_or(false) {
+        diags.error(
+            "Apply outside the maintenance window",
+            format!(
+                "The next maintenance window opens at {}. Apply then, or set maintenance_window_wait to have apply wait for it.",
+                window.local(opening)
+            ),
+            AttributePath::new("maintenance_window_wait"),
+        );
+        return None;
+    }
+    tracing::info!(opens_at = %window.local(opening), "waiting for the maintenance window");
+    tokio::time::sleep((opening - now).to_std().unwrap_or_default()).await;
+    Some(())
+}
+
+fn schema_file_path(state: &SchemaResourceState) -> String {
+    crate::util::resolve_path(
+        state.base_dir.as_ref_option().map(|s| s.as_ref()),
+        state.schema_file.as_str(),
+    )
+}
+
+/// Where plan and refresh introspect: the replica if configured, else the primary.
+fn introspection_url<'s>(state: &'s SchemaResourceState) -> ValueString<'s> {
+    match &state.introspection_url {
+        Value::Value(url) => Value::Value(Cow::Borrowed(url)),
+        _ => database_url(state),
+    }
+}
+
+fn check_introspection_url(diags: &mut Diagnostics, url: Option<&str>) {
+    if url.is_some_and(|u| !u.starts_with("postgres://") && !u.starts_with("postgresql://")) {
+        diags.error(
+            "Invalid introspection_url",
+            "introspection_url must be a URL starting with postgres:// or postgresql://",
+            AttributePath::new("introspection_url"),
+        );
+    }
+}
+
+fn target_schemas(state: &SchemaResourceState) -> Vec<String> {
+    let schemas: Vec<String> = state
+        .target_schemas
+        .iter()
+        .flatten()
+        .filter_map(|s| s.as_ref_option().map(|s| s.to_string()))
+        .collect();
+    if schemas.is_empty() {
+        vec!["public".to_string()]
+    } else {
+        schemas
+    }
+}
+
+fn plan_options(
+    state: &SchemaResourceState,
+    only_objects: Option<std::collections::BTreeSet<String>>,
+    server_version: Option<i64>,
+    lint_rules: Vec<crate::lint_rules::LintRule>,
+    escalate_warnings: bool,
+    operation_policy: crate::operation_policy::OperationPolicy,
+) -> crate::pipeline::PlanOptions {
+    crate::pipeline::PlanOptions {
+        target_schemas: target_schemas(state),
+        lint_rules,
+        escalate_warnings,
+        operation_policy,
+        lint_options: pgmold::lint::LintOptions {
+            allow_destructive: state.allow_destructive.unwrap_or(false),
+            is_production: false,
+        },
+        declared_kinds_only: state.declared_kinds_only.unwrap_or(false),
+        only_objects,
+        // validate rejects patterns that don't parse
+        objects: crate::object_filter::ObjectFilter::parse(&object_patterns(state))
+            .unwrap_or_default(),
+        skip_unreadable_schemas: state.skip_unreadable_schemas.unwrap_or(false),
+        dialect: dialect(state),
+        phase: None,
+        server_version,
+        rebuild_enums: state.rebuild_enums.unwrap_or(false),
+        defer_validation: state.defer_constraint_validation.unwrap_or(false),
+        preserve_sequences: state.preserve_sequences.unwrap_or(false),
+        preserve_data: state.preserve_data.unwrap_or(false),
+        normalize_expressions: state.normalize_expressions.unwrap_or(false),
+    }
+}
+
+fn continue_on(state: &SchemaResourceState) -> Vec<String> {
+    state
+        .continue_on
+        .iter()
+        .flatten()
+        .filter_map(|c| c.as_ref_option().map(|c| c.to_string()))
+        .collect()
+}
+
+fn object_patterns(state: &SchemaResourceState) -> Vec<String> {
+    state
+        .object_patterns
+        .iter()
+        .flatten()
+        .filter_map(|p| p.as_ref_option().map(|p| p.to_string()))
+        .collect()
+}
+
+impl SchemaResource {
+    /// Fills in the provider's `database_url` and `target_schemas` where the
+    /// configuration leaves them out. A `database_url_env` takes precedence
+    /// over the provider's URL.
+    async fn inherit_provider_defaults(
+        &self,
+        state: &mut SchemaResourceState<'_>,
+        config: &SchemaResourceState<'_>,
+    ) {
+        let defaults = self.connections.resource_defaults().await;
+        if config.database_url.is_null() {
+            state.database_url = match (&config.database_url_env, defaults.database_url) {
+                (Value::Null, Some(url)) => Value::Value(Cow::Owned(url)),
+                _ => Value::Null,
+            };
+        }
+        if config.target_schemas.is_null() {
+            state.target_schemas = defaults.target_schemas.map_or(Value::Null, |schemas| {
+                Value::Value(
+                    schemas
+                        .into_iter()
+                        .map(|s| Value::Value(Cow::Owned(s)))
+                        .collect(),
+                )
+            });
+        }
+    }
+
+    /// Registers the objects in the resource's scope with the provider and
+    /// returns those `schema`, its parsed schema file, declares, for
+    /// `managed_objects`. Fails when another resource of the provider targets
+    /// the same database and declares or would drop some of them.
+    async fn claim_objects(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+        schema: &pgmold::model::Schema,
+    ) -> Option<ValueList<ValueString<'static>>> {
+        let schemas = target_schemas(state);
+        let filter =
+            crate::object_filter::ObjectFilter::parse(&object_patterns(state)).unwrap_or_default();
+        let objects: std::collections::BTreeSet<(String, String)> =
+            crate::object_filter::object_names(schema)
+                .into_iter()
+                .filter(|(s, n)| schemas.contains(s) && filter.matches(s, n))
+                .collect();
+        let manifest = Value::Value(
+            objects
+                .iter()
+                .map(|(s, n)| Value::Value(Cow::Owned(format!("{s}.{n}"))))
+                .collect(),
+        );
+
+        // Unknown until the URL is, and then claimed on the next plan
+        if let (Value::Value(database), Value::Value(id)) =
+            (&state.connection_fingerprint, &state.id)
+        {
+            let claim = crate::claims::Claim {
+                schemas,
+                filter,
+                objects,
+            };
+            let conflicts = self.claims.claim(database, id, claim).await;
+            if !conflicts.is_empty() {
+                let conflicts: Vec<String> = conflicts
+                    .iter()
+                    .map(|(object, other)| format!("{object} (also managed by {other})"))
+                    .collect();
+                diags.error(
+                    "Objects managed by more than one pgmold_schema",
+                    format!(
+                        "{}. Each object can only be managed by one resource, or applies would undo each other. Narrow object_patterns or target_schemas so the resources don't overlap.",
+                        conflicts.join(", ")
+                    ),
+                    AttributePath::new("object_patterns"),
+                );
+                return None;
+            }
+        }
+        Some(manifest)
+    }
+
+    /// Diffs and lints over `connection` without executing anything.
+    async fn plan_schema(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+        connection: std::sync::Arc<pgmold::pg::connection::PgConnection>,
+        only_objects: Option<std::collections::BTreeSet<String>>,
+    ) -> Option<(
+        std::sync::Arc<pgmold::pg::connection::PgConnection>,
+        crate::pipeline::MigrationPlan,
+    )> {
+        let options = plan_options(
+            state,
+            only_objects,
+            self.connections.target_server_version().await,
+            self.connections.lint_rules().await,
+            self.connections.escalate_warnings().await,
+            self.connections.operation_policy().await,
+        );
+        let plan =
+            match crate::pipeline::plan_migration(&connection, &schema_file_path(state), &options)
+                .await
+            {
+                Ok(p) => p,
+                Err(e) => {
+                    diags.error_short(format!("Migration failed: {e}"), e.attribute());
+                    return None;
+                }
+            };
+
+        crate::pipeline::report_skipped_schemas(diags, &plan.skipped_schemas);
+        if crate::pipeline::report_lint_errors(diags, &plan.lint_results) {
+            return None;
+        }
+
+        Some((connection, plan))
+    }
+
+    /// Plan-time diff: against the live database, or against the snapshot in
+    /// mock mode.
+    async fn plan_only(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+        only_objects: Option<std::collections::BTreeSet<String>>,
+    ) -> Option<crate::pipeline::MigrationPlan> {
+        let Some(snapshot) = self.connections.snapshot().await else {
+            let connection = self
+                .connections
+                .connect_read_only(diags, introspection_url(state).as_str())
+                .await?;
+            let (_, plan) = self
+                .plan_schema(diags, state, connection, only_objects)
+                .await?;
+            return Some(plan);
+        };
+        let options = plan_options(
+            state,
+            only_objects,
+            self.connections.target_server_version().await,
+            self.connections.lint_rules().await,
+            self.connections.escalate_warnings().await,
+            self.connections.operation_policy().await,
+        );
+        let plan = match crate::pipeline::plan_from_snapshot(
+            &snapshot,
+            &schema_file_path(state),
+            &options,
+        ) {
+            Ok(p) => p,
             Err(e) => {
-                let sanitized = crate::util::sanitize_db_error(&format!("{e}"));
-                diags.root_error_short(format!("Failed to connect to database: {sanitized}"));
+                diags.error_short(format!("Migration failed: {e}"), e.attribute());
                 return None;
             }
         };
+        if crate::pipeline::report_lint_errors(diags, &plan.lint_results) {
+            return None;
+        }
+        Some(plan)
+    }
+
+    /// Plan-time check that only diffs objects whose catalog fingerprint moved since the
+    /// last apply. Falls back to a full diff when the schema file changed or no
+    /// fingerprints were recorded. Returns whether any operations are pending, and
+    /// the hash of the destructive ones.
+    async fn plan_changed_objects(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+        schema_hash: &str,
+        prior: Option<&SchemaPrivateState>,
+    ) -> Option<(bool, Option<String>)> {
+        // Fingerprints need the live catalog, so mock mode always diffs in full
+        let mock = self.connections.snapshot().await.is_some();
+        let key = self.connections.plan_signing_key().await;
+        let key = key.as_deref();
+        let last_sql = prior.and_then(|p| p.last_sql.as_ref());
+        let Some(stored) = prior
+            .filter(|p| !mock && p.schema_hash == schema_hash)
+            .and_then(|p| p.fingerprints.as_ref())
+        else {
+            let plan = self.plan_only(diags, state, None).await?;
+            write_plan_output(diags, state, schema_hash, Some(&plan), last_sql, key)?;
+            return Some(pending_changes(&plan));
+        };
+
+        let live = self.live_fingerprints(diags, state).await?;
+        let changed = crate::fingerprint::changed_objects(stored, &live);
+        if changed.is_empty() {
+            write_plan_output(diags, state, schema_hash, None, last_sql, key)?;
+            return Some((false, None));
+        }
+
+        let plan = self.plan_only(diags, state, Some(changed)).await?;
+        write_plan_output(diags, state, schema_hash, Some(&plan), last_sql, key)?;
+        Some(pending_changes(&plan))
+    }
+
+    async fn live_fingerprints(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+    ) -> Option<crate::fingerprint::Fingerprints> {
+        let connection = self
+            .connections
+            .connect_read_only(diags, introspection_url(state).as_str())
+            .await?;
+        match crate::fingerprint::object_fingerprints(connection.pool(), &target_schemas(state))
+            .await
+        {
+            Ok(f) => Some(f),
+            Err(e) => {
+                diags.root_error_short(format!("Failed to fingerprint objects: {e}"));
+                None
+            }
+        }
+    }
 
-        let schema_file = planned_state.schema_file.as_str().to_string();
-        let allow_destructive = planned_state.allow_destructive.unwrap_or(false);
+    /// Whether the database moved away from the last apply. Compares catalog
+    /// fingerprints when they were recorded; otherwise `auto_remediate_drift`
+    /// diffs the schema file against the database in full.
+    async fn drifted(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+        private_state: Option<&SchemaPrivateState>,
+    ) -> Option<bool> {
+        if let Some(stored) = private_state.and_then(|p| p.fingerprints.as_ref()) {
+            let live = self.live_fingerprints(diags, state).await?;
+            return Some(!crate::fingerprint::changed_objects(stored, &live).is_empty());
+        }
+        if !state.auto_remediate_drift.unwrap_or(false) {
+            return Some(false);
+        }
 
-        let result = match pgmold::apply::apply_migration(
-            &[schema_file],
+        let connection = self
+            .connections
+            .connect_read_only(diags, introspection_url(state).as_str())
+            .await?;
+        // Lint findings are reported by the apply that reverts the drift, not on refresh
+        match crate::pipeline::plan_migration(
             &connection,
-            pgmold::apply::ApplyOptions {
-                dry_run: false,
-                allow_destructive,
-            },
+            &schema_file_path(state),
+            &plan_options(
+                state,
+                None,
+                self.connections.target_server_version().await,
+                Vec::new(),
+                false,
+                Default::default(),
+            ),
         )
         .await
         {
-            Ok(r) => r,
+            Ok(plan) => Some(!plan.operations.is_empty()),
             Err(e) => {
-                diags.root_error_short(format!("Migration failed: {e}"));
-                return None;
-            }
-        };
-
-        if pgmold::lint::has_errors(&result.lint_results) {
-            for lint in &result.lint_results {
-                if lint.severity == pgmold::lint::LintSeverity::Error {
-                    diags.root_error_short(lint.message.to_string());
-                }
+                diags.warning_short(
+                    format!("Failed to check for drift: {e}"),
+                    AttributePath::new("auto_remediate_drift"),
+                );
+                Some(false)
             }
-            return None;
         }
+    }
 
-        let mut state = planned_state;
-        state.applied_at = Value::Value(Cow::Owned(chrono::Utc::now().to_rfc3339()));
-        state.migration_count = Value::Value(result.operations.len() as i64);
+    /// [`crate::pipeline::canonical_sql`] hash of `target_schemas` in the
+    /// database, or null when the resource doesn't opt in to connecting
+    /// outside apply.
+    async fn database_fingerprint(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+    ) -> Option<ValueString<'static>> {
+        let opted_in = state.plan_with_database.unwrap_or(false)
+            || state.auto_remediate_drift.unwrap_or(false);
+        if !opted_in || database_url(state).is_null() {
+            return Some(Value::Null);
+        }
 
-        Some((state, Default::default()))
+        let schemas = target_schemas(state);
+        let live = match self.connections.snapshot().await {
+            Some(snapshot) => crate::pipeline::snapshot_subset(&snapshot, &schemas),
+            None => {
+                let connection = self
+                    .connections
+                    .connect_read_only(diags, introspection_url(state).as_str())
+                    .await?;
+                match crate::pipeline::introspect(&connection, &schemas).await {
+                    Ok(schema) => schema,
+                    Err(e) => {
+                        diags.root_error_short(format!("Failed to introspect database: {e}"));
+                        return None;
+                    }
+                }
+            }
+        };
+        let sql = crate::pipeline::canonical_sql(&live);
+        Some(Value::Value(Cow::Owned(crate::util::compute_content_hash(
+            &sql,
+        ))))
     }
 
-    async fn update<'a>(
+    /// Whether objects in the target schemas are owned by a role other than
+    /// `owner`. Always false without `owner`.
+    async fn ownership_drifted(
         &self,
         diags: &mut Diagnostics,
-        _prior_state: Self::State<'a>,
-        planned_state: Self::State<'a>,
-        _config_state: Self::State<'a>,
-        _planned_private_state: Self::PrivateState<'a>,
-        _provider_meta_state: Self::ProviderMetaState<'a>,
-    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
-        let db_url = planned_state.database_url.as_str();
-
-        let connection = match pgmold::pg::connection::PgConnection::new(db_url).await {
-            Ok(c) => c,
+        state: &SchemaResourceState<'_>,
+    ) -> Option<bool> {
+        let Value::Value(owner) = &state.owner else {
+            return Some(false);
+        };
+        let connection = self
+            .connections
+            .connect_read_only(diags, introspection_url(state).as_str())
+            .await?;
+        match crate::ownership::reassign_statements(
+            connection.pool(),
+            &target_schemas(state),
+            owner,
+        )
+        .await
+        {
+            Ok(statements) => Some(!statements.is_empty()),
             Err(e) => {
-                let sanitized = crate::util::sanitize_db_error(&format!("{e}"));
-                diags.root_error_short(format!("Failed to connect to database: {sanitized}"));
-                return None;
+                diags.warning_short(
+                    format!("Failed to check object ownership: {e}"),
+                    AttributePath::new("owner"),
+                );
+                Some(false)
             }
+        }
+    }
+
+    /// Warns when the configured runner recorded migrations after the last apply,
+    /// i.e. someone changed the schema outside Terraform.
+    async fn warn_external_migrations(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+    ) -> Option<()> {
+        let Some(runner) = state
+            .migration_history
+            .as_ref_option()
+            .and_then(|name| crate::history::HistoryRunner::parse(name))
+        else {
+            return Some(());
+        };
+        let Value::Value(applied_at) = &state.applied_at else {
+            return Some(());
         };
 
-        let schema_file = planned_state.schema_file.as_str().to_string();
-        let allow_destructive = planned_state.allow_destructive.unwrap_or(false);
+        let connection = self
+            .connections
+            .connect_read_only(diags, introspection_url(state).as_str())
+            .await?;
+        match crate::history::applied_since(connection.pool(), runner, applied_at).await {
+            Ok(0) => {}
+            Ok(count) => diags.warning(
+                format!("{count} migration(s) applied outside Terraform since {applied_at}"),
+                format!(
+                    "{} records migrations newer than the last pgmold apply. Review them and update the schema file so the next apply doesn't revert them.",
+                    runner.table()
+                ),
+                AttributePath::new("migration_history"),
+            ),
+            Err(e) => diags.warning_short(
+                format!("Failed to read {}: {e}", runner.table()),
+                AttributePath::new("migration_history"),
+            ),
+        }
+        Some(())
+    }
 
-        let result = match pgmold::apply::apply_migration(
-            &[schema_file],
-            &connection,
-            pgmold::apply::ApplyOptions {
-                dry_run: false,
-                allow_destructive,
-            },
+    /// Checks that every target schema exists, creating missing ones when
+    /// `create_missing_schemas` is set.
+    async fn ensure_target_schemas(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+    ) -> Option<()> {
+        let connection = self
+            .connections
+            .connect(diags, database_url(state).as_str())
+            .await?;
+
+        let missing = match crate::execute::missing_schemas(
+            connection.pool(),
+            &target_schemas(state),
         )
         .await
         {
-            Ok(r) => r,
+            Ok(m) => m,
             Err(e) => {
-                diags.root_error_short(format!("Migration failed: {e}"));
+                diags.root_error_short(format!("Failed to check target_schemas: {e}"));
                 return None;
             }
         };
+        if missing.is_empty() {
+            return Some(());
+        }
+
+        if !state.create_missing_schemas.unwrap_or(false) {
+            diags.error(
+                format!("target_schemas do not exist: {}", missing.join(", ")),
+                "Create them before applying, or set create_missing_schemas = true.",
+                AttributePath::new("target_schemas"),
+            );
+            return None;
+        }
+
+        if let Err(e) = crate::execute::create_schemas(connection.pool(), &missing).await {
+            diags.error_short(
+                format!("Failed to create target_schemas: {e}"),
+                AttributePath::new("target_schemas"),
+            );
+            return None;
+        }
+        Some(())
+    }
 
-        if pgmold::lint::has_errors(&result.lint_results) {
-            for lint in &result.lint_results {
-                if lint.severity == pgmold::lint::LintSeverity::Error {
-                    diags.root_error_short(lint.message.to_string());
+    /// Diffs the schema file against the database and executes the result, or
+    /// with `resume` runs the rest of an interrupted apply's statements.
+    /// Returns a record of the apply and the fingerprints of the result. When
+    /// execution fails after committing some statements, `interrupted` records
+    /// how many.
+    async fn apply_schema(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaResourceState<'_>,
+        log: &mut crate::execution_log::ExecutionLogEntry,
+        resume: Option<&InterruptedApply>,
+        interrupted: &mut Option<InterruptedApply>,
+    ) -> Option<(ApplyRecord, Option<crate::fingerprint::Fingerprints>)> {
+        let connection = self
+            .connections
+            .connect(diags, database_url(state).as_str())
+            .await?;
+        crate::pipeline::check_server_version(
+            diags,
+            &connection,
+            state.min_server_version.as_ref_option().copied(),
+            state.max_server_version.as_ref_option().copied(),
+        )
+        .await?;
+
+        self.ensure_target_schemas(diags, state).await?;
+        let (connection, statements, operations, mut timings, destructive_hash, offset) =
+            match resume {
+                Some(resume) if resume.resumable() => {
+                    // The statements still to run may include the destructive ones, which
+                    // need this apply's approval and confirmation like a fresh plan
+                    let destructive_hash = resume.destructive_hash.as_deref();
+                    check_destructive_approval(diags, state, destructive_hash)?;
+                    let confirmation = self.connections.confirmation().await;
+                    check_confirmation(diags, &confirmation, destructive_hash)?;
+                    diags.root_warning_short(format!(
+                        "Resuming the apply that failed at {} from statement {} of {}",
+                        resume.failed_at,
+                        resume.committed + 1,
+                        resume.plan_statements.len()
+                    ));
+                    log.statements = resume.plan_statements[resume.committed..].to_vec();
+                    (
+                        connection,
+                        resume.plan_statements.clone(),
+                        resume.operations.clone(),
+                        crate::pipeline::PhaseTimings::new(),
+                        resume.destructive_hash.clone(),
+                        resume.committed,
+                    )
+                }
+                _ => {
+                    if resume.is_some() {
+                        diags.root_warning_short(
+                            "The interrupted apply didn't record a usable plan, so the schema is diffed again",
+                        );
+                    }
+                    // Always diff against the primary: a lagging replica could miss changes
+                    // the statements would then conflict with
+                    let (connection, plan) =
+                        self.plan_schema(diags, state, connection, None).await?;
+                    log.record_plan(&plan);
+                    let destructive_hash = crate::operations::destructive_hash(&plan.operations);
+                    check_destructive_approval(diags, state, destructive_hash.as_deref())?;
+                    let confirmation = self.connections.confirmation().await;
+                    check_confirmation(diags, &confirmation, destructive_hash.as_deref())?;
+                    let key = self.connections.plan_signing_key().await;
+                    check_plan_signature(diags, state, key.as_deref(), &plan.statements)?;
+                    let operations = plan
+                        .operations
+                        .iter()
+                        .map(crate::operations::operation_kind)
+                        .collect();
+                    (
+                        connection,
+                        plan.statements,
+                        operations,
+                        plan.timings,
+                        destructive_hash,
+                        0,
+                    )
                 }
+            };
+        tracing::info!(
+            resource = "pgmold_schema",
+            id = state.id.as_str(),
+            operations = operations.len(),
+            "applying schema"
+        );
+
+        let dialect = dialect(state);
+        let options = crate::execute::ExecuteOptions {
+            batch: state.batch_statements.unwrap_or(false),
+            standalone: !dialect.transactional_ddl(),
+            rebuild: rebuild_options(state),
+            chunk_size: state
+                .apply_chunk_size
+                .as_ref_option()
+                .map(|&n| n.max(1) as usize),
+            concurrent_index_retries: state
+                .concurrent_index_retries
+                .as_ref_option()
+                .map_or(0, |&n| n.max(0) as u32),
+            failpoints: self.connections.failpoints().await,
+            continue_on: crate::execute::parse_continue_on(&continue_on(state)).unwrap_or_default(),
+        };
+        let started = std::time::Instant::now();
+        let mut committed = 0;
+        let mut skipped = Vec::new();
+        let result = crate::execute::execute_with_progress(
+            connection.pool(),
+            &statements[offset..],
+            &options,
+            &mut committed,
+            &mut skipped,
+        )
+        .instrument(tracing::info_span!(
+            "apply",
+            statements = statements.len() - offset
+        ))
+        .await;
+        for skipped in &skipped {
+            diags.root_warning_short(format!(
+                "Skipped a statement that failed with a continue_on error: {} (statement: {})",
+                skipped.error, skipped.statement
+            ));
+        }
+        if let Err(e) = result {
+            diags.root_error_short(format!("Migration failed: {e}"));
+            let committed = offset + committed;
+            if committed > 0 {
+                diags.root_warning_short(format!(
+                    "{committed} of {} statements were committed before the failure and stay applied",
+                    statements.len()
+                ));
+                *interrupted = Some(InterruptedApply {
+                    schema_hash: state.schema_hash.as_str().to_string(),
+                    failed_at: crate::clock::now().to_rfc3339(),
+                    committed,
+                    statements: statements.len(),
+                    plan_hash: statements_hash(&statements),
+                    plan_statements: statements,
+                    operations,
+                    destructive_hash,
+                });
             }
             return None;
         }
+        crate::pipeline::record_phase(&mut timings, "apply", started);
+        log.timings = timings.clone();
+
+        // Objects the apply created belong to the connecting role until handed over
+        if let Value::Value(owner) = &state.owner {
+            let schemas = target_schemas(state);
+            let reassigned =
+                match crate::ownership::reassign_statements(connection.pool(), &schemas, owner)
+                    .await
+                {
+                    Ok(statements) => crate::execute::execute_statements(
+                        connection.pool(),
+                        &statements,
+                        &crate::execute::ExecuteOptions::default(),
+                    )
+                    .await
+                    .map(|()| statements.len()),
+                    Err(e) => Err(e),
+                };
+            match reassigned {
+                Ok(0) => {}
+                Ok(objects) => {
+                    tracing::info!(owner = owner.as_ref(), objects, "reassigned ownership")
+                }
+                Err(e) => {
+                    diags.error(
+                        "Failed to reassign ownership",
+                        format!("The schema changes were applied, but handing objects to {owner} failed: {e}"),
+                        AttributePath::new("owner"),
+                    );
+                    return None;
+                }
+            }
+        }
 
-        let mut state = planned_state;
-        state.applied_at = Value::Value(Cow::Owned(chrono::Utc::now().to_rfc3339()));
-        state.migration_count = Value::Value(result.operations.len() as i64);
+        // The schema is applied by now, so stale docs only warrant a warning
+        if let Value::Value(dir) = &state.docs_output_dir {
+            let written = crate::pipeline::parse_schema_file(&schema_file_path(state))
+                .and_then(|schema| crate::schema_docs::write(dir, &schema));
+            if let Err(e) = written {
+                diags.warning(
+                    "Failed to write schema docs",
+                    format!("{dir}: {e}"),
+                    AttributePath::new("docs_output_dir"),
+                );
+            }
+        }
 
-        Some((state, Default::default()))
-    }
+        // Fingerprints only speed up later plans, so failing to record them isn't fatal
+        let fingerprints = if dialect.supports_fingerprints() {
+            crate::fingerprint::object_fingerprints(connection.pool(), &target_schemas(state))
+                .await
+                .ok()
+        } else {
+            None
+        };
 
-    async fn destroy<'a>(
-        &self,
-        _diags: &mut Diagnostics,
-        _prior_state: Self::State<'a>,
-        _prior_private_state: Self::PrivateState<'a>,
-        _provider_meta_state: Self::ProviderMetaState<'a>,
-    ) -> Option<()> {
-        Some(())
+        let record = ApplyRecord {
+            schema_hash: state.schema_hash.as_str().to_string(),
+            applied_at: crate::clock::now().to_rfc3339(),
+            operations,
+            timings,
+            destructive_hash,
+        };
+        Some((record, fingerprints))
     }
 }
 
@@ -344,9 +2208,81 @@ mod tests {
         assert!(state.zero_downtime.is_null());
     }
 
+    #[test]
+    fn schema_state_defaults_plan_with_database_null() {
+        let state = SchemaResourceState::default();
+        assert!(state.plan_with_database.is_null());
+    }
+
+    #[tokio::test]
+    async fn read_recomputes_schema_hash() {
+        let mut schema_file = NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+        let applied = crate::util::compute_schema_hash(schema_file.path()).unwrap();
+        let prior = SchemaResourceState {
+            schema_file: Value::Value(Cow::Owned(schema_file.path().to_string_lossy().to_string())),
+            database_url: Value::Value(Cow::Borrowed("postgres://localhost/db")),
+            schema_hash: Value::Value(Cow::Owned(applied.clone())),
+            ..Default::default()
+        };
+        let resource = SchemaResource::default();
+
+        let mut diags = Diagnostics::default();
+        let (state, _) = resource
+            .read(&mut diags, prior.clone(), None, ValueEmpty::default())
+            .await
+            .expect("read should succeed");
+        assert_eq!(state.schema_hash.as_str(), applied);
+        assert!(diags.warnings.is_empty());
+
+        writeln!(schema_file, "CREATE TABLE orders (id INT PRIMARY KEY);").unwrap();
+        let mut diags = Diagnostics::default();
+        let (state, _) = resource
+            .read(&mut diags, prior, None, ValueEmpty::default())
+            .await
+            .expect("read should succeed");
+        assert!(state.schema_hash.is_null());
+        assert_eq!(diags.warnings.len(), 1);
+        assert_eq!(
+            diags.warnings[0].attribute,
+            AttributePath::new("schema_file")
+        );
+    }
+
+    #[tokio::test]
+    async fn read_skips_drift_check_in_mock_mode() {
+        let resource = SchemaResource::default();
+        resource
+            .connections
+            .set_mock(Some(pgmold::model::Schema::default()))
+            .await;
+        let prior = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://localhost/db")),
+            auto_remediate_drift: Value::Value(true),
+            schema_hash: Value::Value(Cow::Borrowed("abc")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        let (state, _) = resource
+            .read(&mut diags, prior, None, ValueEmpty::default())
+            .await
+            .expect("read should succeed");
+
+        assert!(diags.errors.is_empty());
+        assert_eq!(state.schema_hash.as_str(), "abc");
+        assert_eq!(
+            state.database_fingerprint.as_str(),
+            crate::util::compute_content_hash(&crate::pipeline::canonical_sql(
+                &pgmold::model::Schema::default()
+            ))
+        );
+    }
+
     #[test]
     fn schema_resource_has_required_attributes() {
-        let resource = SchemaResource;
+        let resource = SchemaResource::default();
         let mut diags = Diagnostics::default();
         let schema = resource.schema(&mut diags).expect("schema should exist");
 
@@ -355,15 +2291,56 @@ mod tests {
 
     #[test]
     fn schema_resource_has_optional_attributes() {
-        let resource = SchemaResource;
+        let resource = SchemaResource::default();
         let mut diags = Diagnostics::default();
         let schema = resource.schema(&mut diags).expect("schema should exist");
 
         for name in [
+            "name",
             "database_url",
+            "database_url_env",
+            "introspection_url",
             "target_schemas",
+            "object_patterns",
             "allow_destructive",
+            "allow_empty",
             "zero_downtime",
+            "batch_statements",
+            "apply_chunk_size",
+            "concurrent_index_retries",
+            "continue_on",
+            "resume_interrupted_apply",
+            "rewrite_strategy",
+            "rewrite_batch_pages",
+            "rewrite_cutover_lock_timeout",
+            "rewrite_cutover_retries",
+            "plan_with_database",
+            "auto_remediate_drift",
+            "declared_kinds_only",
+            "base_dir",
+            "create_missing_schemas",
+            "rebuild_enums",
+            "defer_constraint_validation",
+            "preserve_sequences",
+            "preserve_data",
+            "normalize_expressions",
+            "owner",
+            "migration_history",
+            "skip_unreadable_schemas",
+            "dialect",
+            "min_server_version",
+            "max_server_version",
+            "execution_log_path",
+            "plan_output_path",
+            "docs_output_dir",
+            "maintenance_window_cron",
+            "maintenance_window_duration",
+            "maintenance_window_start",
+            "maintenance_window_end",
+            "maintenance_window_timezone",
+            "maintenance_window_wait",
+            "destructive_approval_token",
+            "approved_plan_signature",
         ] {
             assert!(
                 schema.block.attributes.contains_key(name),
@@ -377,7 +2354,7 @@ mod tests {
         let mut schema_file = NamedTempFile::new().unwrap();
         writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
 
-        let resource = SchemaResource;
+        let resource = SchemaResource::default();
         let mut diags = Diagnostics::default();
 
         let proposed = SchemaResourceState {
@@ -404,12 +2381,460 @@ mod tests {
         assert_eq!(state.schema_hash.as_str().len(), 64);
     }
 
+    #[test]
+    fn table_ddl_renders_tables_in_scope() {
+        let schema = pgmold::parser::parse_sql_string(
+            "CREATE TABLE users (id INT PRIMARY KEY);\n\
+             CREATE TABLE audit_log (id INT PRIMARY KEY);\n\
+             CREATE TABLE billing.invoices (id INT PRIMARY KEY);",
+        )
+        .unwrap();
+        let state = SchemaResourceState {
+            object_patterns: Value::Value(vec![Value::Value(Cow::Borrowed("public.user*"))]),
+            ..Default::default()
+        };
+
+        let ddl = table_ddl(&state, &schema);
+
+        let ddl = ddl.as_ref_option().unwrap();
+        assert_eq!(ddl.len(), 1);
+        assert!(ddl["public.users"].as_str().contains("CREATE TABLE"));
+    }
+
+    #[tokio::test]
+    async fn plan_create_inherits_provider_database_url() {
+        let mut schema_file = NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+
+        let resource = SchemaResource::default();
+        resource
+            .connections
+            .set_resource_defaults(crate::connections::ResourceDefaults {
+                database_url: Some("postgres://provider/app".to_string()),
+                target_schemas: Some(vec!["app".to_string()]),
+            })
+            .await;
+        let mut diags = Diagnostics::default();
+
+        let proposed = SchemaResourceState {
+            schema_file: Value::Value(Cow::Owned(schema_file.path().to_string_lossy().to_string())),
+            ..Default::default()
+        };
+
+        let (state, _) = resource
+            .plan_create(
+                &mut diags,
+                proposed.clone(),
+                proposed,
+                ValueEmpty::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(state.database_url.as_str(), "postgres://provider/app");
+        assert_eq!(target_schemas(&state), vec!["app".to_string()]);
+        assert!(state.connection_fingerprint.is_value());
+    }
+
+    #[tokio::test]
+    async fn plan_update_keeps_apply_results_when_schema_unchanged() {
+        let mut schema_file = NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+
+        let resource = SchemaResource::default();
+        let mut diags = Diagnostics::default();
+
+        let prior = SchemaResourceState {
+            schema_file: Value::Value(Cow::Owned(schema_file.path().to_string_lossy().to_string())),
+            database_url: Value::Value(Cow::Borrowed("postgres://test")),
+            schema_hash: Value::Value(Cow::Owned(
+                crate::util::compute_schema_hash(schema_file.path()).unwrap(),
+            )),
+            applied_at: Value::Value(Cow::Borrowed("2024-01-01T00:00:00+00:00")),
+            migration_count: Value::Value(1),
+            ..Default::default()
+        };
+
+        let (state, _, _) = resource
+            .plan_update(
+                &mut diags,
+                prior.clone(),
+                prior.clone(),
+                prior.clone(),
+                None,
+                ValueEmpty::default(),
+            )
+            .await
+            .expect("plan_update should succeed");
+        assert_eq!(state.applied_at, prior.applied_at);
+        assert_eq!(state.operation_counts, prior.operation_counts);
+
+        writeln!(schema_file, "CREATE TABLE orders (id INT PRIMARY KEY);").unwrap();
+        let (state, _, _) = resource
+            .plan_update(
+                &mut diags,
+                prior.clone(),
+                prior.clone(),
+                prior,
+                None,
+                ValueEmpty::default(),
+            )
+            .await
+            .expect("plan_update should succeed");
+        assert!(state.applied_at.is_unknown());
+        assert!(state.operation_counts.is_unknown());
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_batching_without_transactional_ddl() {
+        let config = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://db/app")),
+            batch_statements: Value::Value(true),
+            dialect: Value::Value(Cow::Borrowed("cockroachdb")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        SchemaResource::default().validate(&mut diags, config).await;
+
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("batch_statements")
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_incomplete_maintenance_window() {
+        let config = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://db/app")),
+            maintenance_window_cron: Value::Value(Cow::Borrowed("0 22 * * mon-fri")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        SchemaResource::default().validate(&mut diags, config).await;
+
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("maintenance_window_duration")
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_bad_introspection_url() {
+        let config = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://primary/app")),
+            introspection_url: Value::Value(Cow::Borrowed("replica:5432/app")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        SchemaResource::default().validate(&mut diags, config).await;
+
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("introspection_url")
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_database_url_with_database_url_env() {
+        let config = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://primary/app")),
+            database_url_env: Value::Value(Cow::Borrowed("APP_DATABASE_URL")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        SchemaResource::default().validate(&mut diags, config).await;
+
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("database_url_env")
+        );
+    }
+
+    #[test]
+    fn database_url_reads_database_url_env() {
+        std::env::set_var(
+            "PGMOLD_TEST_SCHEMA_DATABASE_URL",
+            "postgres://user:secret@db:5432/app",
+        );
+        let state = SchemaResourceState {
+            database_url_env: Value::Value(Cow::Borrowed("PGMOLD_TEST_SCHEMA_DATABASE_URL")),
+            ..Default::default()
+        };
+        assert_eq!(
+            database_url(&state).as_str(),
+            "postgres://user:secret@db:5432/app"
+        );
+
+        let unset = SchemaResourceState {
+            database_url_env: Value::Value(Cow::Borrowed("PGMOLD_TEST_SCHEMA_UNSET_URL")),
+            ..Default::default()
+        };
+        assert!(database_url(&unset).is_null());
+        let mut diags = Diagnostics::default();
+        assert!(check_database_url_set(&mut diags, &unset).is_none());
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("database_url_env")
+        );
+    }
+
+    #[test]
+    fn connection_fingerprint_ignores_credentials() {
+        let url = |url: &'static str| SchemaResourceState {
+            database_url: Value::Value(Cow::Borrowed(url)),
+            ..Default::default()
+        };
+        let fingerprint = connection_fingerprint(&url("postgres://app:old@db:5432/app"));
+        assert_eq!(
+            fingerprint,
+            connection_fingerprint(&url("postgres://app:rotated@db:5432/app"))
+        );
+        assert_ne!(
+            fingerprint,
+            connection_fingerprint(&url("postgres://app:old@db:5432/other"))
+        );
+    }
+
+    #[test]
+    fn introspection_url_falls_back_to_database_url() {
+        let mut state = SchemaResourceState {
+            database_url: Value::Value(Cow::Borrowed("postgres://primary/app")),
+            ..Default::default()
+        };
+        assert_eq!(introspection_url(&state).as_str(), "postgres://primary/app");
+
+        state.introspection_url = Value::Value(Cow::Borrowed("postgres://replica/app"));
+        assert_eq!(introspection_url(&state).as_str(), "postgres://replica/app");
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_shadow_rebuilds_without_transactional_ddl() {
+        let config = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://db/app")),
+            rewrite_strategy: Value::Value(Cow::Borrowed("shadow_table")),
+            dialect: Value::Value(Cow::Borrowed("cockroachdb")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        SchemaResource::default().validate(&mut diags, config).await;
+
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("rewrite_strategy")
+        );
+    }
+
+    #[test]
+    fn rebuild_options_only_for_shadow_table_strategy() {
+        let mut state = SchemaResourceState::default();
+        assert!(rebuild_options(&state).is_none());
+
+        state.rewrite_strategy = Value::Value(Cow::Borrowed("shadow_table"));
+        state.rewrite_cutover_lock_timeout = Value::Value(Cow::Borrowed("2s"));
+        let options = rebuild_options(&state).unwrap();
+        assert_eq!(options.cutover_lock_timeout, "2s");
+        assert_eq!(options.batch_pages, 1000);
+    }
+
+    #[test]
+    fn destructive_approval_requires_matching_token() {
+        let mut state = SchemaResourceState::default();
+        let mut diags = Diagnostics::default();
+        assert!(check_destructive_approval(&mut diags, &state, Some("abc")).is_some());
+
+        state.destructive_approval_token = Value::Value(Cow::Borrowed("abc"));
+        assert!(check_destructive_approval(&mut diags, &state, Some("abc")).is_some());
+        assert!(check_destructive_approval(&mut diags, &state, None).is_some());
+        assert!(diags.errors.is_empty());
+
+        assert!(check_destructive_approval(&mut diags, &state, Some("def")).is_none());
+        assert!(diags.errors[0].detail.contains("\"def\""));
+    }
+
+    #[test]
+    fn check_plan_signature_requires_approved_statements() {
+        let statements = vec!["DROP TABLE users;".to_string()];
+        let mut state = SchemaResourceState::default();
+        let mut diags = Diagnostics::default();
+        assert!(check_plan_signature(&mut diags, &state, None, &statements).is_some());
+
+        let signature = crate::signing::sign("key", &statements);
+        state.approved_plan_signature = Value::Value(Cow::Owned(signature.clone()));
+        assert!(check_plan_signature(&mut diags, &state, Some("key"), &statements).is_some());
+        assert!(diags.errors.is_empty());
+
+        let changed = vec!["DROP TABLE accounts;".to_string()];
+        assert!(check_plan_signature(&mut diags, &state, Some("key"), &changed).is_none());
+        assert!(!diags.errors[0].detail.contains(&signature));
+        assert!(check_plan_signature(&mut diags, &state, None, &statements).is_none());
+        assert_eq!(diags.errors.len(), 2);
+    }
+
+    #[test]
+    fn record_apply_keeps_recent_history() {
+        let mut private_state = None;
+        for i in 0..MAX_APPLY_HISTORY + 2 {
+            let record = ApplyRecord {
+                schema_hash: format!("hash-{i}"),
+                ..Default::default()
+            };
+            private_state = Some(record_apply(private_state, record, None, &[]));
+        }
+
+        let private_state = private_state.unwrap();
+        assert_eq!(
+            private_state.schema_hash,
+            format!("hash-{}", MAX_APPLY_HISTORY + 1)
+        );
+        assert_eq!(private_state.history.len(), MAX_APPLY_HISTORY);
+        assert_eq!(private_state.history[0].schema_hash, "hash-2");
+    }
+
+    #[test]
+    fn record_apply_keeps_sql_of_last_change() {
+        let statements = vec!["CREATE TABLE users (id int)".to_string()];
+        let private_state = record_apply(None, ApplyRecord::default(), None, &statements);
+        let private_state = record_apply(Some(private_state), ApplyRecord::default(), None, &[]);
+
+        let last_sql = private_state.last_sql.unwrap();
+        assert_eq!(last_sql.statements, statements);
+        assert_eq!(last_sql.hash, statements_hash(&statements));
+    }
+
+    #[test]
+    fn interrupted_apply_resumes_only_intact_plans() {
+        let statements = vec![
+            "CREATE INDEX a ON t (a)".to_string(),
+            "CREATE INDEX b ON t (b)".to_string(),
+        ];
+        let mut interrupted = InterruptedApply {
+            committed: 1,
+            statements: 2,
+            plan_hash: statements_hash(&statements),
+            plan_statements: statements,
+            ..Default::default()
+        };
+        assert!(interrupted.resumable());
+
+        interrupted.plan_statements[1] = "DROP TABLE t".to_string();
+        assert!(!interrupted.resumable());
+        // Recorded before plans were kept in private state
+        assert!(!InterruptedApply {
+            committed: 1,
+            statements: 2,
+            ..Default::default()
+        }
+        .resumable());
+    }
+
+    #[test]
+    fn interrupted_apply_is_retried_and_cleared_on_success() {
+        let planned = SchemaResourceState {
+            schema_hash: Value::Value(Cow::Borrowed("abc")),
+            applied_at: Value::Unknown,
+            migration_count: Value::Unknown,
+            ..Default::default()
+        };
+        let interrupted = InterruptedApply {
+            schema_hash: "abc".to_string(),
+            committed: 500,
+            statements: 1200,
+            ..Default::default()
+        };
+
+        let (state, private_state) = interrupted_state(planned, None, interrupted.clone());
+        assert!(state.schema_hash.is_null());
+        assert!(state.applied_at.is_null());
+        assert!(state.migration_count.is_null());
+        let private_state = private_state.unwrap();
+        assert_eq!(private_state.interrupted, Some(interrupted));
+
+        let record = ApplyRecord {
+            schema_hash: "abc".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            record_apply(Some(private_state), record, None, &[]).interrupted,
+            None
+        );
+    }
+
+    #[test]
+    fn resource_id_ignores_schema_file_location() {
+        let state = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("/checkout-a/schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://app:secret@db:5432/app")),
+            ..Default::default()
+        };
+        let moved = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("/checkout-b/schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://ci:other@db:5432/app")),
+            ..state.clone()
+        };
+        assert_eq!(resource_id(&state), resource_id(&moved));
+
+        let named = SchemaResourceState {
+            name: Value::Value(Cow::Borrowed("app")),
+            ..state
+        };
+        assert_eq!(
+            resource_id(&named),
+            Value::Value(Cow::Borrowed("pgmold-app"))
+        );
+    }
+
+    #[tokio::test]
+    async fn plan_update_replaces_on_target_schemas_change() {
+        let mut schema_file = NamedTempFile::new().unwrap();
+        writeln!(schema_file, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+
+        let prior = SchemaResourceState {
+            schema_file: Value::Value(Cow::Owned(schema_file.path().to_string_lossy().to_string())),
+            database_url: Value::Value(Cow::Borrowed("postgres://test")),
+            ..Default::default()
+        };
+        let proposed = SchemaResourceState {
+            target_schemas: Value::Value(vec![Value::Value(Cow::Borrowed("billing"))]),
+            ..prior.clone()
+        };
+
+        let mut diags = Diagnostics::default();
+        let (_, _, requires_replace) = SchemaResource::default()
+            .plan_update(
+                &mut diags,
+                prior,
+                proposed.clone(),
+                proposed,
+                None,
+                ValueEmpty::default(),
+            )
+            .await
+            .expect("plan_update should succeed");
+
+        assert_eq!(requires_replace, vec![AttributePath::new("target_schemas")]);
+    }
+
     #[tokio::test]
     async fn plan_create_fails_without_database_url() {
         let mut schema_file = NamedTempFile::new().unwrap();
         writeln!(schema_file, "CREATE TABLE users (id INT);").unwrap();
 
-        let resource = SchemaResource;
+        let resource = SchemaResource::default();
         let mut diags = Diagnostics::default();
 
         let proposed = SchemaResourceState {
@@ -429,4 +2854,28 @@ mod tests {
 
         assert!(result.is_none() || !diags.errors.is_empty());
     }
+
+    #[tokio::test]
+    async fn plan_create_reports_missing_schema_file_on_attribute() {
+        let resource = SchemaResource::default();
+        let mut diags = Diagnostics::default();
+
+        let proposed = SchemaResourceState {
+            schema_file: Value::Value(Cow::Borrowed("/nonexistent/schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://test")),
+            ..Default::default()
+        };
+
+        let result = resource
+            .plan_create(
+                &mut diags,
+                proposed.clone(),
+                proposed,
+                ValueEmpty::default(),
+            )
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("schema_file"));
+    }
 }
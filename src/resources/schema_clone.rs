@@ -0,0 +1,526 @@
+use std::borrow::Cow;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::{Value, ValueBool, ValueEmpty, ValueList, ValueNumber, ValueString},
+    AttributePath, Diagnostics, Resource,
+};
+use tracing::Instrument;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SchemaCloneResourceState<'a> {
+    #[serde(borrow)]
+    pub id: ValueString<'a>,
+    #[serde(borrow)]
+    pub source_database_url: ValueString<'a>,
+    #[serde(borrow)]
+    pub database_url: ValueString<'a>,
+    #[serde(borrow)]
+    pub target_schemas: ValueList<ValueString<'a>>,
+    pub allow_destructive: ValueBool,
+    #[serde(borrow)]
+    pub source_hash: ValueString<'a>,
+    #[serde(borrow)]
+    pub cloned_at: ValueString<'a>,
+    pub migration_count: ValueNumber,
+}
+
+/// Mirrors the schema of a source database onto a target database, without
+/// data, e.g. to give each developer or pull request a copy of staging.
+#[derive(Debug, Default, Clone)]
+pub struct SchemaCloneResource {
+    pub apply_limiter: crate::throttle::ApplyLimiter,
+    pub connections: crate::connections::ConnectionCache,
+    pub telemetry: crate::telemetry::Telemetry,
+}
+
+#[async_trait]
+impl Resource for SchemaCloneResource {
+    type State<'a> = SchemaCloneResourceState<'a>;
+    type PrivateState<'a> = ValueEmpty;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Applies the schema of a source database to a target database",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Resource identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "source_database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL of the database to copy the schema from"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL of the database to apply the schema to"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "target_schemas" => Attribute {
+                        description: Description::plain("PostgreSQL schemas to clone (default: public)"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "allow_destructive" => Attribute {
+                        description: Description::plain("Allow dropping objects in the target that the source doesn't have"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "source_hash" => Attribute {
+                        description: Description::plain("SHA256 hash of the source schema's canonical SQL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "cloned_at" => Attribute {
+                        description: Description::plain("Timestamp of last clone"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "migration_count" => Attribute {
+                        description: Description::plain("Number of operations applied"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        crate::util::check_database_url(
+            diags,
+            config.database_url.as_ref_option().map(|u| u.as_ref()),
+        );
+        check_source_database_url(diags, &config);
+        Some(())
+    }
+
+    async fn read<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        state: Self::State<'a>,
+        private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let mut state = state;
+
+        // source_hash records the source schema last applied; if the target no
+        // longer matches it, forget it so plan_update schedules a new clone
+        if let (Value::Value(stored), Value::Value(database_url)) =
+            (&state.source_hash, &state.database_url)
+        {
+            let live = self
+                .schema_hash(diags, database_url, &target_schemas(&state), "database_url")
+                .await?;
+            if live != *stored {
+                state.source_hash = Value::Null;
+            }
+        }
+
+        Some((state, private_state))
+    }
+
+    async fn plan_create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let source_hash = self.source_hash(diags, &proposed_state).await?;
+
+        let mut state = proposed_state;
+        state.id = resource_id(&state);
+        state.source_hash = source_hash;
+        // Mark computed fields as Unknown during plan so Terraform knows they'll be set during apply
+        state.cloned_at = Value::Unknown;
+        state.migration_count = Value::Unknown;
+
+        Some((state, Default::default()))
+    }
+
+    async fn plan_update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
+        let source_hash = self.source_hash(diags, &proposed_state).await?;
+
+        // Pointing at another target database is a different clone; rotated
+        // credentials for the same database are not
+        let mut requires_replace = vec![];
+        if resource_id(&prior_state) != resource_id(&proposed_state) {
+            requires_replace.push(AttributePath::new("database_url"));
+        }
+
+        let needs_apply = !source_hash.is_value()
+            || prior_state.source_hash != source_hash
+            || prior_state.database_url != proposed_state.database_url
+            || prior_state.target_schemas != proposed_state.target_schemas;
+
+        let mut state = proposed_state;
+        state.id = resource_id(&state);
+        state.source_hash = source_hash;
+        if needs_apply {
+            state.cloned_at = Value::Unknown;
+            state.migration_count = Value::Unknown;
+        } else {
+            state.cloned_at = prior_state.cloned_at;
+            state.migration_count = prior_state.migration_count;
+        }
+
+        Some((state, prior_private_state, requires_replace))
+    }
+
+    async fn plan_destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::PrivateState<'a>> {
+        Some(prior_private_state)
+    }
+
+    async fn create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let state = self.apply_clone(diags, planned_state, "create").await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        // Only settings used by the next clone changed
+        if planned_state.cloned_at.is_value() {
+            return Some((planned_state, planned_private_state));
+        }
+        let state = self.apply_clone(diags, planned_state, "update").await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        Some(())
+    }
+}
+
+impl SchemaCloneResource {
+    /// Hash of the source schema, Unknown while the source URL isn't known yet.
+    async fn source_hash(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaCloneResourceState<'_>,
+    ) -> Option<ValueString<'static>> {
+        let Value::Value(source_url) = &state.source_database_url else {
+            return Some(Value::Unknown);
+        };
+        if state.target_schemas.is_unknown() {
+            return Some(Value::Unknown);
+        }
+        let hash = self
+            .schema_hash(
+                diags,
+                source_url,
+                &target_schemas(state),
+                "source_database_url",
+            )
+            .await?;
+        Some(Value::Value(Cow::Owned(hash)))
+    }
+
+    async fn schema_hash(
+        &self,
+        diags: &mut Diagnostics,
+        url: &str,
+        schemas: &[String],
+        attribute: &'static str,
+    ) -> Option<String> {
+        let schema = self.introspect(diags, url, schemas, attribute).await?;
+        Some(crate::util::compute_content_hash(
+            &crate::pipeline::canonical_sql(&schema),
+        ))
+    }
+
+    async fn introspect(
+        &self,
+        diags: &mut Diagnostics,
+        url: &str,
+        schemas: &[String],
+        attribute: &'static str,
+    ) -> Option<pgmold::model::Schema> {
+        let connection = self.connections.connect_read_only(diags, url).await?;
+        match crate::pipeline::introspect(&connection, schemas).await {
+            Ok(schema) => Some(schema),
+            Err(e) => {
+                diags.error_short(
+                    format!("Failed to introspect database: {e}"),
+                    AttributePath::new(attribute),
+                );
+                None
+            }
+        }
+    }
+
+    async fn apply_clone<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        state: SchemaCloneResourceState<'a>,
+        operation: &'static str,
+    ) -> Option<SchemaCloneResourceState<'a>> {
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let applied = self.clone_schema(diags, &state).await;
+        self.telemetry
+            .record("pgmold_schema_clone", operation, started, applied.is_some())
+            .await;
+        let (source_hash, count) = applied?;
+
+        let mut state = state;
+        state.source_hash = Value::Value(Cow::Owned(source_hash));
+        state.cloned_at = Value::Value(Cow::Owned(crate::clock::now().to_rfc3339()));
+        state.migration_count = Value::Value(count as i64);
+        Some(state)
+    }
+
+    /// Introspects the source, diffs it against the target and executes the
+    /// result. Returns the hash of the source schema applied and the number
+    /// of operations executed.
+    async fn clone_schema(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaCloneResourceState<'_>,
+    ) -> Option<(String, usize)> {
+        let schemas = target_schemas(state);
+        let source = self
+            .introspect(
+                diags,
+                state.source_database_url.as_str(),
+                &schemas,
+                "source_database_url",
+            )
+            .await?;
+        let source_hash =
+            crate::util::compute_content_hash(&crate::pipeline::canonical_sql(&source));
+
+        let connection = self
+            .connections
+            .connect(diags, state.database_url.as_str())
+            .await?;
+        let missing = match crate::execute::missing_schemas(connection.pool(), &schemas).await {
+            Ok(m) => m,
+            Err(e) => {
+                diags.root_error_short(format!("Failed to check target_schemas: {e}"));
+                return None;
+            }
+        };
+        if let Err(e) = crate::execute::create_schemas(connection.pool(), &missing).await {
+            diags.error_short(
+                format!("Failed to create target_schemas: {e}"),
+                AttributePath::new("target_schemas"),
+            );
+            return None;
+        }
+
+        let options = crate::pipeline::PlanOptions {
+            target_schemas: schemas,
+            lint_rules: self.connections.lint_rules().await,
+            escalate_warnings: self.connections.escalate_warnings().await,
+            operation_policy: self.connections.operation_policy().await,
+            lint_options: pgmold::lint::LintOptions {
+                allow_destructive: state.allow_destructive.unwrap_or(false),
+                is_production: false,
+            },
+            declared_kinds_only: false,
+            only_objects: None,
+            objects: Default::default(),
+            normalize_expressions: false,
+            skip_unreadable_schemas: false,
+            dialect: crate::dialect::Dialect::default(),
+            phase: None,
+            server_version: self.connections.target_server_version().await,
+            rebuild_enums: false,
+            defer_validation: false,
+            preserve_sequences: false,
+            preserve_data: false,
+        };
+        let plan = match crate::pipeline::plan_to_schema(&connection, source, &options).await {
+            Ok(p) => p,
+            Err(e) => {
+                diags.error_short(
+                    format!("Clone failed: {e}"),
+                    AttributePath::new("database_url"),
+                );
+                return None;
+            }
+        };
+        if crate::pipeline::report_lint_errors(diags, &plan.lint_results) {
+            return None;
+        }
+        tracing::info!(
+            resource = "pgmold_schema_clone",
+            id = state.id.as_str(),
+            operations = plan.operations.len(),
+            "cloning schema"
+        );
+
+        if let Err(e) = crate::execute::execute_statements(
+            connection.pool(),
+            &plan.statements,
+            &crate::execute::ExecuteOptions::default(),
+        )
+        .instrument(tracing::info_span!(
+            "apply",
+            statements = plan.statements.len()
+        ))
+        .await
+        {
+            diags.root_error_short(format!("Clone failed: {e}"));
+            return None;
+        }
+
+        Some((source_hash, plan.operations.len()))
+    }
+}
+
+/// Checks `source_database_url` is a PostgreSQL URL for a database other
+/// than the target.
+fn check_source_database_url(diags: &mut Diagnostics, config: &SchemaCloneResourceState) {
+    if let Value::Value(source) = &config.source_database_url {
+        if !source.starts_with("postgres://") && !source.starts_with("postgresql://") {
+            diags.error(
+                "Invalid source_database_url",
+                "source_database_url must be a URL starting with postgres:// or postgresql://",
+                AttributePath::new("source_database_url"),
+            );
+        }
+    }
+    if let (Value::Value(source), Value::Value(target)) =
+        (&config.source_database_url, &config.database_url)
+    {
+        if crate::util::database_identity(source) == crate::util::database_identity(target) {
+            diags.error_short(
+                "source_database_url and database_url point at the same database",
+                AttributePath::new("database_url"),
+            );
+        }
+    }
+}
+
+/// `pgmold-clone-<hash>` of the target database identity and schemas.
+fn resource_id(state: &SchemaCloneResourceState) -> ValueString<'static> {
+    let Value::Value(database_url) = &state.database_url else {
+        return Value::Unknown;
+    };
+    if state.target_schemas.is_unknown() {
+        return Value::Unknown;
+    }
+    let mut schemas = target_schemas(state);
+    schemas.sort();
+    let identity = format!(
+        "{}|{}",
+        crate::util::database_identity(database_url),
+        schemas.join(",")
+    );
+    let hash = crate::util::compute_content_hash(&identity);
+    Value::Value(Cow::Owned(format!("pgmold-clone-{}", &hash[..8])))
+}
+
+fn target_schemas(state: &SchemaCloneResourceState) -> Vec<String> {
+    let schemas: Vec<String> = state
+        .target_schemas
+        .iter()
+        .flatten()
+        .filter_map(|s| s.as_ref_option().map(|s| s.to_string()))
+        .collect();
+    if schemas.is_empty() {
+        vec!["public".to_string()]
+    } else {
+        schemas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_id_ignores_credentials() {
+        let state = SchemaCloneResourceState {
+            database_url: Value::Value(Cow::Borrowed("postgres://dev:secret@db:5432/pr_42")),
+            ..Default::default()
+        };
+        let rotated = SchemaCloneResourceState {
+            database_url: Value::Value(Cow::Borrowed("postgres://dev:rotated@db:5432/pr_42")),
+            ..Default::default()
+        };
+
+        let Value::Value(id) = resource_id(&state) else {
+            panic!("expected a known id");
+        };
+        assert!(id.starts_with("pgmold-clone-"));
+        assert_eq!(resource_id(&state), resource_id(&rotated));
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_cloning_onto_the_source() {
+        let config = SchemaCloneResourceState {
+            source_database_url: Value::Value(Cow::Borrowed("postgres://ro@db:5432/staging")),
+            database_url: Value::Value(Cow::Borrowed("postgres://admin@db:5432/staging")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        SchemaCloneResource::default()
+            .validate(&mut diags, config)
+            .await;
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("database_url")
+        );
+    }
+}
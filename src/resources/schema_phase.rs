@@ -0,0 +1,531 @@
+use std::borrow::Cow;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::{Value, ValueBool, ValueEmpty, ValueList, ValueNumber, ValueString},
+    AttributePath, Diagnostics, Resource,
+};
+use tracing::Instrument;
+
+use crate::operations::Phase;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SchemaPhaseResourceState<'a> {
+    #[serde(borrow)]
+    pub id: ValueString<'a>,
+    #[serde(borrow)]
+    pub phase: ValueString<'a>,
+    #[serde(borrow)]
+    pub schema_file: ValueString<'a>,
+    #[serde(borrow)]
+    pub base_dir: ValueString<'a>,
+    #[serde(borrow)]
+    pub database_url: ValueString<'a>,
+    #[serde(borrow)]
+    pub target_schemas: ValueList<ValueString<'a>>,
+    pub allow_destructive: ValueBool,
+    pub defer_constraint_validation: ValueBool,
+    pub concurrent_index_retries: ValueNumber,
+    #[serde(borrow)]
+    pub dialect: ValueString<'a>,
+    #[serde(borrow)]
+    pub schema_hash: ValueString<'a>,
+    #[serde(borrow)]
+    pub applied_at: ValueString<'a>,
+    pub migration_count: ValueNumber,
+}
+
+/// Applies one half of an expand/contract rollout of a schema file, so the
+/// expand phase can run before an application deploy and the contract phase after.
+#[derive(Debug, Default, Clone)]
+pub struct SchemaPhaseResource {
+    pub apply_limiter: crate::throttle::ApplyLimiter,
+    pub connections: crate::connections::ConnectionCache,
+    pub telemetry: crate::telemetry::Telemetry,
+}
+
+#[async_trait]
+impl Resource for SchemaPhaseResource {
+    type State<'a> = SchemaPhaseResourceState<'a>;
+    type PrivateState<'a> = ValueEmpty;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Applies the expand or contract subset of a schema diff",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Resource identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "phase" => Attribute {
+                        description: Description::plain("expand (additive operations) or contract (drops and rewrites). Changing it forces replacement"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "schema_file" => Attribute {
+                        description: Description::plain("Path to SQL schema file"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "base_dir" => Attribute {
+                        description: Description::plain("Directory relative schema_file paths resolve against, e.g. path.module (default: working directory)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "target_schemas" => Attribute {
+                        description: Description::plain("PostgreSQL schemas to manage"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "allow_destructive" => Attribute {
+                        description: Description::plain("Allow destructive operations in the contract phase"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "defer_constraint_validation" => Attribute {
+                        description: Description::plain("Add foreign keys and check constraints to existing tables NOT VALID in the expand phase; the contract phase validates every NOT VALID one in target_schemas (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "concurrent_index_retries" => Attribute {
+                        description: Description::plain("Times a failed CREATE INDEX CONCURRENTLY is retried after dropping the invalid index it left; the invalid index is dropped even without retries (default: 0)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "dialect" => Attribute {
+                        description: Description::plain("Target engine: postgres, cockroachdb or aurora (default: postgres)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "schema_hash" => Attribute {
+                        description: Description::plain("SHA256 hash of schema file"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "applied_at" => Attribute {
+                        description: Description::plain("Timestamp of last apply"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "migration_count" => Attribute {
+                        description: Description::plain("Number of operations applied"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        crate::util::check_database_url(
+            diags,
+            config.database_url.as_ref_option().map(|u| u.as_ref()),
+        );
+        crate::dialect::check_dialect(diags, config.dialect.as_ref_option().map(|d| d.as_ref()));
+        check_phase(diags, config.phase.as_ref_option().map(|p| p.as_ref()));
+        crate::execute::check_concurrent_index_retries(
+            diags,
+            config.concurrent_index_retries.as_ref_option().copied(),
+        );
+        Some(())
+    }
+
+    async fn read<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        state: Self::State<'a>,
+        private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        Some((state, private_state))
+    }
+
+    async fn plan_create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let schema_hash = check_schema_file(diags, &proposed_state)?;
+
+        let mut state = proposed_state;
+        state.id = resource_id(&state);
+        state.schema_hash = Value::Value(Cow::Owned(schema_hash));
+        // Mark computed fields as Unknown during plan so Terraform knows they'll be set during apply
+        state.applied_at = Value::Unknown;
+        state.migration_count = Value::Unknown;
+
+        Some((state, Default::default()))
+    }
+
+    async fn plan_update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
+        let schema_hash = check_schema_file(diags, &proposed_state)?;
+
+        let needs_apply = prior_state.schema_hash.as_str() != schema_hash
+            || prior_state.database_url != proposed_state.database_url
+            || prior_state.target_schemas != proposed_state.target_schemas;
+
+        // A phase resource owns one half of the rollout; switching halves is a new resource
+        let mut requires_replace = vec![];
+        if prior_state.phase != proposed_state.phase {
+            requires_replace.push(AttributePath::new("phase"));
+        }
+        if prior_state.target_schemas != proposed_state.target_schemas {
+            requires_replace.push(AttributePath::new("target_schemas"));
+        }
+
+        let mut state = proposed_state;
+        state.id = resource_id(&state);
+        state.schema_hash = Value::Value(Cow::Owned(schema_hash));
+        if needs_apply {
+            state.applied_at = Value::Unknown;
+            state.migration_count = Value::Unknown;
+        } else {
+            state.applied_at = prior_state.applied_at;
+            state.migration_count = prior_state.migration_count;
+        }
+
+        Some((state, prior_private_state, requires_replace))
+    }
+
+    async fn plan_destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::PrivateState<'a>> {
+        Some(prior_private_state)
+    }
+
+    async fn create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let state = self.apply_phase(diags, planned_state, "create").await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        // Only non-schema settings changed; plan_update kept the prior apply results
+        if planned_state.applied_at.is_value() {
+            return Some((planned_state, planned_private_state));
+        }
+        let state = self.apply_phase(diags, planned_state, "update").await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        Some(())
+    }
+}
+
+impl SchemaPhaseResource {
+    /// Diffs the schema file against the database and executes the operations
+    /// belonging to the resource's phase.
+    async fn apply_phase<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        state: SchemaPhaseResourceState<'a>,
+        operation: &'static str,
+    ) -> Option<SchemaPhaseResourceState<'a>> {
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let applied = self.execute_phase(diags, &state).await;
+        self.telemetry
+            .record("pgmold_schema_phase", operation, started, applied.is_some())
+            .await;
+        let kinds = applied?;
+
+        if !kinds.is_empty() {
+            diags.root_warning_short(crate::operations::summarize_applied(&kinds));
+        }
+
+        let mut state = state;
+        state.applied_at = Value::Value(Cow::Owned(crate::clock::now().to_rfc3339()));
+        state.migration_count = Value::Value(kinds.len() as i64);
+        Some(state)
+    }
+
+    /// Returns the kinds of the operations executed.
+    async fn execute_phase(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaPhaseResourceState<'_>,
+    ) -> Option<Vec<String>> {
+        let connection = self
+            .connections
+            .connect(diags, state.database_url.as_str())
+            .await?;
+        let dialect = state
+            .dialect
+            .as_ref_option()
+            .and_then(|d| crate::dialect::Dialect::parse(d))
+            .unwrap_or_default();
+        let options = crate::pipeline::PlanOptions {
+            target_schemas: target_schemas(state),
+            lint_rules: self.connections.lint_rules().await,
+            escalate_warnings: self.connections.escalate_warnings().await,
+            operation_policy: self.connections.operation_policy().await,
+            lint_options: pgmold::lint::LintOptions {
+                allow_destructive: state.allow_destructive.unwrap_or(false),
+                is_production: false,
+            },
+            declared_kinds_only: false,
+            only_objects: None,
+            objects: Default::default(),
+            normalize_expressions: false,
+            skip_unreadable_schemas: false,
+            dialect,
+            phase: phase(state),
+            server_version: self.connections.target_server_version().await,
+            rebuild_enums: false,
+            defer_validation: state.defer_constraint_validation.unwrap_or(false),
+            preserve_sequences: false,
+            preserve_data: false,
+        };
+
+        let plan =
+            match crate::pipeline::plan_migration(&connection, &schema_file_path(state), &options)
+                .await
+            {
+                Ok(p) => p,
+                Err(e) => {
+                    diags.error_short(format!("Migration failed: {e}"), e.attribute());
+                    return None;
+                }
+            };
+        if crate::pipeline::report_lint_errors(diags, &plan.lint_results) {
+            return None;
+        }
+        tracing::info!(
+            resource = "pgmold_schema_phase",
+            id = state.id.as_str(),
+            phase = state.phase.as_str(),
+            operations = plan.operations.len(),
+            "applying schema phase"
+        );
+
+        let execute_options = crate::execute::ExecuteOptions {
+            batch: false,
+            standalone: !dialect.transactional_ddl(),
+            rebuild: None,
+            chunk_size: None,
+            concurrent_index_retries: state
+                .concurrent_index_retries
+                .as_ref_option()
+                .map_or(0, |&n| n.max(0) as u32),
+            failpoints: self.connections.failpoints().await,
+            continue_on: Vec::new(),
+        };
+        if let Err(e) = crate::execute::execute_statements(
+            connection.pool(),
+            &plan.statements,
+            &execute_options,
+        )
+        .instrument(tracing::info_span!(
+            "apply",
+            statements = plan.statements.len()
+        ))
+        .await
+        {
+            diags.root_error_short(format!("Migration failed: {e}"));
+            return None;
+        }
+
+        Some(
+            plan.operations
+                .iter()
+                .map(crate::operations::operation_kind)
+                .collect(),
+        )
+    }
+}
+
+/// Reports an unknown `phase` value on the attribute.
+fn check_phase(diags: &mut Diagnostics, phase: Option<&str>) -> Option<()> {
+    match phase {
+        Some(name) if Phase::parse(name).is_none() => {
+            diags.error_short(
+                format!(
+                    "Unknown phase \"{name}\", expected one of: {}",
+                    Phase::NAMES.join(", ")
+                ),
+                AttributePath::new("phase"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+/// Checks the schema file exists and parses, returning its hash.
+fn check_schema_file(diags: &mut Diagnostics, state: &SchemaPhaseResourceState) -> Option<String> {
+    let schema_file = schema_file_path(state);
+    if !std::path::Path::new(&schema_file).exists() {
+        diags.error_short(
+            format!("schema_file not found: {schema_file}"),
+            AttributePath::new("schema_file"),
+        );
+        return None;
+    }
+    crate::pipeline::check_schema_syntax(diags, &schema_file)?;
+    check_phase(diags, state.phase.as_ref_option().map(|p| p.as_ref()))?;
+
+    match crate::util::compute_schema_hash(std::path::Path::new(&schema_file)) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            diags.error_short(
+                format!("Failed to read schema file: {e}"),
+                AttributePath::new("schema_file"),
+            );
+            None
+        }
+    }
+}
+
+/// `pgmold-<phase>-<hash>` of the database identity and target schemas, so the
+/// expand and contract resources of one rollout get distinct, stable ids.
+fn resource_id(state: &SchemaPhaseResourceState) -> ValueString<'static> {
+    let (Value::Value(phase), Value::Value(database_url)) = (&state.phase, &state.database_url)
+    else {
+        return Value::Unknown;
+    };
+    if state.target_schemas.is_unknown() {
+        return Value::Unknown;
+    }
+
+    let mut schemas = target_schemas(state);
+    schemas.sort();
+    let identity = format!(
+        "{}|{}",
+        crate::util::database_identity(database_url),
+        schemas.join(",")
+    );
+    let hash = crate::util::compute_content_hash(&identity);
+    Value::Value(Cow::Owned(format!("pgmold-{phase}-{}", &hash[..8])))
+}
+
+fn phase(state: &SchemaPhaseResourceState) -> Option<Phase> {
+    state.phase.as_ref_option().and_then(|p| Phase::parse(p))
+}
+
+fn schema_file_path(state: &SchemaPhaseResourceState) -> String {
+    crate::util::resolve_path(
+        state.base_dir.as_ref_option().map(|s| s.as_ref()),
+        state.schema_file.as_str(),
+    )
+}
+
+fn target_schemas(state: &SchemaPhaseResourceState) -> Vec<String> {
+    let schemas: Vec<String> = state
+        .target_schemas
+        .iter()
+        .flatten()
+        .filter_map(|s| s.as_ref_option().map(|s| s.to_string()))
+        .collect();
+    if schemas.is_empty() {
+        vec!["public".to_string()]
+    } else {
+        schemas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_id_differs_per_phase() {
+        let expand = SchemaPhaseResourceState {
+            phase: Value::Value(Cow::Borrowed("expand")),
+            database_url: Value::Value(Cow::Borrowed("postgres://app@db:5432/app")),
+            ..Default::default()
+        };
+        let contract = SchemaPhaseResourceState {
+            phase: Value::Value(Cow::Borrowed("contract")),
+            ..expand.clone()
+        };
+
+        let Value::Value(expand_id) = resource_id(&expand) else {
+            panic!("expected a known id");
+        };
+        assert!(expand_id.starts_with("pgmold-expand-"));
+        assert_ne!(resource_id(&expand), resource_id(&contract));
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_unknown_phase() {
+        let config = SchemaPhaseResourceState {
+            phase: Value::Value(Cow::Borrowed("migrate")),
+            schema_file: Value::Value(Cow::Borrowed("schema.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://db/app")),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        SchemaPhaseResource::default()
+            .validate(&mut diags, config)
+            .await;
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("phase"));
+    }
+}
@@ -0,0 +1,516 @@
+use std::borrow::Cow;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::{Value, ValueEmpty, ValueString},
+    AttributePath, Diagnostics, Resource,
+};
+
+use crate::functions::quote::quote_ident;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SchemaSwapResourceState<'a> {
+    #[serde(borrow)]
+    pub id: ValueString<'a>,
+    #[serde(borrow)]
+    pub schema_file: ValueString<'a>,
+    #[serde(borrow)]
+    pub base_dir: ValueString<'a>,
+    #[serde(borrow)]
+    pub database_url: ValueString<'a>,
+    #[serde(borrow)]
+    pub schema: ValueString<'a>,
+    #[serde(borrow)]
+    pub staging_schema: ValueString<'a>,
+    #[serde(borrow)]
+    pub previous_schema: ValueString<'a>,
+    #[serde(borrow)]
+    pub populate_sql: ValueString<'a>,
+    #[serde(borrow)]
+    pub validation_sql: ValueString<'a>,
+    #[serde(borrow)]
+    pub schema_hash: ValueString<'a>,
+    #[serde(borrow)]
+    pub swapped_at: ValueString<'a>,
+}
+
+/// Blue/green deployment of a schema: builds the schema file into a fresh
+/// namespace, validates it, then renames it into place in one transaction,
+/// keeping the replaced namespace for rollback.
+#[derive(Debug, Default, Clone)]
+pub struct SchemaSwapResource {
+    pub apply_limiter: crate::throttle::ApplyLimiter,
+    pub connections: crate::connections::ConnectionCache,
+    pub telemetry: crate::telemetry::Telemetry,
+}
+
+#[async_trait]
+impl Resource for SchemaSwapResource {
+    type State<'a> = SchemaSwapResourceState<'a>;
+    type PrivateState<'a> = ValueEmpty;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Builds a schema in a new namespace and swaps it into place",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Resource identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "schema_file" => Attribute {
+                        description: Description::plain("Path to SQL schema file, with unqualified object names"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "base_dir" => Attribute {
+                        description: Description::plain("Directory relative schema_file paths resolve against, e.g. path.module (default: working directory)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "schema" => Attribute {
+                        description: Description::plain("Namespace the application uses, replaced on each swap"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "staging_schema" => Attribute {
+                        description: Description::plain("Namespace the new version is built in (default: <schema>_next)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "previous_schema" => Attribute {
+                        description: Description::plain("Name the replaced namespace is kept under for rollback (default: <schema>_previous)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "populate_sql" => Attribute {
+                        description: Description::plain("SQL run in the new namespace after it is built, e.g. to copy data from the live schema"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "validation_sql" => Attribute {
+                        description: Description::plain("Query returning one boolean, run in the new namespace; the swap is aborted unless it returns true"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "schema_hash" => Attribute {
+                        description: Description::plain("SHA256 hash of schema file"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "swapped_at" => Attribute {
+                        description: Description::plain("Timestamp of the last swap"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        crate::util::check_database_url(
+            diags,
+            config.database_url.as_ref_option().map(|u| u.as_ref()),
+        );
+        if let Some(names) = namespaces(&config) {
+            check_distinct_namespaces(diags, &names);
+        }
+        Some(())
+    }
+
+    async fn read<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        state: Self::State<'a>,
+        private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        Some((state, private_state))
+    }
+
+    async fn plan_create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let schema_hash = check_schema_file(diags, &proposed_state)?;
+
+        let mut state = proposed_state;
+        state.id = match &state.schema {
+            Value::Value(schema) => Value::Value(Cow::Owned(format!("pgmold-swap-{schema}"))),
+            _ => Value::Unknown,
+        };
+        state.schema_hash = Value::Value(Cow::Owned(schema_hash));
+        // Mark computed fields as Unknown during plan so Terraform knows they'll be set during apply
+        state.swapped_at = Value::Unknown;
+
+        Some((state, Default::default()))
+    }
+
+    async fn plan_update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
+        let schema_hash = check_schema_file(diags, &proposed_state)?;
+
+        // A different live namespace is a different deployment
+        let mut requires_replace = vec![];
+        if prior_state.schema != proposed_state.schema {
+            requires_replace.push(AttributePath::new("schema"));
+        }
+
+        let needs_swap = prior_state.schema_hash.as_str() != schema_hash
+            || prior_state.database_url != proposed_state.database_url;
+
+        let mut state = proposed_state;
+        state.id = prior_state.id;
+        state.schema_hash = Value::Value(Cow::Owned(schema_hash));
+        state.swapped_at = if needs_swap {
+            Value::Unknown
+        } else {
+            prior_state.swapped_at
+        };
+
+        Some((state, prior_private_state, requires_replace))
+    }
+
+    async fn plan_destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::PrivateState<'a>> {
+        Some(prior_private_state)
+    }
+
+    async fn create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let state = self.build_and_swap(diags, planned_state, "create").await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        // Only settings used by the next swap changed
+        if planned_state.swapped_at.is_value() {
+            return Some((planned_state, planned_private_state));
+        }
+        let state = self.build_and_swap(diags, planned_state, "update").await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        Some(())
+    }
+}
+
+/// The live, staging and previous namespace names, once all are known.
+#[derive(Debug, PartialEq)]
+struct Namespaces {
+    live: String,
+    staging: String,
+    previous: String,
+}
+
+fn namespaces(state: &SchemaSwapResourceState) -> Option<Namespaces> {
+    let Value::Value(live) = &state.schema else {
+        return None;
+    };
+    let name_or = |value: &ValueString, suffix: &str| match value {
+        Value::Value(name) => Some(name.to_string()),
+        Value::Null => Some(format!("{live}_{suffix}")),
+        Value::Unknown => None,
+    };
+    Some(Namespaces {
+        live: live.to_string(),
+        staging: name_or(&state.staging_schema, "next")?,
+        previous: name_or(&state.previous_schema, "previous")?,
+    })
+}
+
+fn check_distinct_namespaces(diags: &mut Diagnostics, names: &Namespaces) -> Option<()> {
+    let conflict = if names.staging == names.live || names.staging == names.previous {
+        Some("staging_schema")
+    } else if names.previous == names.live {
+        Some("previous_schema")
+    } else {
+        None
+    };
+    if let Some(attribute) = conflict {
+        diags.error(
+            "Swap namespaces must be distinct",
+            format!(
+                "schema, staging_schema and previous_schema resolve to \"{}\", \"{}\" and \"{}\"",
+                names.live, names.staging, names.previous
+            ),
+            AttributePath::new(attribute),
+        );
+        return None;
+    }
+    Some(())
+}
+
+/// Retires the previous rollback copy, moves the live namespace aside and
+/// renames the staging namespace into place.
+fn swap_statements(names: &Namespaces, live_exists: bool) -> Vec<String> {
+    let mut statements = vec![format!(
+        "DROP SCHEMA IF EXISTS {} CASCADE",
+        quote_ident(&names.previous)
+    )];
+    if live_exists {
+        statements.push(format!(
+            "ALTER SCHEMA {} RENAME TO {}",
+            quote_ident(&names.live),
+            quote_ident(&names.previous)
+        ));
+    }
+    statements.push(format!(
+        "ALTER SCHEMA {} RENAME TO {}",
+        quote_ident(&names.staging),
+        quote_ident(&names.live)
+    ));
+    statements
+}
+
+impl SchemaSwapResource {
+    async fn build_and_swap<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        state: SchemaSwapResourceState<'a>,
+        operation: &'static str,
+    ) -> Option<SchemaSwapResourceState<'a>> {
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let swapped = self.swap(diags, &state).await;
+        self.telemetry
+            .record("pgmold_schema_swap", operation, started, swapped.is_some())
+            .await;
+        swapped?;
+
+        let mut state = state;
+        state.swapped_at = Value::Value(Cow::Owned(crate::clock::now().to_rfc3339()));
+        Some(state)
+    }
+
+    /// Builds, populates, validates and swaps in a single transaction, so any
+    /// failure leaves the live namespace untouched.
+    async fn swap(
+        &self,
+        diags: &mut Diagnostics,
+        state: &SchemaSwapResourceState<'_>,
+    ) -> Option<()> {
+        let names = namespaces(state)?;
+        check_distinct_namespaces(diags, &names)?;
+        let schema_file = schema_file_path(state);
+        let content = match crate::util::read_schema_file(std::path::Path::new(&schema_file)) {
+            Ok(content) => content,
+            Err(e) => {
+                diags.error_short(
+                    format!("Failed to read schema file: {e}"),
+                    AttributePath::new("schema_file"),
+                );
+                return None;
+            }
+        };
+
+        let connection = self
+            .connections
+            .connect(diags, state.database_url.as_str())
+            .await?;
+        tracing::info!(
+            resource = "pgmold_schema_swap",
+            schema = names.live,
+            staging = names.staging,
+            "building schema for swap"
+        );
+
+        match run_swap(connection.pool(), &names, state, &content).await {
+            Ok(true) => Some(()),
+            Ok(false) => {
+                diags.error(
+                    "Swap validation failed",
+                    format!(
+                        "validation_sql did not return true for {}; the live schema was left in place",
+                        names.staging
+                    ),
+                    AttributePath::new("validation_sql"),
+                );
+                None
+            }
+            Err(e) => {
+                diags.root_error_short(format!("Schema swap failed: {e}"));
+                None
+            }
+        }
+    }
+}
+
+/// Returns `false` when `validation_sql` rejected the build.
+async fn run_swap(
+    pool: &sqlx::PgPool,
+    names: &Namespaces,
+    state: &SchemaSwapResourceState<'_>,
+    content: &str,
+) -> anyhow::Result<bool> {
+    let staging = quote_ident(&names.staging);
+    let mut tx = pool.begin().await?;
+    sqlx::raw_sql(&format!("DROP SCHEMA IF EXISTS {staging} CASCADE"))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::raw_sql(&format!("CREATE SCHEMA {staging}"))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::raw_sql(&format!("SET LOCAL search_path TO {staging}"))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::raw_sql(content).execute(&mut *tx).await?;
+
+    if let Value::Value(populate_sql) = &state.populate_sql {
+        sqlx::raw_sql(populate_sql).execute(&mut *tx).await?;
+    }
+    if let Value::Value(validation_sql) = &state.validation_sql {
+        let valid: bool = sqlx::query_scalar(validation_sql)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !valid {
+            return Ok(false);
+        }
+    }
+
+    let (live_exists,): (bool,) =
+        sqlx::query_as("SELECT EXISTS (SELECT 1 FROM pg_namespace WHERE nspname = $1)")
+            .bind(&names.live)
+            .fetch_one(&mut *tx)
+            .await?;
+    for statement in swap_statements(names, live_exists) {
+        sqlx::raw_sql(&statement).execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Checks the schema file exists, returning its hash.
+fn check_schema_file(diags: &mut Diagnostics, state: &SchemaSwapResourceState) -> Option<String> {
+    let schema_file = schema_file_path(state);
+    match crate::util::compute_schema_hash(std::path::Path::new(&schema_file)) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            diags.error_short(
+                format!("Failed to read schema file {schema_file}: {e}"),
+                AttributePath::new("schema_file"),
+            );
+            None
+        }
+    }
+}
+
+fn schema_file_path(state: &SchemaSwapResourceState) -> String {
+    crate::util::resolve_path(
+        state.base_dir.as_ref_option().map(|s| s.as_ref()),
+        state.schema_file.as_str(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Namespaces {
+        Namespaces {
+            live: "app".to_string(),
+            staging: "app_next".to_string(),
+            previous: "app_previous".to_string(),
+        }
+    }
+
+    #[test]
+    fn namespaces_default_from_schema() {
+        let state = SchemaSwapResourceState {
+            schema: Value::Value(Cow::Borrowed("app")),
+            ..Default::default()
+        };
+        assert_eq!(namespaces(&state), Some(names()));
+    }
+
+    #[test]
+    fn swap_keeps_the_live_schema_for_rollback() {
+        assert_eq!(
+            swap_statements(&names(), true),
+            vec![
+                "DROP SCHEMA IF EXISTS \"app_previous\" CASCADE",
+                "ALTER SCHEMA \"app\" RENAME TO \"app_previous\"",
+                "ALTER SCHEMA \"app_next\" RENAME TO \"app\"",
+            ]
+        );
+        assert_eq!(swap_statements(&names(), false).len(), 2);
+    }
+
+    #[test]
+    fn namespaces_must_be_distinct() {
+        let mut diags = Diagnostics::default();
+        let names = Namespaces {
+            previous: "app".to_string(),
+            ..names()
+        };
+        assert!(check_distinct_namespaces(&mut diags, &names).is_none());
+        assert_eq!(
+            diags.errors[0].attribute,
+            AttributePath::new("previous_schema")
+        );
+    }
+}
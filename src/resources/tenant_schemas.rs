@@ -0,0 +1,793 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tf_provider::{
+    map,
+    schema::{Attribute, AttributeConstraint, AttributeType, Block, Description, Schema},
+    value::{Value, ValueBool, ValueEmpty, ValueList, ValueMap, ValueNumber, ValueString},
+    AttributePath, Diagnostics, Resource,
+};
+use tracing::Instrument;
+
+use crate::functions::quote::quote_ident;
+
+/// Placeholder in the schema file replaced by each tenant's quoted schema name.
+const TENANT_PLACEHOLDER: &str = "{tenant}";
+
+const APPLIED: &str = "applied";
+const FAILED: &str = "failed";
+const PENDING: &str = "pending";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TenantSchemasResourceState<'a> {
+    #[serde(borrow)]
+    pub id: ValueString<'a>,
+    #[serde(borrow)]
+    pub schema_file: ValueString<'a>,
+    #[serde(borrow)]
+    pub base_dir: ValueString<'a>,
+    #[serde(borrow)]
+    pub database_url: ValueString<'a>,
+    #[serde(borrow)]
+    pub tenants: ValueList<ValueString<'a>>,
+    #[serde(borrow)]
+    pub tenants_query: ValueString<'a>,
+    pub allow_destructive: ValueBool,
+    pub parallelism: ValueNumber,
+    pub continue_on_error: ValueBool,
+    #[serde(borrow)]
+    pub schema_hash: ValueString<'a>,
+    #[serde(borrow)]
+    pub tenant_status: ValueMap<'a, ValueString<'a>>,
+    #[serde(borrow)]
+    pub tenant_results: ValueMap<'a, ValueString<'a>>,
+    #[serde(borrow)]
+    pub applied_at: ValueString<'a>,
+    pub migration_count: ValueNumber,
+}
+
+/// Applies one schema file to every tenant schema of a database, tracking
+/// which tenants are up to date so a failed apply only retries the rest.
+#[derive(Debug, Default, Clone)]
+pub struct TenantSchemasResource {
+    pub apply_limiter: crate::throttle::ApplyLimiter,
+    pub connections: crate::connections::ConnectionCache,
+    pub telemetry: crate::telemetry::Telemetry,
+}
+
+#[async_trait]
+impl Resource for TenantSchemasResource {
+    type State<'a> = TenantSchemasResourceState<'a>;
+    type PrivateState<'a> = ValueEmpty;
+    type ProviderMetaState<'a> = ValueEmpty;
+
+    fn schema(&self, _diags: &mut Diagnostics) -> Option<Schema> {
+        Some(Schema {
+            version: 1,
+            block: Block {
+                version: 1,
+                description: Description::plain(
+                    "Applies one schema file to each tenant schema of a database",
+                ),
+                attributes: map! {
+                    "id" => Attribute {
+                        description: Description::plain("Resource identifier"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "schema_file" => Attribute {
+                        description: Description::plain("Path to SQL schema file, qualifying tenant objects with {tenant}"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        ..Default::default()
+                    },
+                    "base_dir" => Attribute {
+                        description: Description::plain("Directory relative schema_file paths resolve against, e.g. path.module (default: working directory)"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "database_url" => Attribute {
+                        description: Description::plain("PostgreSQL connection URL"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Required,
+                        sensitive: true,
+                        ..Default::default()
+                    },
+                    "tenants" => Attribute {
+                        description: Description::plain("Tenant schema names"),
+                        attr_type: AttributeType::List(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "tenants_query" => Attribute {
+                        description: Description::plain("Query returning one text column of tenant schema names, added to tenants"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "allow_destructive" => Attribute {
+                        description: Description::plain("Allow destructive operations"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "parallelism" => Attribute {
+                        description: Description::plain("Number of tenants applied concurrently (default: 1)"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "continue_on_error" => Attribute {
+                        description: Description::plain("Keep applying the remaining tenants after one fails (default: false)"),
+                        attr_type: AttributeType::Bool,
+                        constraint: AttributeConstraint::Optional,
+                        ..Default::default()
+                    },
+                    "schema_hash" => Attribute {
+                        description: Description::plain("SHA256 hash of schema file"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "tenant_status" => Attribute {
+                        description: Description::plain("Per tenant: applied, failed or pending"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "tenant_results" => Attribute {
+                        description: Description::plain("Per tenant, the outcome of the last apply: operations applied or the error"),
+                        attr_type: AttributeType::Map(Box::new(AttributeType::String)),
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "applied_at" => Attribute {
+                        description: Description::plain("Timestamp of last apply"),
+                        attr_type: AttributeType::String,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    },
+                    "migration_count" => Attribute {
+                        description: Description::plain("Number of operations applied by the last apply, across tenants"),
+                        attr_type: AttributeType::Number,
+                        constraint: AttributeConstraint::Computed,
+                        ..Default::default()
+                    }
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn validate<'a>(&self, diags: &mut Diagnostics, config: Self::State<'a>) -> Option<()> {
+        crate::util::check_database_url(
+            diags,
+            config.database_url.as_ref_option().map(|u| u.as_ref()),
+        );
+        if config.tenants.is_null() && config.tenants_query.is_null() {
+            diags.error_short(
+                "One of tenants or tenants_query is required",
+                AttributePath::new("tenants"),
+            );
+        }
+        check_parallelism(diags, config.parallelism.as_ref_option().copied());
+        Some(())
+    }
+
+    async fn read<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        state: Self::State<'a>,
+        private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        Some((state, private_state))
+    }
+
+    async fn plan_create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let schema_hash = check_schema_file(diags, &proposed_state)?;
+
+        let mut state = proposed_state;
+        state.id = resource_id(&state);
+        state.schema_hash = Value::Value(Cow::Owned(schema_hash));
+        // Mark computed fields as Unknown during plan so Terraform knows they'll be set during apply
+        state.tenant_status = Value::Unknown;
+        state.tenant_results = Value::Unknown;
+        state.applied_at = Value::Unknown;
+        state.migration_count = Value::Unknown;
+
+        Some((state, Default::default()))
+    }
+
+    async fn plan_update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        proposed_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>, Vec<AttributePath>)> {
+        let schema_hash = check_schema_file(diags, &proposed_state)?;
+
+        let mut requires_replace = vec![];
+        if resource_id(&prior_state) != resource_id(&proposed_state) {
+            requires_replace.push(AttributePath::new("database_url"));
+        }
+
+        // The tenant list may come from the database; without it, assume work remains
+        let needs_apply = match self.tenants(diags, &proposed_state).await? {
+            Some(tenants) => {
+                !pending_tenants(&prior_state, &schema_hash, &tenants).is_empty()
+                    || tenants.len() != applied_tenants(&prior_state, &schema_hash).len()
+            }
+            None => true,
+        };
+
+        let mut state = proposed_state;
+        state.id = resource_id(&state);
+        state.schema_hash = Value::Value(Cow::Owned(schema_hash));
+        if needs_apply {
+            state.tenant_status = Value::Unknown;
+            state.tenant_results = Value::Unknown;
+            state.applied_at = Value::Unknown;
+            state.migration_count = Value::Unknown;
+        } else {
+            state.tenant_status = prior_state.tenant_status;
+            state.tenant_results = prior_state.tenant_results;
+            state.applied_at = prior_state.applied_at;
+            state.migration_count = prior_state.migration_count;
+        }
+
+        Some((state, prior_private_state, requires_replace))
+    }
+
+    async fn plan_destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<Self::PrivateState<'a>> {
+        Some(prior_private_state)
+    }
+
+    async fn create<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        let state = self
+            .apply_tenants(diags, &Default::default(), planned_state, "create")
+            .await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn update<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: Self::State<'a>,
+        planned_state: Self::State<'a>,
+        _config_state: Self::State<'a>,
+        planned_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<(Self::State<'a>, Self::PrivateState<'a>)> {
+        // Every tenant was already up to date
+        if planned_state.applied_at.is_value() {
+            return Some((planned_state, planned_private_state));
+        }
+        let state = self
+            .apply_tenants(diags, &prior_state, planned_state, "update")
+            .await?;
+        Some((state, planned_private_state))
+    }
+
+    async fn destroy<'a>(
+        &self,
+        _diags: &mut Diagnostics,
+        _prior_state: Self::State<'a>,
+        _prior_private_state: Self::PrivateState<'a>,
+        _provider_meta_state: Self::ProviderMetaState<'a>,
+    ) -> Option<()> {
+        Some(())
+    }
+}
+
+impl TenantSchemasResource {
+    /// `tenants` plus the rows of `tenants_query`, sorted and deduplicated.
+    /// `None` while either is unknown.
+    async fn tenants(
+        &self,
+        diags: &mut Diagnostics,
+        state: &TenantSchemasResourceState<'_>,
+    ) -> Option<Option<Vec<String>>> {
+        if state.tenants.is_unknown() || state.tenants_query.is_unknown() {
+            return Some(None);
+        }
+        let mut tenants: BTreeSet<String> = state
+            .tenants
+            .iter()
+            .flatten()
+            .filter_map(|t| t.as_ref_option().map(|t| t.to_string()))
+            .collect();
+        if let Value::Value(query) = &state.tenants_query {
+            let Value::Value(database_url) = &state.database_url else {
+                return Some(None);
+            };
+            let connection = self.connections.connect(diags, database_url).await?;
+            match sqlx::query_scalar::<_, String>(query)
+                .fetch_all(connection.pool())
+                .await
+            {
+                Ok(rows) => tenants.extend(rows),
+                Err(e) => {
+                    diags.error_short(
+                        format!("tenants_query failed: {e}"),
+                        AttributePath::new("tenants_query"),
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(Some(tenants.into_iter().collect()))
+    }
+
+    async fn apply_tenants<'a>(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: &TenantSchemasResourceState<'_>,
+        state: TenantSchemasResourceState<'a>,
+        operation: &'static str,
+    ) -> Option<TenantSchemasResourceState<'a>> {
+        let _permit = self.apply_limiter.acquire().await;
+        let started = std::time::SystemTime::now();
+        let applied = self.apply_pending(diags, prior_state, &state).await;
+        self.telemetry
+            .record(
+                "pgmold_tenant_schemas",
+                operation,
+                started,
+                applied
+                    .as_ref()
+                    .is_some_and(|results| results.values().all(|r| r.status() == APPLIED)),
+            )
+            .await;
+        let results = applied?;
+
+        // Saved even when some tenants failed, so the next apply retries only those
+        let mut state = state;
+        state.tenant_status = Value::Value(
+            results
+                .iter()
+                .map(|(tenant, result)| {
+                    (
+                        Cow::Owned(tenant.clone()),
+                        Value::Value(Cow::Borrowed(result.status())),
+                    )
+                })
+                .collect(),
+        );
+        state.tenant_results = Value::Value(
+            results
+                .iter()
+                .map(|(tenant, result)| {
+                    (
+                        Cow::Owned(tenant.clone()),
+                        Value::Value(Cow::Owned(result.to_string())),
+                    )
+                })
+                .collect(),
+        );
+        state.applied_at = Value::Value(Cow::Owned(crate::clock::now().to_rfc3339()));
+        state.migration_count = Value::Value(
+            results
+                .values()
+                .map(|r| match r {
+                    TenantResult::Applied(operations) => *operations as i64,
+                    _ => 0,
+                })
+                .sum(),
+        );
+        Some(state)
+    }
+
+    /// Applies the schema file to each tenant not already up to date, up to
+    /// `parallelism` at a time. Unless `continue_on_error` is set, no further
+    /// tenants are started after the first failure. Returns every tenant's result.
+    async fn apply_pending(
+        &self,
+        diags: &mut Diagnostics,
+        prior_state: &TenantSchemasResourceState<'_>,
+        state: &TenantSchemasResourceState<'_>,
+    ) -> Option<BTreeMap<String, TenantResult>> {
+        let tenants = self.tenants(diags, state).await?.unwrap_or_default();
+        let schema_file = schema_file_path(state);
+        let content: Arc<str> =
+            match crate::util::read_schema_file(std::path::Path::new(&schema_file)) {
+                Ok(content) => content.into(),
+                Err(e) => {
+                    diags.error_short(
+                        format!("Failed to read schema file: {e}"),
+                        AttributePath::new("schema_file"),
+                    );
+                    return None;
+                }
+            };
+        let connection = self
+            .connections
+            .connect(diags, state.database_url.as_str())
+            .await?;
+
+        let pending = pending_tenants(prior_state, state.schema_hash.as_str(), &tenants);
+        let mut results: BTreeMap<String, TenantResult> = tenants
+            .iter()
+            .map(|t| (t.clone(), TenantResult::UpToDate))
+            .collect();
+        for tenant in &pending {
+            results.insert(tenant.clone(), TenantResult::NotAttempted);
+        }
+
+        let parallelism = parallelism(state);
+        let continue_on_error = state.continue_on_error.unwrap_or(false);
+        let allow_destructive = state.allow_destructive.unwrap_or(false);
+        let server_version = self.connections.target_server_version().await;
+        let lint = TenantLint {
+            rules: self.connections.lint_rules().await,
+            escalate_warnings: self.connections.escalate_warnings().await,
+            operation_policy: self.connections.operation_policy().await,
+        };
+        let mut queue = pending.into_iter();
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut stopped = false;
+        loop {
+            while !stopped && tasks.len() < parallelism {
+                let Some(tenant) = queue.next() else {
+                    break;
+                };
+                tracing::info!(
+                    resource = "pgmold_tenant_schemas",
+                    id = state.id.as_str(),
+                    tenant = tenant.as_str(),
+                    "applying schema to tenant"
+                );
+                let connection = connection.clone();
+                let content = content.clone();
+                let lint = lint.clone();
+                let span = tracing::info_span!("tenant", tenant = tenant.as_str());
+                tasks.spawn(
+                    async move {
+                        let result = apply_tenant(
+                            &connection,
+                            &content,
+                            &tenant,
+                            allow_destructive,
+                            server_version,
+                            lint,
+                        )
+                        .await;
+                        (tenant, result)
+                    }
+                    .instrument(span),
+                );
+            }
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            let (tenant, result) = match joined {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    diags.root_error_short(format!("Tenant apply task failed: {e}"));
+                    stopped = true;
+                    continue;
+                }
+            };
+            match result {
+                Ok(operations) => {
+                    results.insert(tenant, TenantResult::Applied(operations));
+                }
+                Err(e) => {
+                    let error = crate::util::sanitize_db_error(&e.to_string(), false);
+                    diags.error_short(
+                        format!("Failed to apply schema to tenant \"{tenant}\": {error}"),
+                        AttributePath::new("tenants"),
+                    );
+                    results.insert(tenant, TenantResult::Failed(error));
+                    stopped |= !continue_on_error;
+                }
+            }
+        }
+        Some(results)
+    }
+}
+
+/// What the last apply did for one tenant.
+#[derive(Debug, Clone, PartialEq)]
+enum TenantResult {
+    UpToDate,
+    Applied(usize),
+    Failed(String),
+    NotAttempted,
+}
+
+impl TenantResult {
+    fn status(&self) -> &'static str {
+        match self {
+            TenantResult::UpToDate | TenantResult::Applied(_) => APPLIED,
+            TenantResult::Failed(_) => FAILED,
+            TenantResult::NotAttempted => PENDING,
+        }
+    }
+}
+
+impl std::fmt::Display for TenantResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TenantResult::UpToDate => write!(f, "up to date"),
+            TenantResult::Applied(operations) => write!(f, "applied {operations} operations"),
+            TenantResult::Failed(error) => write!(f, "failed: {error}"),
+            TenantResult::NotAttempted => write!(f, "not attempted"),
+        }
+    }
+}
+
+/// The provider's lint settings, which every tenant's plan checks.
+#[derive(Clone)]
+struct TenantLint {
+    rules: Vec<crate::lint_rules::LintRule>,
+    escalate_warnings: bool,
+    operation_policy: crate::operation_policy::OperationPolicy,
+}
+
+/// Creates the tenant's schema if needed, then diffs and applies the schema
+/// file rendered for it. Returns the number of operations executed.
+async fn apply_tenant(
+    connection: &pgmold::pg::connection::PgConnection,
+    content: &str,
+    tenant: &str,
+    allow_destructive: bool,
+    server_version: Option<i64>,
+    lint: TenantLint,
+) -> anyhow::Result<usize> {
+    let tenant = tenant.to_string();
+    crate::execute::create_schemas(connection.pool(), std::slice::from_ref(&tenant)).await?;
+    let target = pgmold::parser::parse_sql_string(&tenant_sql(content, &tenant))?;
+    let options = crate::pipeline::PlanOptions {
+        target_schemas: vec![tenant],
+        lint_rules: lint.rules,
+        escalate_warnings: lint.escalate_warnings,
+        operation_policy: lint.operation_policy,
+        lint_options: pgmold::lint::LintOptions {
+            allow_destructive,
+            is_production: false,
+        },
+        declared_kinds_only: false,
+        only_objects: None,
+        objects: Default::default(),
+        normalize_expressions: false,
+        skip_unreadable_schemas: false,
+        dialect: crate::dialect::Dialect::default(),
+        phase: None,
+        server_version,
+        rebuild_enums: false,
+        defer_validation: false,
+        preserve_sequences: false,
+        preserve_data: false,
+    };
+    let plan = crate::pipeline::plan_to_schema(connection, target, &options).await?;
+    if pgmold::lint::has_errors(&plan.lint_results) {
+        let messages: Vec<String> = plan
+            .lint_results
+            .iter()
+            .filter(|lint| lint.severity == pgmold::lint::LintSeverity::Error)
+            .map(|lint| lint.message.to_string())
+            .collect();
+        anyhow::bail!(messages.join("; "));
+    }
+    crate::execute::execute_statements(
+        connection.pool(),
+        &plan.statements,
+        &crate::execute::ExecuteOptions::default(),
+    )
+    .await?;
+    Ok(plan.operations.len())
+}
+
+/// Reports a `parallelism` below 1 on the attribute.
+fn check_parallelism(diags: &mut Diagnostics, parallelism: Option<i64>) -> Option<()> {
+    match parallelism {
+        Some(n) if n < 1 => {
+            diags.error_short(
+                format!("parallelism must be at least 1, got {n}"),
+                AttributePath::new("parallelism"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+fn parallelism(state: &TenantSchemasResourceState) -> usize {
+    state.parallelism.unwrap_or(1).max(1) as usize
+}
+
+/// The schema file with each `{tenant}` replaced by the tenant's quoted name.
+fn tenant_sql(content: &str, tenant: &str) -> String {
+    content.replace(TENANT_PLACEHOLDER, &quote_ident(tenant))
+}
+
+/// Tenants recorded as applied at `schema_hash` by the prior apply.
+fn applied_tenants<'s>(prior: &'s TenantSchemasResourceState, schema_hash: &str) -> Vec<&'s str> {
+    if prior.schema_hash.as_ref_option().map(|h| h.as_ref()) != Some(schema_hash) {
+        return vec![];
+    }
+    prior
+        .tenant_status
+        .iter()
+        .flatten()
+        .filter(|(_, status)| status.as_ref_option().map(|s| s.as_ref()) == Some(APPLIED))
+        .map(|(tenant, _)| tenant.as_ref())
+        .collect()
+}
+
+/// Tenants that still need the schema file at `schema_hash` applied.
+fn pending_tenants(
+    prior: &TenantSchemasResourceState,
+    schema_hash: &str,
+    tenants: &[String],
+) -> Vec<String> {
+    let applied = applied_tenants(prior, schema_hash);
+    tenants
+        .iter()
+        .filter(|tenant| !applied.contains(&tenant.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Checks the schema file exists and uses the tenant placeholder, returning its hash.
+fn check_schema_file(
+    diags: &mut Diagnostics,
+    state: &TenantSchemasResourceState,
+) -> Option<String> {
+    let schema_file = schema_file_path(state);
+    let content = match crate::util::read_schema_file(std::path::Path::new(&schema_file)) {
+        Ok(content) => content,
+        Err(e) => {
+            diags.error_short(
+                format!("Failed to read schema file {schema_file}: {e}"),
+                AttributePath::new("schema_file"),
+            );
+            return None;
+        }
+    };
+    if !content.contains(TENANT_PLACEHOLDER) {
+        diags.error(
+            "Schema file does not reference {tenant}",
+            "Qualify tenant objects with {tenant}, e.g. CREATE TABLE {tenant}.users, so each tenant gets its own copy.",
+            AttributePath::new("schema_file"),
+        );
+        return None;
+    }
+    match crate::util::compute_schema_hash(std::path::Path::new(&schema_file)) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            diags.error_short(
+                format!("Failed to read schema file {schema_file}: {e}"),
+                AttributePath::new("schema_file"),
+            );
+            None
+        }
+    }
+}
+
+/// `pgmold-tenants-<hash>` of the database identity and schema file path.
+fn resource_id(state: &TenantSchemasResourceState) -> ValueString<'static> {
+    let (Value::Value(database_url), Value::Value(_)) = (&state.database_url, &state.schema_file)
+    else {
+        return Value::Unknown;
+    };
+    let identity = format!(
+        "{}|{}",
+        crate::util::database_identity(database_url),
+        schema_file_path(state)
+    );
+    let hash = crate::util::compute_content_hash(&identity);
+    Value::Value(Cow::Owned(format!("pgmold-tenants-{}", &hash[..8])))
+}
+
+fn schema_file_path(state: &TenantSchemasResourceState) -> String {
+    crate::util::resolve_path(
+        state.base_dir.as_ref_option().map(|s| s.as_ref()),
+        state.schema_file.as_str(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prior(
+        schema_hash: &'static str,
+        status: &[(&'static str, &'static str)],
+    ) -> TenantSchemasResourceState<'static> {
+        TenantSchemasResourceState {
+            schema_hash: Value::Value(Cow::Borrowed(schema_hash)),
+            tenant_status: Value::Value(
+                status
+                    .iter()
+                    .map(|(tenant, status)| {
+                        (Cow::Borrowed(*tenant), Value::Value(Cow::Borrowed(*status)))
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tenant_sql_quotes_tenant_names() {
+        assert_eq!(
+            tenant_sql("CREATE TABLE {tenant}.users (id int);", "Acme"),
+            "CREATE TABLE \"Acme\".users (id int);"
+        );
+    }
+
+    #[test]
+    fn tenant_result_status_and_message() {
+        assert_eq!(TenantResult::Applied(3).status(), APPLIED);
+        assert_eq!(TenantResult::Applied(3).to_string(), "applied 3 operations");
+        assert_eq!(TenantResult::NotAttempted.status(), PENDING);
+        assert_eq!(
+            TenantResult::Failed("lock timeout".to_string()).to_string(),
+            "failed: lock timeout"
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_rejects_zero_parallelism() {
+        let config = TenantSchemasResourceState {
+            schema_file: Value::Value(Cow::Borrowed("tenant.sql")),
+            database_url: Value::Value(Cow::Borrowed("postgres://db/app")),
+            tenants: Value::Value(vec![Value::Value(Cow::Borrowed("acme"))]),
+            parallelism: Value::Value(0),
+            ..Default::default()
+        };
+
+        let mut diags = Diagnostics::default();
+        TenantSchemasResource::default()
+            .validate(&mut diags, config)
+            .await;
+        assert_eq!(diags.errors.len(), 1);
+        assert_eq!(diags.errors[0].attribute, AttributePath::new("parallelism"));
+    }
+
+    #[test]
+    fn pending_tenants_retries_failed_and_new_tenants() {
+        let tenants = vec![
+            "acme".to_string(),
+            "globex".to_string(),
+            "initech".to_string(),
+        ];
+        let prior = prior("abc", &[("acme", APPLIED), ("globex", FAILED)]);
+
+        assert_eq!(
+            pending_tenants(&prior, "abc", &tenants),
+            vec!["globex".to_string(), "initech".to_string()]
+        );
+        // A changed schema file makes every tenant pending again
+        assert_eq!(pending_tenants(&prior, "def", &tenants), tenants);
+    }
+}
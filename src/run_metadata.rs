@@ -0,0 +1,106 @@
+//! Where a run comes from: the Terraform workspace, the CI run and the Git
+//! commit. They're stamped into generated migration files and execution log
+//! entries, so each schema change traces back to the pipeline run behind it.
+
+use serde::Serialize;
+
+/// Environment variables read, in order, when the provider doesn't set a value.
+/// Terraform Cloud, Atlantis, GitHub Actions and GitLab CI each set one.
+const WORKSPACE_VARS: &[&str] = &["TF_WORKSPACE", "TFC_WORKSPACE_NAME", "WORKSPACE"];
+const RUN_ID_VARS: &[&str] = &["TFC_RUN_ID", "PULL_URL", "GITHUB_RUN_ID", "CI_PIPELINE_ID"];
+const GIT_COMMIT_VARS: &[&str] = &[
+    "TFC_CONFIGURATION_VERSION_GIT_COMMIT_SHA",
+    "HEAD_COMMIT",
+    "GITHUB_SHA",
+    "CI_COMMIT_SHA",
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct RunMetadata {
+    pub workspace: Option<String>,
+    pub run_id: Option<String>,
+    pub git_commit: Option<String>,
+}
+
+impl RunMetadata {
+    /// The configured values, each falling back to the first of its
+    /// environment variables that isn't blank.
+    pub fn resolve(
+        workspace: Option<String>,
+        run_id: Option<String>,
+        git_commit: Option<String>,
+    ) -> Self {
+        Self::resolve_with(workspace, run_id, git_commit, |name| {
+            std::env::var(name).ok()
+        })
+    }
+
+    fn resolve_with(
+        workspace: Option<String>,
+        run_id: Option<String>,
+        git_commit: Option<String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let value = |configured: Option<String>, vars: &[&str]| {
+            configured
+                .into_iter()
+                .chain(vars.iter().filter_map(|name| env(name)))
+                .map(|v| v.trim().to_string())
+                .find(|v| !v.is_empty())
+        };
+        RunMetadata {
+            workspace: value(workspace, WORKSPACE_VARS),
+            run_id: value(run_id, RUN_ID_VARS),
+            git_commit: value(git_commit, GIT_COMMIT_VARS),
+        }
+    }
+
+    /// SQL comment lines heading a generated migration file, `None` when
+    /// nothing about the run is known.
+    pub fn header(&self) -> Option<String> {
+        let lines: Vec<String> = [
+            ("workspace", &self.workspace),
+            ("run", &self.run_id),
+            ("commit", &self.git_commit),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| {
+            // A line break would end the comment and turn the rest into SQL
+            let value = value.as_deref()?.replace(['\r', '\n'], " ");
+            Some(format!("-- {label}: {value}"))
+        })
+        .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_prefers_configuration_over_environment() {
+        let env = |name: &str| match name {
+            "TF_WORKSPACE" => Some("prod".to_string()),
+            "PULL_URL" => Some("https://github.com/acme/infra/pull/42".to_string()),
+            "GITHUB_SHA" => Some("ffff".to_string()),
+            "HEAD_COMMIT" => Some("  ".to_string()),
+            _ => None,
+        };
+        let run = RunMetadata::resolve_with(Some("staging".to_string()), None, None, env);
+
+        assert_eq!(
+            run,
+            RunMetadata {
+                workspace: Some("staging".to_string()),
+                run_id: Some("https://github.com/acme/infra/pull/42".to_string()),
+                git_commit: Some("ffff".to_string()),
+            }
+        );
+        assert_eq!(
+            run.header().unwrap(),
+            "-- workspace: staging\n-- run: https://github.com/acme/infra/pull/42\n-- commit: ffff"
+        );
+        assert_eq!(RunMetadata::default().header(), None);
+    }
+}
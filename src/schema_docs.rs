@@ -0,0 +1,260 @@
+//! Human-readable documentation of a parsed schema: a Markdown reference of
+//! tables, columns, keys and enums, and a Mermaid ER diagram, written on
+//! apply so the docs can't drift from the schema file.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use pgmold::model::{PgType, Schema, Table};
+
+/// File names written into the docs directory.
+pub const MARKDOWN_FILE: &str = "schema.md";
+pub const MERMAID_FILE: &str = "schema.mmd";
+
+/// Writes [`MARKDOWN_FILE`] and [`MERMAID_FILE`] for `schema` into `dir`,
+/// creating it if needed and replacing previous docs.
+pub fn write(dir: &str, schema: &Schema) -> anyhow::Result<()> {
+    let dir = Path::new(dir);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join(MARKDOWN_FILE), markdown(schema))?;
+    std::fs::write(dir.join(MERMAID_FILE), mermaid(schema))?;
+    Ok(())
+}
+
+/// Markdown reference with one section per table, then enums, then the diagram.
+pub fn markdown(schema: &Schema) -> String {
+    let mut doc = String::from("# Schema\n");
+    for table in schema.tables.values() {
+        let _ = write!(doc, "\n## {}.{}\n\n", table.schema, table.name);
+        if let Some(comment) = &table.comment {
+            let _ = write!(doc, "{}\n\n", comment.trim());
+        }
+        doc.push_str("| Column | Type | Nullable | Default | Key |\n");
+        doc.push_str("|--------|------|----------|---------|-----|\n");
+        for column in table.columns.values() {
+            let _ = writeln!(
+                doc,
+                "| {} | {} | {} | {} | {} |",
+                cell(&column.name),
+                cell(&type_name(&column.data_type)),
+                if column.nullable { "yes" } else { "no" },
+                column.default.as_deref().map(cell).unwrap_or_default(),
+                keys(table, &column.name).join(", ")
+            );
+        }
+        if !table.foreign_keys.is_empty() {
+            doc.push_str("\nForeign keys:\n\n");
+            for fk in &table.foreign_keys {
+                let _ = writeln!(
+                    doc,
+                    "- `{}`: ({}) references `{}.{}` ({})",
+                    fk.name,
+                    fk.columns.join(", "),
+                    fk.referenced_schema,
+                    fk.referenced_table,
+                    fk.referenced_columns.join(", ")
+                );
+            }
+        }
+        if !table.indexes.is_empty() {
+            doc.push_str("\nIndexes:\n\n");
+            for index in &table.indexes {
+                let _ = writeln!(
+                    doc,
+                    "- `{}`{}: ({})",
+                    index.name,
+                    if index.unique { " (unique)" } else { "" },
+                    index.columns.join(", ")
+                );
+            }
+        }
+    }
+    if !schema.enums.is_empty() {
+        doc.push_str("\n## Enums\n\n| Type | Values |\n|------|--------|\n");
+        for enum_type in schema.enums.values() {
+            let _ = writeln!(
+                doc,
+                "| {}.{} | {} |",
+                enum_type.schema,
+                enum_type.name,
+                cell(&enum_type.values.join(", "))
+            );
+        }
+    }
+    let _ = write!(doc, "\n## Diagram\n\n```mermaid\n{}```\n", mermaid(schema));
+    doc
+}
+
+/// Mermaid `erDiagram` with an entity per table and a relationship per
+/// foreign key, from the referenced table to the referencing one.
+pub fn mermaid(schema: &Schema) -> String {
+    let mut diagram = String::from("erDiagram\n");
+    for table in schema.tables.values() {
+        let _ = writeln!(diagram, "    {} {{", entity(&table.schema, &table.name));
+        for column in table.columns.values() {
+            let keys = keys(table, &column.name);
+            let _ = writeln!(
+                diagram,
+                "        {} {}{}",
+                token(&type_name(&column.data_type)),
+                token(&column.name),
+                if keys.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", keys.join(","))
+                }
+            );
+        }
+        diagram.push_str("    }\n");
+    }
+    for table in schema.tables.values() {
+        for fk in &table.foreign_keys {
+            // A nullable foreign key column makes the parent optional
+            let optional = fk
+                .columns
+                .iter()
+                .any(|c| table.columns.get(c).is_some_and(|column| column.nullable));
+            let _ = writeln!(
+                diagram,
+                "    {} {}--o{{ {} : \"{}\"",
+                entity(&fk.referenced_schema, &fk.referenced_table),
+                if optional { "|o" } else { "||" },
+                entity(&table.schema, &table.name),
+                fk.name.replace('"', "'")
+            );
+        }
+    }
+    diagram
+}
+
+/// `PK` and `FK` markers for a column.
+fn keys(table: &Table, column: &str) -> Vec<&'static str> {
+    let mut keys = Vec::new();
+    if table
+        .primary_key
+        .as_ref()
+        .is_some_and(|pk| pk.columns.iter().any(|c| c == column))
+    {
+        keys.push("PK");
+    }
+    if table
+        .foreign_keys
+        .iter()
+        .any(|fk| fk.columns.iter().any(|c| c == column))
+    {
+        keys.push("FK");
+    }
+    keys
+}
+
+/// PostgreSQL spelling of a column type, e.g. `integer` or `varchar(255)`.
+pub fn type_name(data_type: &PgType) -> String {
+    match data_type {
+        PgType::Integer => "integer".to_string(),
+        PgType::BigInt => "bigint".to_string(),
+        PgType::SmallInt => "smallint".to_string(),
+        PgType::Varchar(Some(length)) => format!("varchar({length})"),
+        PgType::Varchar(None) => "varchar".to_string(),
+        PgType::Text => "text".to_string(),
+        PgType::Boolean => "boolean".to_string(),
+        PgType::TimestampTz => "timestamptz".to_string(),
+        PgType::Timestamp => "timestamp".to_string(),
+        PgType::Date => "date".to_string(),
+        PgType::Uuid => "uuid".to_string(),
+        PgType::Json => "json".to_string(),
+        PgType::Jsonb => "jsonb".to_string(),
+        PgType::CustomEnum(name) => name.clone(),
+    }
+}
+
+fn entity(schema: &str, table: &str) -> String {
+    token(&format!("{schema}_{table}"))
+}
+
+/// Mermaid names are bare words, so anything else becomes `_`.
+fn token(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Escapes text for a Markdown table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        pgmold::parser::parse_sql_string(
+            "CREATE TYPE status AS ENUM ('active', 'disabled');\n\
+             CREATE TABLE users (id INT PRIMARY KEY, email TEXT NOT NULL);\n\
+             CREATE TABLE orders (\n\
+                 id INT PRIMARY KEY,\n\
+                 user_id INT REFERENCES users (id),\n\
+                 note TEXT DEFAULT 'a|b'\n\
+             );",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn mermaid_links_foreign_keys() {
+        let diagram = mermaid(&schema());
+        assert!(diagram.starts_with("erDiagram\n"));
+        assert!(diagram.contains("    public_users {\n"));
+        assert!(diagram.contains(" id PK\n"));
+        assert!(diagram.contains(" user_id FK\n"));
+        // user_id is nullable, so an order may have no user
+        assert!(diagram.contains("    public_users |o--o{ public_orders : "));
+    }
+
+    #[test]
+    fn markdown_lists_tables_enums_and_diagram() {
+        let doc = markdown(&schema());
+        assert!(doc.contains("\n## public.orders\n"));
+        assert!(doc.contains("| email |"));
+        assert!(doc.contains("a\\|b"));
+        assert!(doc.contains("references `public.users` (id)"));
+        assert!(doc.contains("| public.status | active, disabled |"));
+        assert!(doc.contains("```mermaid\nerDiagram\n"));
+    }
+
+    #[test]
+    fn type_name_renders_postgres_types() {
+        let schema = pgmold::parser::parse_sql_string(
+            "CREATE TABLE t (a INT, b VARCHAR(255), c VARCHAR, d TIMESTAMPTZ);",
+        )
+        .unwrap();
+        let table = schema.tables.values().next().unwrap();
+        let types: Vec<String> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| type_name(&table.columns[*name].data_type))
+            .collect();
+
+        assert_eq!(types, ["integer", "varchar(255)", "varchar", "timestamptz"]);
+    }
+
+    #[test]
+    fn write_replaces_previous_docs() {
+        let dir = tempfile::tempdir().unwrap();
+        let docs = dir.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(docs.join(MARKDOWN_FILE), "stale").unwrap();
+
+        write(docs.to_str().unwrap(), &schema()).unwrap();
+
+        let markdown = std::fs::read_to_string(docs.join(MARKDOWN_FILE)).unwrap();
+        assert!(markdown.starts_with("# Schema\n"));
+        let mermaid = std::fs::read_to_string(docs.join(MERMAID_FILE)).unwrap();
+        assert!(mermaid.starts_with("erDiagram\n"));
+    }
+}
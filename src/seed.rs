@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use pgmold::diff::MigrationOp;
+use tf_provider::{AttributePath, Diagnostics};
+
+use crate::functions::quote::{quote_ident, quote_literal};
+use crate::masking::MaskingRule;
+
+/// Rows per `INSERT` statement when seeding with [`SeedFormat::Insert`].
+const INSERT_BATCH_ROWS: usize = 500;
+
+/// How seed data is written into a generated migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeedFormat {
+    /// `COPY ... FROM STDIN` with the CSV inline, for psql-style runners.
+    #[default]
+    Copy,
+    /// Batched `INSERT` statements, for runners that can't stream `COPY` data.
+    Insert,
+}
+
+impl SeedFormat {
+    pub const NAMES: [&'static str; 2] = ["copy", "insert"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "copy" => Some(SeedFormat::Copy),
+            "insert" => Some(SeedFormat::Insert),
+            _ => None,
+        }
+    }
+}
+
+/// Reports an unknown `seed_format` value on the attribute.
+pub fn check_seed_format(diags: &mut Diagnostics, format: Option<&str>) -> Option<()> {
+    match format {
+        Some(name) if SeedFormat::parse(name).is_none() => {
+            diags.error_short(
+                format!(
+                    "Unknown seed_format \"{name}\", expected one of: {}",
+                    SeedFormat::NAMES.join(", ")
+                ),
+                AttributePath::new("seed_format"),
+            );
+            None
+        }
+        _ => Some(()),
+    }
+}
+
+/// Tables created by `ops`, as `schema.table`.
+pub fn created_tables(ops: &[MigrationOp]) -> BTreeSet<String> {
+    ops.iter()
+        .filter_map(|op| match op {
+            MigrationOp::CreateTable(table) => Some(format!("{}.{}", table.schema, table.name)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `users` -> `public.users`; qualified names are kept.
+pub fn qualified_table(table: &str) -> String {
+    if table.contains('.') {
+        table.to_string()
+    } else {
+        format!("public.{table}")
+    }
+}
+
+/// The seed data of `path` for `environment`: `${name}` placeholders are
+/// replaced with `variables`, then the overlay next to the file
+/// (`countries.staging.csv` for `countries.csv`), if there is one, replaces
+/// rows whose first column matches and adds the others.
+pub fn effective_csv(
+    path: &str,
+    environment: Option<&str>,
+    variables: &BTreeMap<String, String>,
+) -> anyhow::Result<String> {
+    let base = interpolate(&std::fs::read_to_string(path)?, variables)?;
+    let Some(overlay) = environment.map(|env| overlay_path(path, env)) else {
+        return Ok(base);
+    };
+    if !overlay.exists() {
+        return Ok(base);
+    }
+    let overlay_csv = interpolate(&std::fs::read_to_string(&overlay)?, variables)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", overlay.display()))?;
+    merge_overlay(&base, &overlay_csv).map_err(|e| anyhow::anyhow!("{}: {e}", overlay.display()))
+}
+
+/// `seeds/countries.csv` -> `seeds/countries.<environment>.csv`.
+fn overlay_path(path: &str, environment: &str) -> std::path::PathBuf {
+    let path = std::path::Path::new(path);
+    match path.extension() {
+        Some(extension) => {
+            path.with_extension(format!("{environment}.{}", extension.to_string_lossy()))
+        }
+        None => path.with_extension(environment),
+    }
+}
+
+/// Replaces `${name}` with the variable's value; `$${` is a literal `${`.
+fn interpolate(text: &str, variables: &BTreeMap<String, String>) -> anyhow::Result<String> {
+    let re = regex::Regex::new(r"\$(\$)?\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap();
+    let mut missing = None;
+    let interpolated = re.replace_all(text, |caps: &regex::Captures| {
+        if caps.get(1).is_some() {
+            return format!("${{{}}}", &caps[2]);
+        }
+        variables.get(&caps[2]).cloned().unwrap_or_else(|| {
+            missing.get_or_insert_with(|| caps[2].to_string());
+            String::new()
+        })
+    });
+    match missing {
+        Some(name) => anyhow::bail!("seed variable \"{name}\" is not set in seed_variables"),
+        None => Ok(interpolated.into_owned()),
+    }
+}
+
+fn merge_overlay(base: &str, overlay: &str) -> anyhow::Result<String> {
+    let mut rows = parse_csv(base)?;
+    let mut overlay_rows = parse_csv(overlay)?;
+    if rows.is_empty() || overlay_rows.is_empty() {
+        anyhow::bail!("seed file is empty, expected a header row");
+    }
+    if overlay_rows.remove(0) != rows[0] {
+        anyhow::bail!("overlay columns differ from the base seed file");
+    }
+    let header: Vec<String> = rows
+        .remove(0)
+        .into_iter()
+        .map(|column| column.unwrap_or_default())
+        .collect();
+    for row in overlay_rows {
+        match rows.iter_mut().find(|base| base.first() == row.first()) {
+            Some(base) => *base = row,
+            None => rows.push(row),
+        }
+    }
+    Ok(write_csv(&header, &rows))
+}
+
+/// SQL loading `csv` (with a header row naming the columns) into `table`,
+/// with the columns `masking` covers masked.
+pub fn seed_sql(
+    table: &str,
+    csv: &str,
+    format: SeedFormat,
+    masking: &[MaskingRule],
+) -> anyhow::Result<String> {
+    let mut rows = parse_csv(csv)?;
+    if rows.is_empty() {
+        anyhow::bail!("seed file is empty, expected a header row");
+    }
+    let header: Vec<String> = rows
+        .remove(0)
+        .into_iter()
+        .map(|column| column.unwrap_or_default())
+        .collect();
+    let masked = crate::masking::mask_rows(table, &header, &mut rows, masking);
+    let target = target_sql(table, &header);
+
+    match format {
+        SeedFormat::Copy => {
+            let mut data = if masked {
+                write_csv(&header, &rows)
+            } else {
+                csv.to_string()
+            };
+            if !data.ends_with('\n') {
+                data.push('\n');
+            }
+            Ok(format!(
+                "COPY {target} FROM STDIN WITH (FORMAT csv, HEADER true);\n{data}\\."
+            ))
+        }
+        SeedFormat::Insert => {
+            let mut values = Vec::new();
+            for (line, row) in rows.into_iter().enumerate() {
+                if row.len() != header.len() {
+                    anyhow::bail!(
+                        "row {} has {} values, expected {}",
+                        line + 2,
+                        row.len(),
+                        header.len()
+                    );
+                }
+                let row: Vec<String> = row
+                    .iter()
+                    .map(|value| value.as_deref().map_or("NULL".to_string(), quote_literal))
+                    .collect();
+                values.push(format!("({})", row.join(", ")));
+            }
+            Ok(values
+                .chunks(INSERT_BATCH_ROWS)
+                .map(|batch| format!("INSERT INTO {target} VALUES\n{};", batch.join(",\n")))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+    }
+}
+
+fn target_sql(table: &str, columns: &[String]) -> String {
+    let table = table
+        .split('.')
+        .map(quote_ident)
+        .collect::<Vec<_>>()
+        .join(".");
+    let columns = columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{table} ({columns})")
+}
+
+/// Parses RFC 4180 CSV. As with `COPY ... (FORMAT csv)`, an unquoted empty
+/// field is NULL (`None`) while `""` is an empty string.
+fn parse_csv(text: &str) -> anyhow::Result<Vec<Vec<Option<String>>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_quotes = true;
+                quoted = true;
+            }
+            ',' => row.push(take_field(&mut field, &mut quoted)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(take_field(&mut field, &mut quoted));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        anyhow::bail!("unterminated quoted field");
+    }
+    if !field.is_empty() || quoted || !row.is_empty() {
+        row.push(take_field(&mut field, &mut quoted));
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Writes rows back as CSV, quoting only where needed so that NULLs stay
+/// unquoted empty fields.
+fn write_csv(header: &[String], rows: &[Vec<Option<String>>]) -> String {
+    let field = |value: &str| {
+        if value.is_empty() || value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let mut csv = header
+        .iter()
+        .map(|c| field(c))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for row in rows {
+        let line = row
+            .iter()
+            .map(|value| value.as_deref().map_or(String::new(), field))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&line);
+        csv.push('\n');
+    }
+    csv
+}
+
+fn take_field(field: &mut String, quoted: &mut bool) -> Option<String> {
+    let value = std::mem::take(field);
+    let was_quoted = std::mem::take(quoted);
+    (was_quoted || !value.is_empty()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_csv_handles_quotes_and_nulls() {
+        let rows = parse_csv("code,name\nPT,\"Portugal, \"\"PT\"\"\"\nXX,\n\"\",x\r\n").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![Some("code".to_string()), Some("name".to_string())],
+                vec![Some("PT".to_string()), Some("Portugal, \"PT\"".to_string())],
+                vec![Some("XX".to_string()), None],
+                vec![Some(String::new()), Some("x".to_string())],
+            ]
+        );
+        assert!(parse_csv("a\n\"open").is_err());
+    }
+
+    #[test]
+    fn interpolate_substitutes_variables() {
+        let variables = BTreeMap::from([("region".to_string(), "eu".to_string())]);
+        assert_eq!(
+            interpolate(
+                "flag,value\nregion,${region}\nliteral,$${region}\n",
+                &variables
+            )
+            .unwrap(),
+            "flag,value\nregion,eu\nliteral,${region}\n"
+        );
+        let error = interpolate("a\n${missing}\n", &variables).unwrap_err();
+        assert!(error.to_string().contains("\"missing\""));
+    }
+
+    #[test]
+    fn effective_csv_applies_environment_overlay() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("flags.csv");
+        std::fs::write(&base, "name,enabled\nbilling,false\nsearch,true\n").unwrap();
+        std::fs::write(
+            dir.path().join("flags.staging.csv"),
+            "name,enabled\nbilling,true\nbeta,${beta}\n",
+        )
+        .unwrap();
+        let base = base.to_str().unwrap();
+        let variables = BTreeMap::from([("beta".to_string(), "true".to_string())]);
+
+        assert_eq!(
+            effective_csv(base, Some("staging"), &variables).unwrap(),
+            "name,enabled\nbilling,true\nsearch,true\nbeta,true\n"
+        );
+        assert_eq!(
+            effective_csv(base, Some("production"), &variables).unwrap(),
+            "name,enabled\nbilling,false\nsearch,true\n"
+        );
+    }
+
+    #[test]
+    fn seed_sql_as_copy_keeps_csv_inline() {
+        let sql = seed_sql(
+            "public.countries",
+            "code,name\nPT,Portugal",
+            SeedFormat::Copy,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "COPY \"public\".\"countries\" (\"code\", \"name\") FROM STDIN WITH (FORMAT csv, HEADER true);\ncode,name\nPT,Portugal\n\\."
+        );
+    }
+
+    #[test]
+    fn seed_sql_masks_columns() {
+        let masking = vec![MaskingRule {
+            table: "public.users".to_string(),
+            column: "email".to_string(),
+            strategy: crate::masking::MaskStrategy::Null,
+        }];
+        let sql = seed_sql(
+            "public.users",
+            "id,email\n1,ana@corp.pt\n2,\"\"\n",
+            SeedFormat::Copy,
+            &masking,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "COPY \"public\".\"users\" (\"id\", \"email\") FROM STDIN WITH (FORMAT csv, HEADER true);\nid,email\n1,\n2,\n\\."
+        );
+    }
+
+    #[test]
+    fn seed_sql_as_insert_quotes_values() {
+        let sql = seed_sql(
+            "public.countries",
+            "code,name\nPT,Portugal\nXX,\n",
+            SeedFormat::Insert,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO \"public\".\"countries\" (\"code\", \"name\") VALUES\n('PT', 'Portugal'),\n('XX', NULL);"
+        );
+        assert!(seed_sql("t", "a,b\n1\n", SeedFormat::Insert, &[]).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Fits generated SQL to the PostgreSQL version it will run on. Syntax with an
+//! equivalent older spelling is rewritten; features the server lacks fail the
+//! plan naming the version they need, instead of failing halfway through apply.
+
+use sqlx::PgPool;
+
+/// `server_version_num` of the connected server, e.g. `150004`.
+pub async fn server_version_num(pool: &PgPool) -> sqlx::Result<i64> {
+    let (version,): (i64,) = sqlx::query_as("SELECT current_setting('server_version_num')::bigint")
+        .fetch_one(pool)
+        .await?;
+    Ok(version)
+}
+
+/// Features without an older equivalent: minimum `server_version_num`, name
+/// and a pattern finding them in a statement.
+const FEATURES: [(i64, &str, &str); 4] = [
+    (
+        110000,
+        "INCLUDE in CREATE INDEX",
+        r"(?is)^\s*CREATE\s+(UNIQUE\s+)?INDEX\b.*\)\s*INCLUDE\s*\(",
+    ),
+    (
+        120000,
+        "GENERATED ALWAYS AS ... STORED",
+        r"(?i)\bGENERATED\s+ALWAYS\s+AS\s*\(",
+    ),
+    (
+        140000,
+        "DETACH PARTITION CONCURRENTLY",
+        r"(?i)\bDETACH\s+PARTITION\b.*\bCONCURRENTLY\b",
+    ),
+    (
+        150000,
+        "NULLS NOT DISTINCT",
+        r"(?i)\bNULLS\s+NOT\s+DISTINCT\b",
+    ),
+];
+
+/// Rewrites `statements` for a server at `version`, or names the first
+/// feature it doesn't support.
+pub fn adapt(statements: Vec<String>, version: i64) -> Result<Vec<String>, String> {
+    let mut adapted = Vec::with_capacity(statements.len());
+    for statement in statements {
+        for (min_version, feature, pattern) in FEATURES {
+            if version < min_version && regex::Regex::new(pattern).unwrap().is_match(&statement) {
+                return Err(format!(
+                    "{feature} requires PostgreSQL {}, but the target server is {}: {statement}",
+                    major(min_version),
+                    major(version)
+                ));
+            }
+        }
+        adapted.extend(adapt_statement(statement, version));
+    }
+    Ok(adapted)
+}
+
+fn adapt_statement(statement: String, version: i64) -> Vec<String> {
+    let mut statement = statement;
+    // EXECUTE FUNCTION is the PostgreSQL 11 spelling of EXECUTE PROCEDURE
+    if version < 110000 {
+        let execute_function = regex::Regex::new(r"(?i)\bEXECUTE\s+FUNCTION\b").unwrap();
+        statement = execute_function
+            .replace(&statement, "EXECUTE PROCEDURE")
+            .into_owned();
+    }
+    if version < 140000 {
+        let or_replace = regex::Regex::new(
+            r#"(?is)^\s*CREATE\s+OR\s+REPLACE\s+(TRIGGER\s+((?:"[^"]*"|[\w$]+))\s.*?\bON\s+((?:"[^"]*"|[\w$]+)(?:\.(?:"[^"]*"|[\w$]+))?)\s.*)$"#,
+        )
+        .unwrap();
+        if let Some(captures) = or_replace.captures(&statement) {
+            return vec![
+                format!(
+                    "DROP TRIGGER IF EXISTS {} ON {}",
+                    &captures[2], &captures[3]
+                ),
+                format!("CREATE {}", &captures[1]),
+            ];
+        }
+    }
+    vec![statement]
+}
+
+/// `150004` -> `15`, `90624` -> `9.6`.
+fn major(version: i64) -> String {
+    if version >= 100000 {
+        (version / 10000).to_string()
+    } else {
+        format!("{}.{}", version / 10000, version / 100 % 100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adapt_rejects_features_newer_than_the_server() {
+        let statements = vec!["CREATE UNIQUE INDEX u ON t (a) NULLS NOT DISTINCT".to_string()];
+        assert_eq!(adapt(statements.clone(), 150004).unwrap(), statements);
+        let error = adapt(statements, 140010).unwrap_err();
+        assert!(error
+            .starts_with("NULLS NOT DISTINCT requires PostgreSQL 15, but the target server is 14"));
+    }
+
+    #[test]
+    fn adapt_rewrites_replaceable_triggers_before_14() {
+        let statement = "CREATE OR REPLACE TRIGGER audit AFTER UPDATE ON public.users FOR EACH ROW EXECUTE FUNCTION audit()".to_string();
+        assert_eq!(
+            adapt(vec![statement.clone()], 140000).unwrap(),
+            vec![statement]
+        );
+        assert_eq!(
+            adapt(
+                vec!["CREATE OR REPLACE TRIGGER audit AFTER UPDATE ON public.users FOR EACH ROW EXECUTE FUNCTION audit()".to_string()],
+                100000
+            )
+            .unwrap(),
+            vec![
+                "DROP TRIGGER IF EXISTS audit ON public.users".to_string(),
+                "CREATE TRIGGER audit AFTER UPDATE ON public.users FOR EACH ROW EXECUTE PROCEDURE audit()".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn major_formats_old_and_new_versions() {
+        assert_eq!(major(150004), "15");
+        assert_eq!(major(90624), "9.6");
+    }
+}
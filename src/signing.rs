@@ -0,0 +1,57 @@
+//! HMAC signatures over generated SQL, so apply can prove the statements it
+//! runs are the ones a reviewer approved in a plan artifact.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const SCHEME: &str = "hmac-sha256";
+
+/// Signature of `statements` under `key`, as `hmac-sha256:<hex>`.
+pub fn sign<S: AsRef<str>>(key: &str, statements: &[S]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key");
+    // Length-prefixed, so moving text between statements changes the signature
+    for statement in statements {
+        let statement = statement.as_ref();
+        mac.update(&(statement.len() as u64).to_be_bytes());
+        mac.update(statement.as_bytes());
+    }
+    format!("{SCHEME}:{:x}", mac.finalize().into_bytes())
+}
+
+/// Whether `signature` is the signature of `statements` under `key`.
+pub fn verify<S: AsRef<str>>(key: &str, statements: &[S], signature: &str) -> bool {
+    let expected = sign(key, statements);
+    // Constant time, so a forger can't learn the signature byte by byte
+    expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Path of the signature written next to a generated migration file.
+pub fn signature_path(file: &str) -> String {
+    format!("{file}.sig")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_covers_every_statement() {
+        let statements = ["CREATE TABLE a (id int);", "DROP TABLE b;"];
+        let signature = sign("key", &statements);
+        assert!(signature.starts_with("hmac-sha256:"));
+        assert!(verify("key", &statements, &signature));
+
+        assert!(!verify("other", &statements, &signature));
+        assert!(!verify("key", &["CREATE TABLE a (id int);"], &signature));
+        assert!(!verify(
+            "key",
+            &["CREATE TABLE a (id int);DROP TABLE b;", ""],
+            &signature
+        ));
+    }
+}
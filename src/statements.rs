@@ -0,0 +1,315 @@
+//! Quote-aware reading of SQL text. String literals, quoted identifiers,
+//! dollar-quoted bodies (`CREATE FUNCTION ... AS $$ ... $$`, `DO $$ ... $$`)
+//! and comments can hold semicolons and keywords, which must neither end a
+//! statement nor match keyword checks such as [`crate::execute::is_transactional`].
+
+/// One statement of a script. `COPY ... FROM STDIN` carries its inline data,
+/// which has to go through the copy protocol rather than be executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub sql: String,
+    pub copy_data: Option<String>,
+}
+
+/// Splits `sql` into statements at semicolons outside literals, quoted
+/// identifiers, dollar quotes and comments. Statements with nothing but
+/// comments are dropped.
+pub fn split(sql: &str) -> Vec<Statement> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let (mut start, mut i) = (0, 0);
+    while i <= bytes.len() {
+        if i < bytes.len() {
+            if let Some((_, end)) = quoted_end(sql, i) {
+                i = end;
+                continue;
+            }
+            if bytes[i] != b';' {
+                i += 1;
+                continue;
+            }
+        }
+        let text = &sql[start..i];
+        i += 1;
+        start = i;
+        if code_only(text).trim().is_empty() {
+            continue;
+        }
+        let mut statement = Statement {
+            sql: text.trim().to_string(),
+            copy_data: None,
+        };
+        if is_copy_from_stdin(text) && i < bytes.len() {
+            // The data starts on the line after the statement
+            let data_start = sql[i..].find('\n').map_or(bytes.len(), |n| i + n + 1);
+            let (data, after) = copy_data(sql, data_start);
+            statement.copy_data = Some(data.to_string());
+            i = after;
+            start = after;
+        }
+        statements.push(statement);
+    }
+    statements
+}
+
+/// `sql` with literals, dollar-quoted bodies and comments blanked out, so
+/// keyword patterns only match the statement itself.
+pub fn code_only(sql: &str) -> String {
+    blank(sql, false)
+}
+
+/// [`code_only`] with quoted identifiers blanked out as well, for patterns
+/// that look for keywords and nothing else: a table named `"concurrently"`
+/// isn't the `CONCURRENTLY` keyword.
+pub fn keywords_only(sql: &str) -> String {
+    blank(sql, true)
+}
+
+fn blank(sql: &str, identifiers: bool) -> String {
+    let mut code = String::with_capacity(sql.len());
+    let (mut start, mut i) = (0, 0);
+    while i < sql.len() {
+        match quoted_end(sql, i) {
+            Some((Span::Identifier, end)) if !identifiers => i = end,
+            Some((_, end)) => {
+                code.push_str(&sql[start..i]);
+                code.push_str(&" ".repeat(end - i));
+                i = end;
+                start = end;
+            }
+            None => i += 1,
+        }
+    }
+    code.push_str(&sql[start..]);
+    code
+}
+
+/// Whether `sql` ends inside a `--` comment, so appending `;` would comment it out.
+pub fn ends_in_line_comment(sql: &str) -> bool {
+    let mut i = 0;
+    let mut in_comment = false;
+    while i < sql.len() {
+        match quoted_end(sql, i) {
+            Some((_, end)) => {
+                in_comment = end == sql.len() && sql[i..].starts_with("--");
+                i = end;
+            }
+            None => {
+                in_comment = false;
+                i += 1;
+            }
+        }
+    }
+    in_comment
+}
+
+fn is_copy_from_stdin(statement: &str) -> bool {
+    let re = regex::Regex::new(r"(?is)^\s*COPY\b.*\bFROM\s+STDIN\b").unwrap();
+    re.is_match(&code_only(statement))
+}
+
+/// Inline COPY data from `start` up to the `\.` line, and where the script
+/// continues after it. Without a terminator the data runs to the end.
+fn copy_data(sql: &str, start: usize) -> (&str, usize) {
+    let mut line_start = start;
+    while line_start < sql.len() {
+        let line_end = sql[line_start..]
+            .find('\n')
+            .map_or(sql.len(), |n| line_start + n);
+        if sql[line_start..line_end].trim_end_matches('\r') == "\\." {
+            return (&sql[start..line_start], (line_end + 1).min(sql.len()));
+        }
+        line_start = line_end + 1;
+    }
+    (&sql[start..], sql.len())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Span {
+    /// A quoted identifier, which is part of the statement.
+    Identifier,
+    /// A literal, dollar-quoted body or comment.
+    Blank,
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$'
+}
+
+/// If a literal, quoted identifier, dollar quote or comment starts at byte
+/// `start`, what it is and the byte just past its end. Unterminated ones run
+/// to the end of `sql`.
+fn quoted_end(sql: &str, start: usize) -> Option<(Span, usize)> {
+    let bytes = sql.as_bytes();
+    let rest = &bytes[start..];
+    let len = bytes.len();
+    if rest.starts_with(b"--") {
+        let end = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(len, |n| start + n);
+        return Some((Span::Blank, end));
+    }
+    if rest.starts_with(b"/*") {
+        // Block comments nest in PostgreSQL
+        let (mut depth, mut i) = (0, start);
+        while i < len {
+            if bytes[i..].starts_with(b"/*") {
+                depth += 1;
+                i += 2;
+            } else if bytes[i..].starts_with(b"*/") {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some((Span::Blank, i));
+                }
+            } else {
+                i += 1;
+            }
+        }
+        return Some((Span::Blank, len));
+    }
+    match rest[0] {
+        b'\'' => {
+            // E'...' strings take backslash escapes
+            let escapes = start > 0
+                && matches!(bytes[start - 1], b'E' | b'e')
+                && (start < 2 || !is_ident_byte(bytes[start - 2]));
+            Some((Span::Blank, closing_quote(bytes, start, b'\'', escapes)))
+        }
+        b'"' => Some((Span::Identifier, closing_quote(bytes, start, b'"', false))),
+        b'$' if start == 0 || !is_ident_byte(bytes[start - 1]) => {
+            let tag_len = rest[1..]
+                .iter()
+                .position(|&b| !(b.is_ascii_alphanumeric() || b == b'_'))?;
+            // `$1` is a parameter, not a tag
+            if rest.get(1 + tag_len) != Some(&b'$')
+                || rest.get(1).is_some_and(|b| b.is_ascii_digit())
+            {
+                return None;
+            }
+            let delimiter = &rest[..tag_len + 2];
+            let body = start + delimiter.len();
+            let end = bytes[body..]
+                .windows(delimiter.len())
+                .position(|w| w == delimiter)
+                .map_or(len, |n| body + n + delimiter.len());
+            Some((Span::Blank, end))
+        }
+        _ => None,
+    }
+}
+
+fn closing_quote(bytes: &[u8], start: usize, quote: u8, escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if escapes && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql(statements: &[Statement]) -> Vec<&str> {
+        statements.iter().map(|s| s.sql.as_str()).collect()
+    }
+
+    #[test]
+    fn split_keeps_dollar_quoted_bodies_together() {
+        let script = r#"
+CREATE FUNCTION touch() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at := now();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $body$
+BEGIN
+    EXECUTE 'SELECT 1; SELECT $$;$$';
+END
+$body$;
+CREATE TABLE "semi;colon" (note text DEFAULT 'a;b' /* ; */); -- trailing; comment
+"#;
+        let statements = split(script);
+        assert_eq!(statements.len(), 3, "{statements:#?}");
+        assert!(statements[0].sql.ends_with("$$ LANGUAGE plpgsql"));
+        assert!(statements[1].sql.starts_with("DO $body$"));
+        assert!(statements[1].sql.ends_with("$body$"));
+        assert_eq!(
+            statements[2].sql,
+            r#"CREATE TABLE "semi;colon" (note text DEFAULT 'a;b' /* ; */)"#
+        );
+    }
+
+    #[test]
+    fn split_handles_escapes_and_parameters() {
+        let statements = split(
+            "INSERT INTO t VALUES (E'it\\'s; fine', 'it''s; fine');\nPREPARE q AS SELECT $1;",
+        );
+        assert_eq!(
+            sql(&statements),
+            vec![
+                "INSERT INTO t VALUES (E'it\\'s; fine', 'it''s; fine')",
+                "PREPARE q AS SELECT $1",
+            ]
+        );
+    }
+
+    #[test]
+    fn split_reads_copy_payloads() {
+        let statements = split(
+            "COPY public.countries (code, name) FROM STDIN WITH (FORMAT csv);\nfr,\"France; Paris\"\nit,'Italy\n\\.\nSELECT 1;\n",
+        );
+        assert_eq!(
+            sql(&statements),
+            vec![
+                "COPY public.countries (code, name) FROM STDIN WITH (FORMAT csv)",
+                "SELECT 1",
+            ]
+        );
+        assert_eq!(
+            statements[0].copy_data.as_deref(),
+            Some("fr,\"France; Paris\"\nit,'Italy\n")
+        );
+        assert_eq!(statements[1].copy_data, None);
+    }
+
+    #[test]
+    fn code_only_blanks_bodies_and_comments() {
+        let code = code_only(
+            "CREATE FUNCTION f() RETURNS void AS $$ REFRESH MATERIALIZED VIEW CONCURRENTLY v $$ LANGUAGE sql -- CONCURRENTLY",
+        );
+        assert!(!code.contains("CONCURRENTLY"));
+        assert!(code.starts_with("CREATE FUNCTION f() RETURNS void AS "));
+        assert!(code_only(r#"CREATE INDEX "CONCURRENTLY" ON t (a)"#).contains("CONCURRENTLY"));
+    }
+
+    #[test]
+    fn keywords_only_blanks_quoted_identifiers() {
+        let code = keywords_only(r#"CREATE INDEX "concurrently" ON "t" (a) -- CONCURRENTLY"#);
+        assert!(!code.to_lowercase().contains("concurrently"));
+        assert!(code.starts_with("CREATE INDEX "));
+        assert!(code.contains(" ON "));
+    }
+
+    #[test]
+    fn ends_in_line_comment_ignores_dashes_in_literals() {
+        assert!(ends_in_line_comment("SELECT 1 -- one"));
+        assert!(!ends_in_line_comment("SELECT 1 -- one\n"));
+        assert!(!ends_in_line_comment("SELECT '--'"));
+        assert!(!ends_in_line_comment("SELECT 1 /* -- */"));
+    }
+}
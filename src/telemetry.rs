@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use opentelemetry::metrics::{Counter, Histogram, MeterProvider};
+use opentelemetry::trace::{Span, Status, Tracer, TracerProvider};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tokio::sync::RwLock;
+
+const SERVICE_NAME: &str = "terraform-provider-pgmold";
+
+/// Optional OTLP export of a span and metrics per resource operation, shared by
+/// every resource instance. Disabled until `configure` is called with an endpoint.
+#[derive(Default, Clone)]
+pub struct Telemetry {
+    exporter: Arc<RwLock<Option<Arc<Exporter>>>>,
+}
+
+impl std::fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Telemetry").finish_non_exhaustive()
+    }
+}
+
+struct Exporter {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+    tracer: SdkTracer,
+    duration: Histogram<f64>,
+    failures: Counter<u64>,
+}
+
+impl Telemetry {
+    /// Exports to the OTLP/HTTP collector at `endpoint` (e.g. `http://collector:4318`),
+    /// sending `headers` with every request.
+    pub async fn configure(
+        &self,
+        endpoint: &str,
+        headers: HashMap<String, String>,
+    ) -> anyhow::Result<()> {
+        let endpoint = endpoint.trim_end_matches('/');
+        let resource = Resource::builder().with_service_name(SERVICE_NAME).build();
+
+        let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{endpoint}/v1/traces"))
+            .with_headers(headers.clone())
+            .build()?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{endpoint}/v1/metrics"))
+            .with_headers(headers)
+            .build()?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(metric_exporter)
+            .with_resource(resource)
+            .build();
+
+        let meter = meter_provider.meter(SERVICE_NAME);
+        let exporter = Exporter {
+            tracer: tracer_provider.tracer(SERVICE_NAME),
+            duration: meter
+                .f64_histogram("pgmold.operation.duration")
+                .with_unit("s")
+                .with_description("Duration of pgmold resource operations")
+                .build(),
+            failures: meter
+                .u64_counter("pgmold.operation.failures")
+                .with_description("Failed pgmold resource operations")
+                .build(),
+            tracer_provider,
+            meter_provider,
+        };
+
+        *self.exporter.write().await = Some(Arc::new(exporter));
+        Ok(())
+    }
+
+    /// Records one `operation` (e.g. `create`) of `resource` that began at
+    /// `started`, then flushes, since Terraform may stop the provider at any time.
+    pub async fn record(
+        &self,
+        resource: &'static str,
+        operation: &'static str,
+        started: SystemTime,
+        success: bool,
+    ) {
+        let Some(exporter) = self.exporter.read().await.clone() else {
+            return;
+        };
+
+        let attributes = vec![
+            KeyValue::new("pgmold.resource", resource),
+            KeyValue::new("pgmold.operation", operation),
+            KeyValue::new("pgmold.success", success),
+        ];
+        let mut span = exporter
+            .tracer
+            .span_builder(format!("{resource}.{operation}"))
+            .with_start_time(started)
+            .with_attributes(attributes.clone())
+            .start(&exporter.tracer);
+        if !success {
+            span.set_status(Status::error("operation failed"));
+            exporter.failures.add(1, &attributes);
+        }
+        span.end();
+
+        let elapsed = started.elapsed().unwrap_or_default().as_secs_f64();
+        exporter.duration.record(elapsed, &attributes);
+
+        // The exporters use blocking HTTP clients, so flush off the async runtime
+        let flushed = tokio::task::spawn_blocking(move || {
+            exporter.tracer_provider.force_flush()?;
+            exporter.meter_provider.force_flush()
+        })
+        .await;
+        if let Ok(Err(e)) = flushed {
+            tracing::warn!(error = %e, "failed to export telemetry");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn record_is_a_no_op_until_configured() {
+        let telemetry = Telemetry::default();
+        telemetry
+            .record("pgmold_schema", "create", SystemTime::now(), true)
+            .await;
+        assert!(telemetry.exporter.read().await.is_none());
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore};
+
+/// Provider-wide cap on concurrent applies, shared by every resource instance.
+/// Unlimited until `set_limit` is called with a value.
+#[derive(Debug, Default, Clone)]
+pub struct ApplyLimiter {
+    semaphore: Arc<RwLock<Option<Arc<Semaphore>>>>,
+}
+
+impl ApplyLimiter {
+    pub async fn set_limit(&self, limit: Option<usize>) {
+        let mut guard = self.semaphore.write().await;
+        *guard = limit.map(|n| Arc::new(Semaphore::new(n)));
+    }
+
+    /// Waits for a free slot. Returns `None` when no limit is configured;
+    /// otherwise the slot is released when the permit is dropped.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self.semaphore.read().await.clone()?;
+        semaphore.acquire_owned().await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn acquire_is_unlimited_by_default() {
+        let limiter = ApplyLimiter::default();
+        assert!(limiter.acquire().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn acquire_waits_for_released_permit() {
+        let limiter = ApplyLimiter::default();
+        limiter.set_limit(Some(1)).await;
+
+        let permit = limiter.acquire().await.expect("first permit");
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(blocked.is_err(), "second apply should wait");
+
+        drop(permit);
+        let next = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(next.unwrap().is_some());
+    }
+}
@@ -0,0 +1,111 @@
+//! Deferred validation of new foreign keys and check constraints. Adding one
+//! to an existing table scans it while holding a lock that blocks writes;
+//! added `NOT VALID`, the constraint only checks new rows, and a later
+//! `VALIDATE CONSTRAINT` scans the table under a lock that lets writes through.
+
+use regex::Regex;
+use sqlx::PgPool;
+
+/// Rewrites foreign keys and check constraints added to tables the plan
+/// doesn't create as `NOT VALID`, returning the rewritten statements and the
+/// `VALIDATE CONSTRAINT` statements that complete them.
+pub fn defer(statements: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let add = Regex::new(
+        r#"(?is)^\s*ALTER\s+TABLE\s+(?:ONLY\s+)?((?:"(?:[^"]|"")+"|[^\s."]+)(?:\s*\.\s*(?:"(?:[^"]|"")+"|[^\s."]+))?)\s+ADD\s+CONSTRAINT\s+("(?:[^"]|"")+"|\S+)\s+(?:FOREIGN\s+KEY|CHECK)\b"#,
+    )
+    .unwrap();
+    let not_valid = Regex::new(r"(?i)\bNOT\s+VALID\b").unwrap();
+    let created_tables: Vec<String> = statements
+        .iter()
+        .filter_map(|s| created_table(&crate::statements::code_only(s)))
+        .collect();
+
+    let mut validations = Vec::new();
+    let statements = statements
+        .into_iter()
+        .map(|statement| {
+            let code = crate::statements::code_only(&statement);
+            let Some(caps) = add.captures(&code) else {
+                return statement;
+            };
+            // A new table is empty, so validating it up front costs nothing
+            if not_valid.is_match(&code) || created_tables.contains(&normalize(&caps[1])) {
+                return statement;
+            }
+            validations.push(format!(
+                "ALTER TABLE {} VALIDATE CONSTRAINT {}",
+                &caps[1], &caps[2]
+            ));
+            let body = statement.trim_end();
+            match body.strip_suffix(';') {
+                Some(body) => format!("{} NOT VALID;", body.trim_end()),
+                None => format!("{body} NOT VALID"),
+            }
+        })
+        .collect();
+    (statements, validations)
+}
+
+/// `VALIDATE CONSTRAINT` statements for the foreign keys and check
+/// constraints in `schemas` still marked `NOT VALID`, e.g. by an expand phase.
+pub async fn pending(pool: &PgPool, schemas: &[String]) -> sqlx::Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        "SELECT format('ALTER TABLE %I.%I VALIDATE CONSTRAINT %I', n.nspname, c.relname, con.conname) \
+         FROM pg_constraint con \
+         JOIN pg_class c ON c.oid = con.conrelid \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE NOT con.convalidated AND con.contype IN ('f', 'c') AND n.nspname = ANY($1) \
+         ORDER BY n.nspname, c.relname, con.conname",
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(statement,)| statement).collect())
+}
+
+fn created_table(code: &str) -> Option<String> {
+    let re = Regex::new(
+        r#"(?is)^\s*CREATE\s+(?:UNLOGGED\s+)?TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?((?:"(?:[^"]|"")+"|[^\s."(]+)(?:\s*\.\s*(?:"(?:[^"]|"")+"|[^\s."(]+))?)"#,
+    )
+    .unwrap();
+    re.captures(code).map(|caps| normalize(&caps[1]))
+}
+
+/// Compares table names regardless of quoting, whitespace and the `public` prefix.
+fn normalize(name: &str) -> String {
+    let name: String = name.split_whitespace().collect();
+    let name = name.replace('"', "").to_lowercase();
+    name.strip_prefix("public.")
+        .map(str::to_string)
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defer_marks_constraints_on_existing_tables() {
+        let (statements, validations) = defer(vec![
+            "CREATE TABLE public.orgs (id int PRIMARY KEY);".to_string(),
+            "ALTER TABLE public.orgs ADD CONSTRAINT orgs_id_check CHECK (id > 0);".to_string(),
+            "ALTER TABLE \"public\".\"users\" ADD CONSTRAINT \"users_org_fk\" FOREIGN KEY (org_id) REFERENCES public.orgs (id);".to_string(),
+            "ALTER TABLE public.users ADD CONSTRAINT users_age_check CHECK (age >= 0) NOT VALID".to_string(),
+            "ALTER TABLE public.users ADD CONSTRAINT users_email_key UNIQUE (email)".to_string(),
+        ]);
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE public.orgs (id int PRIMARY KEY);",
+                "ALTER TABLE public.orgs ADD CONSTRAINT orgs_id_check CHECK (id > 0);",
+                "ALTER TABLE \"public\".\"users\" ADD CONSTRAINT \"users_org_fk\" FOREIGN KEY (org_id) REFERENCES public.orgs (id) NOT VALID;",
+                "ALTER TABLE public.users ADD CONSTRAINT users_age_check CHECK (age >= 0) NOT VALID",
+                "ALTER TABLE public.users ADD CONSTRAINT users_email_key UNIQUE (email)",
+            ]
+        );
+        assert_eq!(
+            validations,
+            vec!["ALTER TABLE \"public\".\"users\" VALIDATE CONSTRAINT \"users_org_fk\""]
+        );
+    }
+}
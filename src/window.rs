@@ -0,0 +1,473 @@
+//! Maintenance windows: the times applies may change the database, either a
+//! recurring window opened by a cron schedule and lasting a fixed duration, or
+//! a single window between two timestamps. Schedules and naive timestamps are
+//! read in the window's timezone, so a window follows daylight saving time.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use tf_provider::{AttributePath, Diagnostics};
+
+/// How far ahead [`MaintenanceWindow::next_opening`] looks for a recurring window.
+const LOOKAHEAD_DAYS: i64 = 366;
+
+/// The `maintenance_window_*` attributes as configured.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WindowSettings<'a> {
+    pub cron: Option<&'a str>,
+    pub duration: Option<&'a str>,
+    pub start: Option<&'a str>,
+    pub end: Option<&'a str>,
+    pub timezone: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Schedule {
+    Recurring {
+        cron: Cron,
+        duration: Duration,
+    },
+    Between {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    schedule: Schedule,
+    timezone: Tz,
+}
+
+impl MaintenanceWindow {
+    /// The window the settings describe, `None` if none is configured. Errors
+    /// name the attribute at fault.
+    pub fn parse(settings: WindowSettings) -> Result<Option<Self>, (&'static str, String)> {
+        let timezone = match settings.timezone {
+            Some(name) => name.parse::<Tz>().map_err(|_| {
+                (
+                    "maintenance_window_timezone",
+                    format!("Unknown maintenance_window_timezone \"{name}\", expected an IANA name such as Europe/Lisbon"),
+                )
+            })?,
+            None => chrono_tz::UTC,
+        };
+        let schedule = match settings {
+            WindowSettings {
+                cron: Some(_),
+                start: Some(_),
+                ..
+            }
+            | WindowSettings {
+                cron: Some(_),
+                end: Some(_),
+                ..
+            } => {
+                return Err((
+                    "maintenance_window_cron",
+                    "maintenance_window_cron conflicts with maintenance_window_start and maintenance_window_end".to_string(),
+                ))
+            }
+            WindowSettings {
+                cron: Some(cron),
+                duration: Some(duration),
+                ..
+            } => Schedule::Recurring {
+                cron: Cron::parse(cron).map_err(|e| {
+                    (
+                        "maintenance_window_cron",
+                        format!("Invalid maintenance_window_cron \"{cron}\": {e}"),
+                    )
+                })?,
+                duration: parse_duration(duration).ok_or_else(|| {
+                    (
+                        "maintenance_window_duration",
+                        format!("Invalid maintenance_window_duration \"{duration}\", expected e.g. 30min, 4h or 1d2h"),
+                    )
+                })?,
+            },
+            WindowSettings { cron: Some(_), .. } => {
+                return Err((
+                    "maintenance_window_duration",
+                    "maintenance_window_cron requires maintenance_window_duration".to_string(),
+                ))
+            }
+            WindowSettings {
+                duration: Some(_), ..
+            } => {
+                return Err((
+                    "maintenance_window_duration",
+                    "maintenance_window_duration requires maintenance_window_cron".to_string(),
+                ))
+            }
+            WindowSettings {
+                start: Some(start),
+                end: Some(end),
+                ..
+            } => {
+                let start = parse_timestamp(start, &timezone)
+                    .ok_or_else(|| invalid_timestamp("maintenance_window_start", start))?;
+                let end = parse_timestamp(end, &timezone)
+                    .ok_or_else(|| invalid_timestamp("maintenance_window_end", end))?;
+                if end <= start {
+                    return Err((
+                        "maintenance_window_end",
+                        "maintenance_window_end must be after maintenance_window_start"
+                            .to_string(),
+                    ));
+                }
+                Schedule::Between { start, end }
+            }
+            WindowSettings { start: Some(_), .. } => {
+                return Err((
+                    "maintenance_window_end",
+                    "maintenance_window_start requires maintenance_window_end".to_string(),
+                ))
+            }
+            WindowSettings { end: Some(_), .. } => {
+                return Err((
+                    "maintenance_window_start",
+                    "maintenance_window_end requires maintenance_window_start".to_string(),
+                ))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(MaintenanceWindow { schedule, timezone }))
+    }
+
+    /// Whether the window is open at `at`.
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        match &self.schedule {
+            Schedule::Recurring { cron, duration } => {
+                // A window opened within the last `duration` is still open
+                let minute = truncate_to_minute(at);
+                (0..duration.num_minutes()).any(|ago| {
+                    cron.matches(&(minute - Duration::minutes(ago)).with_timezone(&self.timezone))
+                })
+            }
+            Schedule::Between { start, end } => *start <= at && at < *end,
+        }
+    }
+
+    /// `at` if the window is open then, otherwise when it next opens. `None`
+    /// if it never will: a single window that has passed, or a schedule that
+    /// doesn't fire within a year.
+    pub fn next_opening(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.is_open(at) {
+            return Some(at);
+        }
+        match &self.schedule {
+            Schedule::Recurring { cron, .. } => {
+                let minute = truncate_to_minute(at);
+                (1..=LOOKAHEAD_DAYS * 24 * 60)
+                    .map(|ahead| minute + Duration::minutes(ahead))
+                    .find(|t| cron.matches(&t.with_timezone(&self.timezone)))
+            }
+            Schedule::Between { start, .. } => (at < *start).then_some(*start),
+        }
+    }
+
+    /// `at` in the window's timezone, for messages.
+    pub fn local(&self, at: DateTime<Utc>) -> String {
+        at.with_timezone(&self.timezone)
+            .format("%Y-%m-%d %H:%M %Z")
+            .to_string()
+    }
+}
+
+/// Reports invalid or incomplete `maintenance_window_*` settings on the
+/// attribute at fault, returning the window otherwise.
+pub fn check_window(
+    diags: &mut Diagnostics,
+    settings: WindowSettings,
+) -> Option<Option<MaintenanceWindow>> {
+    match MaintenanceWindow::parse(settings) {
+        Ok(window) => Some(window),
+        Err((attribute, message)) => {
+            diags.error_short(message, AttributePath::new(attribute));
+            None
+        }
+    }
+}
+
+fn invalid_timestamp(attribute: &'static str, value: &str) -> (&'static str, String) {
+    (
+        attribute,
+        format!("Invalid {attribute} \"{value}\", expected RFC 3339 like 2024-06-01T22:00:00Z, or 2024-06-01T22:00 in maintenance_window_timezone"),
+    )
+}
+
+/// RFC 3339 timestamps carry their offset; others are local to `timezone`.
+fn parse_timestamp(text: &str, timezone: &Tz) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Some(at.with_timezone(&Utc));
+    }
+    let naive = [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())?;
+    timezone
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// Durations such as `30min`, `4h` or `1d2h`, at least a minute long.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let re = regex::Regex::new(r"^(?:(\d+)d)?(?:(\d+)h)?(?:(\d+)(?:min|m))?$").unwrap();
+    let text: String = text.split_whitespace().collect();
+    let caps = re.captures(&text)?;
+    let part =
+        |i: usize| -> Option<i64> { caps.get(i).map_or(Some(0), |m| m.as_str().parse().ok()) };
+    let minutes = part(1)?
+        .checked_mul(24 * 60)?
+        .checked_add(part(2)?.checked_mul(60)?)?
+        .checked_add(part(3)?)?;
+    (minutes > 0).then(|| Duration::minutes(minutes))
+}
+
+fn truncate_to_minute(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.with_second(0)
+        .and_then(|at| at.with_nanosecond(0))
+        .unwrap_or(at)
+}
+
+/// A standard five-field cron schedule: minute, hour, day of month, month and
+/// day of week. Fields take `*`, values, ranges, steps, lists and, for months
+/// and weekdays, three-letter names. As in cron, a time matches a restricted
+/// day of month or a restricted day of week.
+#[derive(Debug, Clone, PartialEq)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl Cron {
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = match text.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            text => text,
+        };
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 fields, found {}", fields.len()));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7, WEEKDAYS, "day of week")?;
+        // 7 is another way to write Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Cron {
+            minutes: parse_field(minute, 0, 59, &[], "minute")?,
+            hours: parse_field(hour, 0, 23, &[], "hour")?,
+            days: parse_field(day, 1, 31, &[], "day of month")?,
+            months: parse_field(month, 1, 12, MONTHS, "month")?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    fn matches<T: Datelike + Timelike>(&self, at: &T) -> bool {
+        let has = |set: u64, value: u32| set & (1 << value) != 0;
+        let day = has(self.days, at.day());
+        let weekday = has(self.weekdays, at.weekday().num_days_from_sunday());
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        has(self.minutes, at.minute())
+            && has(self.hours, at.hour())
+            && has(self.months, at.month())
+            && day_matches
+    }
+}
+
+/// The values a cron field allows, as a bit set.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], what: &str) -> Result<u64, String> {
+    let value = |text: &str| -> Result<u32, String> {
+        let lower = text.to_lowercase();
+        let value = match names.iter().position(|n| *n == lower) {
+            Some(i) => i as u32 + min,
+            None => text
+                .parse()
+                .map_err(|_| format!("invalid {what} \"{text}\""))?,
+        };
+        if value < min || value > max {
+            return Err(format!("{what} {value} is outside {min}-{max}"));
+        }
+        Ok(value)
+    };
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, Some(step)),
+                _ => return Err(format!("invalid step \"{step}\" in {what}")),
+            },
+            None => (part, None),
+        };
+        let (low, high) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((low, high)) => (value(low)?, value(high)?),
+            // `5/15` steps from 5 to the end of the range
+            None if step.is_some() => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if low > high {
+            return Err(format!("{what} range {range} is backwards"));
+        }
+        for v in (low..=high).step_by(step.unwrap_or(1) as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn window(settings: WindowSettings) -> MaintenanceWindow {
+        MaintenanceWindow::parse(settings).unwrap().unwrap()
+    }
+
+    #[test]
+    fn recurring_window_opens_on_schedule() {
+        // Weeknights from 22:00 for four hours, into the next morning
+        let window = window(WindowSettings {
+            cron: Some("0 22 * * mon-fri"),
+            duration: Some("4h"),
+            ..Default::default()
+        });
+
+        assert!(window.is_open(at("2024-06-03T22:00:00Z")));
+        assert!(window.is_open(at("2024-06-04T01:59:59Z")));
+        assert!(!window.is_open(at("2024-06-04T02:00:00Z")));
+        assert!(!window.is_open(at("2024-06-01T23:00:00Z")));
+        assert_eq!(
+            window.next_opening(at("2024-06-01T12:30:00Z")),
+            Some(at("2024-06-03T22:00:00Z"))
+        );
+        let open = at("2024-06-05T23:15:00Z");
+        assert_eq!(window.next_opening(open), Some(open));
+    }
+
+    #[test]
+    fn recurring_window_follows_its_timezone() {
+        let window = window(WindowSettings {
+            cron: Some("30 2 * * *"),
+            duration: Some("1h"),
+            timezone: Some("Europe/Lisbon"),
+            ..Default::default()
+        });
+        // 02:30 in Lisbon is 01:30 UTC in summer and 02:30 UTC in winter
+        assert!(window.is_open(at("2024-07-01T01:45:00Z")));
+        assert!(window.is_open(at("2024-01-15T02:45:00Z")));
+        assert!(!window.is_open(at("2024-01-15T01:45:00Z")));
+    }
+
+    #[test]
+    fn cron_combines_day_of_month_and_weekday_like_cron() {
+        let cron = Cron::parse("0 0 1,15 * sun").unwrap();
+        let midnight = |text: &str| at(text).naive_utc();
+        assert!(cron.matches(&midnight("2024-06-15T00:00:00Z")));
+        assert!(cron.matches(&midnight("2024-06-09T00:00:00Z")));
+        assert!(!cron.matches(&midnight("2024-06-10T00:00:00Z")));
+
+        let cron = Cron::parse("*/15 9-17 * jan,jul 7").unwrap();
+        assert!(cron.matches(&midnight("2024-07-07T09:45:00Z")));
+        assert!(!cron.matches(&midnight("2024-07-07T09:50:00Z")));
+
+        assert_eq!(
+            Cron::parse("0 25 * * *").unwrap_err(),
+            "hour 25 is outside 0-23"
+        );
+        assert_eq!(
+            Cron::parse("0 0 * *").unwrap_err(),
+            "expected 5 fields, found 4"
+        );
+    }
+
+    #[test]
+    fn fixed_window_opens_once() {
+        let window = window(WindowSettings {
+            start: Some("2024-06-01T22:00"),
+            end: Some("2024-06-02T02:00:00+01:00"),
+            timezone: Some("Europe/Lisbon"),
+            ..Default::default()
+        });
+
+        assert!(!window.is_open(at("2024-06-01T20:59:00Z")));
+        assert!(window.is_open(at("2024-06-01T21:00:00Z")));
+        assert!(!window.is_open(at("2024-06-02T01:00:00Z")));
+        assert_eq!(
+            window.next_opening(at("2024-06-01T12:00:00Z")),
+            Some(at("2024-06-01T21:00:00Z"))
+        );
+        assert_eq!(window.next_opening(at("2024-06-03T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn parse_names_the_attribute_at_fault() {
+        let attribute = |settings| MaintenanceWindow::parse(settings).unwrap_err().0;
+        assert_eq!(
+            attribute(WindowSettings {
+                cron: Some("0 22 * * *"),
+                ..Default::default()
+            }),
+            "maintenance_window_duration"
+        );
+        assert_eq!(
+            attribute(WindowSettings {
+                cron: Some("0 22 * * *"),
+                duration: Some("4 hours"),
+                ..Default::default()
+            }),
+            "maintenance_window_duration"
+        );
+        assert_eq!(
+            attribute(WindowSettings {
+                start: Some("2024-06-02T00:00:00Z"),
+                end: Some("2024-06-01T00:00:00Z"),
+                ..Default::default()
+            }),
+            "maintenance_window_end"
+        );
+        assert_eq!(
+            attribute(WindowSettings {
+                start: Some("2024-06-01T00:00:00Z"),
+                end: Some("2024-06-02T00:00:00Z"),
+                timezone: Some("Mars/Olympus"),
+                ..Default::default()
+            }),
+            "maintenance_window_timezone"
+        );
+        assert_eq!(
+            MaintenanceWindow::parse(WindowSettings::default()),
+            Ok(None)
+        );
+    }
+}